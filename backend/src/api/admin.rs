@@ -0,0 +1,30 @@
+use axum::{
+    extract::Json,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::api::auth;
+use crate::api::error::ApiError;
+use crate::services::health;
+use crate::services::runtime_settings::{self, RuntimeSettingsUpdate};
+
+pub async fn get_settings() -> impl IntoResponse {
+    (StatusCode::OK, Json(runtime_settings::current())).into_response()
+}
+
+// the faucet limits and the renewal policy in here are guards of their own, so changing them needs the approver token
+pub async fn update_settings(headers: HeaderMap, Json(update): Json<RuntimeSettingsUpdate>) -> impl IntoResponse {
+    if let Err(e) = auth::require_approver(&headers, "Changing runtime settings") {
+        return e.into_response();
+    }
+    match runtime_settings::update(update) {
+        Ok(settings) => (StatusCode::OK, Json(settings)).into_response(),
+        Err(e) => ApiError::BadRequest(e.to_string()).into_response(),
+    }
+}
+
+// always 200, the report itself says what failed
+pub async fn self_check() -> impl IntoResponse {
+    (StatusCode::OK, Json(health::self_check().await)).into_response()
+}
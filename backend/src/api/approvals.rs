@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Json, Path, Query},
+    response::{IntoResponse, Response},
+    http::{HeaderMap, StatusCode},
+};
+use crate::api::auth;
+use crate::api::error::ApiError;
+use crate::api::validation;
+use crate::models::wallet::{ApprovalListQuery, RejectApprovalRequest};
+use crate::services::approvals;
+use crate::services::APP_STATE;
+
+pub async fn list_approvals(Query(query): Query<ApprovalListQuery>) -> impl IntoResponse {
+    let status = match query.status.as_deref() {
+        None => Some("pending"),
+        Some("all") => None,
+        Some(status) => Some(status),
+    };
+    match approvals::list(status) {
+        Ok(approvals) => (StatusCode::OK, Json(approvals)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn get_approval(Path(id): Path<String>) -> impl IntoResponse {
+    match approvals::get(&id) {
+        Ok(approval) => (StatusCode::OK, Json(approval)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn approve(headers: HeaderMap, Path(id): Path<String>) -> impl IntoResponse {
+    if let Err(response) = require_approver(&headers) {
+        return response;
+    }
+    match approvals::approve(&id).await {
+        Ok(approval) => (StatusCode::OK, Json(approval)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn reject(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    request: Option<Json<RejectApprovalRequest>>,
+) -> impl IntoResponse {
+    if let Err(response) = require_approver(&headers) {
+        return response;
+    }
+    if let Some(Json(request)) = &request {
+        if let Err(e) = validation::check(request) {
+            return e.into_response();
+        }
+    }
+    let reason = request.and_then(|Json(request)| request.reason);
+    match approvals::reject(&id, reason) {
+        Ok(approval) => (StatusCode::OK, Json(approval)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+// deciding needs the approver token, so whoever queued a send can't approve it too
+fn require_approver(headers: &HeaderMap) -> Result<(), Response> {
+    if !APP_STATE.config.approvals.is_enabled() {
+        return Err(ApiError::NotFound("Approvals are not enabled".to_string()).into_response());
+    }
+    auth::require_approver(headers, "Approving").map_err(IntoResponse::into_response)
+}
@@ -0,0 +1,33 @@
+use axum::http::HeaderMap;
+
+use crate::api::error::ApiError;
+use crate::services::APP_STATE;
+
+// changes that loosen what the wallet may do need the approver token, so a client that can
+// send can't also lift the limits that are meant to restrict it. `action` starts the error message
+pub fn require_approver(headers: &HeaderMap, action: &str) -> Result<(), ApiError> {
+    let Some(expected) = &APP_STATE.config.approvals.approver_token else {
+        return Err(ApiError::Forbidden(format!("{} needs approvals.approver_token (APPROVER_TOKEN) to be set", action)));
+    };
+    let token = headers.get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if tokens_match(token, expected) => Ok(()),
+        _ => Err(ApiError::Unauthorized(format!("{} needs Authorization: Bearer <approver token>", action))),
+    }
+}
+
+// for changes a default install must still be able to make: the token is only checked once one is configured
+pub fn require_approver_if_configured(headers: &HeaderMap, action: &str) -> Result<(), ApiError> {
+    if APP_STATE.config.approvals.approver_token.is_none() {
+        return Ok(());
+    }
+    require_approver(headers, action)
+}
+
+// compares every byte so the response time doesn't leak how much of the token matched
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
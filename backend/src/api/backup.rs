@@ -0,0 +1,68 @@
+use axum::{
+    extract::Json,
+    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+};
+use crate::api::auth;
+use crate::api::error::ApiError;
+use bitcoin::base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use crate::models::wallet::{BackupRequest, RestoreRequest};
+use crate::services::backup;
+use crate::services::APP_STATE;
+
+// the archive holds the mnemonic, so taking one out needs the approver token
+pub async fn create_backup(headers: HeaderMap, Json(request): Json<BackupRequest>) -> impl IntoResponse {
+    if let Err(e) = auth::require_approver(&headers, "Creating a backup") {
+        return e.into_response();
+    }
+    match backup::create(&request.passphrase) {
+        Ok(archive) => (StatusCode::OK, Json(serde_json::json!({
+            "created_at": chrono::Utc::now().timestamp(),
+            "size": archive.len(),
+            "archive": BASE64.encode(&archive),
+        }))).into_response(),
+        Err(e) => {
+            tracing::error!("Error creating backup: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn restore_backup(Json(request): Json<RestoreRequest>) -> impl IntoResponse {
+    let archive = match (&request.archive, &request.file) {
+        (Some(archive), None) => BASE64.decode(archive.trim())
+            .map_err(|e| format!("archive must be base64: {}", e)),
+        (None, Some(file)) => backup::read_file(&APP_STATE.config.backup, file)
+            .map_err(|e| e.to_string()),
+        _ => Err("Provide either archive or file".to_string()),
+    };
+    let archive = match archive {
+        Ok(archive) => archive,
+        Err(error) => return ApiError::BadRequest(error).into_response(),
+    };
+
+    match backup::restore(&archive, &request.passphrase).await {
+        Ok(summary) => (StatusCode::OK, Json(summary)).into_response(),
+        Err(e) => {
+            tracing::error!("Error restoring backup: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn list_backups() -> impl IntoResponse {
+    let config = &APP_STATE.config.backup;
+    match backup::list(config) {
+        Ok(backups) => (StatusCode::OK, Json(serde_json::json!({
+            "enabled": config.is_enabled(),
+            "dir": config.dir,
+            "interval_secs": config.interval_secs,
+            "keep": config.keep,
+            "backups": backups,
+        }))).into_response(),
+        Err(e) => {
+            tracing::error!("Error listing backups: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
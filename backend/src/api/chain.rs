@@ -0,0 +1,42 @@
+use axum::{
+    extract::Json,
+    response::IntoResponse,
+    http::StatusCode,
+};
+use crate::api::error::ApiError;
+use crate::api::validation::Valid;
+use crate::models::wallet::RawTransactionRequest;
+use crate::services::{chain, raw_tx};
+
+// lets the frontend warn that balances may be stale while the backend catches up with the chain
+pub async fn get_status() -> impl IntoResponse {
+    match chain::status().await {
+        Ok(status) => (StatusCode::OK, Json(status)).into_response(),
+        Err(e) => {
+            tracing::warn!("Error getting chain status: {}", e);
+            ApiError::Unavailable(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn decode_transaction(Valid(request): Valid<RawTransactionRequest>) -> impl IntoResponse {
+    match raw_tx::parse(&request.hex) {
+        Ok(tx) => (StatusCode::OK, Json(raw_tx::decode(&tx).await)).into_response(),
+        Err(e) => ApiError::field("hex", e.to_string()).into_response(),
+    }
+}
+
+// pushes an externally built transaction through the configured chain backend
+pub async fn broadcast_transaction(Valid(request): Valid<RawTransactionRequest>) -> impl IntoResponse {
+    let result = match raw_tx::parse(&request.hex) {
+        Ok(tx) => raw_tx::broadcast(&tx).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(txid) => (StatusCode::OK, Json(serde_json::json!({ "txid": txid.to_string() }))).into_response(),
+        Err(e) => {
+            tracing::warn!("Error broadcasting transaction: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
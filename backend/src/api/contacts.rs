@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Json, Path},
+    response::IntoResponse,
+    http::StatusCode,
+};
+use crate::api::error::ApiError;
+use crate::api::validation::Valid;
+use crate::models::wallet::ContactRequest;
+use crate::services::contacts;
+
+pub async fn list_contacts() -> impl IntoResponse {
+    match contacts::list_contacts() {
+        Ok(contacts) => (StatusCode::OK, Json(contacts)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn get_contact(Path(id): Path<i64>) -> impl IntoResponse {
+    match contacts::get_contact(id) {
+        Ok(contact) => (StatusCode::OK, Json(contact)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn create_contact(Valid(request): Valid<ContactRequest>) -> impl IntoResponse {
+    match contacts::create_contact(request) {
+        Ok(contact) => (StatusCode::CREATED, Json(contact)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn update_contact(
+    Path(id): Path<i64>,
+    Valid(request): Valid<ContactRequest>,
+) -> impl IntoResponse {
+    match contacts::update_contact(id, request) {
+        Ok(contact) => (StatusCode::OK, Json(contact)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn delete_contact(Path(id): Path<i64>) -> impl IntoResponse {
+    match contacts::delete_contact(id) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
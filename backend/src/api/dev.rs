@@ -0,0 +1,37 @@
+use axum::{
+    extract::{Json, Query},
+    response::IntoResponse,
+    http::StatusCode,
+};
+use crate::api::error::ApiError;
+use crate::models::wallet::MineQuery;
+
+const MAX_BLOCKS: u64 = 1000;
+
+pub async fn mine(Query(query): Query<MineQuery>) -> impl IntoResponse {
+    if crate::services::profiles::active().network != bitcoin::Network::Regtest {
+        return ApiError::Forbidden("Mining is only available on regtest".to_string()).into_response();
+    }
+
+    let blocks = query.blocks.unwrap_or(1);
+    if blocks == 0 || blocks > MAX_BLOCKS {
+        return ApiError::BadRequest(format!("blocks must be between 1 and {}", MAX_BLOCKS)).into_response();
+    }
+
+    let hashes = match crate::services::bitcoin_rpc::generate_blocks(blocks).await {
+        Ok(hashes) => hashes,
+        Err(e) => {
+            tracing::error!("Error mining blocks: {}", e);
+            return ApiError::from(e).into_response();
+        }
+    };
+    tracing::info!("Mined {} blocks", hashes.len());
+
+    // esplora may lag a moment behind bitcoind, the height is best effort
+    let height = crate::services::APP_STATE.blockchain.get_height().await.ok();
+
+    (StatusCode::OK, Json(serde_json::json!({
+        "height": height,
+        "hashes": hashes.iter().map(|hash| hash.to_string()).collect::<Vec<_>>(),
+    }))).into_response()
+}
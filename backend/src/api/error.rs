@@ -0,0 +1,215 @@
+use axum::{
+    extract::Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::services::approvals::ApprovalError;
+use crate::services::ark_grpc;
+use crate::services::contacts::ContactError;
+use crate::services::exit::ClaimInProgress;
+use crate::services::faucet::FaucetError;
+use crate::services::spending_policy::PolicyViolation;
+use crate::services::watched_addresses::WatchError;
+use crate::storage::key_manager::WalletLocked;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+// every error body is {"error": message, "code": ...}, validation errors add "fields"
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    Validation(Vec<FieldError>),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Locked(String),
+    Timeout(String),
+    RateLimited(String),
+    Unavailable(String),
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn field(field: &str, message: impl Into<String>) -> Self {
+        ApiError::Validation(vec![FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        }])
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Locked(_) => StatusCode::LOCKED,
+            ApiError::Timeout(_) => StatusCode::REQUEST_TIMEOUT,
+            ApiError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // stable names for clients to branch on, the messages may change
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Locked(_) => "wallet_locked",
+            ApiError::Timeout(_) => "timeout",
+            ApiError::RateLimited(_) => "rate_limited",
+            ApiError::Unavailable(_) => "unavailable",
+            ApiError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::Validation(fields) => fields.iter()
+                .map(|field| format!("{}: {}", field.field, field.message))
+                .collect::<Vec<_>>()
+                .join(", "),
+            ApiError::BadRequest(message)
+            | ApiError::Unauthorized(message)
+            | ApiError::Forbidden(message)
+            | ApiError::NotFound(message)
+            | ApiError::Conflict(message)
+            | ApiError::Locked(message)
+            | ApiError::Timeout(message)
+            | ApiError::RateLimited(message)
+            | ApiError::Unavailable(message)
+            | ApiError::Internal(message) => message.clone(),
+        }
+    }
+
+    // the body on its own, for responses that are stored before they're sent (idempotent sends)
+    pub fn body(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "error": self.message(),
+            "code": self.code(),
+        });
+        if let ApiError::Validation(fields) = self {
+            body["fields"] = serde_json::json!(fields);
+        }
+        body
+    }
+
+    pub fn into_parts(self) -> (StatusCode, serde_json::Value) {
+        (self.status(), self.body())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status(), Json(self.body())).into_response()
+    }
+}
+
+// typed service errors keep their meaning, anything else is an internal error
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        if e.downcast_ref::<PolicyViolation>().is_some() {
+            return ApiError::Forbidden(e.to_string());
+        }
+        if e.downcast_ref::<ClaimInProgress>().is_some() {
+            return ApiError::Conflict(e.to_string());
+        }
+        if e.chain().any(|cause| cause.is::<WalletLocked>()) {
+            return ApiError::Locked("Wallet is locked, unlock it with POST /api/v1/wallet/unlock".to_string());
+        }
+        if ark_grpc::is_unavailable(&e) {
+            return ApiError::Unavailable(e.to_string());
+        }
+        ApiError::Internal(e.to_string())
+    }
+}
+
+impl From<ContactError> for ApiError {
+    fn from(e: ContactError) -> Self {
+        match e {
+            ContactError::NotFound(_) => ApiError::NotFound(e.to_string()),
+            ContactError::Invalid(_) => ApiError::BadRequest(e.to_string()),
+            ContactError::Storage(_) => {
+                tracing::error!("Contact storage error: {}", e);
+                ApiError::Internal(e.to_string())
+            }
+        }
+    }
+}
+
+impl From<ApprovalError> for ApiError {
+    fn from(e: ApprovalError) -> Self {
+        match e {
+            ApprovalError::NotFound(_) => ApiError::NotFound(e.to_string()),
+            ApprovalError::NotPending(..) => ApiError::Conflict(e.to_string()),
+            ApprovalError::Storage(_) => {
+                tracing::error!("Approval storage error: {}", e);
+                ApiError::Internal(e.to_string())
+            }
+        }
+    }
+}
+
+impl From<WatchError> for ApiError {
+    fn from(e: WatchError) -> Self {
+        match e {
+            WatchError::NotFound(_) => ApiError::NotFound(e.to_string()),
+            WatchError::Invalid(_) => ApiError::BadRequest(e.to_string()),
+            WatchError::Storage(_) => {
+                tracing::error!("Watched address storage error: {}", e);
+                ApiError::Internal(e.to_string())
+            }
+        }
+    }
+}
+
+impl From<FaucetError> for ApiError {
+    fn from(e: FaucetError) -> Self {
+        match e {
+            FaucetError::RateLimited(_) => ApiError::RateLimited(e.to_string()),
+            FaucetError::InvalidRequest(_) => ApiError::BadRequest(e.to_string()),
+            FaucetError::Unavailable(_) => ApiError::Unavailable(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body() {
+        let body = ApiError::NotFound("Swap abc not found".to_string()).body();
+        assert_eq!(body, serde_json::json!({"error": "Swap abc not found", "code": "not_found"}));
+
+        let error = ApiError::field("amount", "must be at least 546 sats");
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error.body()["error"], "amount: must be at least 546 sats");
+        assert_eq!(error.body()["fields"][0]["field"], "amount");
+    }
+
+    #[test]
+    fn test_from_anyhow_keeps_typed_errors() {
+        let violation = anyhow::Error::from(PolicyViolation("over the limit".to_string()));
+        assert_eq!(ApiError::from(violation).status(), StatusCode::FORBIDDEN);
+        let unavailable = anyhow::Error::from(ark_grpc::ClientUnavailable).context("Failed to send");
+        assert_eq!(ApiError::from(unavailable).status(), StatusCode::SERVICE_UNAVAILABLE);
+        let locked = anyhow::Error::from(WalletLocked).context("Failed to derive an address");
+        assert_eq!(ApiError::from(locked).status(), StatusCode::LOCKED);
+        assert_eq!(ApiError::from(anyhow::anyhow!("boom")).code(), "internal");
+    }
+}
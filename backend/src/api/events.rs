@@ -0,0 +1,86 @@
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse},
+};
+use futures::stream::Stream;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+use crate::services::APP_STATE;
+
+pub async fn wallet_ws(ws: WebSocketUpgrade) -> impl IntoResponse {
+    ws.on_upgrade(handle_wallet_socket)
+}
+
+async fn handle_wallet_socket(mut socket: WebSocket) {
+    tracing::info!("WebSocket client connected");
+    let mut events = APP_STATE.events.subscribe();
+
+    // send the current balance first so the client doesn't start from an empty state
+    let snapshot = {
+        let balance = APP_STATE.balance.lock().await;
+        crate::services::events::WalletEvent::BalanceUpdate {
+            confirmed: balance.confirmed,
+            pending: balance.trusted_pending + balance.untrusted_pending,
+            total: balance.total,
+        }
+    };
+    if let Ok(text) = serde_json::to_string(&snapshot) {
+        if socket.send(Message::Text(text)).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let text = match serde_json::to_string(&event) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            tracing::error!("Failed to serialize wallet event: {}", e);
+                            continue;
+                        }
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                },
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("WebSocket client lagged behind, skipped {} events", skipped);
+                },
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // clients don't send anything meaningful, ignore pings/text
+                Some(Ok(_)) => {},
+            },
+        }
+    }
+
+    tracing::info!("WebSocket client disconnected");
+}
+
+pub async fn round_events() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = APP_STATE.events.subscribe();
+
+    let stream = futures::stream::unfold(events, |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) if event.is_round_event() => {
+                    let sse_event = Event::default()
+                        .json_data(&event)
+                        .unwrap_or_else(|_| Event::default().comment("unserializable event"));
+                    return Some((Ok(sse_event), events));
+                },
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Round event stream lagged behind, skipped {} events", skipped);
+                },
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,76 @@
+use axum::{
+    extract::{ConnectInfo, Json, Path},
+    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+};
+use crate::api::auth;
+use crate::api::error::ApiError;
+use crate::api::validation::Valid;
+use std::net::SocketAddr;
+use crate::models::wallet::FaucetRequestBody;
+use crate::services::faucet::FaucetConfig;
+use crate::services::APP_STATE;
+
+pub async fn request_funds(
+    ConnectInfo(remote_addr): ConnectInfo<SocketAddr>,
+    Valid(request): Valid<FaucetRequestBody>,
+) -> impl IntoResponse {
+    let ip = remote_addr.ip().to_string();
+
+    match APP_STATE.faucet.request(request.address, ip).await {
+        Ok(response) => {
+            let status = if response.queue_position.is_some() {
+                StatusCode::ACCEPTED
+            } else {
+                StatusCode::OK
+            };
+            (status, Json(response)).into_response()
+        },
+        Err(e) => {
+            tracing::warn!("Faucet request from {} rejected: {}", remote_addr, e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn get_queue_status() -> impl IntoResponse {
+    match APP_STATE.faucet.queue_status() {
+        Ok(status) => (StatusCode::OK, Json(status)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn get_request_status(Path(id): Path<u64>) -> impl IntoResponse {
+    match APP_STATE.faucet.get_request(id) {
+        Some((request, queue_position)) => (StatusCode::OK, Json(serde_json::json!({
+            "request": request,
+            "queue_position": queue_position,
+        }))).into_response(),
+        None => ApiError::NotFound(format!("Faucet request not found: {}", id)).into_response(),
+    }
+}
+
+pub async fn get_balance() -> impl IntoResponse {
+    match APP_STATE.faucet.balance().await {
+        Ok(balance) => (StatusCode::OK, Json(balance)).into_response(),
+        Err(e) => ApiError::Unavailable(e.to_string()).into_response(),
+    }
+}
+
+pub async fn get_config() -> impl IntoResponse {
+    (StatusCode::OK, Json(APP_STATE.faucet.config())).into_response()
+}
+
+// in public mode anyone can reach the faucet, so only the approver may raise its payout or limits.
+// a private faucet needs the token too once one is configured
+pub async fn update_config(headers: HeaderMap, Json(config): Json<FaucetConfig>) -> impl IntoResponse {
+    if APP_STATE.faucet.is_public_mode() || APP_STATE.config.approvals.approver_token.is_some() {
+        if let Err(e) = auth::require_approver(&headers, "Changing the faucet config") {
+            return e.into_response();
+        }
+    }
+    match APP_STATE.faucet.set_config(config) {
+        Ok(()) => (StatusCode::OK, Json(APP_STATE.faucet.config())).into_response(),
+        Err(e) => ApiError::BadRequest(e.to_string()).into_response(),
+    }
+}
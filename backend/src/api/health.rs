@@ -0,0 +1,23 @@
+use axum::{
+    extract::Json,
+    response::IntoResponse,
+    http::StatusCode,
+};
+use crate::services::health::{self, HealthReport};
+
+fn respond(report: HealthReport) -> axum::response::Response {
+    let status = if report.is_available() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report)).into_response()
+}
+
+pub async fn healthz() -> impl IntoResponse {
+    respond(health::liveness().await)
+}
+
+pub async fn readyz() -> impl IntoResponse {
+    respond(health::readiness().await)
+}
@@ -1,2 +1,23 @@
 pub mod wallet;
-pub mod transactions;
\ No newline at end of file
+pub mod transactions;
+pub mod payments;
+pub mod events;
+pub mod faucet;
+pub mod contacts;
+pub mod monitor;
+pub mod dev;
+pub mod swaps;
+pub mod health;
+pub mod backup;
+pub mod multisig;
+pub mod wallet_lock;
+pub mod approvals;
+pub mod rates;
+pub mod simulation;
+pub mod versioning;
+pub mod error;
+pub mod validation;
+pub mod chain;
+pub mod watched_addresses;
+pub mod admin;
+pub mod auth;
\ No newline at end of file
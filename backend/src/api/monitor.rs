@@ -0,0 +1,68 @@
+use axum::{
+    extract::Json,
+    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+};
+use crate::api::auth;
+use crate::api::error::ApiError;
+use crate::services::renewal::RenewalPolicy;
+use crate::services::spending_policy::SpendingPolicy;
+use crate::services::APP_STATE;
+
+pub async fn get_status() -> impl IntoResponse {
+    (StatusCode::OK, Json(APP_STATE.monitor.status())).into_response()
+}
+
+pub async fn start() -> impl IntoResponse {
+    if !APP_STATE.monitor.start() {
+        return ApiError::Conflict("Background monitor is already running".to_string()).into_response();
+    }
+    (StatusCode::OK, Json(APP_STATE.monitor.status())).into_response()
+}
+
+// stopping the monitor also stops VTXO renewal, so it's guarded like the renewal policy
+pub async fn stop(headers: HeaderMap) -> impl IntoResponse {
+    if let Err(e) = auth::require_approver_if_configured(&headers, "Stopping the background monitor") {
+        return e.into_response();
+    }
+    if !APP_STATE.monitor.stop() {
+        return ApiError::Conflict("Background monitor is not running".to_string()).into_response();
+    }
+    (StatusCode::OK, Json(APP_STATE.monitor.status())).into_response()
+}
+
+pub async fn get_renewal_policy() -> impl IntoResponse {
+    (StatusCode::OK, Json(APP_STATE.monitor.policy())).into_response()
+}
+
+// the same policy is editable through PUT /admin/settings. Without an approver token configured
+// anyone can change it, so a default install can still turn auto renewal off
+pub async fn update_renewal_policy(headers: HeaderMap, Json(policy): Json<RenewalPolicy>) -> impl IntoResponse {
+    if let Err(e) = auth::require_approver_if_configured(&headers, "Changing the renewal policy") {
+        return e.into_response();
+    }
+    match APP_STATE.monitor.set_policy(policy) {
+        Ok(()) => (StatusCode::OK, Json(APP_STATE.monitor.policy())).into_response(),
+        Err(e) => ApiError::BadRequest(e.to_string()).into_response(),
+    }
+}
+
+pub async fn get_spending_policy() -> impl IntoResponse {
+    match APP_STATE.spending.status() {
+        Ok(status) => (StatusCode::OK, Json(status)).into_response(),
+        Err(e) => {
+            tracing::error!("Error getting spending policy: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn update_spending_policy(headers: HeaderMap, Json(policy): Json<SpendingPolicy>) -> impl IntoResponse {
+    if let Err(e) = auth::require_approver(&headers, "Changing the spending policy") {
+        return e.into_response();
+    }
+    if let Err(e) = APP_STATE.spending.set_policy(policy) {
+        return ApiError::BadRequest(e.to_string()).into_response();
+    }
+    get_spending_policy().await.into_response()
+}
@@ -0,0 +1,117 @@
+use axum::{
+    extract::Json,
+    response::IntoResponse,
+    http::StatusCode,
+};
+use crate::api::error::ApiError;
+use crate::api::validation::Valid;
+use std::str::FromStr;
+use crate::models::wallet::{MultisigSetupRequest, MultisigCombineRequest, PsbtCreateRequest, PsbtRequest, PsbtResponse};
+use crate::services::onchain::multisig;
+
+fn parse_psbt(psbt: &str) -> Result<bitcoin::Psbt, axum::response::Response> {
+    bitcoin::Psbt::from_str(psbt).map_err(|e| ApiError::BadRequest(format!("Invalid PSBT: {}", e)).into_response())
+}
+
+pub async fn setup(Valid(request): Valid<MultisigSetupRequest>) -> impl IntoResponse {
+    if let Err(e) = multisig::setup(request.cosigners) {
+        tracing::error!("Error setting up multisig wallet: {}", e);
+        return ApiError::BadRequest(e.to_string()).into_response();
+    }
+    get_info().await.into_response()
+}
+
+pub async fn get_info() -> impl IntoResponse {
+    match multisig::info().await {
+        Ok(info) => (StatusCode::OK, Json(info)).into_response(),
+        Err(e) => {
+            tracing::error!("Error getting multisig wallet: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn get_address() -> impl IntoResponse {
+    match multisig::new_receive_address() {
+        Ok(address) => (StatusCode::OK, Json(serde_json::json!({
+            "address": address
+        }))).into_response(),
+        Err(e) => {
+            tracing::error!("Error getting multisig address: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn create_psbt(Valid(request): Valid<PsbtCreateRequest>) -> impl IntoResponse {
+    let priority = request.priority.unwrap_or_else(|| "normal".to_string());
+    match multisig::create_psbt(request.address, request.amount, priority.into()).await {
+        Ok((psbt, fee)) => (StatusCode::OK, Json(PsbtResponse {
+            psbt: psbt.to_string(),
+            fee: Some(fee.to_sat()),
+            finalized: false,
+        })).into_response(),
+        Err(e) => {
+            tracing::error!("Error creating multisig PSBT: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn sign_psbt(Json(request): Json<PsbtRequest>) -> impl IntoResponse {
+    let mut psbt = match parse_psbt(&request.psbt) {
+        Ok(psbt) => psbt,
+        Err(response) => return response,
+    };
+
+    match multisig::sign_psbt(&mut psbt) {
+        Ok(finalized) => (StatusCode::OK, Json(PsbtResponse {
+            fee: psbt.fee().ok().map(|fee| fee.to_sat()),
+            psbt: psbt.to_string(),
+            finalized,
+        })).into_response(),
+        Err(e) => {
+            tracing::error!("Error signing multisig PSBT: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn combine_psbts(Json(request): Json<MultisigCombineRequest>) -> impl IntoResponse {
+    let mut psbts = Vec::new();
+    for psbt in &request.psbts {
+        match parse_psbt(psbt) {
+            Ok(psbt) => psbts.push(psbt),
+            Err(response) => return response,
+        }
+    }
+
+    match multisig::combine_psbts(psbts) {
+        Ok((psbt, finalized)) => (StatusCode::OK, Json(PsbtResponse {
+            fee: psbt.fee().ok().map(|fee| fee.to_sat()),
+            psbt: psbt.to_string(),
+            finalized,
+        })).into_response(),
+        Err(e) => {
+            tracing::error!("Error combining multisig PSBTs: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn broadcast_psbt(Json(request): Json<PsbtRequest>) -> impl IntoResponse {
+    let psbt = match parse_psbt(&request.psbt) {
+        Ok(psbt) => psbt,
+        Err(response) => return response,
+    };
+
+    match multisig::broadcast_psbt(psbt).await {
+        Ok(txid) => (StatusCode::OK, Json(serde_json::json!({
+            "txid": txid
+        }))).into_response(),
+        Err(e) => {
+            tracing::error!("Error broadcasting multisig PSBT: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
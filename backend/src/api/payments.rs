@@ -0,0 +1,75 @@
+use axum::{
+    extract::{Json, Path, Query},
+    response::IntoResponse,
+    http::StatusCode,
+};
+use crate::api::error::ApiError;
+use crate::api::validation::Valid;
+use crate::models::wallet::{PaymentUriQuery, PaymentUriResponse, ParsePaymentUriRequest, DecodeInvoiceRequest, CreatePaymentRequestBody};
+use crate::services::payments::{self, PaymentUri};
+use crate::services::APP_STATE;
+
+pub async fn create_payment_uri(Query(query): Query<PaymentUriQuery>) -> impl IntoResponse {
+    match payments::create_payment_uri(query.amount, query.label, query.message).await {
+        Ok(payment) => (StatusCode::OK, Json(PaymentUriResponse {
+            uri: payment.to_uri(),
+            payment,
+        })).into_response(),
+        Err(e) => {
+            tracing::error!("Error creating payment URI: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn decode_invoice(Json(request): Json<DecodeInvoiceRequest>) -> impl IntoResponse {
+    match crate::services::payments::bolt11::decode(&request.invoice) {
+        Ok(invoice) => (StatusCode::OK, Json(invoice)).into_response(),
+        Err(e) => {
+            tracing::warn!("Error decoding invoice: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn parse_payment_uri(Json(request): Json<ParsePaymentUriRequest>) -> impl IntoResponse {
+    match PaymentUri::parse(&request.uri) {
+        Ok(payment) => (StatusCode::OK, Json(payment)).into_response(),
+        Err(e) => {
+            tracing::warn!("Error parsing payment URI: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn create_payment_request(Valid(request): Valid<CreatePaymentRequestBody>) -> impl IntoResponse {
+    let expiry_secs = request.expiry_secs.unwrap_or(3600);
+    match APP_STATE.payment_requests.create(request.amount, request.memo, expiry_secs, request.webhook_url).await {
+        Ok(payment_request) => (StatusCode::OK, Json(payment_request)).into_response(),
+        Err(e) => {
+            tracing::warn!("Error creating payment request: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn list_payment_requests() -> impl IntoResponse {
+    match APP_STATE.payment_requests.list() {
+        Ok(requests) => (StatusCode::OK, Json(requests)).into_response(),
+        Err(e) => {
+            tracing::error!("Error listing payment requests: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn get_payment_request(Path(id): Path<String>) -> impl IntoResponse {
+    match APP_STATE.payment_requests.get(&id) {
+        Ok(Some(payment_request)) => (StatusCode::OK, Json(payment_request)).into_response(),
+        Ok(None) => ApiError::NotFound(format!("Payment request {} not found", id)).into_response(),
+        Err(e) => {
+            tracing::error!("Error getting payment request {}: {}", id, e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
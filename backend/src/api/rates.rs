@@ -0,0 +1,17 @@
+use axum::{
+    extract::Json,
+    response::IntoResponse,
+    http::StatusCode,
+};
+use crate::services::APP_STATE;
+
+pub async fn get_rates() -> impl IntoResponse {
+    let rates = &APP_STATE.rates;
+    let snapshot = rates.get();
+    (StatusCode::OK, Json(serde_json::json!({
+        "enabled": rates.is_enabled(),
+        "source": snapshot.as_ref().map(|snapshot| snapshot.source.clone()),
+        "fetched_at": snapshot.as_ref().map(|snapshot| snapshot.fetched_at),
+        "rates": snapshot.map(|snapshot| snapshot.rates).unwrap_or_default(),
+    }))).into_response()
+}
@@ -0,0 +1,44 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use crate::services::simulation;
+
+// responses built from simulated data carry X-Simulated: true, and JSON objects also get "simulated": true.
+// in simulation mode that is every response
+pub async fn mark_simulated(request: Request, next: Next) -> Response {
+    let (response, used) = simulation::track(next.run(request)).await;
+    if !used && !simulation::is_enabled() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    parts.headers.insert("X-Simulated", HeaderValue::from_static("true"));
+
+    let is_json = parts.headers.get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if !is_json {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to read response body: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(mut object)) => {
+            object.insert("simulated".to_string(), true.into());
+            serde_json::Value::Object(object).to_string().into_bytes()
+        },
+        // arrays and scalars keep their shape, the header marks them
+        _ => bytes.to_vec(),
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
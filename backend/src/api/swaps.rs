@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Json, Path},
+    response::IntoResponse,
+    http::StatusCode,
+};
+use crate::api::error::ApiError;
+use crate::api::validation::Valid;
+use crate::api::wallet::held_for_approval;
+use crate::models::wallet::PayInvoiceRequest;
+use crate::services::approvals::HeldSend;
+use crate::services::swaps::SwapFunding;
+use crate::services::APP_STATE;
+
+pub async fn pay_invoice(Valid(request): Valid<PayInvoiceRequest>) -> impl IntoResponse {
+    if !APP_STATE.swaps.is_enabled() {
+        return ApiError::Unavailable("Swaps are disabled, set boltz_url to enable them".to_string()).into_response();
+    }
+    if !crate::services::swaps::REFUNDS_SUPPORTED {
+        return ApiError::Unavailable(
+            "Paying invoices through swaps is disabled until failed swaps can be refunded".to_string()
+        ).into_response();
+    }
+
+    let funding = request.funding.unwrap_or(SwapFunding::Offchain);
+    // held on the invoice amount, the provider's fees are only known once the swap exists
+    let amount = match crate::services::payments::bolt11::decode(&request.invoice) {
+        Ok(decoded) => decoded.amount_msat.unwrap_or(0) / 1000,
+        Err(e) => return ApiError::BadRequest(e.to_string()).into_response(),
+    };
+    if let Some((status, body)) = held_for_approval(HeldSend::Swap { funding }, &request.invoice, amount) {
+        return (status, Json(body)).into_response();
+    }
+
+    match APP_STATE.swaps.pay_invoice(&request.invoice, funding).await {
+        Ok(swap) => (StatusCode::OK, Json(swap)).into_response(),
+        Err(e) => {
+            tracing::error!("Error paying invoice: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn list_swaps() -> impl IntoResponse {
+    match APP_STATE.swaps.list() {
+        Ok(swaps) => (StatusCode::OK, Json(swaps)).into_response(),
+        Err(e) => {
+            tracing::error!("Error listing swaps: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn get_swap(Path(id): Path<String>) -> impl IntoResponse {
+    // refreshes from the provider so the answer isn't a poll interval behind
+    let result = match APP_STATE.swaps.is_enabled() {
+        true => APP_STATE.swaps.refresh(&id).await,
+        false => APP_STATE.swaps.get(&id),
+    };
+
+    match result {
+        Ok(Some(swap)) => (StatusCode::OK, Json(swap)).into_response(),
+        Ok(None) => ApiError::NotFound(format!("Swap {} not found", id)).into_response(),
+        Err(e) => {
+            tracing::error!("Error getting swap {}: {}", id, e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
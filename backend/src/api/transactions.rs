@@ -1,24 +1,41 @@
 #![allow(unused_imports, unused_variables, unused_assignments, dead_code, unused_features)]
 use axum::{
-    extract::{Json, Path},
+    extract::{Json, Path, Query},
     response::IntoResponse,
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
 };
+use crate::api::error::ApiError;
+use crate::api::validation::Valid;
+use crate::models::wallet::{RoundHistoryQuery, TransactionHistoryQuery};
+use crate::services::round_schedule::RoundSchedule;
 use crate::services::transactions;
 
-pub async fn get_history() -> impl IntoResponse {
+pub async fn get_history(Query(query): Query<TransactionHistoryQuery>) -> impl IntoResponse {
     tracing::info!("API: Received request for transaction history");
 
-    match transactions::get_transaction_history().await {
-        Ok(history) => {
-            tracing::info!("API: Successfully retrieved {} transactions", history.len());
-            (StatusCode::OK, Json(history)).into_response()
+    match transactions::get_transaction_history(&query).await {
+        Ok((history, total)) => {
+            tracing::info!("API: Successfully retrieved {} of {} transactions", history.len(), total);
+            let Some(currency) = query.fiat.as_deref() else {
+                return (StatusCode::OK, [("X-Total-Count", total.to_string())], Json(history)).into_response();
+            };
+
+            // converted at today's rate, historical prices aren't tracked
+            let mut body = Vec::with_capacity(history.len());
+            for tx in &history {
+                let fiat = match crate::services::APP_STATE.rates.convert(tx.amount, currency) {
+                    Ok(fiat) => fiat,
+                    Err(e) => return ApiError::BadRequest(e.to_string()).into_response(),
+                };
+                let mut item = serde_json::json!(tx);
+                item["fiat"] = serde_json::json!(fiat);
+                body.push(item);
+            }
+            (StatusCode::OK, [("X-Total-Count", total.to_string())], Json(body)).into_response()
         },
         Err(e) => {
             tracing::error!("Error getting transaction history: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
@@ -28,9 +45,7 @@ pub async fn get_transaction(Path(txid): Path<String>) -> impl IntoResponse {
         Ok(tx) => (StatusCode::OK, Json(tx)).into_response(),
         Err(e) => {
             tracing::error!("Error getting transaction: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
@@ -55,28 +70,185 @@ pub async fn participate_in_round() -> impl IntoResponse {
             },
             Err(e) => {
                 tracing::error!("API: Error participating in round: {}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ 
-                    "error": e.to_string() 
-                }))).into_response()
+                ApiError::from(e).into_response()
             }
         },
         Err(_) => {
             tracing::error!("API: Timeout while participating in round");
-            (StatusCode::REQUEST_TIMEOUT, Json(serde_json::json!({ 
-                "error": "Operation timed out. This could be due to network issues or a deadlock."
-            }))).into_response()
+            ApiError::Timeout("Operation timed out. This could be due to network issues or a deadlock.".to_string()).into_response()
+        }
+    }
+}
+
+pub async fn get_round_history(Query(query): Query<RoundHistoryQuery>) -> impl IntoResponse {
+    let result = crate::services::rounds::history(query.limit, query.offset)
+        .and_then(|rounds| Ok((rounds, crate::services::rounds::totals()?)));
+    match result {
+        Ok((rounds, (count, vtxos_renewed, fees))) => (StatusCode::OK, Json(serde_json::json!({
+            "rounds": rounds,
+            "total_rounds": count,
+            "total_vtxos_renewed": vtxos_renewed,
+            "total_fees": fees,
+        }))).into_response(),
+        Err(e) => {
+            tracing::error!("API: Error loading round history: {}", e);
+            ApiError::from(e).into_response()
         }
     }
 }
 
-pub async fn unilateral_exit(Json(request): Json<crate::models::wallet::ExitRequest>) -> impl IntoResponse {
+pub async fn get_consolidation_preview() -> impl IntoResponse {
+    match crate::services::rounds::consolidation_preview().await {
+        Ok(preview) => (StatusCode::OK, Json(preview)).into_response(),
+        Err(e) => {
+            tracing::error!("API: Error previewing VTXO consolidation: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn get_round_schedule() -> impl IntoResponse {
+    match crate::services::APP_STATE.round_scheduler.planned().await {
+        Ok(planned) => (StatusCode::OK, Json(planned)).into_response(),
+        Err(e) => {
+            tracing::error!("API: Error checking round schedule: {}", e);
+            ApiError::Unavailable(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn update_round_schedule(Json(schedule): Json<RoundSchedule>) -> impl IntoResponse {
+    let scheduler = &crate::services::APP_STATE.round_scheduler;
+    match scheduler.set_schedule(schedule) {
+        Ok(()) => (StatusCode::OK, Json(scheduler.schedule())).into_response(),
+        Err(e) => ApiError::BadRequest(e.to_string()).into_response(),
+    }
+}
+
+pub async fn unilateral_exit(Valid(request): Valid<crate::models::wallet::ExitRequest>) -> impl IntoResponse {
     match transactions::unilateral_exit(request.vtxo_txid).await {
         Ok(response) => (StatusCode::OK, Json(response)).into_response(),
         Err(e) => {
             tracing::error!("Error performing unilateral exit: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
-}
\ No newline at end of file
+}
+
+pub async fn list_exits() -> impl IntoResponse {
+    (StatusCode::OK, Json(crate::services::APP_STATE.exits.list())).into_response()
+}
+
+// refreshes the exit against the chain before reporting it
+pub async fn get_exit(Path(vtxo_txid): Path<String>) -> impl IntoResponse {
+    let exits = &crate::services::APP_STATE.exits;
+    if exits.get(&vtxo_txid).is_none() {
+        return ApiError::NotFound(format!("No exit in progress for {}", vtxo_txid)).into_response();
+    }
+
+    match exits.refresh(&vtxo_txid).await {
+        Ok(exit) => (StatusCode::OK, Json(exit)).into_response(),
+        Err(e) => {
+            tracing::error!("Error refreshing exit {}: {}", vtxo_txid, e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn claim_exit(Path(vtxo_txid): Path<String>) -> impl IntoResponse {
+    let exits = &crate::services::APP_STATE.exits;
+    if exits.get(&vtxo_txid).is_none() {
+        return ApiError::NotFound(format!("No exit in progress for {}", vtxo_txid)).into_response();
+    }
+
+    match exits.claim(&vtxo_txid).await {
+        Ok(exit) => (StatusCode::OK, Json(exit)).into_response(),
+        Err(e) => {
+            tracing::error!("Error claiming exit {}: {}", vtxo_txid, e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn sweep_expired_vtxos() -> impl IntoResponse {
+    match crate::services::APP_STATE.exits.sweep_expired().await {
+        Ok(result) => (StatusCode::OK, Json(result)).into_response(),
+        Err(e) => {
+            tracing::error!("Error sweeping expired VTXOs: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn get_exit_recommendations() -> impl IntoResponse {
+    match crate::services::APP_STATE.exits.recommendations().await {
+        Ok(recommendations) => (StatusCode::OK, Json(recommendations)).into_response(),
+        Err(e) => {
+            tracing::error!("Error building exit recommendations: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+// ?dry_run=true only reports what would be exited
+pub async fn emergency_exit_all(Query(query): Query<crate::models::wallet::EmergencyExitQuery>) -> impl IntoResponse {
+    let exits = &crate::services::APP_STATE.exits;
+    if query.dry_run {
+        return match exits.plan_exit_all().await {
+            Ok(plan) => (StatusCode::OK, Json(plan)).into_response(),
+            Err(e) => {
+                tracing::error!("Error planning emergency exit: {}", e);
+                ApiError::from(e).into_response()
+            }
+        };
+    }
+
+    match exits.emergency_exit_all().await {
+        Ok(result) => (StatusCode::OK, Json(result)).into_response(),
+        Err(e) => {
+            tracing::error!("Error performing emergency exit: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+// counting a held deposit loosens the screening, so it needs the approver token
+pub async fn release_deposit(headers: HeaderMap, Path(txid): Path<String>) -> impl IntoResponse {
+    if let Err(e) = crate::api::auth::require_approver(&headers, "Releasing a deposit") {
+        return e.into_response();
+    }
+    match transactions::release_deposit(&txid).await {
+        Ok(Some(tx)) => (StatusCode::OK, Json(tx)).into_response(),
+        Ok(None) => ApiError::NotFound(format!("No held deposit {}", txid)).into_response(),
+        Err(e) => {
+            tracing::error!("Error releasing deposit {}: {}", txid, e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn bump_fee(
+    Path(txid): Path<String>,
+    Json(request): Json<crate::models::wallet::BumpFeeRequest>,
+) -> impl IntoResponse {
+    match crate::services::wallet::bump_fee(txid, request).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(e) => {
+            tracing::error!("Error bumping fee: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn cpfp(
+    Path(txid): Path<String>,
+    Json(request): Json<crate::models::wallet::CpfpRequest>,
+) -> impl IntoResponse {
+    match crate::services::wallet::cpfp(txid, request).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(e) => {
+            tracing::error!("Error creating CPFP child: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
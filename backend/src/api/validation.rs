@@ -0,0 +1,346 @@
+use axum::{
+    async_trait,
+    extract::{FromRequest, Json, Request},
+};
+use bitcoin::Network;
+use serde::de::DeserializeOwned;
+use std::str::FromStr;
+
+use crate::api::error::{ApiError, FieldError};
+use crate::models::wallet::{
+    ContactRequest, CreatePaymentRequestBody, EstimateFeeDetailedRequest, ExitRequest, FaucetRequestBody,
+    MultisigSetupRequest, PayInvoiceRequest, PsbtCreateRequest, RawTransactionRequest, ReceiveRequest, RejectApprovalRequest,
+    SendOnchainRequest, SendRequest, WatchAddressRequest, WithdrawOnchainRequest,
+};
+use crate::services::payment_requests::{MAX_EXPIRY_SECS, MIN_EXPIRY_SECS};
+
+// used until the Ark server has told us its own
+pub const DUST_LIMIT_SATS: u64 = 546;
+pub const MAX_NAME_LENGTH: usize = 64;
+pub const MAX_TEXT_LENGTH: usize = 256;
+
+const PRIORITIES: &[&str] = &["fastest", "urgent", "fast", "high", "normal", "medium", "slow", "low", "economy"];
+
+// checked before a handler runs, so bad input is answered with 422 instead of failing inside ark-client
+pub trait Validate {
+    fn validate(&self, errors: &mut FieldErrors);
+}
+
+// a JSON body that passed Validate, extracted like Json<T>
+pub struct Valid<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Valid<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state).await
+            .map_err(|rejection| ApiError::field("body", rejection.body_text()))?;
+        check(&value)?;
+        Ok(Valid(value))
+    }
+}
+
+pub fn check(value: &impl Validate) -> Result<(), ApiError> {
+    let dust = crate::services::APP_STATE.server_info.dust().unwrap_or(DUST_LIMIT_SATS);
+    let mut errors = FieldErrors::new(crate::services::profiles::active().network, dust);
+    value.validate(&mut errors);
+    errors.into_result()
+}
+
+pub struct FieldErrors {
+    network: Network,
+    dust: u64,
+    errors: Vec<FieldError>,
+}
+
+impl FieldErrors {
+    pub fn new(network: Network, dust: u64) -> Self {
+        Self { network, dust, errors: Vec::new() }
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    pub fn into_result(self) -> Result<(), ApiError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::Validation(self.errors))
+        }
+    }
+
+    pub fn amount(&mut self, field: &str, sats: u64) {
+        if sats < self.dust {
+            self.add(field, format!("must be at least the {} sat dust limit", self.dust));
+        }
+    }
+
+    pub fn required(&mut self, field: &str, value: &str) -> bool {
+        if value.trim().is_empty() {
+            self.add(field, "is required");
+            return false;
+        }
+        true
+    }
+
+    pub fn max_length(&mut self, field: &str, value: &str, max: usize) {
+        if value.chars().count() > max {
+            self.add(field, format!("must be at most {} characters", max));
+        }
+    }
+
+    pub fn ark_address(&mut self, field: &str, address: &str) {
+        if self.required(field, address) {
+            if let Err(message) = ark_address(address.trim(), self.network) {
+                self.add(field, message);
+            }
+        }
+    }
+
+    pub fn onchain_address(&mut self, field: &str, address: &str) {
+        if self.required(field, address) {
+            if let Err(message) = onchain_address(address.trim(), self.network) {
+                self.add(field, message);
+            }
+        }
+    }
+
+    pub fn any_address(&mut self, field: &str, address: &str) {
+        if self.required(field, address) {
+            let address = address.trim();
+            if ark_address(address, self.network).is_err() {
+                if let Err(message) = onchain_address(address, self.network) {
+                    self.add(field, message);
+                }
+            }
+        }
+    }
+
+    pub fn priority(&mut self, field: &str, priority: Option<&str>) {
+        if let Some(priority) = priority {
+            if !PRIORITIES.contains(&priority.to_lowercase().as_str()) {
+                self.add(field, "must be one of fastest, fast, normal or slow");
+            }
+        }
+    }
+}
+
+// mainnet Ark addresses use the "ark" prefix, every test network "tark"
+fn ark_address(address: &str, network: Network) -> Result<(), String> {
+    ark_core::ArkAddress::decode(address)
+        .map_err(|e| format!("is not an Ark address: {}", e))?;
+    let expected = if network == Network::Bitcoin { "ark" } else { "tark" };
+    match address.rsplit_once('1') {
+        Some((hrp, _)) if hrp.eq_ignore_ascii_case(expected) => Ok(()),
+        _ => Err(format!("is an Ark address for a different network than {}", network)),
+    }
+}
+
+fn onchain_address(address: &str, network: Network) -> Result<(), String> {
+    bitcoin::Address::from_str(address)
+        .map_err(|e| format!("is not a Bitcoin address: {}", e))?
+        .require_network(network)
+        .map_err(|_| format!("is not an address for {}", network))?;
+    Ok(())
+}
+
+// sends may name the recipient by URI or contact instead, those are checked once resolved
+fn recipient(errors: &mut FieldErrors, address: &str, amount: u64, uri: Option<&str>, contact_id: Option<i64>, onchain: bool) {
+    if uri.is_some() {
+        return;
+    }
+    if contact_id.is_none() {
+        if onchain {
+            errors.onchain_address("address", address);
+        } else {
+            errors.ark_address("address", address);
+        }
+    }
+    errors.amount("amount", amount);
+}
+
+impl Validate for SendRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        recipient(errors, &self.address, self.amount, self.uri.as_deref(), self.contact_id, false);
+    }
+}
+
+impl Validate for SendOnchainRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        recipient(errors, &self.address, self.amount, self.uri.as_deref(), self.contact_id, true);
+        errors.priority("priority", self.priority.as_deref());
+        if self.fee_rate_sat_vb == Some(0) {
+            errors.add("fee_rate_sat_vb", "must be at least 1 sat/vB");
+        }
+    }
+}
+
+impl Validate for WithdrawOnchainRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        recipient(errors, &self.address, self.amount, self.uri.as_deref(), self.contact_id, true);
+    }
+}
+
+impl Validate for EstimateFeeDetailedRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.onchain_address("address", &self.address);
+        errors.amount("amount", self.amount);
+    }
+}
+
+impl Validate for PsbtCreateRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.onchain_address("address", &self.address);
+        errors.amount("amount", self.amount);
+        errors.priority("priority", self.priority.as_deref());
+    }
+}
+
+impl Validate for ReceiveRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("from_address", &self.from_address);
+        errors.amount("amount", self.amount);
+    }
+}
+
+impl Validate for CreatePaymentRequestBody {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.amount("amount", self.amount);
+        if let Some(memo) = &self.memo {
+            errors.max_length("memo", memo, MAX_TEXT_LENGTH);
+        }
+        if let Some(expiry_secs) = self.expiry_secs {
+            if !(MIN_EXPIRY_SECS..=MAX_EXPIRY_SECS).contains(&expiry_secs) {
+                errors.add("expiry_secs", format!("must be between {} and {}", MIN_EXPIRY_SECS, MAX_EXPIRY_SECS));
+            }
+        }
+        if let Some(webhook_url) = &self.webhook_url {
+            if !webhook_url.starts_with("http://") && !webhook_url.starts_with("https://") {
+                errors.add("webhook_url", "must be an http(s) URL");
+            }
+        }
+    }
+}
+
+impl Validate for ContactRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if errors.required("name", &self.name) {
+            errors.max_length("name", self.name.trim(), MAX_NAME_LENGTH);
+        }
+        errors.any_address("address", &self.address);
+    }
+}
+
+impl Validate for WatchAddressRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.onchain_address("address", &self.address);
+        if let Some(label) = &self.label {
+            errors.max_length("label", label, MAX_NAME_LENGTH);
+        }
+        if let Some(webhook_url) = &self.webhook_url {
+            if !webhook_url.starts_with("http://") && !webhook_url.starts_with("https://") {
+                errors.add("webhook_url", "must be an http(s) URL");
+            }
+        }
+    }
+}
+
+impl Validate for RejectApprovalRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if let Some(reason) = &self.reason {
+            errors.max_length("reason", reason, MAX_TEXT_LENGTH);
+        }
+    }
+}
+
+impl Validate for FaucetRequestBody {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.onchain_address("address", &self.address);
+    }
+}
+
+impl Validate for PayInvoiceRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("invoice", &self.invoice);
+    }
+}
+
+impl Validate for ExitRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if bitcoin::Txid::from_str(&self.vtxo_txid).is_err() {
+            errors.add("vtxo_txid", "must be a transaction id");
+        }
+    }
+}
+
+impl Validate for RawTransactionRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if errors.required("hex", &self.hex) && crate::services::raw_tx::parse(&self.hex).is_err() {
+            errors.add("hex", "is not a raw transaction");
+        }
+    }
+}
+
+impl Validate for MultisigSetupRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self.cosigners.len() != 2 {
+            errors.add("cosigners", "must list exactly two xpubs");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(value: &impl Validate) -> Vec<String> {
+        let mut errors = FieldErrors::new(Network::Testnet, DUST_LIMIT_SATS);
+        value.validate(&mut errors);
+        errors.errors.into_iter().map(|error| error.field).collect()
+    }
+
+    #[test]
+    fn test_onchain_send() {
+        let mut request = SendOnchainRequest {
+            address: "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+            amount: 10_000,
+            priority: None,
+            fee_rate_sat_vb: None,
+            uri: None,
+            contact_id: None,
+        };
+        assert!(validate(&request).is_empty());
+
+        request.amount = 100;
+        request.priority = Some("whenever".to_string());
+        assert_eq!(validate(&request), vec!["amount", "priority"]);
+
+        // a mainnet address on testnet
+        request.amount = 10_000;
+        request.priority = None;
+        request.address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string();
+        assert_eq!(validate(&request), vec!["address"]);
+
+        // resolved from the URI later
+        request.uri = Some("bitcoin:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx?amount=0.001".to_string());
+        assert!(validate(&request).is_empty());
+    }
+
+    #[test]
+    fn test_contact() {
+        let request = ContactRequest {
+            name: "x".repeat(MAX_NAME_LENGTH + 1),
+            address: "not an address".to_string(),
+        };
+        assert_eq!(validate(&request), vec!["name", "address"]);
+    }
+}
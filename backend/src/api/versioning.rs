@@ -0,0 +1,52 @@
+use axum::{
+    extract::{OriginalUri, Request},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+
+pub const API_VERSION: &str = "1";
+
+// the route below the API root, the same for /api/v1/... and the deprecated /api/...
+pub fn route_path(path: &str) -> Option<&str> {
+    path.strip_prefix("/api/v1")
+        .or_else(|| path.strip_prefix("/api"))
+        .filter(|rest| rest.starts_with('/'))
+}
+
+pub async fn current(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    response.headers_mut().insert("API-Version", HeaderValue::from_static(API_VERSION));
+    response
+}
+
+// same handlers as /api/v1, flagged so clients move over before the aliases go away
+pub async fn deprecated(request: Request, next: Next) -> Response {
+    let path = request.extensions().get::<OriginalUri>()
+        .map(|uri| uri.path().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let successor = format!("/api/v{}{}", API_VERSION, route_path(&path).unwrap_or(""));
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("API-Version", HeaderValue::from_static(API_VERSION));
+    headers.insert("Deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor)) {
+        headers.insert("Link", link);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_path() {
+        assert_eq!(route_path("/api/v1/wallet/unlock"), Some("/wallet/unlock"));
+        assert_eq!(route_path("/api/wallet/unlock"), Some("/wallet/unlock"));
+        assert_eq!(route_path("/api/v1"), None);
+        assert_eq!(route_path("/apiary/x"), None);
+        assert_eq!(route_path("/healthz"), None);
+    }
+}
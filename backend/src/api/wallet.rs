@@ -1,20 +1,25 @@
 #![allow(unused_imports, unused_variables, unused_assignments, dead_code, unused_features)]
 use axum::{
-    extract::Json,
-    response::IntoResponse,
-    http::StatusCode,
+    extract::{Json, Path, Query},
+    response::{IntoResponse, Response},
+    http::{HeaderMap, StatusCode},
 };
-use crate::models::wallet::{SendRequest, SendOnchainRequest, EstimateFeeDetailedRequest};
+use crate::api::error::ApiError;
+use crate::api::validation::Valid;
+use std::future::Future;
+use crate::models::wallet::{BalanceQuery, BalanceSnapshot, SendRequest, SendOnchainRequest, WithdrawOnchainRequest, EstimateFeeDetailedRequest, PsbtCreateRequest, PsbtRequest, ConsolidateRequest, OnchainAddressQuery};
 use crate::services::wallet;
+use crate::services::idempotency::{self, IdempotencyState};
+use crate::services::approvals::{self, HeldSend};
+use crate::services::rates;
+use crate::services::spending_policy::PolicyViolation;
 
 pub async fn get_info() -> impl IntoResponse {
     match wallet::get_wallet_info().await {
         Ok(info) => (StatusCode::OK, Json(info)).into_response(),
         Err(e) => {
             tracing::error!("Error getting wallet info: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
@@ -25,23 +30,93 @@ pub async fn get_address() -> impl IntoResponse {
         Ok(address) => (StatusCode::OK, Json(address)).into_response(),
         Err(e) => {
             tracing::error!("Error getting address: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
 
-pub async fn send_vtxo(Json(request): Json<SendRequest>) -> impl IntoResponse {
-    match wallet::send_vtxo(request.address, request.amount).await {
-        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+pub async fn send_vtxo(headers: HeaderMap, Valid(request): Valid<SendRequest>) -> impl IntoResponse {
+    idempotent(&headers, "send", &request, async {
+        let (address, amount) = match crate::services::payments::resolve_recipient(
+            &request.address,
+            request.amount,
+            request.uri.as_deref(),
+            request.contact_id,
+            true,
+        ) {
+            Ok(recipient) => recipient,
+            Err(e) => {
+                return ApiError::BadRequest(e.to_string()).into_parts();
+            }
+        };
+        if let Some(held) = held_for_approval(HeldSend::Ark, &address, amount) {
+            return held;
+        }
+
+        match wallet::send_vtxo(address, amount).await {
+            Ok(response) => (StatusCode::OK, serde_json::json!(response)),
+            Err(e) => {
+                tracing::error!("Error sending VTXO: {}", e);
+                ApiError::from(e).into_parts()
+            }
+        }
+    }).await
+}
+
+// a send above the approval threshold is recorded and answered with 202 instead of sent
+pub(crate) fn held_for_approval(send: HeldSend, address: &str, amount: u64) -> Option<(StatusCode, serde_json::Value)> {
+    match approvals::hold(send, address, amount) {
+        Ok(Some(approval)) => Some((StatusCode::ACCEPTED, serde_json::json!(approval))),
+        Ok(None) => None,
+        Err(e) => {
+            tracing::error!("Error holding send for approval: {}", e);
+            Some(ApiError::from(e).into_parts())
+        }
+    }
+}
+
+// runs a send at most once per Idempotency-Key, replaying the stored response on retries.
+// failures worth retrying aren't stored
+async fn idempotent<F>(
+    headers: &HeaderMap,
+    endpoint: &str,
+    request: &impl serde::Serialize,
+    send: F,
+) -> Response
+where
+    F: Future<Output = (StatusCode, serde_json::Value)>,
+{
+    let key = headers.get("Idempotency-Key").and_then(|v| v.to_str().ok()).map(str::to_string);
+    let Some(key) = key else {
+        let (status, body) = send.await;
+        return (status, Json(body)).into_response();
+    };
+
+    let reservation = match idempotency::begin(&key, endpoint, request) {
+        Ok(IdempotencyState::New(reservation)) => reservation,
+        Ok(IdempotencyState::Replay { status, body }) => {
+            tracing::info!("Replaying stored response for idempotency key {}", key);
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+            return (status, [("Idempotent-Replayed", "true")], Json(body)).into_response();
+        },
+        Ok(IdempotencyState::InProgress) => {
+            return ApiError::Conflict("A request with this Idempotency-Key is still being processed".to_string()).into_response();
+        },
+        Ok(IdempotencyState::Mismatch) => {
+            return ApiError::field("Idempotency-Key", "was already used for a different request").into_response();
+        },
         Err(e) => {
-            tracing::error!("Error sending VTXO: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            tracing::error!("Error checking idempotency key {}: {}", key, e);
+            return ApiError::from(e).into_response();
         }
+    };
+
+    // dropping the reservation, when this future is cancelled, releases the key
+    let (status, body) = send.await;
+    if let Err(e) = reservation.finish(status.as_u16(), &body) {
+        tracing::error!("Failed to store response for idempotency key {}: {}", key, e);
     }
+    (status, Json(body)).into_response()
 }
 
 pub async fn get_available_balance() -> impl IntoResponse {
@@ -51,9 +126,7 @@ pub async fn get_available_balance() -> impl IntoResponse {
         }))).into_response(),
         Err(e) => {
             tracing::error!("Error getting available balance: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
@@ -63,49 +136,90 @@ pub async fn check_deposits() -> impl IntoResponse {
         Ok(response) => (StatusCode::OK, Json(response)).into_response(),
         Err(e) => {
             tracing::error!("Error checking deposits: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn get_boarding_outputs() -> impl IntoResponse {
+    match wallet::list_boarding_outputs().await {
+        Ok(outputs) => (StatusCode::OK, Json(outputs)).into_response(),
+        Err(e) => {
+            tracing::error!("Error listing boarding outputs: {}", e);
+            ApiError::from(e).into_response()
         }
     }
 }
 
-pub async fn receive_vtxo(Json(request): Json<crate::models::wallet::ReceiveRequest>) -> impl IntoResponse {
+pub async fn receive_vtxo(Valid(request): Valid<crate::models::wallet::ReceiveRequest>) -> impl IntoResponse {
     match wallet::receive_vtxo(request.from_address, request.amount).await {
         Ok(response) => (StatusCode::OK, Json(response)).into_response(),
         Err(e) => {
             tracing::error!("Error receiving VTXO: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
 
 
-pub async fn get_balance() -> impl IntoResponse {
+// ?cached=true answers from the stored snapshot right away and refreshes it in the background
+pub async fn get_balance(Query(query): Query<BalanceQuery>) -> impl IntoResponse {
+    if query.cached {
+        match crate::services::APP_STATE.cached_balance() {
+            Ok(Some(snapshot)) => {
+                wallet::refresh_balance_in_background();
+                return balance_response(snapshot, query.fiat.as_deref());
+            },
+            Ok(None) => {},
+            Err(e) => tracing::warn!("Failed to read cached balance: {}", e),
+        }
+    }
+
     match crate::services::APP_STATE.recalculate_balance().await {
         Ok(_) => {
             let balance = crate::services::APP_STATE.balance.lock().await.clone();
-            (StatusCode::OK, Json(balance)).into_response()
+            balance_response(BalanceSnapshot {
+                balance,
+                last_updated: chrono::Utc::now().timestamp(),
+            }, query.fiat.as_deref())
         },
         Err(e) => {
             tracing::error!("Error recalculating balance: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
 
+// ?fiat=USD adds the amounts converted at the cached exchange rate
+fn balance_response(snapshot: BalanceSnapshot, fiat: Option<&str>) -> Response {
+    let Some(currency) = fiat else {
+        return (StatusCode::OK, Json(snapshot)).into_response();
+    };
+    let balance = &snapshot.balance;
+    let total = match crate::services::APP_STATE.rates.convert(balance.total as i64, currency) {
+        Ok(total) => total,
+        Err(e) => return ApiError::BadRequest(e.to_string()).into_response(),
+    };
+    let pending = (balance.trusted_pending + balance.untrusted_pending) as i64;
+
+    let mut body = serde_json::json!(snapshot);
+    body["fiat"] = serde_json::json!({
+        "currency": total.currency,
+        "rate": total.rate,
+        "rate_updated_at": total.rate_updated_at,
+        "confirmed": rates::to_fiat(balance.confirmed as i64, total.rate),
+        "pending": rates::to_fiat(pending, total.rate),
+        "total": total.value,
+    });
+    (StatusCode::OK, Json(body)).into_response()
+}
+
 pub async fn debug_vtxos() -> impl IntoResponse {
     match wallet::debug_vtxos().await {
         Ok(result) => (StatusCode::OK, Json(result)).into_response(),
         Err(e) => {
             tracing::error!("Error debugging VTXOs: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
@@ -115,23 +229,25 @@ pub async fn get_boarding_address() -> impl IntoResponse {
         Ok(address) => (StatusCode::OK, Json(address)).into_response(),
         Err(e) => {
             tracing::error!("Error getting boarding address: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
 
-pub async fn get_onchain_address() -> impl IntoResponse {
-    match wallet::get_onchain_address().await {
+// ?type=p2tr returns the taproot address, P2WPKH otherwise
+pub async fn get_onchain_address(Query(query): Query<OnchainAddressQuery>) -> impl IntoResponse {
+    let address = match query.address_type.as_deref() {
+        Some("p2tr") | Some("taproot") => wallet::get_taproot_address().await,
+        _ => wallet::get_onchain_address().await,
+    };
+
+    match address {
         Ok(address) => (StatusCode::OK, Json(serde_json::json!({
             "address": address
         }))).into_response(),
         Err(e) => {
             tracing::error!("Error getting onchain address: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
@@ -143,9 +259,7 @@ pub async fn get_onchain_balance() -> impl IntoResponse {
         }))).into_response(),
         Err(e) => {
             tracing::error!("Error getting on-chain balance: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
@@ -155,43 +269,233 @@ pub async fn get_fee_estimates_detailed() -> impl IntoResponse {
         Ok(estimates) => (StatusCode::OK, Json(estimates)).into_response(),
         Err(e) => {
             tracing::error!("Error getting fee estimates: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
 
 pub async fn estimate_transaction_fees(
-    Json(request): Json<EstimateFeeDetailedRequest>
+    Valid(request): Valid<EstimateFeeDetailedRequest>
 ) -> impl IntoResponse {
     match wallet::estimate_onchain_fee_detailed(request.address, request.amount).await {
         Ok(response) => (StatusCode::OK, Json(response)).into_response(),
         Err(e) => {
             tracing::error!("Error estimating transaction fees: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::from(e).into_response()
         }
     }
 }
 
 pub async fn send_onchain_with_priority(
-    Json(request): Json<SendOnchainRequest>
+    headers: HeaderMap,
+    Valid(request): Valid<SendOnchainRequest>
 ) -> impl IntoResponse {
-    let priority = request.priority.unwrap_or_else(|| "normal".to_string());
-    
-    match wallet::send_onchain_payment_with_fee_priority(
-        request.address,
+    idempotent(&headers, "send-onchain", &request, async {
+        let priority = request.priority.clone().unwrap_or_else(|| "normal".to_string());
+
+        let (address, amount) = match crate::services::payments::resolve_recipient(
+            &request.address,
+            request.amount,
+            request.uri.as_deref(),
+            request.contact_id,
+            false,
+        ) {
+            Ok(recipient) => recipient,
+            Err(e) => {
+                return ApiError::BadRequest(e.to_string()).into_parts();
+            }
+        };
+        let send = HeldSend::Onchain {
+            priority: priority.clone(),
+            fee_rate_sat_vb: request.fee_rate_sat_vb,
+        };
+        if let Some(held) = held_for_approval(send, &address, amount) {
+            return held;
+        }
+
+        match wallet::send_onchain_payment_with_fee_priority(
+            address,
+            amount,
+            priority.into(),
+            request.fee_rate_sat_vb,
+        ).await {
+            Ok(response) => (StatusCode::OK, serde_json::json!(response)),
+            Err(e) => {
+                tracing::error!("Error sending payment: {}", e);
+                ApiError::from(e).into_parts()
+            }
+        }
+    }).await
+}
+
+pub async fn withdraw_onchain(
+    headers: HeaderMap,
+    Valid(request): Valid<WithdrawOnchainRequest>
+) -> impl IntoResponse {
+    let (address, amount) = match crate::services::payments::resolve_recipient(
+        &request.address,
         request.amount,
-        priority.into()
-    ).await {
+        request.uri.as_deref(),
+        request.contact_id,
+        false,
+    ) {
+        Ok(recipient) => recipient,
+        Err(e) => {
+            return ApiError::BadRequest(e.to_string()).into_response();
+        }
+    };
+
+    if request.dry_run {
+        return match wallet::preview_onchain_withdrawal(&address, amount).await {
+            Ok(preview) => (StatusCode::OK, Json(preview)).into_response(),
+            Err(e) => ApiError::BadRequest(e.to_string()).into_response(),
+        };
+    }
+
+    idempotent(&headers, "withdraw-onchain", &request, async {
+        if let Some(held) = held_for_approval(HeldSend::Withdraw, &address, amount) {
+            return held;
+        }
+
+        match wallet::withdraw_onchain(address, amount).await {
+            Ok(response) => (StatusCode::OK, serde_json::json!(response)),
+            Err(e) => {
+                tracing::error!("Error withdrawing on-chain: {}", e);
+                ApiError::from(e).into_parts()
+            }
+        }
+    }).await
+}
+
+pub async fn get_withdrawal_status(Path(txid): Path<String>) -> impl IntoResponse {
+    match wallet::withdrawal_status(&txid).await {
+        Ok(status) => (StatusCode::OK, Json(status)).into_response(),
+        Err(e) => {
+            tracing::error!("Error checking withdrawal {}: {}", txid, e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn consolidate_utxos(Json(request): Json<ConsolidateRequest>) -> impl IntoResponse {
+    match wallet::consolidate_utxos(request).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(e) => {
+            tracing::error!("Error consolidating UTXOs: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn create_psbt(Valid(request): Valid<PsbtCreateRequest>) -> impl IntoResponse {
+    let priority = request.priority.unwrap_or_else(|| "normal".to_string());
+    match wallet::create_psbt(request.address, request.amount, priority.into()).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(e) => {
+            tracing::error!("Error creating PSBT: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn sign_psbt(Json(request): Json<PsbtRequest>) -> impl IntoResponse {
+    match wallet::sign_psbt(request.psbt).await {
         Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(e) if e.is::<PolicyViolation>() => ApiError::from(e).into_response(),
         Err(e) => {
-            tracing::error!("Error sending payment: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            tracing::error!("Error signing PSBT: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
         }
     }
-}
\ No newline at end of file
+}
+
+pub async fn broadcast_psbt(Json(request): Json<PsbtRequest>) -> impl IntoResponse {
+    let outgoing = match wallet::psbt_outgoing(&request.psbt) {
+        Ok(outgoing) => outgoing,
+        Err(e) => return ApiError::BadRequest(e.to_string()).into_response(),
+    };
+    let amount = outgoing.iter().map(|(_, amount)| amount).sum();
+    let address = outgoing.first().map(|(destination, _)| destination.as_str()).unwrap_or_default();
+    let send = HeldSend::Psbt { psbt: request.psbt.clone() };
+    if let Some((status, body)) = held_for_approval(send, address, amount) {
+        return (status, Json(body)).into_response();
+    }
+
+    match wallet::broadcast_psbt(request.psbt).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(e) if e.is::<PolicyViolation>() => ApiError::from(e).into_response(),
+        Err(e) => {
+            tracing::error!("Error broadcasting PSBT: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+// operator parameters of the connected ASP, fetched on demand if nothing is cached yet.
+// served at /wallet/server-info and /ark/server-info
+pub async fn get_server_info() -> impl IntoResponse {
+    let app_state = &crate::services::APP_STATE;
+    let cached = match app_state.server_info.get() {
+        Some(cached) => cached,
+        None => match app_state.server_info.refresh(&app_state.config.profile.ark_server_url).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                tracing::error!("Error fetching ASP server info: {}", e);
+                return ApiError::Unavailable(e.to_string()).into_response();
+            }
+        },
+    };
+
+    // lets the frontend warn when the ASP runs on a different network than the wallet
+    let expected_network = app_state.config.profile.network.to_string();
+    let network_matches = cached.info.network == expected_network;
+
+    // the ASP publishes no fee schedule at the pinned ark-rs version, these are the on-chain costs
+    // of boarding and exiting at today's rate. null when no fee source answers
+    let fee_rate = crate::services::onchain::fee_estimator::FeeEstimator::new(app_state.blockchain.clone())
+        .estimate_fee_rate().await
+        .map_err(|e| tracing::warn!("Failed to estimate fee rate for server info: {}", e))
+        .ok();
+    let claim_fee = crate::services::exit::claim_fee().await.ok();
+
+    let mut body = serde_json::json!(cached);
+    body["server_url"] = serde_json::json!(app_state.config.profile.ark_server_url);
+    body["expected_network"] = serde_json::json!(expected_network);
+    body["network_matches"] = serde_json::json!(network_matches);
+    body["fees"] = serde_json::json!({
+        "fee_rate_sat_vb": fee_rate.map(|rate| rate.to_sat_per_vb_ceil()),
+        "exit_claim_fee": claim_fee.map(|fee| fee.to_sat()),
+        "dust": cached.info.dust,
+    });
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+pub async fn rescan_addresses() -> impl IntoResponse {
+    match wallet::rescan_addresses().await {
+        Ok(summary) => (StatusCode::OK, Json(summary)).into_response(),
+        Err(e) => {
+            tracing::error!("Error rescanning addresses: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn recover_offchain() -> impl IntoResponse {
+    match wallet::recover_offchain().await {
+        Ok(summary) => (StatusCode::OK, Json(summary)).into_response(),
+        Err(e) => {
+            tracing::error!("Error recovering off-chain state: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+pub async fn get_script_descriptors() -> impl IntoResponse {
+    match wallet::get_script_descriptors().await {
+        Ok(descriptors) => (StatusCode::OK, Json(descriptors)).into_response(),
+        Err(e) => {
+            tracing::error!("Error exporting script descriptors: {}", e);
+            ApiError::from(e).into_response()
+        }
+    }
+}
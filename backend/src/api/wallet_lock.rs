@@ -0,0 +1,67 @@
+use axum::{
+    extract::{Json, Request},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use crate::api::error::ApiError;
+use crate::api::versioning;
+use crate::models::wallet::{LockPassphraseRequest, UnlockRequest};
+use crate::services::APP_STATE;
+
+// POST routes that don't touch funds, still available while locked, below the API root
+const READ_ONLY_POSTS: &[&str] = &[
+    "/wallet/unlock",
+    "/wallet/lock",
+    "/payments/parse-uri",
+    "/decode-invoice",
+    "/wallet/estimate-transaction-fees",
+];
+
+// rejects every write while the wallet is locked, and counts writes as activity for the idle timeout
+pub async fn require_unlocked(request: Request, next: Next) -> Response {
+    let read_only = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        || versioning::route_path(request.uri().path()).is_some_and(|path| READ_ONLY_POSTS.contains(&path));
+    if read_only {
+        return next.run(request).await;
+    }
+
+    let lock = &APP_STATE.wallet_lock;
+    if lock.is_locked() {
+        return ApiError::Locked("Wallet is locked, unlock it with POST /api/v1/wallet/unlock".to_string()).into_response();
+    }
+    lock.touch();
+    next.run(request).await
+}
+
+pub async fn get_status() -> impl IntoResponse {
+    (StatusCode::OK, Json(APP_STATE.wallet_lock.status())).into_response()
+}
+
+pub async fn unlock(Json(request): Json<UnlockRequest>) -> impl IntoResponse {
+    match APP_STATE.wallet_lock.unlock(&request.passphrase).await {
+        Ok(true) => (StatusCode::OK, Json(APP_STATE.wallet_lock.status())).into_response(),
+        Ok(false) => ApiError::Unauthorized("Wrong passphrase".to_string()).into_response(),
+        Err(e) => {
+            tracing::error!("Error unlocking wallet: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
+
+pub async fn lock() -> impl IntoResponse {
+    match APP_STATE.wallet_lock.lock().await {
+        Ok(()) => (StatusCode::OK, Json(APP_STATE.wallet_lock.status())).into_response(),
+        Err(e) => ApiError::BadRequest(e.to_string()).into_response(),
+    }
+}
+
+pub async fn set_passphrase(Json(request): Json<LockPassphraseRequest>) -> impl IntoResponse {
+    match APP_STATE.wallet_lock.set_passphrase(request.current_passphrase.as_deref(), &request.passphrase) {
+        Ok(()) => (StatusCode::OK, Json(APP_STATE.wallet_lock.status())).into_response(),
+        Err(e) => {
+            tracing::error!("Error setting lock passphrase: {}", e);
+            ApiError::BadRequest(e.to_string()).into_response()
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use axum::{
+    extract::{Json, Path},
+    response::IntoResponse,
+    http::StatusCode,
+};
+use crate::api::error::ApiError;
+use crate::api::validation::Valid;
+use crate::models::wallet::WatchAddressRequest;
+use crate::services::watched_addresses;
+
+pub async fn list_watched() -> impl IntoResponse {
+    match watched_addresses::list() {
+        Ok(addresses) => (StatusCode::OK, Json(addresses)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn watch_address(Valid(request): Valid<WatchAddressRequest>) -> impl IntoResponse {
+    match watched_addresses::watch(request) {
+        Ok(watched) => (StatusCode::CREATED, Json(watched)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn unwatch_address(Path(address): Path<String>) -> impl IntoResponse {
+    match watched_addresses::unwatch(&address) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+pub async fn get_history(Path(address): Path<String>) -> impl IntoResponse {
+    match watched_addresses::history(&address) {
+        Ok(txs) => (StatusCode::OK, Json(txs)).into_response(),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
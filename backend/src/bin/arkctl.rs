@@ -0,0 +1,214 @@
+// admin CLI for headless servers, talks to the running backend over HTTP
+use anyhow::{anyhow, bail, Context, Result};
+use ark_web_backend::storage::KeyManager;
+use bitcoin::base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use reqwest::Method;
+use serde_json::{json, Value};
+use std::io::BufRead;
+
+const USAGE: &str = "usage: arkctl [--url URL] <command>
+
+commands:
+  status                     wallet info and balance
+  round                      join the next round now
+  exits                      list unilateral exits in progress
+  exit-all [--yes]           show the plan for an emergency exit of every VTXO, --yes starts it
+  claim-exit <vtxo_txid>     sweep a matured exit
+  monitor <start|stop>       start or stop the background monitor, stop sends APPROVER_TOKEN when set
+  lock                       lock the wallet
+  unlock                     unlock it, reads the passphrase from ARKCTL_PASSPHRASE or stdin
+  backup [--out FILE]        write an encrypted backup to FILE (default ark-backup-<UTC time>.bak),
+                             same passphrase handling as unlock, needs APPROVER_TOKEN
+  approvals [all]            list pending approvals, or every one with all
+  approve <id>               send a held payment, needs APPROVER_TOKEN
+  reject <id> [reason]       drop a held payment, needs APPROVER_TOKEN
+  export-mnemonic [--data-dir DIR]
+                             print the wallet mnemonic, read from disk so the backend can be stopped,
+                             an encrypted one needs the lock passphrase like unlock
+
+the API defaults to ARKCTL_URL, then http://localhost:$PORT";
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+
+    if let Err(e) = run(std::env::args().skip(1).collect()).await {
+        eprintln!("arkctl: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(mut args: Vec<String>) -> Result<()> {
+    let url = match take_option(&mut args, "--url")? {
+        Some(url) => url,
+        None => std::env::var("ARKCTL_URL").unwrap_or_else(|_| {
+            format!("http://localhost:{}", std::env::var("PORT").unwrap_or_else(|_| "3000".to_string()))
+        }),
+    };
+    let api = Api::new(url.trim_end_matches('/'));
+
+    let command = args.first().cloned().unwrap_or_default();
+    let rest = args.get(1..).unwrap_or_default();
+    let result = match (command.as_str(), rest) {
+        ("status", []) => json!({
+            "info": api.call(Method::GET, "/api/v1/wallet/info", None).await?,
+            "balance": api.call(Method::GET, "/api/v1/wallet/balance", None).await?,
+        }),
+        ("round", []) => api.call(Method::POST, "/api/v1/round/participate", None).await?,
+        ("exits", []) => api.call(Method::GET, "/api/v1/exits", None).await?,
+        // exiting can't be undone, so without --yes this only shows what would be exited
+        ("exit-all", [flag]) if flag == "--yes" => api.call(Method::POST, "/api/v1/exits/emergency", None).await?,
+        ("exit-all", []) | ("exit-all", [_]) if rest.iter().all(|flag| flag == "--dry-run") => {
+            let plan = api.call(Method::POST, "/api/v1/exits/emergency?dry_run=true", None).await?;
+            println!("{}", serde_json::to_string_pretty(&plan)?);
+            eprintln!("Nothing was broadcast, run `arkctl exit-all --yes` to exit these VTXOs");
+            return Ok(());
+        },
+        ("claim-exit", [txid]) => api.call(Method::POST, &format!("/api/v1/exits/{}/claim", txid), None).await?,
+        ("monitor", [action]) if action == "start" || action == "stop" => {
+            api.with_approver_token_if_set()
+                .call(Method::POST, &format!("/api/v1/monitor/{}", action), None).await?
+        },
+        ("lock", []) => api.call(Method::POST, "/api/v1/wallet/lock", None).await?,
+        ("unlock", []) => {
+            let body = json!({ "passphrase": passphrase()? });
+            api.call(Method::POST, "/api/v1/wallet/unlock", Some(body)).await?
+        },
+        ("backup", _) => {
+            let mut rest = rest.to_vec();
+            let path = take_option(&mut rest, "--out")?.unwrap_or_else(|| {
+                format!("ark-backup-{}.bak", chrono::Utc::now().format("%Y%m%dT%H%M%SZ"))
+            });
+            if !rest.is_empty() {
+                bail!("{}", USAGE);
+            }
+            let body = json!({ "passphrase": passphrase()? });
+            let backup = api.with_approver_token()?
+                .call(Method::POST, "/api/v1/backup", Some(body)).await?;
+            let archive = backup.get("archive").and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("The backend returned no archive"))?;
+            let archive = BASE64.decode(archive).context("The backend returned an unreadable archive")?;
+            std::fs::write(&path, &archive).with_context(|| format!("Failed to write {}", path))?;
+            json!({ "file": path, "size": archive.len(), "created_at": backup.get("created_at") })
+        },
+        ("approvals", []) => api.call(Method::GET, "/api/v1/wallet/pending-approvals", None).await?,
+        ("approvals", [all]) if all == "all" => {
+            api.call(Method::GET, "/api/v1/wallet/pending-approvals?status=all", None).await?
+        },
+        ("approve", [id]) => {
+            api.with_approver_token()?
+                .call(Method::POST, &format!("/api/v1/wallet/pending-approvals/{}/approve", id), None).await?
+        },
+        ("reject", [id, reason @ ..]) => {
+            let body = (!reason.is_empty()).then(|| json!({ "reason": reason.join(" ") }));
+            api.with_approver_token()?
+                .call(Method::POST, &format!("/api/v1/wallet/pending-approvals/{}/reject", id), body).await?
+        },
+        ("export-mnemonic", _) => {
+            let mut rest = rest.to_vec();
+            let data_dir = take_option(&mut rest, "--data-dir")?
+                .or_else(|| std::env::var("DATA_DIR").ok())
+                .unwrap_or_else(|| "./data".to_string());
+            if !rest.is_empty() {
+                bail!("{}", USAGE);
+            }
+            return export_mnemonic(&data_dir);
+        },
+        ("help" | "--help" | "-h", _) => {
+            println!("{}", USAGE);
+            return Ok(());
+        },
+        _ => bail!("{}", USAGE),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
+
+struct Api {
+    base_url: String,
+    http_client: reqwest::Client,
+    bearer: Option<String>,
+}
+
+impl Api {
+    fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            http_client: reqwest::Client::new(),
+            bearer: None,
+        }
+    }
+
+    fn with_approver_token(mut self) -> Result<Self> {
+        self.bearer = Some(std::env::var("APPROVER_TOKEN").context("APPROVER_TOKEN is not set")?);
+        Ok(self)
+    }
+
+    // for routes that only check the token once the backend has one configured
+    fn with_approver_token_if_set(mut self) -> Self {
+        self.bearer = std::env::var("APPROVER_TOKEN").ok();
+        self
+    }
+
+    // the parsed JSON body, error responses become the error with their message
+    async fn call(&self, method: Method, path: &str, body: Option<Value>) -> Result<Value> {
+        let mut request = self.http_client.request(method, format!("{}{}", self.base_url, path));
+        if let Some(token) = &self.bearer {
+            request = request.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await
+            .with_context(|| format!("Failed to reach the backend at {}", self.base_url))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let message = body.get("error").and_then(Value::as_str).unwrap_or("no error message");
+            bail!("{} {}: {}", status.as_u16(), path, message);
+        }
+        Ok(body)
+    }
+}
+
+// opens the mnemonic with the backend's key manager, an encrypted one needs the lock passphrase.
+// the network only matters for deriving keys, which this doesn't do
+fn export_mnemonic(data_dir: &str) -> Result<()> {
+    let key_manager = KeyManager::new(data_dir, bitcoin::Network::Bitcoin);
+    if key_manager.is_encrypted() && !key_manager.unlock(&passphrase()?)? {
+        bail!("Wrong passphrase");
+    }
+    let phrase = key_manager.get_mnemonic()
+        .with_context(|| format!("Failed to read the mnemonic in {}", data_dir))?;
+    println!("{}", phrase.trim());
+    Ok(())
+}
+
+fn passphrase() -> Result<String> {
+    if let Ok(passphrase) = std::env::var("ARKCTL_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    eprint!("passphrase: ");
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    let passphrase = line.trim_end_matches(['\r', '\n']).to_string();
+    if passphrase.is_empty() {
+        return Err(anyhow!("No passphrase given"));
+    }
+    Ok(passphrase)
+}
+
+// removes `name value` from the arguments wherever it appears
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>> {
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        bail!("{} needs a value", name);
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
@@ -0,0 +1,2 @@
+// the storage layer as a library, so arkctl can read the data dir with the same code as the server
+pub mod storage;
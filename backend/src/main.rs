@@ -1,10 +1,11 @@
 mod api;
 mod services;
 mod models;
-mod storage;
+use ark_web_backend::storage;
 
 use axum::{
-    routing::{get, post},
+    http::{HeaderName, HeaderValue, Method},
+    routing::{delete, get, post, put},
     Router,
 };
 use std::net::SocketAddr;
@@ -30,9 +31,21 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
     
+    // load and validate config.toml + env overrides before anything reads it
+    let config = match services::config::init() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("Invalid configuration: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    tracing::info!(
+        "Using network profile '{}': network={}, ark={}, esplora={}",
+        config.profile.name, config.profile.network, config.profile.ark_server_url, config.profile.esplora_url
+    );
+
     // create data directory if it doesn't exist
-    let data_dir = std::env::var("DATA_DIR").unwrap_or_else(|_| "./data".to_string());
-    std::fs::create_dir_all(&data_dir).expect("Failed to create data directory");
+    std::fs::create_dir_all(&config.data_dir).expect("Failed to create data directory");
 
     // initialize Ark client
     match services::APP_STATE.initialize().await {
@@ -40,81 +53,308 @@ async fn main() {
         Err(e) => tracing::error!("Failed to initialize Ark client: {}", e),
     }
 
-    let app_state = services::APP_STATE.clone();
-    tokio::spawn(async move {
-        loop {
-            // Sync every 30 seconds
-            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-                
-            let grpc_client = app_state.grpc_client.lock().await;
-            if grpc_client.is_connected() {
-                match grpc_client.update_app_state().await {
-                    Ok(_) => tracing::debug!("Successfully synced app state with Ark client"),
-                    Err(e) => tracing::warn!("Failed to sync app state with Ark client: {}", e),
-                }
-            }
-        }
-    });
+    // reports misconfigured networks, data dir or database in the log instead of as empty balances
+    services::health::start_self_check();
 
-    // CORS layer
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // relay ASP round events to SSE/websocket subscribers
+    let ark_server_url = config.profile.ark_server_url.clone();
+    std::sync::Arc::new(services::rounds::RoundCoordinator::new(ark_server_url.clone())).start_event_loop();
 
+    // keep the cached ASP parameters fresh and warn when the operator changes them
+    services::APP_STATE.server_info.start_refresh_loop(ark_server_url, std::time::Duration::from_secs(600));
 
-    let app = Router::new()
+    if services::APP_STATE.faucet.is_public_mode() {
+        tracing::info!("Faucet running in public mode with request queue");
+        services::APP_STATE.faucet.start_queue_worker();
+    }
+
+    // brings failed Esplora servers back into rotation once they answer again
+    services::APP_STATE.blockchain.start_health_checks(std::time::Duration::from_secs(30));
+
+    // one shared fee estimate cache, refreshed in the background
+    services::onchain::FeeEstimator::start_refresher(
+        services::APP_STATE.blockchain.clone(),
+        std::time::Duration::from_secs(config.fee_refresh_interval_secs),
+    );
+
+    // periodic VTXO/history sync, deposit detection and VTXO renewal
+    services::APP_STATE.monitor.start();
+
+    // opt-in automatic round participation, disabled until configured
+    services::APP_STATE.round_scheduler.start();
+
+    // follows Lightning swaps until the provider settles or fails them
+    services::APP_STATE.swaps.start_status_poller(std::time::Duration::from_secs(15));
+
+    // marks payment requests paid as matching VTXOs arrive, expires the rest
+    services::APP_STATE.payment_requests.start_watcher();
+
+    // encrypted snapshots to backup.dir, only when configured
+    services::backup::start_scheduler(&config.backup);
+
+    // BTC prices for ?fiat= conversions, only when rates_url is set
+    services::APP_STATE.rates.start_refresher(std::time::Duration::from_secs(config.rates_refresh_secs));
+
+    // relocks the wallet after lock_idle_timeout_secs without a write request
+    services::APP_STATE.wallet_lock.start_auto_relock();
+
+    // claims unilateral exits, including ones started before a restart, once their timelock matures
+    services::APP_STATE.exits.start_watcher(std::time::Duration::from_secs(60));
+
+    // block height, hash and confirmation count of on-chain history entries
+    services::confirmations::start_tracker(std::time::Duration::from_secs(30));
+
+    // records unconfirmed payments to the wallet's addresses as pending deposits
+    services::deposit_watcher::start(std::time::Duration::from_secs(15));
+
+    // reports activity on addresses registered with POST /api/watch-addresses
+    services::watched_addresses::start_watcher(std::time::Duration::from_secs(30));
+
+    // finds addresses an imported seed used before, once per seed
+    services::onchain::keychain::start_discovery();
+
+    // CORS layer, the bundled frontend only unless cors.allowed_origins says otherwise
+    let cors = cors_layer(&config.cors);
+
+
+    let api_routes = Router::new()
         // wallet routes
-        .route("/api/wallet/info", get(api::wallet::get_info))
-        .route("/api/wallet/balance", get(api::wallet::get_balance))
-        .route("/api/wallet/address", get(api::wallet::get_address))
-        .route("/api/wallet/boarding-address", get(api::wallet::get_boarding_address))
-        .route("/api/wallet/onchain-address", get(api::wallet::get_onchain_address))
-        .route("/api/wallet/send", post(api::wallet::send_vtxo))
-        .route("/api/wallet/available-balance", get(api::wallet::get_available_balance))
-        // .route("/api/wallet/check-deposits", post(api::wallet::check_deposits))
-        .route("/api/wallet/receive", post(api::wallet::receive_vtxo))
+        .route("/wallet/info", get(api::wallet::get_info))
+        .route("/wallet/balance", get(api::wallet::get_balance))
+        .route("/wallet/address", get(api::wallet::get_address))
+        .route("/wallet/boarding-address", get(api::wallet::get_boarding_address))
+        .route("/wallet/onchain-address", get(api::wallet::get_onchain_address))
+        .route("/wallet/send", post(api::wallet::send_vtxo))
+        .route("/wallet/available-balance", get(api::wallet::get_available_balance))
+        .route("/wallet/board", post(api::wallet::check_deposits))
+        .route("/wallet/boarding-outputs", get(api::wallet::get_boarding_outputs))
+        .route("/wallet/receive", post(api::wallet::receive_vtxo))
+        .route("/wallet/scripts", get(api::wallet::get_script_descriptors))
+        .route("/wallet/rescan", post(api::wallet::rescan_addresses))
+        .route("/wallet/recover-offchain", post(api::wallet::recover_offchain))
+        .route("/wallet/server-info", get(api::wallet::get_server_info))
+        .route("/ark/server-info", get(api::wallet::get_server_info))
+        .route("/chain/status", get(api::chain::get_status))
+        .route("/tx/decode", post(api::chain::decode_transaction))
+        .route("/tx/broadcast", post(api::chain::broadcast_transaction))
+        .route("/rates", get(api::rates::get_rates))
+        .route("/wallet/payment-uri", get(api::payments::create_payment_uri))
+        .route("/wallet/ws", get(api::events::wallet_ws))
+
+        // wallet lock
+        .route("/wallet/lock", get(api::wallet_lock::get_status).post(api::wallet_lock::lock))
+        .route("/wallet/unlock", post(api::wallet_lock::unlock))
+        .route("/wallet/lock/passphrase", put(api::wallet_lock::set_passphrase))
+
+        // payment URIs
+        .route("/payments/parse-uri", post(api::payments::parse_payment_uri))
+        .route("/decode-invoice", post(api::payments::decode_invoice))
 
         // on-chain tx
-        .route("/api/wallet/onchain-balance", get(api::wallet::get_onchain_balance))
-        .route("/api/wallet/fee-estimates", get(api::wallet::get_fee_estimates_detailed))
-        .route("/api/wallet/estimate-transaction-fees", post(api::wallet::estimate_transaction_fees))
-        .route("/api/wallet/send-onchain", post(api::wallet::send_onchain_with_priority))
+        .route("/wallet/onchain-balance", get(api::wallet::get_onchain_balance))
+        .route("/wallet/fee-estimates", get(api::wallet::get_fee_estimates_detailed))
+        .route("/wallet/estimate-transaction-fees", post(api::wallet::estimate_transaction_fees))
+        .route("/wallet/send-onchain", post(api::wallet::send_onchain_with_priority))
+        .route("/wallet/withdraw-onchain", post(api::wallet::withdraw_onchain))
+        .route("/wallet/withdraw-onchain/:txid", get(api::wallet::get_withdrawal_status))
+        .route("/wallet/consolidate", post(api::wallet::consolidate_utxos))
+        .route("/wallet/psbt/create", post(api::wallet::create_psbt))
+        .route("/wallet/psbt/sign", post(api::wallet::sign_psbt))
+        .route("/wallet/psbt/broadcast", post(api::wallet::broadcast_psbt))
+
+        // sends held for approval
+        .route("/wallet/pending-approvals", get(api::approvals::list_approvals))
+        .route("/wallet/pending-approvals/:id", get(api::approvals::get_approval))
+        .route("/wallet/pending-approvals/:id/approve", post(api::approvals::approve))
+        .route("/wallet/pending-approvals/:id/reject", post(api::approvals::reject))
         
+        // 2-of-3 multisig on-chain wallet
+        .route("/multisig", get(api::multisig::get_info).post(api::multisig::setup))
+        .route("/multisig/address", get(api::multisig::get_address))
+        .route("/multisig/psbt/create", post(api::multisig::create_psbt))
+        .route("/multisig/psbt/sign", post(api::multisig::sign_psbt))
+        .route("/multisig/psbt/combine", post(api::multisig::combine_psbts))
+        .route("/multisig/psbt/broadcast", post(api::multisig::broadcast_psbt))
+
         // tx routes
-        .route("/api/transactions", get(api::transactions::get_history))
-        .route("/api/transactions/:txid", get(api::transactions::get_transaction))
+        .route("/transactions", get(api::transactions::get_history))
+        .route("/transactions/:txid", get(api::transactions::get_transaction))
+        .route("/transactions/:txid/bump", post(api::transactions::bump_fee))
+        .route("/transactions/:txid/cpfp", post(api::transactions::cpfp))
+        .route("/transactions/:txid/release", post(api::transactions::release_deposit))
         
         // round participation
-        .route("/api/round/participate", post(api::transactions::participate_in_round))
+        .route("/round/participate", post(api::transactions::participate_in_round))
+        .route("/round/events", get(api::events::round_events))
+        .route("/rounds", get(api::transactions::get_round_history))
+        .route("/round/schedule", get(api::transactions::get_round_schedule)
+            .put(api::transactions::update_round_schedule))
 
         // unilateral exit
-        .route("/api/transactions/exit", post(api::transactions::unilateral_exit))
+        .route("/transactions/exit", post(api::transactions::unilateral_exit))
+        .route("/exits", get(api::transactions::list_exits))
+        .route("/exits/emergency", post(api::transactions::emergency_exit_all))
+        .route("/exits/recommendations", get(api::transactions::get_exit_recommendations))
+        .route("/vtxos/sweep-expired", post(api::transactions::sweep_expired_vtxos))
+        .route("/vtxos/consolidation-preview", get(api::transactions::get_consolidation_preview))
+        .route("/exits/:vtxo_txid", get(api::transactions::get_exit))
+        .route("/exits/:vtxo_txid/claim", post(api::transactions::claim_exit))
+
+        // lightning swaps
+        .route("/swaps", get(api::swaps::list_swaps))
+        .route("/swaps/pay-invoice", post(api::swaps::pay_invoice))
+        .route("/swaps/:id", get(api::swaps::get_swap))
+
+        // payment requests
+        .route("/payment-requests", get(api::payments::list_payment_requests)
+            .post(api::payments::create_payment_request))
+        .route("/payment-requests/:id", get(api::payments::get_payment_request))
+
+        // backup and restore
+        .route("/backup", post(api::backup::create_backup))
+        .route("/backups", get(api::backup::list_backups))
+        .route("/restore", post(api::backup::restore_backup))
+
+        // address book
+        .route("/contacts", get(api::contacts::list_contacts).post(api::contacts::create_contact))
+        .route("/contacts/:id", get(api::contacts::get_contact)
+            .put(api::contacts::update_contact)
+            .delete(api::contacts::delete_contact))
+
+        // watched addresses
+        .route("/watch-addresses", get(api::watched_addresses::list_watched)
+            .post(api::watched_addresses::watch_address))
+        .route("/watch-addresses/:address", delete(api::watched_addresses::unwatch_address))
+        .route("/watch-addresses/:address/transactions", get(api::watched_addresses::get_history))
+
+        // background monitor
+        .route("/monitor/status", get(api::monitor::get_status))
+        .route("/monitor/start", post(api::monitor::start))
+        .route("/monitor/stop", post(api::monitor::stop))
+        .route("/settings/renewal-policy", get(api::monitor::get_renewal_policy)
+            .put(api::monitor::update_renewal_policy))
+        .route("/settings/spending-policy", get(api::monitor::get_spending_policy)
+            .put(api::monitor::update_spending_policy))
+
+        // faucet
+        .route("/faucet", post(api::faucet::request_funds))
+        .route("/faucet/queue", get(api::faucet::get_queue_status))
+        .route("/faucet/requests/:id", get(api::faucet::get_request_status))
+        .route("/faucet/balance", get(api::faucet::get_balance))
+        .route("/faucet/config", get(api::faucet::get_config)
+            .put(api::faucet::update_config))
+
+        // admin: settings that take effect without a restart, environment self-check
+        .route("/admin/settings", get(api::admin::get_settings)
+            .put(api::admin::update_settings))
+        .route("/admin/selfcheck", get(api::admin::self_check))
 
         // debug
-        .route("/api/debug/vtxos", get(api::wallet::debug_vtxos))
-        
-        // add middleware
+        .route("/debug/vtxos", get(api::wallet::debug_vtxos));
+
+    // regtest helpers for demos and integration tests, only mounted when asked for
+    let api_routes = if config.dev_routes {
+        tracing::warn!("Dev routes enabled");
+        api_routes.route("/dev/mine", post(api::dev::mine))
+    } else {
+        api_routes
+    };
+
+    // /api/v1 is the current version, the unversioned /api paths stay as deprecated aliases
+    let app = Router::new()
+        // liveness and readiness probes
+        .route("/healthz", get(api::health::healthz))
+        .route("/readyz", get(api::health::readyz))
+        .nest("/api/v1", api_routes.clone().layer(axum::middleware::from_fn(api::versioning::current)))
+        .nest("/api", api_routes.layer(axum::middleware::from_fn(api::versioning::deprecated)));
+
+    // flag fake data so it can't be mistaken for real operations
+    let app = if config.simulation_mode || !config.strict_mode {
+        if config.simulation_mode {
+            tracing::warn!("Simulation mode enabled, Ark responses are fake");
+        } else {
+            tracing::warn!("Strict mode disabled, Ark operations return simulated data while the Ark server is unreachable");
+        }
+        app.layer(axum::middleware::from_fn(api::simulation::mark_simulated))
+    } else {
+        app
+    };
+
+    // add middleware
+    let app = app
+        .layer(axum::middleware::from_fn(api::wallet_lock::require_unlocked))
         .layer(TraceLayer::new_for_http())
         .layer(cors);
 
     // run the server
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "3000".to_string())
-        .parse::<u16>()
-        .expect("PORT must be a number");
-    
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+
+    // HTTPS when [tls] is configured, so the API can be exposed without a reverse proxy.
+    // tonic already links rustls with ring, name it as the provider so rustls doesn't have to pick one
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let tls = match services::tls::load(&config.tls, &config.data_dir).await {
+        Ok(tls) => tls,
+        Err(e) => {
+            tracing::error!("Invalid TLS setup: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(tls) = tls {
+        let handle = axum_server::Handle::new();
+        let shutdown = handle.clone();
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            shutdown.graceful_shutdown(Some(std::time::Duration::from_secs(10)));
+        });
+
+        tracing::info!("listening on https://{}", addr);
+
+        axum_server::bind_rustls(addr, tls)
+            .handle(handle)
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+            .unwrap();
+        return;
+    }
+
     let listener = TcpListener::bind(addr).await.unwrap();
     
     tracing::info!("listening on {}", addr);
     
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
 }
 
+// the lists are checked in Config::validate, "*" in a list allows anything
+fn cors_layer(config: &services::config::CorsConfig) -> CorsLayer {
+    let layer = CorsLayer::new().expose_headers(Any);
+
+    let layer = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        tracing::warn!("CORS allows any origin");
+        layer.allow_origin(Any)
+    } else {
+        layer.allow_origin(config.allowed_origins.iter()
+            .filter_map(|origin| HeaderValue::from_str(origin.trim_end_matches('/')).ok())
+            .collect::<Vec<_>>())
+    };
+    let layer = if config.allowed_methods.iter().any(|method| method == "*") {
+        layer.allow_methods(Any)
+    } else {
+        layer.allow_methods(config.allowed_methods.iter()
+            .filter_map(|method| Method::from_bytes(method.as_bytes()).ok())
+            .collect::<Vec<_>>())
+    };
+    if config.allowed_headers.iter().any(|header| header == "*") {
+        layer.allow_headers(Any)
+    } else {
+        layer.allow_headers(config.allowed_headers.iter()
+            .filter_map(|header| HeaderName::from_bytes(header.as_bytes()).ok())
+            .collect::<Vec<_>>())
+    }
+}
+
 async fn shutdown_signal() {
     tokio::signal::ctrl_c()
         .await
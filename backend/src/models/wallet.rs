@@ -6,10 +6,11 @@ use bitcoin::{opcodes::all, Amount};
 pub struct WalletInfo {
     pub network: String,
     pub server_url: String,
+    pub profile: String,
     pub connected: bool,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WalletBalance {
     pub confirmed: u64,
     pub trusted_pending: u64,
@@ -18,6 +19,22 @@ pub struct WalletBalance {
     pub total: u64,
 }
 
+// the balance as last stored, served by GET /api/wallet/balance?cached=true
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BalanceSnapshot {
+    #[serde(flatten)]
+    pub balance: WalletBalance,
+    #[serde(default)]
+    pub last_updated: i64, // snapshots written before this field read as 0
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceQuery {
+    #[serde(default)]
+    pub cached: bool,
+    pub fiat: Option<String>, // currency code, e.g. USD
+}
+
 #[derive(Debug, Serialize)]
 pub struct AddressResponse {
     pub address: String,
@@ -28,19 +45,237 @@ pub struct TransactionResponse {
     pub txid: String,
     pub amount: i64,
     pub timestamp: i64,
-    pub type_name: String,
+    #[serde(flatten)]
+    pub kind: TransactionKind,
+    pub direction: TransactionDirection,
+    pub counterparty_address: Option<String>, // who was paid, or paid us, when we know it
+    pub fee: Option<u64>, // sats paid by this wallet, None when unknown or paid by the sender
     pub is_settled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screening: Option<crate::services::screening::ScreeningResult>,
+    pub confirmations: Option<u32>, // on-chain txs only, 0 while in the mempool
+    pub block_height: Option<u32>,
+    pub block_hash: Option<String>,
+}
+
+// what a history entry is, with the details only that kind has. Tagged by type_name and flattened
+// into the entry, so clients still read {"type_name": "OnChain", ...}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type_name")]
+pub enum TransactionKind {
+    Boarding,
+    Round {
+        #[serde(default)]
+        round_id: Option<String>, // the ASP's id, for rounds joined through this backend
+    },
+    Redeem,
+    OnChain,
+    Exit {
+        vtxo_txid: String,
+        #[serde(default)]
+        claimable_height: Option<u32>, // block based exit delays
+        #[serde(default)]
+        claimable_at: Option<i64>, // time based exit delays
+        #[serde(default)]
+        claim_txid: Option<String>, // the sweep to our on-chain address, once claimed
+    },
+    Faucet,
+    Receive,
+}
+
+impl TransactionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionKind::Boarding => "Boarding",
+            TransactionKind::Round { .. } => "Round",
+            TransactionKind::Redeem => "Redeem",
+            TransactionKind::OnChain => "OnChain",
+            TransactionKind::Exit { .. } => "Exit",
+            TransactionKind::Faucet => "Faucet",
+            TransactionKind::Receive => "Receive",
+        }
+    }
+
+    // entries stored before kinds carried details only have their name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Boarding" => Some(TransactionKind::Boarding),
+            "Round" => Some(TransactionKind::Round { round_id: None }),
+            "Redeem" => Some(TransactionKind::Redeem),
+            "OnChain" => Some(TransactionKind::OnChain),
+            "Exit" => Some(TransactionKind::Exit { vtxo_txid: String::new(), claimable_height: None, claimable_at: None, claim_txid: None }),
+            "Faucet" => Some(TransactionKind::Faucet),
+            "Receive" => Some(TransactionKind::Receive),
+            _ => None,
+        }
+    }
+
+    // whether there is anything to store beyond the name
+    pub fn has_details(&self) -> bool {
+        match self {
+            TransactionKind::Round { round_id } => round_id.is_some(),
+            TransactionKind::Exit { .. } => true,
+            TransactionKind::Boarding
+            | TransactionKind::Redeem
+            | TransactionKind::OnChain
+            | TransactionKind::Faucet
+            | TransactionKind::Receive => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionDirection {
+    Incoming,
+    Outgoing,
+    Internal, // rounds and txs paying ourselves, only a fee leaves the wallet
+}
+
+impl TransactionDirection {
+    // what the amount says, senders override it for txs paying ourselves
+    pub fn of(amount: i64) -> Self {
+        match amount {
+            amount if amount > 0 => TransactionDirection::Incoming,
+            amount if amount < 0 => TransactionDirection::Outgoing,
+            _ => TransactionDirection::Internal,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionDirection::Incoming => "incoming",
+            TransactionDirection::Outgoing => "outgoing",
+            TransactionDirection::Internal => "internal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "incoming" => Some(TransactionDirection::Incoming),
+            "outgoing" => Some(TransactionDirection::Outgoing),
+            "internal" => Some(TransactionDirection::Internal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TransactionHistoryQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    #[serde(rename = "type")]
+    pub type_name: Option<String>, // "OnChain", "Boarding", "Round", "Redeem", ...
+    pub from: Option<i64>, // unix timestamp, inclusive
+    pub to: Option<i64>, // unix timestamp, inclusive
+    pub direction: Option<TransactionDirection>,
+    pub fiat: Option<String>, // adds each amount converted to this currency
+}
+
+impl TransactionResponse {
+    // everything else starts unset, callers fill in what they know with struct update syntax
+    pub fn new(txid: impl Into<String>, kind: TransactionKind, amount: i64, timestamp: i64) -> Self {
+        Self {
+            txid: txid.into(),
+            amount,
+            timestamp,
+            kind,
+            direction: TransactionDirection::of(amount),
+            counterparty_address: None,
+            fee: None,
+            is_settled: None,
+            screening: None,
+            confirmations: None,
+            block_height: None,
+            block_hash: None,
+        }
+    }
+
+    // quarantined, or still waiting to be screened
+    pub fn is_quarantined(&self) -> bool {
+        use crate::services::screening::ScreeningAction;
+        matches!(
+            &self.screening,
+            Some(result) if matches!(result.action, ScreeningAction::Quarantine | ScreeningAction::Pending)
+        )
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SendRequest {
+    #[serde(default)]
     pub address: String,
+    #[serde(default)]
     pub amount: u64,
+    pub uri: Option<String>, // BIP21 URI, used instead of address + amount
+    pub contact_id: Option<i64>, // saved contact, used instead of address
 }
 
 #[derive(Debug, Serialize)]
 pub struct SendResponse {
     pub txid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Vec<SpentVtxo>>, // Ark sends only, the VTXOs the send spent
+}
+
+// GET /api/transactions/:txid
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionDetail {
+    #[serde(flatten)]
+    pub transaction: TransactionResponse,
+    pub raw_hex: Option<String>, // None for off-chain txs and ones the chain backend doesn't know
+    pub decoded: Option<DecodedTransaction>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RawTransactionRequest {
+    pub hex: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedTransaction {
+    pub txid: String,
+    pub wtxid: String,
+    pub version: i32,
+    pub locktime: u32,
+    pub size: u64,
+    pub vsize: u64,
+    pub weight: u64,
+    pub fee: Option<u64>, // None when a spent output couldn't be looked up
+    pub fee_rate_sat_vb: Option<f64>,
+    pub inputs: Vec<DecodedInput>,
+    pub outputs: Vec<DecodedOutput>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedInput {
+    pub txid: String,
+    pub vout: u32,
+    pub sequence: u32,
+    pub witness_items: usize,
+    pub value: Option<u64>,
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedOutput {
+    pub vout: u32,
+    pub value: u64,
+    pub script_pubkey: String,
+    pub address: Option<String>, // None for OP_RETURN and other non-standard scripts
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpentVtxo {
+    pub outpoint: String,
+    pub amount: u64,
+    pub expire_at: i64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OnchainAddressQuery {
+    #[serde(rename = "type")]
+    pub address_type: Option<String>, // "p2wpkh" (default) or "p2tr"
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,11 +289,136 @@ pub struct ExitRequest {
     pub vtxo_txid: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
+pub struct MineQuery {
+    pub blocks: Option<u64>, // default 1
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EmergencyExitQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct SendOnchainRequest {
+    #[serde(default)]
     pub address: String,
+    #[serde(default)]
     pub amount: u64,
     pub priority: Option<String>, // "fastest", "fast", "normal", "slow"
+    pub fee_rate_sat_vb: Option<u64>, // explicit fee rate, takes precedence over priority
+    pub uri: Option<String>, // BIP21 URI, used instead of address + amount
+    pub contact_id: Option<i64>, // saved contact, used instead of address
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WithdrawOnchainRequest {
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub amount: u64,
+    pub uri: Option<String>, // BIP21 URI, used instead of address + amount
+    pub contact_id: Option<i64>, // saved contact, used instead of address
+    #[serde(default)]
+    pub dry_run: bool, // only return the preview
+}
+
+// what a collaborative redeem would do, compared with exiting unilaterally
+#[derive(Debug, Serialize)]
+pub struct WithdrawalPreview {
+    pub address: String,
+    pub amount: u64,
+    pub available: u64,
+    pub change: u64,   // stays off-chain as a new VTXO
+    pub fee: u64,      // round fee charged by the ASP, none at the pinned server version
+    pub unilateral_exit_fees: Option<u64>, // claim fees for exiting every VTXO instead
+    pub unilateral_exit_wait_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WithdrawalStatus {
+    pub txid: String,
+    pub confirmed: bool,
+    pub confirmations: u32,
+    pub confirmed_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PayInvoiceRequest {
+    pub invoice: String,
+    pub funding: Option<crate::services::swaps::SwapFunding>, // defaults to offchain
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BumpFeeRequest {
+    pub fee_rate_sat_vb: Option<u64>,
+    pub priority: Option<String>, // used when no explicit fee rate is given, defaults to "fastest"
+}
+
+#[derive(Debug, Serialize)]
+pub struct BumpFeeResponse {
+    pub txid: String,
+    pub replaces: String,
+    pub fee: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CpfpRequest {
+    pub target_blocks: Option<u32>, // confirmation target for parent and child together, defaults to 1
+}
+
+#[derive(Debug, Serialize)]
+pub struct CpfpResponse {
+    pub txid: String,
+    pub parent_txid: String,
+    pub fee: u64,
+    pub package_fee_rate: u64, // sat/vB
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ConsolidateRequest {
+    pub dust_threshold: Option<u64>, // UTXOs below this many sats cost more to spend than they're worth, default 546
+    pub max_inputs: Option<usize>,   // default 50
+    pub max_fee_rate_sat_vb: Option<u64>, // refuse while the slow fee rate is above this
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsolidateResponse {
+    pub txid: String,
+    pub inputs: usize,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PsbtCreateRequest {
+    pub address: String,
+    pub amount: u64,
+    pub priority: Option<String>, // "fastest", "fast", "normal", "slow"
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PsbtRequest {
+    pub psbt: String, // base64
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MultisigSetupRequest {
+    pub cosigners: Vec<String>, // two xpubs, optionally with key origin: "[fingerprint/48'/0'/0'/2']xpub..."
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MultisigCombineRequest {
+    pub psbts: Vec<String>, // base64, copies of the same PSBT signed by different cosigners
+}
+
+#[derive(Debug, Serialize)]
+pub struct PsbtResponse {
+    pub psbt: String, // base64
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+    pub finalized: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -79,4 +439,198 @@ pub struct TransactionFeeEstimate {
     pub blocks: String,
     pub fee_rate: u64,
     pub total_fee: u64,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize)]
+pub struct TapLeafInfo {
+    pub name: String,          // "exit" (CSV unilateral path) or "forfeit" (collaborative path)
+    pub script_hex: String,
+    pub script_asm: String,
+    pub control_block: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScriptDescriptor {
+    pub kind: String, // "boarding" or "vtxo"
+    pub outpoint: Option<String>,
+    pub address: String,
+    pub descriptor: String,
+    pub internal_key: String,
+    pub leaves: Vec<TapLeafInfo>,
+}
+
+// a deposit on a boarding address that hasn't been settled into a round yet
+#[derive(Debug, Serialize)]
+pub struct BoardingOutputInfo {
+    pub outpoint: String,
+    pub address: String,
+    pub amount: u64,
+    pub confirmations: u32,
+    pub confirmed_at: Option<i64>,
+    // the ASP stops accepting the deposit once its exit path opens
+    pub expires_at_height: Option<u32>, // block based boarding delay
+    pub expires_at: Option<i64>,        // time based delay, or estimated from the remaining blocks
+    pub seconds_until_expiry: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PaymentUriQuery {
+    pub amount: Option<u64>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentUriResponse {
+    pub uri: String,
+    pub payment: crate::services::payments::PaymentUri,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParsePaymentUriRequest {
+    pub uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DecodeInvoiceRequest {
+    pub invoice: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BackupRequest {
+    pub passphrase: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoreRequest {
+    pub passphrase: String,
+    pub archive: Option<String>, // base64, as returned by POST /api/backup
+    pub file: Option<String>,    // or the name of a scheduled backup from GET /api/backups
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalListQuery {
+    pub status: Option<String>, // defaults to pending, "all" lists every approval
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RejectApprovalRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnlockRequest {
+    pub passphrase: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockPassphraseRequest {
+    pub passphrase: String,
+    pub current_passphrase: Option<String>, // required once a passphrase is set
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePaymentRequestBody {
+    pub amount: u64,
+    pub memo: Option<String>,
+    pub expiry_secs: Option<u64>, // defaults to one hour
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FaucetRequestBody {
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactKind {
+    Ark,
+    Onchain,
+}
+
+impl ContactKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContactKind::Ark => "ark",
+            ContactKind::Onchain => "onchain",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Contact {
+    pub id: i64,
+    pub name: String,
+    pub address: String,
+    pub kind: ContactKind, // detected from the address when saved
+    pub created_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContactRequest {
+    pub name: String,
+    pub address: String,
+}
+
+// a round the wallet took part in, amounts in sats
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundRecord {
+    pub txid: String,
+    pub vtxos_renewed: u32,
+    pub boarding_inputs: u32,
+    pub outputs_created: u32,
+    pub input_amount: u64,
+    pub output_amount: u64,
+    pub fee: u64, // inputs minus the VTXOs the round created
+    pub started_at: i64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewVtxo {
+    pub outpoint: Option<String>,
+    pub amount: u64,
+    pub expire_at: Option<i64>, // boarding outputs don't expire
+}
+
+// the wallet's VTXOs now and after the next round merges them
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsolidationPreview {
+    pub before: Vec<PreviewVtxo>,
+    pub boarding_outputs: Vec<PreviewVtxo>, // confirmed deposits the round boards as well
+    pub after: Vec<PreviewVtxo>,
+    pub claim_fees_before: u64, // claiming every VTXO after a unilateral exit, at today's fee rate
+    pub claim_fees_after: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RoundHistoryQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchedAddress {
+    pub address: String,
+    pub label: Option<String>,
+    pub webhook_url: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WatchAddressRequest {
+    pub address: String,
+    pub label: Option<String>,
+    pub webhook_url: Option<String>, // POSTed every new or newly confirmed tx
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchedAddressTx {
+    pub address: String,
+    pub txid: String,
+    pub amount: i64, // received minus spent by the address
+    pub confirmed: bool,
+    pub block_height: Option<u32>,
+    pub seen_at: i64,
+}
@@ -0,0 +1,257 @@
+use anyhow::anyhow;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use crate::models::wallet::SendResponse;
+use crate::services::events::WalletEvent;
+use crate::services::swaps::SwapFunding;
+use crate::services::APP_STATE;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApprovalError {
+    #[error("Approval not found: {0}")]
+    NotFound(String),
+    #[error("Approval {0} is already {1}")]
+    NotPending(String, String),
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+impl From<rusqlite::Error> for ApprovalError {
+    fn from(e: rusqlite::Error) -> Self {
+        ApprovalError::Storage(e.into())
+    }
+}
+
+// the send to run once approved, with the options of the endpoint it came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeldSend {
+    Ark,
+    Onchain {
+        priority: String,
+        fee_rate_sat_vb: Option<u64>,
+    },
+    Withdraw,
+    // a Lightning payment, the address is the invoice
+    Swap {
+        funding: SwapFunding,
+    },
+    // an externally signed tx, the address is its first external destination
+    Psbt {
+        psbt: String,
+    },
+}
+
+impl HeldSend {
+    fn kind(&self) -> &'static str {
+        match self {
+            HeldSend::Ark => "ark",
+            HeldSend::Onchain { .. } => "onchain",
+            HeldSend::Withdraw => "withdraw",
+            HeldSend::Swap { .. } => "swap",
+            HeldSend::Psbt { .. } => "psbt",
+        }
+    }
+}
+
+// status goes pending -> approved -> sent | failed, or pending -> rejected
+#[derive(Debug, Clone, Serialize)]
+pub struct Approval {
+    pub id: String,
+    pub kind: String,
+    pub address: String,
+    pub amount: u64,
+    pub priority: Option<String>,
+    pub fee_rate_sat_vb: Option<u64>,
+    pub funding: Option<SwapFunding>,
+    pub psbt: Option<String>,
+    pub status: String,
+    pub txid: Option<String>,
+    pub error: Option<String>,
+    pub reason: Option<String>, // given by the approver when rejecting
+    pub created_at: i64,
+    pub decided_at: Option<i64>,
+}
+
+// a send above the threshold, recorded instead of sent, when approvals are configured
+pub fn hold(send: HeldSend, address: &str, amount: u64) -> Result<Option<Approval>, ApprovalError> {
+    let config = &APP_STATE.config.approvals;
+    match config.threshold_sats {
+        Some(threshold) if config.is_enabled() && amount > threshold => {},
+        _ => return Ok(None),
+    }
+
+    let (priority, fee_rate_sat_vb) = match &send {
+        HeldSend::Onchain { priority, fee_rate_sat_vb } => (Some(priority.clone()), *fee_rate_sat_vb),
+        _ => (None, None),
+    };
+    let funding = match &send {
+        HeldSend::Swap { funding } => Some(*funding),
+        _ => None,
+    };
+    let psbt = match &send {
+        HeldSend::Psbt { psbt } => Some(psbt.clone()),
+        _ => None,
+    };
+    let approval = Approval {
+        id: hex::encode(rand::random::<[u8; 16]>()),
+        kind: send.kind().to_string(),
+        address: address.to_string(),
+        amount,
+        priority,
+        fee_rate_sat_vb,
+        funding,
+        psbt,
+        status: "pending".to_string(),
+        txid: None,
+        error: None,
+        reason: None,
+        created_at: chrono::Utc::now().timestamp(),
+        decided_at: None,
+    };
+
+    let conn = APP_STATE.db_manager.get_conn()?;
+    conn.execute(
+        "INSERT INTO pending_approvals
+         (id, kind, address, amount, priority, fee_rate_sat_vb, funding, psbt, status, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            approval.id,
+            approval.kind,
+            approval.address,
+            approval.amount as i64,
+            approval.priority,
+            approval.fee_rate_sat_vb.map(|rate| rate as i64),
+            approval.funding.map(|funding| funding.as_str()),
+            approval.psbt,
+            approval.status,
+            approval.created_at,
+        ],
+    )?;
+
+    tracing::info!("Holding {} send of {} sats to {} for approval {}", approval.kind, amount, address, approval.id);
+    publish(&approval);
+    Ok(Some(approval))
+}
+
+pub fn list(status: Option<&str>) -> Result<Vec<Approval>, ApprovalError> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM pending_approvals WHERE (?1 IS NULL OR status = ?1) ORDER BY created_at DESC",
+        COLUMNS
+    ))?;
+    let approvals = stmt.query_map(params![status], row_to_approval)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(approvals)
+}
+
+pub fn get(id: &str) -> Result<Approval, ApprovalError> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    conn.query_row(
+        &format!("SELECT {} FROM pending_approvals WHERE id = ?", COLUMNS),
+        params![id],
+        row_to_approval,
+    ).optional()?
+        .ok_or_else(|| ApprovalError::NotFound(id.to_string()))
+}
+
+// runs the held send, the spending policy still applies at this point
+pub async fn approve(id: &str) -> Result<Approval, ApprovalError> {
+    let approval = claim(id, "approved", None)?;
+
+    let result: anyhow::Result<SendResponse> = match approval.kind.as_str() {
+        "ark" => crate::services::wallet::send_vtxo(approval.address.clone(), approval.amount).await,
+        "onchain" => crate::services::wallet::send_onchain_payment_with_fee_priority(
+            approval.address.clone(),
+            approval.amount,
+            approval.priority.clone().unwrap_or_else(|| "normal".to_string()).into(),
+            approval.fee_rate_sat_vb,
+        ).await,
+        "withdraw" => crate::services::wallet::withdraw_onchain(approval.address.clone(), approval.amount).await,
+        // the approval records the swap's lockup tx
+        "swap" => APP_STATE.swaps.pay_invoice(
+            &approval.address,
+            approval.funding.unwrap_or(SwapFunding::Offchain),
+        ).await.map(|swap| SendResponse { txid: swap.lockup_txid.unwrap_or(swap.id), inputs: None }),
+        "psbt" => match approval.psbt.clone() {
+            Some(psbt) => crate::services::wallet::broadcast_psbt(psbt).await,
+            None => Err(anyhow!("Approval {} has no PSBT", approval.id)),
+        },
+        other => Err(anyhow!("Unknown send kind {}", other)),
+    };
+
+    let conn = APP_STATE.db_manager.get_conn()?;
+    match result {
+        Ok(response) => {
+            tracing::info!("Approval {} sent as {}", id, response.txid);
+            conn.execute(
+                "UPDATE pending_approvals SET status = 'sent', txid = ? WHERE id = ?",
+                params![response.txid, id],
+            )?;
+        },
+        Err(e) => {
+            tracing::error!("Approved send {} failed: {}", id, e);
+            conn.execute(
+                "UPDATE pending_approvals SET status = 'failed', error = ? WHERE id = ?",
+                params![e.to_string(), id],
+            )?;
+        },
+    }
+    drop(conn);
+
+    let approval = get(id)?;
+    publish(&approval);
+    Ok(approval)
+}
+
+pub fn reject(id: &str, reason: Option<String>) -> Result<Approval, ApprovalError> {
+    let approval = claim(id, "rejected", reason)?;
+    tracing::info!("Approval {} rejected", id);
+    publish(&approval);
+    Ok(approval)
+}
+
+// moves a pending approval on, the status check makes a double approve a no-op
+fn claim(id: &str, status: &str, reason: Option<String>) -> Result<Approval, ApprovalError> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let updated = conn.execute(
+        "UPDATE pending_approvals SET status = ?, reason = ?, decided_at = ? WHERE id = ? AND status = 'pending'",
+        params![status, reason, chrono::Utc::now().timestamp(), id],
+    )?;
+    drop(conn);
+
+    let approval = get(id)?;
+    if updated == 0 {
+        return Err(ApprovalError::NotPending(id.to_string(), approval.status));
+    }
+    Ok(approval)
+}
+
+fn publish(approval: &Approval) {
+    APP_STATE.events.publish(WalletEvent::ApprovalUpdated {
+        id: approval.id.clone(),
+        status: approval.status.clone(),
+        amount: approval.amount,
+    });
+}
+
+const COLUMNS: &str = "id, kind, address, amount, priority, fee_rate_sat_vb, status, txid, error, reason, created_at, decided_at, funding, psbt";
+
+fn row_to_approval(row: &rusqlite::Row) -> rusqlite::Result<Approval> {
+    Ok(Approval {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        address: row.get(2)?,
+        amount: row.get::<_, i64>(3)? as u64,
+        priority: row.get(4)?,
+        fee_rate_sat_vb: row.get::<_, Option<i64>>(5)?.map(|rate| rate as u64),
+        status: row.get(6)?,
+        txid: row.get(7)?,
+        error: row.get(8)?,
+        reason: row.get(9)?,
+        created_at: row.get(10)?,
+        decided_at: row.get(11)?,
+        funding: row.get::<_, Option<String>>(12)?.map(|funding| SwapFunding::parse(&funding)),
+        psbt: row.get(13)?,
+    })
+}
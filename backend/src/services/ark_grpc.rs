@@ -2,6 +2,7 @@
 use anyhow::{anyhow, Context, Result};
 use std::sync::Arc;
 use parking_lot::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use rand::Rng;
 
 use ark_grpc::Client as ArkGrpcClient;
@@ -11,38 +12,172 @@ use ark_bdk_wallet::Wallet as BdkWallet;
 use ark_core::{ArkAddress, ArkTransaction, BoardingOutput};
 
 use bitcoin::key::{Keypair, Secp256k1};
-use bitcoin::secp256k1::SecretKey;
 use bitcoin::{Address, Amount, Network, Transaction, Txid};
 use bitcoin::hashes::Hash;
 
-// Blockchain impl for Esplora
-pub struct EsploraBlockchain {
+// the Ark client isn't connected, answered with 503 so it can't be mistaken for a failed or faked operation
+#[derive(Debug, thiserror::Error)]
+#[error("Ark client not initialized, the Ark server is unreachable")]
+pub struct ClientUnavailable;
+
+pub fn is_unavailable(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| cause.is::<ClientUnavailable>())
+}
+
+// one esplora server, marked unhealthy on transport errors until a health check sees it answer again
+struct EsploraBackend {
+    url: String,
     client: esplora_client::AsyncClient,
+    healthy: AtomicBool,
+}
+
+// Blockchain impl for Esplora, fails over between the configured servers
+pub struct EsploraBlockchain {
+    backends: Vec<EsploraBackend>,
+    preferred: AtomicUsize, // last backend that answered, tried first
 }
 
 impl EsploraBlockchain {
-    pub fn new(url: &str) -> Result<Self> {
-        let client = esplora_client::Builder::new(url).build_async()?;
-        Ok(Self { client })
+    pub fn new(urls: &[String]) -> Result<Self> {
+        let backends = urls.iter()
+            .map(|url| Ok(EsploraBackend {
+                url: url.clone(),
+                client: esplora_client::Builder::new(url).build_async()?,
+                healthy: AtomicBool::new(true),
+            }))
+            .collect::<Result<Vec<_>>>()?;
+        if backends.is_empty() {
+            return Err(anyhow!("At least one Esplora URL is required"));
+        }
+
+        Ok(Self { backends, preferred: AtomicUsize::new(0) })
+    }
+
+    // runs a request against the preferred backend, moving on to the next one when a server is unreachable or failing.
+    // unhealthy backends are only tried after all healthy ones
+    async fn request<T, F, Fut>(&self, op: &str, f: F) -> Result<T, esplora_client::Error>
+    where
+        F: Fn(esplora_client::AsyncClient) -> Fut,
+        Fut: std::future::Future<Output = Result<T, esplora_client::Error>>,
+    {
+        let start = self.preferred.load(Ordering::Relaxed);
+        let mut order: Vec<usize> = (0..self.backends.len())
+            .map(|offset| (start + offset) % self.backends.len())
+            .collect();
+        order.sort_by_key(|index| !self.backends[*index].healthy.load(Ordering::Relaxed));
+
+        let mut last_error = None;
+        for index in order {
+            let backend = &self.backends[index];
+            match f(backend.client.clone()).await {
+                Err(e) if is_backend_failure(&e) => {
+                    if backend.healthy.swap(false, Ordering::Relaxed) {
+                        tracing::warn!("Esplora backend {} failed on {}, failing over: {}", backend.url, op, e);
+                    }
+                    last_error = Some(e);
+                }
+                result => {
+                    backend.healthy.store(true, Ordering::Relaxed);
+                    self.preferred.store(index, Ordering::Relaxed);
+                    tracing::debug!("{} served by {}", op, backend.url);
+                    return result;
+                }
+            }
+        }
+
+        Err(last_error.expect("at least one backend"))
+    }
+
+    // pings every backend on an interval so failed ones come back once they recover
+    pub fn start_health_checks(self: &Arc<Self>, interval: std::time::Duration) {
+        if self.backends.len() < 2 {
+            return;
+        }
+
+        let blockchain = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                for backend in &blockchain.backends {
+                    let healthy = backend.client.get_height().await.is_ok();
+                    if backend.healthy.swap(healthy, Ordering::Relaxed) != healthy {
+                        tracing::info!("Esplora backend {} is {}", backend.url, if healthy { "back up" } else { "down" });
+                    }
+                }
+            }
+        });
+    }
+
+    // height and block time of the block that confirmed a tx, None while unconfirmed
+    pub async fn get_tx_confirmation(&self, txid: &Txid) -> Result<Option<(u32, u64)>> {
+        let status = self.request("get_tx_status", |client| async move { client.get_tx_status(txid).await }).await
+            .map_err(|e| anyhow!("Failed to get status of {}: {}", txid, e))?;
+
+        match (status.confirmed, status.block_height, status.block_time) {
+            (true, Some(height), Some(time)) => Ok(Some((height, time))),
+            _ => Ok(None),
+        }
+    }
+
+    pub async fn get_tx_block(&self, txid: &Txid) -> Result<Option<(u32, bitcoin::BlockHash)>> {
+        let status = self.request("get_tx_status", |client| async move { client.get_tx_status(txid).await }).await
+            .map_err(|e| anyhow!("Failed to get status of {}: {}", txid, e))?;
+
+        match (status.confirmed, status.block_height, status.block_hash) {
+            (true, Some(height), Some(hash)) => Ok(Some((height, hash))),
+            _ => Ok(None),
+        }
+    }
+
+    // fee and virtual size of a tx as reported by esplora
+    pub async fn get_tx_fee_and_vsize(&self, txid: &Txid) -> Result<Option<(u64, u64)>> {
+        let info = self.request("get_tx_info", |client| async move { client.get_tx_info(txid).await }).await
+            .map_err(|e| anyhow!("Failed to get info for {}: {}", txid, e))?;
+
+        Ok(info.map(|tx| (tx.fee, tx.weight.div_ceil(4))))
+    }
+
+    pub async fn get_height(&self) -> Result<u32> {
+        self.request("get_height", |client| async move { client.get_height().await }).await
+            .map_err(|e| anyhow!("Failed to get blockchain height: {}", e))
+    }
+
+    pub async fn get_block_hash(&self, height: u32) -> Result<bitcoin::BlockHash> {
+        self.request("get_block_hash", |client| async move { client.get_block_hash(height).await }).await
+            .map_err(|e| anyhow!("Failed to get block hash at height {}: {}", height, e))
+    }
+
+    // txs paying or spending a script, every unconfirmed one and the newest confirmed ones
+    pub async fn get_script_txs(&self, script: &bitcoin::Script) -> Result<Vec<esplora_client::Tx>> {
+        self.request("scripthash_txs", |client| async move { client.scripthash_txs(script, None).await }).await
+            .map_err(|e| anyhow!("Failed to get transactions for script: {}", e))
+    }
+
+    // a block can arrive between the two requests, the hash is fetched second so it's never older than the height
+    pub async fn get_tip(&self) -> Result<(u32, bitcoin::BlockHash)> {
+        let height = self.get_height().await?;
+        let hash = self.request("get_tip_hash", |client| async move { client.get_tip_hash().await }).await
+            .map_err(|e| anyhow!("Failed to get tip hash: {}", e))?;
+        Ok((height, hash))
     }
 
     pub async fn test_esplora_connectivity(&self) -> Result<(), anyhow::Error> {
         tracing::info!("Testing Esplora connectivity...");
         
         // get the blockchain tip hash
-        match self.client.get_tip_hash().await {
+        match self.request("get_tip_hash", |client| async move { client.get_tip_hash().await }).await {
             Ok(hash) => {
                 tracing::info!("Esplora server is accessible, tip hash: {}", hash);
                 
                 // get the current height as additional verification
-                match self.client.get_height().await {
+                match self.get_height().await {
                     Ok(height) => {
                         tracing::info!("Current blockchain height: {}", height);
                         Ok(())
                     },
                     Err(e) => {
-                        tracing::error!("Failed to get blockchain height: {}", e);
-                        Err(anyhow::anyhow!("Failed to get blockchain height: {}", e))
+                        tracing::error!("{}", e);
+                        Err(e)
                     }
                 }
             },
@@ -54,26 +189,23 @@ impl EsploraBlockchain {
     }
 }
 
+// errors that say something about the server rather than the request
+fn is_backend_failure(e: &esplora_client::Error) -> bool {
+    match e {
+        esplora_client::Error::Reqwest(_) => true,
+        esplora_client::Error::HttpResponse { status, .. } => *status == 429 || *status >= 500,
+        _ => false,
+    }
+}
+
 impl Blockchain for EsploraBlockchain {
     async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, ark_client::Error> {
         let script_pubkey = address.script_pubkey();
         
         tracing::debug!("Finding outpoints for address: {}", address);
         
-        // [Debug!!]: get the tip hash to verify connectivity
-        match self.client.get_tip_hash().await {
-            Ok(hash) => {
-                tracing::debug!("Esplora server is accessible, tip hash: {}", hash);
-            },
-            Err(e) => {
-                tracing::warn!("Esplora server connectivity check failed: {}", e);
-                // return an empty list instead of failing
-                return Ok(Vec::new());
-            }
-        }
-        
         // get address stats (lighter call)
-        match self.client.get_address_stats(address).await {
+        match self.request("get_address_stats", |client| async move { client.get_address_stats(address).await }).await {
             Ok(stats) => {
                 // log stats using the actual fields available in AddressStats
                 tracing::debug!("Address stats for {}: chain_stats: {:?}, mempool_stats: {:?}", address, stats.chain_stats, stats.mempool_stats);
@@ -91,7 +223,8 @@ impl Blockchain for EsploraBlockchain {
         }
         
         // get tx
-        match self.client.scripthash_txs(&script_pubkey, None).await {
+        let script = &script_pubkey;
+        match self.request("scripthash_txs", |client| async move { client.scripthash_txs(script, None).await }).await {
             Ok(txs) => {
                 tracing::debug!("Successfully fetched {} transactions for address {}", txs.len(), address);
                 
@@ -105,7 +238,8 @@ impl Blockchain for EsploraBlockchain {
                             };
                             
                             // check if output is spent
-                            let is_spent = match self.client.get_output_status(&tx.txid, vout as u64).await {
+                            let txid = &tx.txid;
+                            let is_spent = match self.request("get_output_status", |client| async move { client.get_output_status(txid, vout as u64).await }).await {
                                 Ok(Some(status)) => status.spent,
                                 Ok(None) => false,
                                 Err(e) => {
@@ -139,9 +273,9 @@ impl Blockchain for EsploraBlockchain {
                     return Ok(Vec::new());
                 }
                 
-                // For other errors, log and return empty list
+                // every backend failed, an empty list would look like a wallet without funds
                 tracing::error!("Error fetching transactions for address {}: {}", address, e);
-                Ok(Vec::new())
+                Err(ark_client::Error::wallet(anyhow!("Esplora error: {}", e)))
             }
         }
     }
@@ -149,7 +283,7 @@ impl Blockchain for EsploraBlockchain {
     async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, ark_client::Error> {
         tracing::debug!("Finding transaction: {}", txid);
         
-        match self.client.get_tx(txid).await {
+        match self.request("get_tx", |client| async move { client.get_tx(txid).await }).await {
             Ok(Some(tx)) => {
                 let tx_bytes = bitcoin::consensus::serialize(&tx);
                 match bitcoin::consensus::deserialize(&tx_bytes) {
@@ -175,7 +309,7 @@ impl Blockchain for EsploraBlockchain {
     async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, ark_client::Error> {
         tracing::debug!("Getting output status for {}:{}", txid, vout);
         
-        let status = match self.client.get_output_status(txid, vout as u64).await {
+        let status = match self.request("get_output_status", |client| async move { client.get_output_status(txid, vout as u64).await }).await {
             Ok(status) => status,
             Err(e) => {
                 tracing::error!("Error getting output status: {}", e);
@@ -191,8 +325,8 @@ impl Blockchain for EsploraBlockchain {
     async fn broadcast(&self, tx: &Transaction) -> Result<(), ark_client::Error> {
         tracing::info!("Broadcasting transaction: {}", tx.compute_txid());
         
-        let _tx_bytes = bitcoin::consensus::serialize(tx);
-        match self.client.broadcast(&tx).await {
+        // the first backend that accepts it is enough, it relays the tx from there
+        match self.request("broadcast", |client| async move { client.broadcast(tx).await }).await {
             Ok(_) => {
                 tracing::info!("Successfully broadcast transaction: {}", tx.compute_txid());
                 Ok(())
@@ -211,22 +345,97 @@ pub struct ArkWallet {
     secp: Secp256k1<bitcoin::secp256k1::All>,
     network: Network,
     boarding_outputs: RwLock<Vec<BoardingOutput>>,
-    secret_keys: Mutex<std::collections::HashMap<String, SecretKey>>,
+    utxos: RwLock<Vec<crate::services::onchain::utxo_manager::SpendableUtxo>>, // on-chain UTXOs as of the last sync
 }
 
 impl ArkWallet {
     pub fn new(keypair: Keypair, network: Network) -> Self {
         let secp = Secp256k1::new();
-        Self {
+        let wallet = Self {
             keypair,
             secp,
             network,
             boarding_outputs: RwLock::new(Vec::new()),
-            secret_keys: Mutex::new(std::collections::HashMap::new()),
+            utxos: RwLock::new(Vec::new()),
+        };
+        wallet.load_boarding_outputs();
+        wallet
+    }
+
+    // boarding addresses handed out before a restart, so deposits to them are still found
+    fn load_boarding_outputs(&self) {
+        let (owner_pk, _) = self.keypair.x_only_public_key();
+        let stored = match stored_boarding_outputs(owner_pk, self.network) {
+            Ok(stored) => stored,
+            Err(e) => {
+                tracing::error!("Failed to load stored boarding outputs: {}", e);
+                return;
+            }
+        };
+
+        let mut boarding_outputs = self.boarding_outputs.write();
+        for (server_pk, exit_delay) in stored {
+            match BoardingOutput::new(&self.secp, server_pk, owner_pk, exit_delay, self.network) {
+                Ok(boarding_output) => boarding_outputs.push(boarding_output),
+                Err(e) => tracing::warn!("Skipping stored boarding output for server key {}: {}", server_pk, e),
+            }
+        }
+        if !boarding_outputs.is_empty() {
+            crate::services::APP_STATE.signer.register(&self.keypair);
+            tracing::info!("Loaded {} boarding outputs from the database", boarding_outputs.len());
         }
     }
 }
 
+// the wallet lock drops the wallet, its copy of the key goes with it
+impl Drop for ArkWallet {
+    fn drop(&mut self) {
+        self.keypair.non_secure_erase();
+    }
+}
+
+fn save_boarding_output(
+    boarding_output: &BoardingOutput,
+    server_pk: bitcoin::XOnlyPublicKey,
+    owner_pk: bitcoin::XOnlyPublicKey,
+    exit_delay: bitcoin::Sequence,
+    network: Network,
+) -> Result<()> {
+    let conn = crate::services::APP_STATE.db_manager.get_conn()?;
+    conn.execute(
+        "INSERT OR IGNORE INTO boarding_outputs (address, server_pk, owner_pk, exit_delay, network, created_at)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            boarding_output.address().to_string(),
+            server_pk.to_string(),
+            owner_pk.to_string(),
+            exit_delay.to_consensus_u32(),
+            network.to_string(),
+            chrono::Utc::now().timestamp(),
+        ],
+    )?;
+    Ok(())
+}
+
+// outputs of this key on this network, a restored or switched wallet doesn't pick up others
+fn stored_boarding_outputs(owner_pk: bitcoin::XOnlyPublicKey, network: Network) -> Result<Vec<(bitcoin::XOnlyPublicKey, bitcoin::Sequence)>> {
+    let conn = crate::services::APP_STATE.db_manager.get_conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT server_pk, exit_delay FROM boarding_outputs WHERE owner_pk = ? AND network = ? ORDER BY created_at"
+    )?;
+    let rows = stmt.query_map(rusqlite::params![owner_pk.to_string(), network.to_string()], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?))
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+    rows.into_iter()
+        .map(|(server_pk, exit_delay)| {
+            let server_pk = server_pk.parse()
+                .map_err(|e| anyhow!("Invalid stored server key {}: {}", server_pk, e))?;
+            Ok((server_pk, bitcoin::Sequence::from_consensus(exit_delay)))
+        })
+        .collect()
+}
+
 impl ark_client::wallet::BoardingWallet for ArkWallet {
     fn new_boarding_output(
         &self,
@@ -236,7 +445,6 @@ impl ark_client::wallet::BoardingWallet for ArkWallet {
     ) -> Result<BoardingOutput, ark_client::Error> {
         tracing::info!("Creating new boarding output");
         
-        let sk = self.keypair.secret_key();
         let (owner_pk, _) = self.keypair.x_only_public_key();
         
         let boarding_output = BoardingOutput::new(&self.secp, server_pk, owner_pk, exit_delay, network)
@@ -245,8 +453,17 @@ impl ark_client::wallet::BoardingWallet for ArkWallet {
                 ark_client::Error::wallet(anyhow!("Failed to create boarding output: {}", e))
             })?;
         
-        self.secret_keys.lock().insert(owner_pk.to_string(), sk);
-        self.boarding_outputs.write().push(boarding_output.clone());
+        crate::services::APP_STATE.signer.register(&self.keypair);
+        if let Err(e) = save_boarding_output(&boarding_output, server_pk, owner_pk, exit_delay, network) {
+            // still usable this run, but it won't be watched after a restart
+            tracing::error!("Failed to persist boarding output {}: {}", boarding_output.address(), e);
+        }
+        let mut boarding_outputs = self.boarding_outputs.write();
+        // the same server key and delay give the same address as one loaded from the database
+        if !boarding_outputs.iter().any(|existing| existing.address() == boarding_output.address()) {
+            boarding_outputs.push(boarding_output.clone());
+        }
+        drop(boarding_outputs);
        
         tracing::info!("Created boarding output with address: {}", boarding_output.address());
         Ok(boarding_output)
@@ -260,17 +477,10 @@ impl ark_client::wallet::BoardingWallet for ArkWallet {
     }
 
     fn sign_for_pk(&self, pk: &bitcoin::XOnlyPublicKey, msg: &bitcoin::secp256k1::Message) -> Result<bitcoin::secp256k1::schnorr::Signature, ark_client::Error> {
-        let secret_keys = self.secret_keys.lock();
-        
-        if let Some(sk) = secret_keys.get(&pk.to_string()) {
-            let keypair = Keypair::from_secret_key(&self.secp, sk);
-            let sig = self.secp.sign_schnorr_no_aux_rand(msg, &keypair);
-            Ok(sig)
-        } 
-        else {
-            tracing::error!("No secret key found for public key: {}", pk);
-            Err(ark_client::Error::wallet(anyhow!("No secret key found for public key: {}", pk)))
-        }
+        crate::services::APP_STATE.signer.sign_schnorr(pk, msg, false).map_err(|e| {
+            tracing::error!("Failed to sign for {}: {}", pk, e);
+            ark_client::Error::wallet(e)
+        })
     }
 }
 
@@ -288,44 +498,62 @@ impl ark_client::wallet::OnchainWallet for ArkWallet {
     }
     
 
+    // refreshes the UTXO cache that balance and prepare_send_to_address work from
     async fn sync(&self) -> Result<(), ark_client::Error> {
-        // [TODO!!] implement a full sync
-        tracing::info!("Syncing wallet (placeholder)");
+        let utxo_manager = crate::services::onchain::utxo_manager::UtxoManager::new(crate::services::APP_STATE.blockchain.clone());
+        let utxos = utxo_manager.get_spendable_utxos().await
+            .map_err(|e| ark_client::Error::wallet(anyhow!("Failed to sync on-chain wallet: {}", e)))?;
+
+        tracing::debug!("Synced on-chain wallet, {} UTXOs", utxos.len());
+        *self.utxos.write() = utxos;
         Ok(())
     }
 
     fn balance(&self) -> Result<ark_client::wallet::Balance, ark_client::Error> {
-        // [TODO!!]
-        // [Demo:] returning a placeholder balance
+        let utxos = self.utxos.read();
+        let (confirmed, pending): (Vec<_>, Vec<_>) = utxos.iter().partition(|u| u.confirmation_time.is_some());
+
+        // esplora can't tell our own change from incoming payments, so unconfirmed funds count as untrusted
         Ok(ark_client::wallet::Balance {
-            confirmed: Amount::from_sat(0),
-            trusted_pending: Amount::from_sat(0),
-            untrusted_pending: Amount::from_sat(0),
-            immature: Amount::from_sat(0),
+            confirmed: confirmed.iter().map(|u| u.amount).sum(),
+            trusted_pending: Amount::ZERO,
+            untrusted_pending: pending.iter().map(|u| u.amount).sum(),
+            immature: Amount::ZERO,
         })
     }
 
+    // unsigned PSBT spending from the synced UTXO set, change goes to a fresh internal address
     fn prepare_send_to_address(
         &self,
         address: Address,
         amount: Amount,
         fee_rate: bitcoin::FeeRate,
     ) -> Result<bitcoin::Psbt, ark_client::Error> {
-        // [TODO!!]
-        tracing::error!("prepare_send_to_address not fully implemented");
-        Err(ark_client::Error::wallet(anyhow!("Not implemented")))
+        let utxos = self.utxos.read().clone();
+        let (psbt, fee) = transaction_builder()
+            .build_psbt(utxos, address.clone(), amount, fee_rate)
+            .map_err(|e| ark_client::Error::wallet(anyhow!("Failed to prepare PSBT: {}", e)))?;
+
+        tracing::info!("Prepared PSBT sending {} to {} with {} inputs, fee {}", amount, address, psbt.inputs.len(), fee);
+        Ok(psbt)
     }
 
     fn sign(&self, psbt: &mut bitcoin::Psbt) -> Result<bool, ark_client::Error> {
-        // [TODO!!]
-        tracing::error!("sign not fully implemented");
-        Err(ark_client::Error::wallet(anyhow!("Not implemented")))
+        transaction_builder()
+            .sign_psbt(psbt)
+            .map_err(|e| ark_client::Error::wallet(anyhow!("Failed to sign PSBT: {}", e)))
     }
 }
 
+fn transaction_builder() -> crate::services::onchain::TransactionBuilder {
+    crate::services::onchain::TransactionBuilder::new(crate::services::APP_STATE.blockchain.clone())
+}
+
 pub struct ArkGrpcService {
     grpc_client: Option<ArkGrpcClient>,
-    ark_client: Arc<Mutex<Option<Arc<Client<EsploraBlockchain, ArkWallet>>>>>
+    ark_client: Arc<Mutex<Option<Arc<Client<crate::services::chain::ChainBackend, ArkWallet>>>>>,
+    ark_wallet: Arc<Mutex<Option<Arc<ArkWallet>>>>,
+    known_vtxos: Arc<Mutex<std::collections::HashMap<String, u64>>>,
 }
 
 impl ArkGrpcService {
@@ -333,6 +561,8 @@ impl ArkGrpcService {
         Self { 
             grpc_client: None,
             ark_client: Arc::new(Mutex::new(None)),
+            ark_wallet: Arc::new(Mutex::new(None)),
+            known_vtxos: Arc::new(Mutex::new(std::collections::HashMap::new())),
         }
     }
 
@@ -352,6 +582,12 @@ impl ArkGrpcService {
                 tracing::info!("ArkGrpcService::connect: Successfully connected to {} via gRPC", server_url);
                 self.grpc_client = Some(grpc_client);
                 
+                // the key isn't available while locked
+                if crate::services::APP_STATE.wallet_lock.is_locked() {
+                    tracing::info!("Wallet is locked, the Ark client is initialized on unlock");
+                    return Ok(());
+                }
+
                 // Now initialize the Ark client
                 match self.init_ark_client_with_retry(server_url).await {
                     Ok(_) => {
@@ -372,6 +608,18 @@ impl ArkGrpcService {
         }
     }
     
+    // after an unlock, the client comes back with the key from the key manager
+    pub async fn reconnect_client(&mut self) -> Result<()> {
+        let server_url = crate::services::profiles::active().ark_server_url.clone();
+        self.init_ark_client_with_retry(&server_url).await
+    }
+
+    // wallet lock: the client and the wallet hold the keypair, dropping them lets it go
+    pub fn drop_client(&mut self) {
+        *self.ark_client.lock() = None;
+        *self.ark_wallet.lock() = None;
+    }
+
     pub fn is_connected(&self) -> bool {
         let connected = self.grpc_client.is_some();
         tracing::info!("ArkGrpcService::is_connected: {}", connected);
@@ -380,14 +628,9 @@ impl ArkGrpcService {
     
     async fn init_ark_client(&mut self, server_url: &str) -> Result<()> {
         // load env vars
-        let network = match std::env::var("BITCOIN_NETWORK").unwrap_or_else(|_| "regtest".to_string()).as_str() {
-            "mainnet" => Network::Bitcoin,
-            "testnet" => Network::Testnet,
-            "signet" => Network::Signet,
-            _ => Network::Regtest,
-        };
+        let network = crate::services::profiles::active().network;
         
-        let esplora_url = std::env::var("ESPLORA_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+        let esplora_url = crate::services::profiles::active().esplora_url.clone();
         
         tracing::info!("Using network: {}, esplora: {}, ark server: {}", network, esplora_url, server_url);
         
@@ -395,13 +638,13 @@ impl ArkGrpcService {
         let keypair = self.load_or_create_keypair()?;
         
         // initialize blockchain and wallet impls
-        let blockchain = Arc::new(EsploraBlockchain::new(&esplora_url)?);
-        match blockchain.test_esplora_connectivity().await {
-            Ok(_) => tracing::info!("Esplora connectivity test passed"),
-            Err(e) => tracing::warn!("Esplora connectivity test failed: {}", e),
+        let blockchain = crate::services::APP_STATE.blockchain.clone();
+        match blockchain.test_connectivity().await {
+            Ok(_) => tracing::info!("Chain backend connectivity test passed"),
+            Err(e) => tracing::warn!("Chain backend connectivity test failed: {}", e),
         }
         let wallet = Arc::new(ArkWallet::new(keypair.clone(), network));
-        
+        *self.ark_wallet.lock() = Some(wallet.clone());
 
         let offline_client = OfflineClient::new(
             "ark-web-app".to_string(),
@@ -429,6 +672,7 @@ impl ArkGrpcService {
                     server_info.pk,
                     server_info.unilateral_exit_delay
                 );
+                crate::services::APP_STATE.server_info.update((&server_info).into());
                 let mut ark_client = self.ark_client.lock();
                 *ark_client = Some(Arc::new(client));
                 Ok(())
@@ -459,19 +703,19 @@ impl ArkGrpcService {
                     match grpc_client.get_info().await {
                         Ok(info) => {
                             tracing::info!("Successfully got server info: {:?}", info);
+                            crate::services::APP_STATE.server_info.update((&info).into());
                             
                             // try the full client initialization
-                            let network = Network::Regtest;
-                            let esplora_url = std::env::var("ESPLORA_URL")
-                                .unwrap_or_else(|_| "http://localhost:3000".to_string());
+                            let network = crate::services::profiles::active().network;
                             
                             let keypair = self.load_or_create_keypair()?;
-                            let blockchain = Arc::new(EsploraBlockchain::new(&esplora_url)?);
-                            match blockchain.test_esplora_connectivity().await {
-                                Ok(_) => tracing::info!("Esplora connectivity test passed"),
-                                Err(e) => tracing::warn!("Esplora connectivity test failed: {}", e),
+                            let blockchain = crate::services::APP_STATE.blockchain.clone();
+                            match blockchain.test_connectivity().await {
+                                Ok(_) => tracing::info!("Chain backend connectivity test passed"),
+                                Err(e) => tracing::warn!("Chain backend connectivity test failed: {}", e),
                             }
                             let wallet = Arc::new(ArkWallet::new(keypair.clone(), network));
+                            *self.ark_wallet.lock() = Some(wallet.clone());
                             
                             let offline_client = OfflineClient::new(
                                 "ark-web-app".to_string(),
@@ -550,10 +794,14 @@ impl ArkGrpcService {
         Ok(keypair)
     }
 
-    pub fn get_ark_client(&self) -> parking_lot::MutexGuard<'_, Option<Arc<Client<EsploraBlockchain, ArkWallet>>>> {
+    pub fn get_ark_client(&self) -> parking_lot::MutexGuard<'_, Option<Arc<Client<crate::services::chain::ChainBackend, ArkWallet>>>> {
         self.ark_client.lock()
     }
 
+    pub fn get_ark_wallet(&self) -> Option<Arc<ArkWallet>> {
+        self.ark_wallet.lock().clone()
+    }
+
     // update app state with client info
     pub async fn update_app_state(&self) -> Result<()> {
 
@@ -567,11 +815,42 @@ impl ArkGrpcService {
             // get offchain balance
             if let Ok(offchain_balance) = client.offchain_balance().await {
                 let mut balance = crate::services::APP_STATE.balance.lock().await;
+                let previous_total = (balance.confirmed, balance.total);
                 balance.confirmed = offchain_balance.confirmed().to_sat();
                 balance.trusted_pending = offchain_balance.pending().to_sat();
                 balance.untrusted_pending = 0;
                 balance.immature = 0;
                 balance.total = offchain_balance.total().to_sat();
+
+                if previous_total != (balance.confirmed, balance.total) {
+                    crate::services::APP_STATE.publish_balance(&balance);
+                }
+            }
+
+            // diff the VTXO set against the last sync to publish lifecycle events
+            if let Ok(vtxos) = client.spendable_vtxos().await {
+                let current: std::collections::HashMap<String, u64> = vtxos.iter()
+                    .flat_map(|(outpoints, _)| outpoints.iter())
+                    .map(|o| (o.outpoint.to_string(), o.amount.to_sat()))
+                    .collect();
+
+                let mut known = self.known_vtxos.lock();
+                let events = &crate::services::APP_STATE.events;
+                for (outpoint, amount) in current.iter().filter(|(o, _)| !known.contains_key(*o)) {
+                    events.publish(crate::services::events::WalletEvent::VtxoUpdate {
+                        outpoint: outpoint.clone(),
+                        amount: *amount,
+                        status: crate::services::events::VtxoStatus::Added,
+                    });
+                }
+                for (outpoint, amount) in known.iter().filter(|(o, _)| !current.contains_key(*o)) {
+                    events.publish(crate::services::events::WalletEvent::VtxoUpdate {
+                        outpoint: outpoint.clone(),
+                        amount: *amount,
+                        status: crate::services::events::VtxoStatus::Spent,
+                    });
+                }
+                *known = current;
             }
             
             // get tx history
@@ -583,29 +862,35 @@ impl ArkGrpcService {
                     let tx_response = match tx {
                         ArkTransaction::Boarding { txid, amount, confirmed_at } => {
                             crate::models::wallet::TransactionResponse {
-                                txid: txid.to_string(),
-                                amount: amount.to_sat() as i64,
-                                timestamp: confirmed_at.unwrap_or(chrono::Utc::now().timestamp()),
-                                type_name: "Boarding".to_string(),
                                 is_settled: Some(confirmed_at.is_some()),
+                                ..crate::models::wallet::TransactionResponse::new(
+                                    txid.to_string(),
+                                    crate::models::wallet::TransactionKind::Boarding,
+                                    amount.to_sat() as i64,
+                                    confirmed_at.unwrap_or(chrono::Utc::now().timestamp()),
+                                )
                             }
                         },
                         ArkTransaction::Round { txid, amount, created_at } => {
                             crate::models::wallet::TransactionResponse {
-                                txid: txid.to_string(),
-                                amount: amount.to_sat() as i64,
-                                timestamp: created_at,
-                                type_name: "Round".to_string(),
                                 is_settled: Some(true),
+                                ..crate::models::wallet::TransactionResponse::new(
+                                    txid.to_string(),
+                                    crate::models::wallet::TransactionKind::Round { round_id: None },
+                                    amount.to_sat() as i64,
+                                    created_at,
+                                )
                             }
                         },
                         ArkTransaction::Redeem { txid, amount, is_settled, created_at } => {
                             crate::models::wallet::TransactionResponse {
-                                txid: txid.to_string(),
-                                amount: amount.to_sat() as i64,
-                                timestamp: created_at,
-                                type_name: "Redeem".to_string(),
                                 is_settled: Some(is_settled),
+                                ..crate::models::wallet::TransactionResponse::new(
+                                    txid.to_string(),
+                                    crate::models::wallet::TransactionKind::Redeem,
+                                    amount.to_sat() as i64,
+                                    created_at,
+                                )
                             }
                         },
                     };
@@ -639,8 +924,11 @@ impl ArkGrpcService {
                 }
             }
         } 
+        else if crate::services::simulation::fallback_enabled() {
+            crate::services::simulation::offchain_address()
+        }
         else {
-            Err(anyhow::anyhow!("Ark client not initialized"))
+            Err(ClientUnavailable.into())
         }
     }
     
@@ -656,12 +944,16 @@ impl ArkGrpcService {
                 Err(e) => Err(anyhow::anyhow!("Failed to get boarding address: {}", e))
             }
         } 
+        else if crate::services::simulation::fallback_enabled() {
+            crate::services::simulation::boarding_address().await
+        }
         else {
-            Err(anyhow::anyhow!("Ark client not initialized"))
+            Err(ClientUnavailable.into())
         }
     }
     
-    pub async fn send_vtxo(&self, address_str: String, amount: u64) -> Result<String> {
+    // ark-client picks the inputs, they're looked up in the VTXO set from before the send to report their expiry
+    pub async fn send_vtxo(&self, address_str: String, amount: u64) -> Result<(String, Vec<crate::models::wallet::SpentVtxo>)> {
         let client = {
             let client_opt = self.get_ark_client();
             client_opt.as_ref().map(|c| Arc::clone(c))
@@ -676,6 +968,13 @@ impl ArkGrpcService {
             let amount = Amount::from_sat(amount);
             
             tracing::info!("Sending {} sats to {}", amount.to_sat(), address_str);
+
+            let vtxos: std::collections::HashMap<bitcoin::OutPoint, (u64, i64)> = client.spendable_vtxos().await
+                .map(|vtxos| vtxos.iter()
+                    .flat_map(|(outpoints, _)| outpoints.iter())
+                    .map(|o| (o.outpoint, (o.amount.to_sat(), o.expire_at)))
+                    .collect())
+                .unwrap_or_default();
             
             match client.send_vtxo(address, amount).await {
                 Ok(psbt) => {
@@ -683,13 +982,23 @@ impl ArkGrpcService {
                         .map_err(|e| anyhow::anyhow!("Failed to extract transaction: {}", e))?;
                     let txid = tx.compute_txid();
                     tracing::info!("Successfully sent VTXO with txid: {}", txid);
+
+                    let inputs = tx.input.iter()
+                        .filter_map(|input| vtxos.get(&input.previous_output).map(|(amount, expire_at)| {
+                            crate::models::wallet::SpentVtxo {
+                                outpoint: input.previous_output.to_string(),
+                                amount: *amount,
+                                expire_at: *expire_at,
+                            }
+                        }))
+                        .collect();
                     
                     // update app state after sending
                     if let Err(e) = self.update_app_state().await {
                         tracing::warn!("Failed to update app state after sending: {}", e);
                     }
                     
-                    Ok(txid.to_string())
+                    Ok((txid.to_string(), inputs))
                 },
                 Err(e) => {
                     tracing::error!("Failed to send VTXO: {}", e);
@@ -697,8 +1006,11 @@ impl ArkGrpcService {
                 }
             }
         } 
+        else if crate::services::simulation::fallback_enabled() {
+            Ok((crate::services::simulation::txid("send", &format!("{}:{}", address_str, amount)), Vec::new()))
+        }
         else {
-            Err(anyhow::anyhow!("Ark client not available"))
+            Err(ClientUnavailable.into())
         }
     }
     
@@ -736,106 +1048,14 @@ impl ArkGrpcService {
             }
         }
         
-        // fallback if client unavailable
-        let mut transactions = crate::services::APP_STATE.transactions.lock().await;
-        transactions.push(crate::models::wallet::TransactionResponse {
-            txid: format!("deposit_{}", chrono::Utc::now().timestamp()),
-            amount: 100000000, // 1 BTC in satoshis
-            timestamp: chrono::Utc::now().timestamp(),
-            type_name: "Boarding".to_string(),
-            is_settled: Some(true),
-        });
-        
-        // recalculate balance
-        drop(transactions);
-        crate::services::APP_STATE.recalculate_balance().await?;
-        
-        Ok(true)
-    }
-    
-    pub async fn participate_in_round(&self) -> Result<Option<String>> {
-        let client_opt = self.get_ark_client();
-        
-        if let Some(client) = client_opt.as_ref() {
-            // random no. for round participation
-            let mut rng = bitcoin::secp256k1::rand::thread_rng();
-            
-            // participate in round
-            tracing::info!("Participating in a round");
-            let result = client.board(&mut rng).await;
-            
-            match result {
-                Ok(_) => {
-                    tracing::info!("Successfully participated in round");
-                    
-                    // update app state after round participation
-                    self.update_app_state().await?;
-                    
-                    // [TODO!! (get the round txid)] 
-                    // Dummy impl
-                    let placeholder_txid = format!("round_{}", chrono::Utc::now().timestamp());
-                    return Ok(Some(placeholder_txid));
-                },
-                Err(e) => {
-                    if e.to_string().contains("No boarding outputs") && e.to_string().contains("No VTXOs") {
-                        tracing::info!("No outputs to include in round");
-                        return Ok(None);
-                    } 
-                    else {
-                        tracing::error!("Error participating in round: {}", e);
-                        return Err(anyhow::anyhow!("Error participating in round: {}", e));
-                    }
-                }
-            }
-        }
-        
-        // fallback if client unavailable (simulate round participation)
-        let mut transactions = crate::services::APP_STATE.transactions.lock().await;
-        
-        let pending_txs: Vec<_> = transactions.iter()
-            .filter(|tx| tx.is_settled == Some(false))
-            .collect();
-        
-        if pending_txs.is_empty() {
-            return Ok(None);
-        }
-        
-        // mark all pending tx as settled
-        let mut settled_txids = Vec::new();
-        for tx in transactions.iter_mut() {
-            if tx.is_settled == Some(false) {
-                tx.is_settled = Some(true);
-                settled_txids.push(tx.txid.clone());
-            }
+        drop(client_opt);
+        if crate::services::simulation::fallback_enabled() {
+            return crate::services::simulation::board().await;
         }
-        
-        let round_txid = format!("round_{}_{}", chrono::Utc::now().timestamp(), rand::random::<u32>());
-        
-        // add round tx to history
-        transactions.push(crate::models::wallet::TransactionResponse {
-            txid: round_txid.clone(),
-            amount: 0, // rounds don't change balance directly
-            timestamp: chrono::Utc::now().timestamp(),
-            type_name: "Round".to_string(),
-            is_settled: Some(true),
-        });
-        
-        drop(transactions);
-        
-        // recalculate balance for consistency
-        crate::services::APP_STATE.recalculate_balance().await?;
-        
-        // log settled tx
-        tracing::info!(
-            "Round {} settled {} transactions: {:?}",
-            round_txid, settled_txids.len(), settled_txids
-        );
-        
-        Ok(Some(round_txid))
+        Err(ClientUnavailable.into())
     }
     
-
-    pub async fn get_transaction_history(&self) -> Result<Vec<(String, i64, i64, String, bool)>> {
+    pub async fn get_transaction_history(&self) -> Result<Vec<(String, i64, i64, crate::models::wallet::TransactionKind, bool)>> {
         tracing::info!("ArkGrpcService: Starting to fetch transaction history");
         
         let timeout_duration = std::time::Duration::from_secs(5);
@@ -870,7 +1090,7 @@ impl ArkGrpcService {
                     tx.txid.clone(),
                     tx.amount,
                     tx.timestamp,
-                    tx.type_name.clone(),
+                    tx.kind.clone(),
                     tx.is_settled.unwrap_or(false),
                 )
             }).collect::<Vec<_>>();
@@ -881,24 +1101,4 @@ impl ArkGrpcService {
             Err(anyhow::anyhow!("Ark client not available"))
         }
     }
-    
-    // [TODO!!]
-    pub async fn unilateral_exit(&self, vtxo_txid: String) -> Result<crate::models::wallet::TransactionResponse> {
-        // TODO!! [implment unilateral exit]
-        tracing::warn!("Unilateral exit is not fully implemented yet");
-        
-        // [TODO!!]
-        // Dummy Tx
-        let exit_txid = format!("exit_{}_{}", chrono::Utc::now().timestamp(), rand::random::<u32>());
-        
-        let tx = crate::models::wallet::TransactionResponse {
-            txid: exit_txid,
-            amount: -1000, // [TODO!! (modify to calcualte fee)]
-            timestamp: chrono::Utc::now().timestamp(),
-            type_name: "Exit".to_string(),
-            is_settled: Some(true),
-        };
-        
-        Ok(tx)
-    }
-}
\ No newline at end of file
+}
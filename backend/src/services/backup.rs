@@ -0,0 +1,286 @@
+use anyhow::{anyhow, Result};
+use argon2::Argon2;
+use bitcoin::base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::services::config::BackupConfig;
+use crate::services::APP_STATE;
+
+// archive layout: MAGIC | salt | nonce | ChaCha20-Poly1305 ciphertext of the JSON contents
+const MAGIC: &[u8] = b"ARKBAK1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const MIN_PASSPHRASE_LEN: usize = 8;
+const FILE_PREFIX: &str = "ark-backup-";
+const FILE_EXTENSION: &str = ".bak";
+
+// what a backup carries, the database already holds everything except the mnemonic file
+#[derive(Serialize, Deserialize)]
+struct BackupContents {
+    version: u32,
+    created_at: i64,
+    network: String,
+    mnemonic: String,
+    database: String, // base64 SQLite snapshot
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreSummary {
+    pub created_at: i64,
+    pub network: String,
+    pub restart_required: bool,
+    pub previous_mnemonic: Option<String>, // where the replaced wallet's mnemonic was moved
+}
+
+// encrypted archive of the database and the wallet mnemonic
+pub fn create(passphrase: &str) -> Result<Vec<u8>> {
+    check_passphrase(passphrase)?;
+
+    let contents = BackupContents {
+        version: 1,
+        created_at: chrono::Utc::now().timestamp(),
+        network: APP_STATE.config.profile.network.to_string(),
+        mnemonic: APP_STATE.key_manager.get_mnemonic()?,
+        database: BASE64.encode(APP_STATE.db_manager.snapshot()?),
+    };
+    encrypt(&serde_json::to_vec(&contents)?, passphrase)
+}
+
+// only into a fresh instance, the restored files take over on the next start
+pub async fn restore(archive: &[u8], passphrase: &str) -> Result<RestoreSummary> {
+    let contents: BackupContents = serde_json::from_slice(&decrypt(archive, passphrase)?)
+        .map_err(|e| anyhow!("Unreadable backup contents: {}", e))?;
+
+    let network = APP_STATE.config.profile.network.to_string();
+    if contents.network != network {
+        return Err(anyhow!("Backup is for {}, this instance runs on {}", contents.network, network));
+    }
+    check_empty().await?;
+
+    let database = BASE64.decode(&contents.database)
+        .map_err(|e| anyhow!("Invalid database in backup: {}", e))?;
+    APP_STATE.db_manager.stage_restore(&database)?;
+    // kept next to the restored one in case the check above missed funds
+    let previous_mnemonic = APP_STATE.key_manager.archive_mnemonic()?;
+    APP_STATE.key_manager.import_wallet(&contents.mnemonic)?;
+
+    tracing::warn!("Restored backup from {}, restart the backend to load it", contents.created_at);
+    Ok(RestoreSummary {
+        created_at: contents.created_at,
+        network: contents.network,
+        restart_required: true,
+        previous_mnemonic: previous_mnemonic.map(|path| path.display().to_string()),
+    })
+}
+
+// a restore replaces the wallet's seed, so the wallet it replaces has to be empty. History alone
+// doesn't show that, a funded wallet can have no entries yet
+async fn check_empty() -> Result<()> {
+    use ark_client::Blockchain;
+
+    let transactions: i64 = APP_STATE.db_manager.get_conn()?
+        .query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0))?;
+    if transactions > 0 {
+        return Err(anyhow!("This instance already has {} transactions, restore into a fresh instance", transactions));
+    }
+    let balance = APP_STATE.balance.lock().await.total;
+    if balance > 0 {
+        return Err(anyhow!("This wallet holds {} sats, restore into a fresh instance", balance));
+    }
+
+    let utxos = crate::services::onchain::utxo_manager::UtxoManager::new(APP_STATE.blockchain.clone())
+        .get_spendable_utxos().await
+        .map_err(|e| anyhow!("Can't check the wallet's on-chain funds before restoring: {}", e))?;
+    if !utxos.is_empty() {
+        return Err(anyhow!("This wallet has {} on-chain UTXOs, restore into a fresh instance", utxos.len()));
+    }
+
+    let boarding_addresses: Vec<String> = {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        let mut stmt = conn.prepare("SELECT address FROM boarding_outputs")?;
+        let addresses = stmt.query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        addresses
+    };
+    for address in boarding_addresses {
+        let parsed = bitcoin::Address::from_str(&address)
+            .map_err(|e| anyhow!("Invalid stored boarding address {}: {}", address, e))?
+            .assume_checked();
+        let outpoints = APP_STATE.blockchain.find_outpoints(&parsed).await
+            .map_err(|e| anyhow!("Can't check boarding address {} before restoring: {}", address, e))?;
+        if outpoints.iter().any(|outpoint| !outpoint.is_spent) {
+            return Err(anyhow!("Boarding address {} holds funds, restore into a fresh instance", address));
+        }
+    }
+
+    // without the ASP there's no telling whether VTXOs exist
+    let client = {
+        let grpc_client = APP_STATE.grpc_client.lock().await;
+        let client = grpc_client.get_ark_client().as_ref().map(std::sync::Arc::clone);
+        client
+    };
+    let client = client.ok_or_else(|| anyhow!("Can't check the wallet's VTXOs before restoring, the Ark client is unavailable"))?;
+    let vtxos = client.spendable_vtxos().await
+        .map_err(|e| anyhow!("Can't check the wallet's VTXOs before restoring: {}", e))?;
+    let count = vtxos.iter().map(|(outpoints, _)| outpoints.len()).sum::<usize>();
+    if count > 0 {
+        return Err(anyhow!("This wallet has {} VTXOs, restore into a fresh instance", count));
+    }
+    Ok(())
+}
+
+// a scheduled snapshot in the backup dir, newest first when listed
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupFile {
+    pub name: String,
+    pub size: u64,
+    pub created_at: i64,
+}
+
+pub fn list(config: &BackupConfig) -> Result<Vec<BackupFile>> {
+    let Some(dir) = &config.dir else {
+        return Ok(Vec::new());
+    };
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(anyhow!("Failed to read backup dir {}: {}", dir, e)),
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with(FILE_PREFIX) || !name.ends_with(FILE_EXTENSION) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        let created_at = metadata.modified()?
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        backups.push(BackupFile { name, size: metadata.len(), created_at });
+    }
+    // names embed the UTC time, so they sort chronologically
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+// reads a snapshot listed by list(), by name only so requests can't escape the backup dir
+pub fn read_file(config: &BackupConfig, name: &str) -> Result<Vec<u8>> {
+    let dir = config.dir.as_ref().ok_or_else(|| anyhow!("Scheduled backups are disabled"))?;
+    if !list(config)?.iter().any(|backup| backup.name == name) {
+        return Err(anyhow!("Backup {} not found", name));
+    }
+    Ok(std::fs::read(Path::new(dir).join(name))?)
+}
+
+// writes a snapshot and deletes the ones beyond the retention count
+fn write_snapshot(config: &BackupConfig) -> Result<BackupFile> {
+    let (Some(dir), Some(passphrase)) = (&config.dir, &config.passphrase) else {
+        return Err(anyhow!("Scheduled backups are disabled"));
+    };
+    std::fs::create_dir_all(dir)?;
+
+    let archive = create(passphrase)?;
+    let name = format!("{}{}{}", FILE_PREFIX, chrono::Utc::now().format("%Y%m%dT%H%M%SZ"), FILE_EXTENSION);
+    let path = Path::new(dir).join(&name);
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, &archive)?;
+    std::fs::rename(&partial, &path)?;
+
+    for old in list(config)?.into_iter().skip(config.keep) {
+        match std::fs::remove_file(Path::new(dir).join(&old.name)) {
+            Ok(()) => tracing::info!("Deleted old backup {}", old.name),
+            Err(e) => tracing::warn!("Failed to delete old backup {}: {}", old.name, e),
+        }
+    }
+
+    Ok(BackupFile {
+        name,
+        size: archive.len() as u64,
+        created_at: chrono::Utc::now().timestamp(),
+    })
+}
+
+pub fn start_scheduler(config: &'static BackupConfig) {
+    if !config.is_enabled() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+        loop {
+            interval.tick().await;
+            // the mnemonic can't be read while locked
+            if APP_STATE.wallet_lock.skips("the scheduled backup") {
+                continue;
+            }
+            match tokio::task::spawn_blocking(move || write_snapshot(config)).await {
+                Ok(Ok(backup)) => tracing::info!("Wrote backup {} ({} bytes)", backup.name, backup.size),
+                Ok(Err(e)) => tracing::error!("Scheduled backup failed: {}", e),
+                Err(e) => tracing::error!("Scheduled backup task panicked: {}", e),
+            }
+        }
+    });
+}
+
+fn check_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(anyhow!("passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    Ok(())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive backup key: {}", e))?;
+    Ok(Key::from(key))
+}
+
+fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let salt = rand::random::<[u8; SALT_LEN]>();
+    let nonce = rand::random::<[u8; NONCE_LEN]>();
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt backup"))?;
+
+    let mut archive = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    archive.extend_from_slice(MAGIC);
+    archive.extend_from_slice(&salt);
+    archive.extend_from_slice(&nonce);
+    archive.extend_from_slice(&ciphertext);
+    Ok(archive)
+}
+
+fn decrypt(archive: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let body = archive.strip_prefix(MAGIC)
+        .filter(|body| body.len() > SALT_LEN + NONCE_LEN)
+        .ok_or_else(|| anyhow!("Not a wallet backup"))?;
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Wrong passphrase or corrupted backup"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let archive = encrypt(b"wallet state", "correct horse").unwrap();
+        assert!(archive.starts_with(MAGIC));
+        assert_eq!(decrypt(&archive, "correct horse").unwrap(), b"wallet state");
+        assert!(decrypt(&archive, "wrong horse").is_err());
+        assert!(decrypt(b"ARKBAK1short", "correct horse").is_err());
+    }
+}
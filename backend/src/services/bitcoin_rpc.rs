@@ -0,0 +1,74 @@
+use anyhow::{anyhow, Result};
+use bitcoin::{Address, Amount, Network, Txid};
+use bitcoincore_rpc::{Auth, Client, RpcApi};
+
+// connection settings for the Bitcoin Core node, defaults match nigiri's bitcoind
+#[derive(Debug, Clone)]
+struct RpcConfig {
+    url: String,
+    auth: Auth,
+}
+
+fn config() -> RpcConfig {
+    let config = crate::services::config::get();
+    let url = config.bitcoind.rpc_url.clone().unwrap_or_else(|| {
+        let port = match config.profile.network {
+            Network::Bitcoin => 8332,
+            Network::Testnet => 18332,
+            Network::Signet => 38332,
+            _ => 18443,
+        };
+        format!("http://localhost:{}", port)
+    });
+
+    // a cookie file wins over user/password, like bitcoin-cli
+    let auth = match &config.bitcoind.rpc_cookie {
+        Some(path) => Auth::CookieFile(path.into()),
+        None => Auth::UserPass(config.bitcoind.rpc_user.clone(), config.bitcoind.rpc_password.clone()),
+    };
+
+    RpcConfig { url, auth }
+}
+
+// the rpc client is blocking, every call runs on the blocking pool
+pub(crate) async fn call<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&Client) -> bitcoincore_rpc::Result<T> + Send + 'static,
+{
+    let config = config();
+    tokio::task::spawn_blocking(move || {
+        let client = Client::new(&config.url, config.auth)
+            .map_err(|e| anyhow!("Failed to connect to bitcoind at {}: {}", config.url, e))?;
+        f(&client).map_err(|e| anyhow!("bitcoind RPC failed: {}", e))
+    })
+    .await?
+}
+
+// sat/vB for a confirmation target, None while the node has too little data to estimate
+pub async fn estimate_smart_fee(conf_target: u16) -> Result<Option<u64>> {
+    let result = call(move |client| client.estimate_smart_fee(conf_target, None)).await?;
+    if let Some(errors) = result.errors.filter(|errors| !errors.is_empty()) {
+        tracing::debug!("estimatesmartfee {} returned errors: {:?}", conf_target, errors);
+    }
+
+    // bitcoind reports BTC/kvB
+    Ok(result.fee_rate.map(|rate| (rate.to_sat() / 1000).max(1)))
+}
+
+// mines blocks paying to a fresh address of the node's wallet, returns their hashes
+pub async fn generate_blocks(blocks: u64) -> Result<Vec<bitcoin::BlockHash>> {
+    call(move |client| {
+        let address = client.get_new_address(None, None)?.assume_checked();
+        client.generate_to_address(blocks, &address)
+    }).await
+}
+
+// spendable balance of the node's wallet
+pub async fn get_balance() -> Result<Amount> {
+    call(|client| client.get_balance(None, None)).await
+}
+
+pub async fn send_to_address(address: Address, amount: Amount) -> Result<Txid> {
+    call(move |client| client.send_to_address(&address, amount, None, None, None, None, None, None)).await
+}
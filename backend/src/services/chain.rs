@@ -0,0 +1,292 @@
+use anyhow::{anyhow, Result};
+use ark_client::{Blockchain, ExplorerUtxo, SpendStatus};
+use bitcoin::{Address, BlockHash, Transaction, Txid};
+use bitcoincore_rpc::json::ScanTxOutRequest;
+use bitcoincore_rpc::RpcApi;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::services::ark_grpc::EsploraBlockchain;
+use crate::services::bitcoin_rpc;
+use crate::services::config::{ChainBackendKind, Config};
+use crate::services::APP_STATE;
+
+// chain data source picked with chain_backend, everything on-chain goes through here
+pub enum ChainBackend {
+    Esplora(Arc<EsploraBlockchain>),
+    BitcoinCore(BitcoinCoreBlockchain),
+}
+
+impl ChainBackend {
+    pub fn new(config: &Config) -> Result<Self> {
+        match config.chain_backend {
+            ChainBackendKind::BitcoinCore => {
+                tracing::info!("Using Bitcoin Core RPC as chain backend");
+                Ok(Self::BitcoinCore(BitcoinCoreBlockchain))
+            },
+            ChainBackendKind::Esplora => Ok(Self::Esplora(Arc::new(EsploraBlockchain::new(
+                &config.profile.esplora_urls(),
+            )?))),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Esplora(_) => "esplora",
+            Self::BitcoinCore(_) => "bitcoind",
+        }
+    }
+
+    // height and block time of the block that confirmed a tx, None while unconfirmed
+    pub async fn get_tx_confirmation(&self, txid: &Txid) -> Result<Option<(u32, u64)>> {
+        match self {
+            Self::Esplora(esplora) => esplora.get_tx_confirmation(txid).await,
+            Self::BitcoinCore(core) => core.get_tx_confirmation(txid).await,
+        }
+    }
+
+    // height and hash of the block that confirmed a tx, None while unconfirmed
+    pub async fn get_tx_block(&self, txid: &Txid) -> Result<Option<(u32, BlockHash)>> {
+        match self {
+            Self::Esplora(esplora) => esplora.get_tx_block(txid).await,
+            Self::BitcoinCore(core) => core.get_tx_block(txid).await,
+        }
+    }
+
+    // fee and virtual size of an unconfirmed tx
+    pub async fn get_tx_fee_and_vsize(&self, txid: &Txid) -> Result<Option<(u64, u64)>> {
+        match self {
+            Self::Esplora(esplora) => esplora.get_tx_fee_and_vsize(txid).await,
+            Self::BitcoinCore(core) => core.get_tx_fee_and_vsize(txid).await,
+        }
+    }
+
+    pub async fn get_height(&self) -> Result<u32> {
+        match self {
+            Self::Esplora(esplora) => esplora.get_height().await,
+            Self::BitcoinCore(core) => core.get_height().await,
+        }
+    }
+
+    pub async fn get_tip(&self) -> Result<(u32, BlockHash)> {
+        match self {
+            Self::Esplora(esplora) => esplora.get_tip().await,
+            Self::BitcoinCore(core) => core.get_tip().await,
+        }
+    }
+
+    pub async fn get_block_hash(&self, height: u32) -> Result<BlockHash> {
+        match self {
+            Self::Esplora(esplora) => esplora.get_block_hash(height).await,
+            Self::BitcoinCore(core) => core.get_block_hash(height).await,
+        }
+    }
+
+    // whether an address ever received coins, bitcoind only sees the ones it still holds
+    pub async fn address_used(&self, address: &Address) -> Result<bool> {
+        match self {
+            Self::Esplora(esplora) => Ok(!esplora.get_script_txs(&address.script_pubkey()).await?.is_empty()),
+            Self::BitcoinCore(core) => Ok(!core.find_outpoints(address).await
+                .map_err(|e| anyhow!("{}", e))?
+                .is_empty()),
+        }
+    }
+
+    pub async fn test_connectivity(&self) -> Result<()> {
+        match self {
+            Self::Esplora(esplora) => esplora.test_esplora_connectivity().await,
+            Self::BitcoinCore(core) => core.get_height().await.map(|height| {
+                tracing::info!("bitcoind is accessible, height {}", height);
+            }),
+        }
+    }
+
+    // only esplora has several servers to fail over between
+    pub fn start_health_checks(&self, interval: std::time::Duration) {
+        if let Self::Esplora(esplora) = self {
+            esplora.start_health_checks(interval);
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChainStatus {
+    pub backend: &'static str,
+    pub height: u32,
+    pub tip_hash: String,
+    pub latency_ms: u64,
+    pub synced_height: Option<u32>, // tip the confirmation tracker last checked history against
+    pub balance_updated_at: Option<i64>,
+    pub in_sync: bool, // false when balances or confirmations shown may be stale
+}
+
+// the cached balance counts as stale after this many missed background monitor runs
+const STALE_MONITOR_RUNS: i64 = 3;
+
+pub async fn status() -> Result<ChainStatus> {
+    let blockchain = &APP_STATE.blockchain;
+    let started = std::time::Instant::now();
+    let (height, tip_hash) = blockchain.get_tip().await?;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let synced_height = crate::services::confirmations::synced_height();
+    let balance_updated_at = APP_STATE.cached_balance()?
+        .map(|snapshot| snapshot.last_updated)
+        .filter(|updated| *updated > 0);
+    let stale_after = APP_STATE.monitor.status().interval_secs as i64 * STALE_MONITOR_RUNS;
+    let balance_fresh = balance_updated_at
+        .is_some_and(|updated| chrono::Utc::now().timestamp() - updated <= stale_after);
+
+    Ok(ChainStatus {
+        backend: blockchain.name(),
+        height,
+        tip_hash: tip_hash.to_string(),
+        latency_ms,
+        synced_height,
+        balance_updated_at,
+        in_sync: synced_height == Some(height) && balance_fresh,
+    })
+}
+
+impl Blockchain for ChainBackend {
+    async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, ark_client::Error> {
+        match self {
+            Self::Esplora(esplora) => esplora.find_outpoints(address).await,
+            Self::BitcoinCore(core) => core.find_outpoints(address).await,
+        }
+    }
+
+    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, ark_client::Error> {
+        match self {
+            Self::Esplora(esplora) => esplora.find_tx(txid).await,
+            Self::BitcoinCore(core) => core.find_tx(txid).await,
+        }
+    }
+
+    async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, ark_client::Error> {
+        match self {
+            Self::Esplora(esplora) => esplora.get_output_status(txid, vout).await,
+            Self::BitcoinCore(core) => core.get_output_status(txid, vout).await,
+        }
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> Result<(), ark_client::Error> {
+        match self {
+            Self::Esplora(esplora) => esplora.broadcast(tx).await,
+            Self::BitcoinCore(core) => core.broadcast(tx).await,
+        }
+    }
+}
+
+// Blockchain impl for a full node over JSON-RPC, no Esplora needed.
+// scantxoutset only sees the UTXO set, so spent outputs and unconfirmed coins don't show up
+// and looking up arbitrary confirmed txs needs bitcoind running with -txindex
+pub struct BitcoinCoreBlockchain;
+
+impl BitcoinCoreBlockchain {
+    pub async fn get_tx_confirmation(&self, txid: &Txid) -> Result<Option<(u32, u64)>> {
+        let txid = *txid;
+        bitcoin_rpc::call(move |client| {
+            let info = client.get_raw_transaction_info(&txid, None)?;
+            let Some(block_hash) = info.blockhash else {
+                return Ok(None);
+            };
+            let header = client.get_block_header_info(&block_hash)?;
+            Ok(Some((header.height as u32, header.time as u64)))
+        }).await
+    }
+
+    pub async fn get_tx_block(&self, txid: &Txid) -> Result<Option<(u32, BlockHash)>> {
+        let txid = *txid;
+        bitcoin_rpc::call(move |client| {
+            let info = client.get_raw_transaction_info(&txid, None)?;
+            let Some(block_hash) = info.blockhash else {
+                return Ok(None);
+            };
+            let header = client.get_block_header_info(&block_hash)?;
+            Ok(Some((header.height as u32, block_hash)))
+        }).await
+    }
+
+    pub async fn get_tx_fee_and_vsize(&self, txid: &Txid) -> Result<Option<(u64, u64)>> {
+        let txid = *txid;
+        bitcoin_rpc::call(move |client| match client.get_mempool_entry(&txid) {
+            Ok(entry) => Ok(Some((entry.fees.base.to_sat(), entry.vsize))),
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => Err(e),
+        }).await
+    }
+
+    pub async fn get_height(&self) -> Result<u32> {
+        bitcoin_rpc::call(|client| client.get_block_count()).await
+            .map(|height| height as u32)
+    }
+
+    pub async fn get_tip(&self) -> Result<(u32, BlockHash)> {
+        bitcoin_rpc::call(|client| client.get_blockchain_info()).await
+            .map(|info| (info.blocks as u32, info.best_block_hash))
+    }
+
+    pub async fn get_block_hash(&self, height: u32) -> Result<BlockHash> {
+        bitcoin_rpc::call(move |client| client.get_block_hash(u64::from(height))).await
+    }
+}
+
+impl Blockchain for BitcoinCoreBlockchain {
+    async fn find_outpoints(&self, address: &Address) -> Result<Vec<ExplorerUtxo>, ark_client::Error> {
+        let descriptor = format!("addr({})", address);
+        bitcoin_rpc::call(move |client| {
+            let result = client.scan_tx_out_set_blocking(&[ScanTxOutRequest::Single(descriptor)])?;
+
+            result.unspents.into_iter().map(|utxo| {
+                let block_hash = client.get_block_hash(utxo.height)?;
+                let header = client.get_block_header(&block_hash)?;
+                Ok(ExplorerUtxo {
+                    outpoint: bitcoin::OutPoint { txid: utxo.txid, vout: utxo.vout },
+                    amount: utxo.amount,
+                    confirmation_blocktime: Some(header.time as u64),
+                    is_spent: false,
+                })
+            }).collect()
+        }).await
+        .map_err(|e| ark_client::Error::wallet(anyhow!("Failed to scan UTXO set: {}", e)))
+    }
+
+    async fn find_tx(&self, txid: &Txid) -> Result<Option<Transaction>, ark_client::Error> {
+        let txid = *txid;
+        bitcoin_rpc::call(move |client| match client.get_raw_transaction(&txid, None) {
+            Ok(tx) => Ok(Some(tx)),
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => Err(e),
+        }).await
+        .map_err(|e| ark_client::Error::wallet(anyhow!("Failed to fetch transaction: {}", e)))
+    }
+
+    // gettxout can tell that an output is gone but not which tx spent it
+    async fn get_output_status(&self, txid: &Txid, vout: u32) -> Result<SpendStatus, ark_client::Error> {
+        let txid = *txid;
+        let unspent = bitcoin_rpc::call(move |client| client.get_tx_out(&txid, vout, Some(true))).await
+            .map_err(|e| ark_client::Error::wallet(anyhow!("Failed to get output status: {}", e)))?;
+        if unspent.is_none() {
+            tracing::debug!("{}:{} is spent or unknown, bitcoind can't tell the spending tx", txid, vout);
+        }
+
+        Ok(SpendStatus { spend_txid: None })
+    }
+
+    async fn broadcast(&self, tx: &Transaction) -> Result<(), ark_client::Error> {
+        let tx = tx.clone();
+        let txid = bitcoin_rpc::call(move |client| client.send_raw_transaction(&tx)).await
+            .map_err(|e| ark_client::Error::wallet(anyhow!("Failed to broadcast transaction: {}", e)))?;
+        tracing::info!("Successfully broadcast transaction: {}", txid);
+        Ok(())
+    }
+}
+
+// RPC_INVALID_ADDRESS_OR_KEY, what bitcoind returns for unknown txs
+fn is_not_found(e: &bitcoincore_rpc::Error) -> bool {
+    matches!(
+        e,
+        bitcoincore_rpc::Error::JsonRpc(bitcoincore_rpc::jsonrpc::Error::Rpc(rpc)) if rpc.code == -5
+    )
+}
@@ -0,0 +1,571 @@
+use anyhow::{anyhow, bail, Context, Result};
+use bitcoin::Network;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::str::FromStr;
+
+use crate::services::profiles::{self, NetworkProfile};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChainBackendKind {
+    Esplora,
+    BitcoinCore,
+}
+
+impl FromStr for ChainBackendKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "esplora" => Ok(Self::Esplora),
+            "bitcoind" | "bitcoin_core" => Ok(Self::BitcoinCore),
+            other => Err(anyhow!("unknown chain backend '{}', expected esplora or bitcoind", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BitcoindConfig {
+    pub rpc_url: Option<String>, // None picks the network's default port on localhost
+    pub rpc_cookie: Option<String>,
+    pub rpc_user: String,
+    pub rpc_password: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignerKind {
+    Local,
+    Remote,
+}
+
+impl FromStr for SignerKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "local" => Ok(Self::Local),
+            "remote" => Ok(Self::Remote),
+            other => Err(anyhow!("unknown signer '{}', expected local or remote", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignerConfig {
+    pub kind: SignerKind,
+    pub url: Option<String>, // the signing daemon, required for remote
+}
+
+// HTTPS on the API port, from cert/key files or a generated self-signed pair for dev
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
+    pub self_signed: bool,
+}
+
+impl TlsConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.self_signed || self.cert_path.is_some()
+    }
+}
+
+// origins, methods and headers browsers may use, "*" allows any
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+}
+
+// sends above the threshold wait for a holder of the approver token, disabled unless both are set.
+// the token also guards changes to the spending policy
+#[derive(Debug, Clone)]
+pub struct ApprovalConfig {
+    pub threshold_sats: Option<u64>,
+    pub approver_token: Option<String>,
+}
+
+impl ApprovalConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.threshold_sats.is_some() && self.approver_token.is_some()
+    }
+}
+
+// scheduled encrypted snapshots, disabled unless both dir and passphrase are set
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    pub dir: Option<String>,
+    pub passphrase: Option<String>,
+    pub interval_secs: u64,
+    pub keep: usize, // newest snapshots kept, older ones are deleted
+}
+
+impl BackupConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some() && self.passphrase.is_some()
+    }
+}
+
+// everything the backend reads at startup, loaded once from config.toml and env overrides
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub profile: NetworkProfile,
+    pub port: u16,
+    pub data_dir: String,
+    pub chain_backend: ChainBackendKind,
+    pub bitcoind: BitcoindConfig,
+    pub fee_refresh_interval_secs: u64,
+    pub monitor_interval_secs: u64,
+    pub dev_routes: bool,
+    pub simulation_mode: bool, // fake Ark operations for frontend work, never against a real ASP
+    pub strict_mode: bool, // false answers with simulated data while the Ark client is down, dev only
+    pub boltz_url: Option<String>, // swaps are disabled without it
+    pub lock_idle_timeout_secs: u64, // 0 keeps an unlocked wallet unlocked
+    pub rates_url: Option<String>, // BTC price API, fiat conversion is disabled without it
+    pub rates_refresh_secs: u64,
+    pub address_gap_limit: u32, // unused addresses in a row before discovery stops scanning a chain
+    pub backup: BackupConfig,
+    pub signer: SignerConfig,
+    pub approvals: ApprovalConfig,
+    pub cors: CorsConfig,
+    pub tls: TlsConfig,
+}
+
+// the file layout, every field optional so a partial file (or none) works
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    profile: Option<String>,
+    network: Option<String>,
+    ark_server_url: Option<String>,
+    esplora_url: Option<String>,
+    esplora_fallback_urls: Option<Vec<String>>,
+    port: Option<u16>,
+    data_dir: Option<String>,
+    chain_backend: Option<String>,
+    fee_refresh_interval_secs: Option<u64>,
+    monitor_interval_secs: Option<u64>,
+    dev_routes: Option<bool>,
+    simulation_mode: Option<bool>,
+    strict_mode: Option<bool>,
+    boltz_url: Option<String>,
+    lock_idle_timeout_secs: Option<u64>,
+    rates_url: Option<String>,
+    rates_refresh_secs: Option<u64>,
+    address_gap_limit: Option<u32>,
+    #[serde(default)]
+    bitcoind: FileBitcoindConfig,
+    #[serde(default)]
+    backup: FileBackupConfig,
+    #[serde(default)]
+    signer: FileSignerConfig,
+    #[serde(default)]
+    approvals: FileApprovalConfig,
+    #[serde(default)]
+    cors: FileCorsConfig,
+    #[serde(default)]
+    tls: FileTlsConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileBitcoindConfig {
+    rpc_url: Option<String>,
+    rpc_cookie: Option<String>,
+    rpc_user: Option<String>,
+    rpc_password: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileBackupConfig {
+    dir: Option<String>,
+    passphrase: Option<String>,
+    interval_secs: Option<u64>,
+    keep: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileSignerConfig {
+    kind: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileApprovalConfig {
+    threshold_sats: Option<u64>,
+    approver_token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileCorsConfig {
+    allowed_origins: Option<Vec<String>>,
+    allowed_methods: Option<Vec<String>>,
+    allowed_headers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileTlsConfig {
+    cert_path: Option<String>,
+    key_path: Option<String>,
+    self_signed: Option<bool>,
+}
+
+impl FileConfig {
+    // CONFIG_FILE must exist when set, the default config.toml is optional
+    fn read() -> Result<Self> {
+        let (path, required) = match std::env::var("CONFIG_FILE") {
+            Ok(path) => (path, true),
+            Err(_) => ("config.toml".to_string(), false),
+        };
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(anyhow!("Failed to read config file {}: {}", path, e)),
+        };
+        tracing::info!("Loading config from {}", path);
+
+        toml::from_str(&contents).with_context(|| format!("Invalid config file {}", path))
+    }
+
+    // env vars win over the file, same names the backend has always used
+    fn apply_env(&mut self) -> Result<()> {
+        env_string("NETWORK_PROFILE", &mut self.profile);
+        env_string("BITCOIN_NETWORK", &mut self.network);
+        env_string("ARK_SERVER_URL", &mut self.ark_server_url);
+        env_string("ESPLORA_URL", &mut self.esplora_url);
+        env_list("ESPLORA_FALLBACK_URLS", &mut self.esplora_fallback_urls);
+        env_parse("PORT", &mut self.port)?;
+        env_string("DATA_DIR", &mut self.data_dir);
+        env_string("CHAIN_BACKEND", &mut self.chain_backend);
+        env_parse("FEE_REFRESH_INTERVAL_SECS", &mut self.fee_refresh_interval_secs)?;
+        env_parse("MONITOR_INTERVAL_SECS", &mut self.monitor_interval_secs)?;
+        if let Ok(value) = std::env::var("DEV_ROUTES") {
+            self.dev_routes = Some(value == "true" || value == "1");
+        }
+        if let Ok(value) = std::env::var("SIMULATION_MODE") {
+            self.simulation_mode = Some(value == "true" || value == "1");
+        }
+        if let Ok(value) = std::env::var("STRICT_MODE") {
+            self.strict_mode = Some(value == "true" || value == "1");
+        }
+        env_string("BOLTZ_URL", &mut self.boltz_url);
+        env_parse("LOCK_IDLE_TIMEOUT_SECS", &mut self.lock_idle_timeout_secs)?;
+        env_string("RATES_URL", &mut self.rates_url);
+        env_parse("RATES_REFRESH_SECS", &mut self.rates_refresh_secs)?;
+        env_parse("ADDRESS_GAP_LIMIT", &mut self.address_gap_limit)?;
+        env_string("BITCOIND_RPC_URL", &mut self.bitcoind.rpc_url);
+        env_string("BITCOIND_RPC_COOKIE", &mut self.bitcoind.rpc_cookie);
+        env_string("BITCOIND_RPC_USER", &mut self.bitcoind.rpc_user);
+        env_string("BITCOIND_RPC_PASSWORD", &mut self.bitcoind.rpc_password);
+        env_string("BACKUP_DIR", &mut self.backup.dir);
+        env_string("BACKUP_PASSPHRASE", &mut self.backup.passphrase);
+        env_parse("BACKUP_INTERVAL_SECS", &mut self.backup.interval_secs)?;
+        env_parse("BACKUP_KEEP", &mut self.backup.keep)?;
+        env_string("SIGNER", &mut self.signer.kind);
+        env_string("SIGNER_URL", &mut self.signer.url);
+        env_parse("APPROVAL_THRESHOLD_SATS", &mut self.approvals.threshold_sats)?;
+        env_string("APPROVER_TOKEN", &mut self.approvals.approver_token);
+        env_list("CORS_ALLOWED_ORIGINS", &mut self.cors.allowed_origins);
+        env_list("CORS_ALLOWED_METHODS", &mut self.cors.allowed_methods);
+        env_list("CORS_ALLOWED_HEADERS", &mut self.cors.allowed_headers);
+        env_string("TLS_CERT_PATH", &mut self.tls.cert_path);
+        env_string("TLS_KEY_PATH", &mut self.tls.key_path);
+        if let Ok(value) = std::env::var("TLS_SELF_SIGNED") {
+            self.tls.self_signed = Some(value == "true" || value == "1");
+        }
+        Ok(())
+    }
+
+    // a named profile supplies defaults, explicit fields override it
+    fn build(self) -> Result<Config> {
+        let network = match &self.network {
+            Some(value) => Some(profiles::parse_network(value)
+                .ok_or_else(|| anyhow!("unknown network '{}', expected mainnet, testnet, signet or regtest", value))?),
+            None => None,
+        };
+
+        let mut profile = match self.profile.as_deref() {
+            None | Some("custom") => NetworkProfile::custom(network.unwrap_or(Network::Regtest)),
+            Some(name) => NetworkProfile::by_name(name)
+                .ok_or_else(|| anyhow!("unknown network profile '{}', expected regtest-nigiri, mutinynet-arkade or custom", name))?,
+        };
+        if let Some(network) = network {
+            profile.network = network;
+        }
+        if let Some(url) = self.ark_server_url {
+            profile.ark_server_url = url;
+        }
+        if let Some(url) = self.esplora_url {
+            profile.esplora_url = url;
+        }
+        if let Some(urls) = self.esplora_fallback_urls {
+            profile.esplora_fallback_urls = urls;
+        }
+
+        let chain_backend = match &self.chain_backend {
+            Some(value) => value.parse()?,
+            None => ChainBackendKind::Esplora,
+        };
+
+        let signer_kind = match &self.signer.kind {
+            Some(value) => value.parse()?,
+            None => SignerKind::Local,
+        };
+
+        // simulated history and keys live apart from the real wallet's
+        let simulation_mode = self.simulation_mode.unwrap_or(false);
+        let mut data_dir = self.data_dir.unwrap_or_else(|| "./data".to_string());
+        if simulation_mode {
+            data_dir = format!("{}/simulation", data_dir.trim_end_matches('/'));
+        }
+
+        let config = Config {
+            profile,
+            port: self.port.unwrap_or(3000),
+            data_dir,
+            chain_backend,
+            bitcoind: BitcoindConfig {
+                rpc_url: self.bitcoind.rpc_url,
+                rpc_cookie: self.bitcoind.rpc_cookie,
+                rpc_user: self.bitcoind.rpc_user.unwrap_or_else(|| "admin1".to_string()),
+                rpc_password: self.bitcoind.rpc_password.unwrap_or_else(|| "123".to_string()),
+            },
+            fee_refresh_interval_secs: self.fee_refresh_interval_secs.unwrap_or(60),
+            monitor_interval_secs: self.monitor_interval_secs.unwrap_or(30),
+            dev_routes: self.dev_routes.unwrap_or(false),
+            simulation_mode,
+            strict_mode: self.strict_mode.unwrap_or(true),
+            boltz_url: self.boltz_url,
+            lock_idle_timeout_secs: self.lock_idle_timeout_secs.unwrap_or(900),
+            rates_url: self.rates_url,
+            rates_refresh_secs: self.rates_refresh_secs.unwrap_or(300),
+            address_gap_limit: self.address_gap_limit.unwrap_or(20),
+            backup: BackupConfig {
+                dir: self.backup.dir,
+                passphrase: self.backup.passphrase,
+                interval_secs: self.backup.interval_secs.unwrap_or(86400),
+                keep: self.backup.keep.unwrap_or(7),
+            },
+            signer: SignerConfig {
+                kind: signer_kind,
+                url: self.signer.url,
+            },
+            approvals: ApprovalConfig {
+                threshold_sats: self.approvals.threshold_sats,
+                approver_token: self.approvals.approver_token,
+            },
+            // only the bundled frontend on its dev server port unless configured
+            cors: CorsConfig {
+                allowed_origins: self.cors.allowed_origins
+                    .unwrap_or_else(|| vec!["http://localhost:3000".to_string()]),
+                allowed_methods: self.cors.allowed_methods
+                    .unwrap_or_else(|| ["GET", "POST", "PUT", "DELETE"].map(String::from).to_vec()),
+                allowed_headers: self.cors.allowed_headers
+                    .unwrap_or_else(|| ["Content-Type", "Authorization", "Idempotency-Key"].map(String::from).to_vec()),
+            },
+            tls: TlsConfig {
+                cert_path: self.tls.cert_path,
+                key_path: self.tls.key_path,
+                self_signed: self.tls.self_signed.unwrap_or(false),
+            },
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        let mut file = FileConfig::read()?;
+        file.apply_env()?;
+        file.build()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        check_url("ark_server_url", &self.profile.ark_server_url)?;
+        for url in self.profile.esplora_urls() {
+            check_url("esplora_url", &url)?;
+        }
+        if let Some(url) = &self.bitcoind.rpc_url {
+            check_url("bitcoind.rpc_url", url)?;
+        }
+        if let Some(url) = &self.boltz_url {
+            check_url("boltz_url", url)?;
+        }
+        if let Some(url) = &self.rates_url {
+            check_url("rates_url", url)?;
+        }
+        if self.port == 0 {
+            bail!("port must not be 0");
+        }
+        if self.data_dir.trim().is_empty() {
+            bail!("data_dir must not be empty");
+        }
+        if self.fee_refresh_interval_secs == 0 {
+            bail!("fee_refresh_interval_secs must be at least 1");
+        }
+        if self.monitor_interval_secs == 0 {
+            bail!("monitor_interval_secs must be at least 1");
+        }
+        if self.rates_refresh_secs < 10 {
+            bail!("rates_refresh_secs must be at least 10");
+        }
+        if !(1..=1000).contains(&self.address_gap_limit) {
+            bail!("address_gap_limit must be between 1 and 1000");
+        }
+        if self.backup.dir.is_some() != self.backup.passphrase.is_some() {
+            bail!("backup.dir and backup.passphrase must be set together");
+        }
+        if self.backup.passphrase.as_ref().is_some_and(|passphrase| passphrase.chars().count() < 8) {
+            bail!("backup.passphrase must be at least 8 characters");
+        }
+        if self.backup.interval_secs < 60 {
+            bail!("backup.interval_secs must be at least 60");
+        }
+        if self.backup.keep == 0 {
+            bail!("backup.keep must be at least 1");
+        }
+        match (self.signer.kind, &self.signer.url) {
+            (SignerKind::Remote, None) => bail!("signer.url is required for the remote signer"),
+            (_, Some(url)) => check_url("signer.url", url)?,
+            _ => {},
+        }
+        // the token alone only guards policy changes, a threshold without it couldn't be approved
+        if self.approvals.threshold_sats.is_some() && self.approvals.approver_token.is_none() {
+            bail!("approvals.threshold_sats needs approvals.approver_token");
+        }
+        if self.approvals.approver_token.as_ref().is_some_and(|token| token.len() < 16) {
+            bail!("approvals.approver_token must be at least 16 characters");
+        }
+        if self.tls.cert_path.is_some() != self.tls.key_path.is_some() {
+            bail!("tls.cert_path and tls.key_path must be set together");
+        }
+        if self.tls.self_signed && self.tls.cert_path.is_some() {
+            bail!("tls.self_signed can't be combined with tls.cert_path");
+        }
+        for origin in self.cors.allowed_origins.iter().filter(|origin| *origin != "*") {
+            check_url("cors.allowed_origins", origin)?;
+            if origin.trim_end_matches('/').matches('/').count() > 2 {
+                bail!("cors.allowed_origins entries are scheme://host[:port] without a path, got '{}'", origin);
+            }
+        }
+        for method in self.cors.allowed_methods.iter().filter(|method| *method != "*") {
+            axum::http::Method::from_bytes(method.as_bytes())
+                .map_err(|_| anyhow!("cors.allowed_methods has invalid method '{}'", method))?;
+        }
+        for header in self.cors.allowed_headers.iter().filter(|header| *header != "*") {
+            axum::http::HeaderName::from_bytes(header.as_bytes())
+                .map_err(|_| anyhow!("cors.allowed_headers has invalid header '{}'", header))?;
+        }
+        Ok(())
+    }
+}
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+// called first thing in main so a bad value stops startup with a readable error
+pub fn init() -> Result<&'static Config> {
+    CONFIG.get_or_try_init(Config::load)
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| Config::load().unwrap_or_else(|e| panic!("Invalid configuration: {:#}", e)))
+}
+
+fn check_url(field: &str, url: &str) -> Result<()> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(anyhow!("{} must be an http(s) URL, got '{}'", field, url))
+    }
+}
+
+fn env_string(key: &str, target: &mut Option<String>) {
+    if let Ok(value) = std::env::var(key) {
+        *target = Some(value);
+    }
+}
+
+// comma separated, empty entries dropped
+fn env_list(key: &str, target: &mut Option<Vec<String>>) {
+    if let Ok(value) = std::env::var(key) {
+        *target = Some(value.split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect());
+    }
+}
+
+fn env_parse<T: FromStr>(key: &str, target: &mut Option<T>) -> Result<()>
+where
+    T::Err: std::fmt::Display,
+{
+    if let Ok(value) = std::env::var(key) {
+        *target = Some(value.parse().map_err(|e| anyhow!("{} has invalid value '{}': {}", key, value, e))?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(toml: &str) -> Result<Config> {
+        toml::from_str::<FileConfig>(toml)?.build()
+    }
+
+    #[test]
+    fn test_empty_file_uses_defaults() {
+        let config = parse("").unwrap();
+        assert_eq!(config.profile.name, "custom");
+        assert_eq!(config.port, 3000);
+        assert_eq!(config.chain_backend, ChainBackendKind::Esplora);
+        assert_eq!(config.cors.allowed_origins, ["http://localhost:3000"]);
+    }
+
+    #[test]
+    fn test_fields_override_the_profile() {
+        let config = parse(r#"
+            profile = "mutinynet-arkade"
+            esplora_url = "https://esplora.example.com"
+
+            [bitcoind]
+            rpc_user = "alice"
+        "#).unwrap();
+        assert_eq!(config.profile.ark_server_url, "https://mutinynet.arkade.sh");
+        assert_eq!(config.profile.esplora_url, "https://esplora.example.com");
+        assert_eq!(config.bitcoind.rpc_user, "alice");
+    }
+
+    #[test]
+    fn test_rejects_bad_values() {
+        assert!(parse(r#"profile = "mainnet-typo""#).is_err());
+        assert!(parse(r#"network = "bitcoin-cash""#).is_err());
+        assert!(parse(r#"ark_server_url = "localhost:7070""#).is_err());
+        assert!(parse(r#"chain_backend = "electrum""#).is_err());
+        assert!(parse("monitor_interval_secs = 0").is_err());
+        assert!(parse("address_gap_limit = 0").is_err());
+        assert!(parse("unknown_key = 1").is_err());
+        assert!(parse("[backup]\ndir = \"/backups\"").is_err());
+        assert!(parse("[signer]\nkind = \"remote\"").is_err());
+        assert!(parse("[approvals]\nthreshold_sats = 100000").is_err());
+        assert!(parse("[cors]\nallowed_origins = [\"localhost:3000\"]").is_err());
+        assert!(parse("[cors]\nallowed_origins = [\"https://wallet.example.com/app\"]").is_err());
+        assert!(parse("[cors]\nallowed_methods = [\"GET POST\"]").is_err());
+        assert!(parse("[tls]\ncert_path = \"cert.pem\"").is_err());
+    }
+
+    #[test]
+    fn test_simulation_mode_uses_its_own_data_dir() {
+        let config = parse("data_dir = \"/var/ark/\"\nsimulation_mode = true").unwrap();
+        assert_eq!(config.data_dir, "/var/ark/simulation");
+        assert_eq!(parse("data_dir = \"/var/ark\"").unwrap().data_dir, "/var/ark");
+    }
+}
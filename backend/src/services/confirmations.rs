@@ -0,0 +1,143 @@
+use anyhow::Result;
+use bitcoin::{BlockHash, Txid};
+use rusqlite::params;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::services::APP_STATE;
+
+// history entries with an on-chain txid, everything else is settled off-chain
+const TRACKED_TYPES: &[&str] = &["OnChain", "Boarding", "Round", "Exit"];
+
+// deep enough that a reorg isn't expected, confirmed txs stop being rechecked after this
+pub const FINAL_CONFIRMATIONS: u32 = 6;
+
+// checked per pass, newest first, so a backlog of old unconfirmed txs can't stall the tracker
+const BATCH_SIZE: i64 = 200;
+
+// chain tip as of the last completed pass, confirmations are counted against it
+static TIP_HEIGHT: AtomicU32 = AtomicU32::new(0);
+
+pub fn start_tracker(interval: Duration) {
+    if crate::services::simulation::is_enabled() {
+        return;
+    }
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = poll().await {
+                tracing::warn!("Failed to update confirmations: {}", e);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// whether history entries of this type have a txid on the chain
+pub fn is_on_chain(type_name: &str) -> bool {
+    TRACKED_TYPES.contains(&type_name)
+}
+
+// confirmations for a history entry, None for off-chain txs, for placeholder txids such as
+// round_<ts> that never reach the chain, and until the tracker's first pass has a tip to count from
+pub fn count(type_name: &str, txid: &str, block_height: Option<u32>) -> Option<u32> {
+    if !is_on_chain(type_name) || Txid::from_str(txid).is_err() {
+        return None;
+    }
+    Some(confirmations(synced_height()?, block_height))
+}
+
+// None until the tracker's first pass
+pub fn synced_height() -> Option<u32> {
+    Some(TIP_HEIGHT.load(Ordering::Relaxed)).filter(|height| *height > 0)
+}
+
+fn confirmations(tip: u32, block_height: Option<u32>) -> u32 {
+    match block_height {
+        Some(height) => tip.saturating_sub(height) + 1,
+        None => 0,
+    }
+}
+
+async fn poll() -> Result<()> {
+    let tip = APP_STATE.blockchain.get_height().await?;
+    for txid in pending(tip)? {
+        let Ok(parsed) = Txid::from_str(&txid) else {
+            continue;
+        };
+        match APP_STATE.blockchain.get_tx_block(&parsed).await {
+            Ok(block) => store(&txid, block).await?,
+            Err(e) => tracing::debug!("Failed to check confirmation of {}: {}", txid, e),
+        }
+    }
+
+    // only now is history in step with this tip
+    TIP_HEIGHT.store(tip, Ordering::Relaxed);
+    Ok(())
+}
+
+// unconfirmed txs, and confirmed ones that could still be reorged out. Placeholder txids are left out
+fn pending(tip: u32) -> Result<Vec<String>> {
+    let placeholders = vec!["?"; TRACKED_TYPES.len()].join(", ");
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT txid FROM transactions
+         WHERE type_name IN ({}) AND (block_height IS NULL OR block_height > ?)
+           AND length(txid) = 64
+         ORDER BY timestamp DESC LIMIT ?",
+        placeholders
+    ))?;
+
+    let mut values: Vec<rusqlite::types::Value> = TRACKED_TYPES.iter()
+        .map(|type_name| rusqlite::types::Value::Text(type_name.to_string()))
+        .collect();
+    values.push(rusqlite::types::Value::Integer(i64::from(tip) + 1 - i64::from(FINAL_CONFIRMATIONS)));
+    values.push(rusqlite::types::Value::Integer(BATCH_SIZE));
+
+    let txids = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(txids)
+}
+
+// None clears a block that was reorged out
+async fn store(txid: &str, block: Option<(u32, BlockHash)>) -> Result<()> {
+    let block_height = block.map(|(height, _)| height);
+    let block_hash = block.map(|(_, hash)| hash.to_string());
+
+    {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.execute(
+            "UPDATE transactions SET block_height = ?, block_hash = ? WHERE txid = ?",
+            params![block_height, block_hash, txid],
+        )?;
+    }
+
+    let mut transactions = APP_STATE.transactions.lock().await;
+    if let Some(tx) = transactions.iter_mut().find(|tx| tx.txid == txid) {
+        tx.block_height = block_height;
+        tx.block_hash = block_hash;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirmations() {
+        assert_eq!(confirmations(100, None), 0);
+        assert_eq!(confirmations(100, Some(100)), 1);
+        assert_eq!(confirmations(100, Some(95)), 6);
+    }
+
+    #[test]
+    fn test_count_skips_untracked_entries() {
+        let txid = "a".repeat(64);
+        assert_eq!(count("Redeem", &txid, Some(95)), None);
+        assert_eq!(count("Round", "round_1700000000", None), None);
+        // the tracker hasn't fetched a tip yet
+        assert_eq!(count("OnChain", &txid, Some(95)), None);
+    }
+}
@@ -0,0 +1,122 @@
+use ark_core::ArkAddress;
+use rusqlite::{params, OptionalExtension};
+use std::str::FromStr;
+
+use crate::models::wallet::{Contact, ContactKind, ContactRequest};
+use crate::services::APP_STATE;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ContactError {
+    #[error("Contact not found: {0}")]
+    NotFound(i64),
+    #[error("Invalid contact: {0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+impl From<rusqlite::Error> for ContactError {
+    fn from(e: rusqlite::Error) -> Self {
+        ContactError::Storage(e.into())
+    }
+}
+
+// works out whether the address is an Ark or on-chain address for the active network
+pub fn classify_address(address: &str) -> Result<ContactKind, ContactError> {
+    if ArkAddress::decode(address).is_ok() {
+        return Ok(ContactKind::Ark);
+    }
+
+    let network = crate::services::profiles::active().network;
+    bitcoin::Address::from_str(address)
+        .map_err(|e| ContactError::Invalid(format!("Not an Ark or Bitcoin address: {}", e)))?
+        .require_network(network)
+        .map_err(|e| ContactError::Invalid(format!("Address is not valid for {}: {}", network, e)))?;
+
+    Ok(ContactKind::Onchain)
+}
+
+fn validate(request: &ContactRequest) -> Result<ContactKind, ContactError> {
+    if request.name.trim().is_empty() {
+        return Err(ContactError::Invalid("Name must not be empty".to_string()));
+    }
+    classify_address(request.address.trim())
+}
+
+pub fn list_contacts() -> Result<Vec<Contact>, ContactError> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT id, name, address, kind, created_at FROM contacts ORDER BY name COLLATE NOCASE"
+    )?;
+
+    let contacts = stmt
+        .query_map([], row_to_contact)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(contacts)
+}
+
+pub fn get_contact(id: i64) -> Result<Contact, ContactError> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    conn.query_row(
+        "SELECT id, name, address, kind, created_at FROM contacts WHERE id = ?",
+        params![id],
+        row_to_contact,
+    )
+    .optional()?
+    .ok_or(ContactError::NotFound(id))
+}
+
+pub fn create_contact(request: ContactRequest) -> Result<Contact, ContactError> {
+    let kind = validate(&request)?;
+    let created_at = chrono::Utc::now().timestamp();
+
+    let id = {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.execute(
+            "INSERT INTO contacts (name, address, kind, created_at) VALUES (?, ?, ?, ?)",
+            params![request.name.trim(), request.address.trim(), kind.as_str(), created_at],
+        )?;
+        conn.last_insert_rowid()
+    };
+
+    get_contact(id)
+}
+
+pub fn update_contact(id: i64, request: ContactRequest) -> Result<Contact, ContactError> {
+    let kind = validate(&request)?;
+
+    let updated = {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.execute(
+            "UPDATE contacts SET name = ?, address = ?, kind = ? WHERE id = ?",
+            params![request.name.trim(), request.address.trim(), kind.as_str(), id],
+        )?
+    };
+
+    if updated == 0 {
+        return Err(ContactError::NotFound(id));
+    }
+    get_contact(id)
+}
+
+pub fn delete_contact(id: i64) -> Result<(), ContactError> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let deleted = conn.execute("DELETE FROM contacts WHERE id = ?", params![id])?;
+
+    if deleted == 0 {
+        return Err(ContactError::NotFound(id));
+    }
+    Ok(())
+}
+
+fn row_to_contact(row: &rusqlite::Row) -> rusqlite::Result<Contact> {
+    let kind: String = row.get(3)?;
+    Ok(Contact {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        address: row.get(2)?,
+        kind: if kind == "ark" { ContactKind::Ark } else { ContactKind::Onchain },
+        created_at: row.get(4)?,
+    })
+}
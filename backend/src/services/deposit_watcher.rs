@@ -0,0 +1,109 @@
+use anyhow::Result;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::wallet::{TransactionKind, TransactionResponse};
+use crate::services::ark_grpc::EsploraBlockchain;
+use crate::services::chain::ChainBackend;
+use crate::services::transactions;
+use crate::services::APP_STATE;
+
+// picks up payments to the wallet's on-chain and boarding addresses while they are still in the mempool,
+// the history sync only runs with the background monitor and doesn't tell unconfirmed deposits apart
+pub fn start(interval: Duration) {
+    if crate::services::simulation::is_enabled() {
+        return;
+    }
+    // bitcoind's scantxoutset can't see the mempool
+    let ChainBackend::Esplora(esplora) = APP_STATE.blockchain.as_ref() else {
+        tracing::info!("Mempool deposit watcher needs the esplora chain backend, not started");
+        return;
+    };
+
+    let esplora = Arc::clone(esplora);
+    tokio::spawn(async move {
+        loop {
+            // the watched addresses are derived from the key
+            if !APP_STATE.wallet_lock.skips("the mempool deposit watcher") {
+                if let Err(e) = poll(&esplora).await {
+                    tracing::warn!("Mempool deposit watcher failed: {}", e);
+                }
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+async fn poll(esplora: &EsploraBlockchain) -> Result<()> {
+    let network = crate::services::profiles::active().network;
+    for (address, kind) in watched_addresses().await? {
+        let script = address.script_pubkey();
+        for tx in esplora.get_script_txs(&script).await? {
+            let amount: u64 = tx.vout.iter()
+                .filter(|output| output.scriptpubkey == script)
+                .map(|output| output.value)
+                .sum();
+            // spends from the address are recorded when they are sent
+            if amount == 0 {
+                continue;
+            }
+
+            let txid = tx.txid.to_string();
+            match (transactions::find_stored_transaction(&txid)?, tx.status.confirmed) {
+                (None, false) => {
+                    // the first input's address, a deposit from several is credited to the first
+                    let sender = tx.vin.iter()
+                        .find_map(|input| input.prevout.as_ref())
+                        .and_then(|prevout| bitcoin::Address::from_script(&prevout.scriptpubkey, network).ok());
+                    // the history sync skips txids it already has, so this is the only place this deposit is screened
+                    let screening = crate::services::screening::screen_or_hold(&APP_STATE.blockchain, &txid, amount).await;
+                    // no fee, paid by the sender
+                    let deposit = TransactionResponse {
+                        counterparty_address: sender.map(|address| address.to_string()),
+                        is_settled: Some(false),
+                        screening,
+                        confirmations: Some(0),
+                        ..TransactionResponse::new(txid, kind.clone(), amount as i64, chrono::Utc::now().timestamp())
+                    };
+                    transactions::record_transaction(&deposit).await?;
+                    APP_STATE.monitor.note_deposit(&deposit);
+                    APP_STATE.recalculate_balance().await?;
+                },
+                // seen unconfirmed before, the block time replaces when we first saw it.
+                // the stored entry carries its screening, so confirming keeps it
+                (Some(mut recorded), true) if recorded.is_settled == Some(false) => {
+                    recorded.is_settled = Some(true);
+                    if let Some(block_time) = tx.status.block_time {
+                        recorded.timestamp = block_time as i64;
+                    }
+                    transactions::record_transaction(&recorded).await?;
+                    APP_STATE.recalculate_balance().await?;
+                },
+                _ => {},
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn watched_addresses() -> Result<Vec<(bitcoin::Address, TransactionKind)>> {
+    let mut addresses: Vec<_> = crate::services::wallet::get_onchain_addresses().await?
+        .into_iter()
+        .map(|address| (address, TransactionKind::OnChain))
+        .collect();
+
+    // only known while the Ark client is connected
+    match crate::services::wallet::get_boarding_address().await {
+        Ok(boarding) => {
+            let network = crate::services::profiles::active().network;
+            match bitcoin::Address::from_str(&boarding.address).map(|address| address.require_network(network)) {
+                Ok(Ok(address)) => addresses.push((address, TransactionKind::Boarding)),
+                _ => tracing::warn!("Ignoring invalid boarding address {}", boarding.address),
+            }
+        },
+        Err(e) => tracing::debug!("Not watching the boarding address: {}", e),
+    }
+
+    Ok(addresses)
+}
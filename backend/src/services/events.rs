@@ -0,0 +1,119 @@
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WalletEvent {
+    BalanceUpdate {
+        confirmed: u64,
+        pending: u64,
+        total: u64,
+    },
+    VtxoUpdate {
+        outpoint: String,
+        amount: u64,
+        status: VtxoStatus,
+    },
+    RoundCompleted {
+        txid: String,
+    },
+    RoundStarted,
+    RoundSigning {
+        round_id: String,
+    },
+    RoundFinalizing {
+        round_id: String,
+    },
+    RoundFinalized {
+        round_id: String,
+        txid: String,
+    },
+    RoundFailed {
+        round_id: Option<String>,
+        reason: String,
+    },
+    VtxoExpiring {
+        outpoint: String,
+        expire_at: i64,
+        critical: bool,
+    },
+    DepositDetected {
+        txid: String,
+        amount: u64,
+        type_name: String,
+    },
+    WatchedAddressActivity {
+        address: String,
+        txid: String,
+        amount: i64,
+        confirmed: bool,
+    },
+    ServerInfoChanged {
+        changes: Vec<String>,
+    },
+    ExpiredVtxosSwept {
+        vtxos: usize,
+        amount: u64,
+    },
+    ExitClaimed {
+        vtxo_txid: String,
+        claim_txid: String,
+        amount: u64,
+    },
+    SwapUpdated {
+        id: String,
+        status: String,
+    },
+    PaymentRequestPaid {
+        id: String,
+        outpoint: String,
+        amount: u64,
+    },
+    ApprovalUpdated {
+        id: String,
+        status: String,
+        amount: u64,
+    },
+}
+
+impl WalletEvent {
+    pub fn is_round_event(&self) -> bool {
+        matches!(
+            self,
+            WalletEvent::RoundCompleted { .. }
+                | WalletEvent::RoundStarted
+                | WalletEvent::RoundSigning { .. }
+                | WalletEvent::RoundFinalizing { .. }
+                | WalletEvent::RoundFinalized { .. }
+                | WalletEvent::RoundFailed { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VtxoStatus {
+    Added,
+    Spent,
+}
+
+// fan-out of wallet events to any number of subscribers (websocket clients etc.)
+pub struct EventBus {
+    sender: broadcast::Sender<WalletEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: WalletEvent) {
+        // send only fails when nobody is subscribed, which is fine
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WalletEvent> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,665 @@
+use anyhow::{anyhow, Result};
+use bitcoin::relative::LockTime;
+use bitcoin::{Address, Amount, Sequence, Txid};
+use parking_lot::RwLock;
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::wallet::{TransactionKind, TransactionResponse};
+use crate::services::events::WalletEvent;
+use crate::services::APP_STATE;
+use crate::storage::DbManager;
+
+// one input spending the CSV leaf and one p2wpkh output
+const CLAIM_TX_VBYTES: u64 = 200;
+pub(crate) const BLOCK_INTERVAL_SECS: u64 = 600;
+// time on top of the unroll itself for the branch to confirm before the VTXO expires
+const EXIT_MARGIN_SECS: u64 = 6 * BLOCK_INTERVAL_SECS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitPhase {
+    Unrolling,       // branch txs are still being broadcast
+    WaitingTimelock, // leaf is on chain, the CSV exit path isn't spendable yet
+    Claimable,
+    Claiming, // claim being broadcast, only ever held in memory
+    Claimed,
+}
+
+impl ExitPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExitPhase::Unrolling => "unrolling",
+            ExitPhase::WaitingTimelock => "waiting_timelock",
+            ExitPhase::Claimable => "claimable",
+            ExitPhase::Claiming => "claiming",
+            ExitPhase::Claimed => "claimed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "waiting_timelock" => ExitPhase::WaitingTimelock,
+            "claimable" => ExitPhase::Claimable,
+            "claimed" => ExitPhase::Claimed,
+            _ => ExitPhase::Unrolling,
+        }
+    }
+}
+
+// another claim of the same exit got there first, answered with 409
+#[derive(Debug, thiserror::Error)]
+#[error("Exit for {0} is already being claimed")]
+pub struct ClaimInProgress(pub String);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingExit {
+    pub vtxo_txid: String,
+    pub amount: u64, // sats
+    pub phase: ExitPhase,
+    pub broadcast_txids: Vec<String>,
+    pub leaf_confirmed_height: Option<u32>,
+    pub claimable_height: Option<u32>, // set for block based exit delays
+    pub claimable_at: Option<i64>,     // set for time based exit delays
+    pub claim_txid: Option<String>,
+    pub created_at: i64,
+}
+
+// what exiting every VTXO at once would involve
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitPlan {
+    pub vtxos: Vec<PlannedExit>,
+    pub total_amount: u64,
+    pub estimated_fees: u64,     // claim fees, the unroll txs are presigned by the server
+    pub exit_delay_secs: u64,    // CSV wait after the leaf confirms
+    pub expected_wait_secs: u64, // until the last claim can be broadcast
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedExit {
+    pub vtxo_txid: String,
+    pub amount: u64,
+    pub unroll_txs: usize,
+    pub claim_fee: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitAction {
+    Exit,  // too close to expiry to count on a round, unroll it now
+    Renew, // inside the renewal window, a round is cheaper than an exit
+    Keep,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitRecommendation {
+    pub vtxo_txid: String,
+    pub amount: u64,
+    pub expire_at: i64,
+    pub unroll_txs: usize,
+    pub claim_fee: u64,
+    pub action: ExitAction,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmergencyExitResult {
+    pub plan: ExitPlan,
+    pub started: Vec<PendingExit>,
+    pub failed: Vec<FailedExit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepResult {
+    pub started: Vec<PendingExit>,
+    pub failed: Vec<FailedExit>,
+    pub amount: u64, // in the started exits, on-chain once they are claimed
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedExit {
+    pub vtxo_txid: String,
+    pub error: String,
+}
+
+// rough duration of a relative timelock, blocks are counted at ten minutes
+pub(crate) fn delay_secs(exit_delay: u32) -> u64 {
+    match Sequence::from_consensus(exit_delay).to_relative_lock_time() {
+        Some(LockTime::Blocks(blocks)) => blocks.value() as u64 * BLOCK_INTERVAL_SECS,
+        Some(LockTime::Time(interval)) => interval.value() as u64 * 512,
+        None => 0,
+    }
+}
+
+// exits only pay off for VTXOs worth more than the claim, and only when a round may come too late
+pub(crate) fn recommend(
+    amount: u64,
+    claim_fee: u64,
+    remaining_secs: i64,
+    unroll_txs: usize,
+    renew_before_secs: u64,
+) -> (ExitAction, &'static str) {
+    let unroll_secs = unroll_txs as u64 * BLOCK_INTERVAL_SECS + EXIT_MARGIN_SECS;
+    let renewable = remaining_secs <= renew_before_secs as i64;
+    if amount <= claim_fee {
+        let action = if renewable { ExitAction::Renew } else { ExitAction::Keep };
+        return (action, "claiming on chain would cost more than the VTXO holds");
+    }
+    if remaining_secs <= unroll_secs as i64 {
+        (ExitAction::Exit, "expires before the next rounds can be relied on, the unroll needs the time left")
+    } else if renewable {
+        (ExitAction::Renew, "inside the renewal window")
+    } else {
+        (ExitAction::Keep, "not close to expiry")
+    }
+}
+
+// when the CSV exit path of a leaf confirmed at (height, time) becomes spendable
+pub(crate) fn claimable_after(exit_delay: u32, height: u32, time: u64) -> (Option<u32>, Option<i64>) {
+    match Sequence::from_consensus(exit_delay).to_relative_lock_time() {
+        Some(LockTime::Blocks(blocks)) => (Some(height + blocks.value() as u32), None),
+        Some(LockTime::Time(interval)) => (None, Some(time as i64 + interval.value() as i64 * 512)),
+        // relative timelocks disabled, spendable right away
+        None => (Some(height), None),
+    }
+}
+
+// unrolls VTXOs onto the chain and sweeps them to the on-chain address once the exit delay passes
+pub struct ExitManager {
+    exits: RwLock<HashMap<String, PendingExit>>,
+}
+
+impl ExitManager {
+    // picks up exits from previous runs so their claims aren't lost on restart
+    pub fn load(db: &DbManager) -> Self {
+        let exits = match load_exits(db) {
+            Ok(exits) => exits,
+            Err(e) => {
+                tracing::warn!("Failed to load pending exits: {}", e);
+                Vec::new()
+            }
+        };
+
+        Self {
+            exits: RwLock::new(exits.into_iter().map(|e| (e.vtxo_txid.clone(), e)).collect()),
+        }
+    }
+
+    pub fn list(&self) -> Vec<PendingExit> {
+        let mut exits: Vec<_> = self.exits.read().values().cloned().collect();
+        exits.sort_by_key(|e| e.created_at);
+        exits
+    }
+
+    pub fn get(&self, vtxo_txid: &str) -> Option<PendingExit> {
+        self.exits.read().get(vtxo_txid).cloned()
+    }
+
+    // broadcasts the branch of the VTXO tree that leads to the given VTXO
+    pub async fn start_exit(&self, vtxo_txid: &str) -> Result<PendingExit> {
+        if self.get(vtxo_txid).is_some() {
+            return self.refresh(vtxo_txid).await;
+        }
+
+        let client = ark_client().await?;
+        let amount = client.spendable_vtxos().await
+            .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?
+            .iter()
+            .flat_map(|(outpoints, _)| outpoints.iter())
+            .find(|o| o.outpoint.txid.to_string() == vtxo_txid)
+            .map(|o| o.amount.to_sat())
+            .ok_or_else(|| anyhow!("No spendable VTXO with txid {}", vtxo_txid))?;
+
+        let exit = PendingExit {
+            vtxo_txid: vtxo_txid.to_string(),
+            amount,
+            phase: ExitPhase::Unrolling,
+            broadcast_txids: Vec::new(),
+            leaf_confirmed_height: None,
+            claimable_height: None,
+            claimable_at: None,
+            claim_txid: None,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        self.store(exit)?;
+
+        self.refresh(vtxo_txid).await
+    }
+
+    // moves an exit forward as far as the chain allows right now. From claimable on only claim
+    // changes the phase, so a refresh can't undo a claim that started after it read the exit
+    pub async fn refresh(&self, vtxo_txid: &str) -> Result<PendingExit> {
+        let mut exit = self.get(vtxo_txid)
+            .ok_or_else(|| anyhow!("No exit in progress for {}", vtxo_txid))?;
+        if !matches!(exit.phase, ExitPhase::Unrolling | ExitPhase::WaitingTimelock) {
+            return Ok(exit);
+        }
+
+        if exit.phase == ExitPhase::Unrolling {
+            exit.broadcast_txids.extend(self.unroll(vtxo_txid).await?);
+            exit.phase = ExitPhase::WaitingTimelock;
+            tracing::info!("Unrolled VTXO {} with {} txs", vtxo_txid, exit.broadcast_txids.len());
+        }
+
+        if exit.phase == ExitPhase::WaitingTimelock {
+            self.check_timelock(&mut exit).await?;
+        }
+
+        self.store(exit.clone())?;
+        Ok(exit)
+    }
+
+    // sweeps a matured exit to the wallet's on-chain address
+    pub async fn claim(&self, vtxo_txid: &str) -> Result<PendingExit> {
+        let exit = self.refresh(vtxo_txid).await?;
+        match exit.phase {
+            ExitPhase::Claimable => {},
+            ExitPhase::Claiming => return Err(ClaimInProgress(vtxo_txid.to_string()).into()),
+            ExitPhase::Claimed => return Ok(exit),
+            _ => return Err(anyhow!("Exit for {} is not claimable yet", vtxo_txid)),
+        }
+
+        let mut exit = self.begin_claim(vtxo_txid)?;
+        let (txid, amount) = match self.broadcast_claim(&exit).await {
+            Ok(claimed) => claimed,
+            Err(e) => {
+                // nothing went out, the next attempt may claim it
+                self.set_phase(vtxo_txid, ExitPhase::Claimable);
+                return Err(e);
+            }
+        };
+        tracing::info!("Claimed exited VTXO {} in {}", vtxo_txid, txid);
+
+        exit.phase = ExitPhase::Claimed;
+        exit.claim_txid = Some(txid.to_string());
+        self.store(exit.clone())?;
+
+        crate::services::transactions::record_transaction(&exit_transaction(&exit)).await?;
+        APP_STATE.events.publish(WalletEvent::ExitClaimed {
+            vtxo_txid: vtxo_txid.to_string(),
+            claim_txid: txid.to_string(),
+            amount: amount.to_sat(),
+        });
+        Ok(exit)
+    }
+
+    // moves a claimable exit to claiming, a second caller (the watcher racing a manual claim) gets ClaimInProgress
+    fn begin_claim(&self, vtxo_txid: &str) -> Result<PendingExit> {
+        let mut exits = self.exits.write();
+        let exit = exits.get_mut(vtxo_txid)
+            .ok_or_else(|| anyhow!("No exit in progress for {}", vtxo_txid))?;
+        match exit.phase {
+            ExitPhase::Claimable => {
+                exit.phase = ExitPhase::Claiming;
+                Ok(exit.clone())
+            },
+            ExitPhase::Claiming | ExitPhase::Claimed => Err(ClaimInProgress(vtxo_txid.to_string()).into()),
+            _ => Err(anyhow!("Exit for {} is not claimable yet", vtxo_txid)),
+        }
+    }
+
+    fn set_phase(&self, vtxo_txid: &str, phase: ExitPhase) {
+        if let Some(exit) = self.exits.write().get_mut(vtxo_txid) {
+            exit.phase = phase;
+        }
+    }
+
+    async fn broadcast_claim(&self, exit: &PendingExit) -> Result<(Txid, Amount)> {
+        let network = crate::services::profiles::active().network;
+        let address = Address::from_str(&crate::services::wallet::get_onchain_address().await?)?
+            .require_network(network)?;
+
+        // the claim pays its own fee out of the exited amount
+        let fee = claim_fee().await?;
+        let amount = Amount::from_sat(exit.amount)
+            .checked_sub(fee)
+            .ok_or_else(|| anyhow!("Exit amount {} doesn't cover the claim fee {}", exit.amount, fee))?;
+
+        let client = ark_client().await?;
+        let txid = client.send_on_chain(address, amount).await
+            .map_err(|e| anyhow!("Failed to broadcast claim transaction: {}", e))?;
+        Ok((txid, amount))
+    }
+
+    // lists every spendable VTXO with what exiting it would cost, without broadcasting anything
+    pub async fn plan_exit_all(&self) -> Result<ExitPlan> {
+        let client = ark_client().await?;
+        let vtxos = client.spendable_vtxos().await
+            .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?;
+        let trees = client.build_unilateral_exit_trees().await
+            .map_err(|e| anyhow!("Failed to build exit trees: {}", e))?;
+        let fee = claim_fee().await?.to_sat();
+
+        let planned: Vec<PlannedExit> = vtxos.iter()
+            .flat_map(|(outpoints, _)| outpoints.iter())
+            .filter(|o| self.get(&o.outpoint.txid.to_string()).is_none())
+            .map(|o| {
+                let txid = o.outpoint.txid;
+                let unroll_txs = trees.iter()
+                    .find(|branch| branch.last().map(|leaf| leaf.compute_txid()) == Some(txid))
+                    .map(|branch| branch.len())
+                    .unwrap_or(0);
+                PlannedExit {
+                    vtxo_txid: txid.to_string(),
+                    amount: o.amount.to_sat(),
+                    unroll_txs,
+                    claim_fee: fee,
+                }
+            })
+            .collect();
+
+        let exit_delay_secs = APP_STATE.server_info.get()
+            .map(|c| delay_secs(c.info.unilateral_exit_delay))
+            .unwrap_or(0);
+        // branch txs confirm one level per block in the worst case
+        let deepest = planned.iter().map(|p| p.unroll_txs as u64).max().unwrap_or(0);
+
+        Ok(ExitPlan {
+            total_amount: planned.iter().map(|p| p.amount).sum(),
+            estimated_fees: planned.iter().map(|p| p.claim_fee).sum(),
+            exit_delay_secs,
+            expected_wait_secs: if planned.is_empty() { 0 } else { deepest * BLOCK_INTERVAL_SECS + exit_delay_secs },
+            vtxos: planned,
+        })
+    }
+
+    // what to do with each spendable VTXO given its expiry, exit depth and the current claim fee, soonest expiry first
+    pub async fn recommendations(&self) -> Result<Vec<ExitRecommendation>> {
+        let client = ark_client().await?;
+        let vtxos = client.spendable_vtxos().await
+            .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?;
+        let trees = client.build_unilateral_exit_trees().await
+            .map_err(|e| anyhow!("Failed to build exit trees: {}", e))?;
+        let fee = claim_fee().await?.to_sat();
+        let renew_before_secs = APP_STATE.monitor.policy().renew_before_secs;
+        let now = chrono::Utc::now().timestamp();
+
+        let mut recommendations: Vec<ExitRecommendation> = vtxos.iter()
+            .flat_map(|(outpoints, _)| outpoints.iter())
+            .filter(|o| self.get(&o.outpoint.txid.to_string()).is_none())
+            .map(|o| {
+                let txid = o.outpoint.txid;
+                let unroll_txs = trees.iter()
+                    .find(|branch| branch.last().map(|leaf| leaf.compute_txid()) == Some(txid))
+                    .map(|branch| branch.len())
+                    .unwrap_or(0);
+                let (action, reason) = recommend(o.amount.to_sat(), fee, o.expire_at - now, unroll_txs, renew_before_secs);
+                ExitRecommendation {
+                    vtxo_txid: txid.to_string(),
+                    amount: o.amount.to_sat(),
+                    expire_at: o.expire_at,
+                    unroll_txs,
+                    claim_fee: fee,
+                    action,
+                    reason,
+                }
+            })
+            .collect();
+
+        recommendations.sort_by_key(|r| r.expire_at);
+        Ok(recommendations)
+    }
+
+    // unilaterally exits every VTXO, used when the ASP stops cooperating
+    pub async fn emergency_exit_all(&self) -> Result<EmergencyExitResult> {
+        let plan = self.plan_exit_all().await?;
+        tracing::warn!("Emergency exit of {} VTXOs ({} sats)", plan.vtxos.len(), plan.total_amount);
+
+        let mut started = Vec::new();
+        let mut failed = Vec::new();
+        for vtxo in &plan.vtxos {
+            match self.start_exit(&vtxo.vtxo_txid).await {
+                Ok(exit) => {
+                    if let Err(e) = crate::services::transactions::record_transaction(&exit_transaction(&exit)).await {
+                        tracing::error!("Failed to record emergency exit of {}: {}", vtxo.vtxo_txid, e);
+                    }
+                    started.push(exit);
+                },
+                Err(e) => {
+                    tracing::error!("Emergency exit of {} failed: {}", vtxo.vtxo_txid, e);
+                    failed.push(FailedExit {
+                        vtxo_txid: vtxo.vtxo_txid.clone(),
+                        error: e.to_string(),
+                    });
+                },
+            }
+        }
+
+        Ok(EmergencyExitResult { plan, started, failed })
+    }
+
+    // unilaterally exits the VTXOs that expired unrenewed, they can't join a round anymore and only
+    // an exit gets them back before the ASP sweeps the round's outputs
+    pub async fn sweep_expired(&self) -> Result<SweepResult> {
+        let client = ark_client().await?;
+        let vtxos = client.spendable_vtxos().await
+            .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?;
+        let now = chrono::Utc::now().timestamp();
+        let expired: Vec<String> = vtxos.iter()
+            .flat_map(|(outpoints, _)| outpoints.iter())
+            .filter(|o| o.expire_at <= now)
+            .map(|o| o.outpoint.txid.to_string())
+            .filter(|txid| self.get(txid).is_none())
+            .collect();
+
+        let mut started = Vec::new();
+        let mut failed = Vec::new();
+        for vtxo_txid in expired {
+            match self.start_exit(&vtxo_txid).await {
+                Ok(exit) => {
+                    // the exit itself is stored and under way, a missing history entry shouldn't hide it
+                    if let Err(e) = crate::services::transactions::record_transaction(&exit_transaction(&exit)).await {
+                        tracing::error!("Failed to record exit of expired VTXO {}: {}", vtxo_txid, e);
+                    }
+                    started.push(exit);
+                },
+                Err(e) => {
+                    tracing::error!("Sweeping expired VTXO {} failed: {}", vtxo_txid, e);
+                    failed.push(FailedExit { vtxo_txid, error: e.to_string() });
+                },
+            }
+        }
+
+        let amount = started.iter().map(|exit| exit.amount).sum();
+        if !started.is_empty() {
+            tracing::warn!("Started exits for {} expired VTXOs ({} sats)", started.len(), amount);
+            APP_STATE.events.publish(WalletEvent::ExpiredVtxosSwept { vtxos: started.len(), amount });
+        }
+        Ok(SweepResult { started, failed, amount })
+    }
+
+    // advances every unclaimed exit and claims the ones whose timelock matured
+    pub fn start_watcher(self: &Arc<Self>, interval: Duration) {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if APP_STATE.wallet_lock.skips("exit unrolls and claims") {
+                    continue;
+                }
+
+                let pending: Vec<_> = manager.list().into_iter()
+                    .filter(|e| !matches!(e.phase, ExitPhase::Claiming | ExitPhase::Claimed))
+                    .map(|e| e.vtxo_txid)
+                    .collect();
+
+                for vtxo_txid in pending {
+                    let result = match manager.refresh(&vtxo_txid).await {
+                        Ok(exit) if exit.phase == ExitPhase::Claimable => manager.claim(&vtxo_txid).await.map(|_| ()),
+                        Ok(_) => Ok(()),
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = result {
+                        tracing::warn!("Failed to advance exit {}: {}", vtxo_txid, e);
+                    }
+                }
+            }
+        });
+    }
+
+    // keeps memory and the exits table in step
+    fn store(&self, exit: PendingExit) -> Result<()> {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO exits (vtxo_txid, amount, phase, broadcast_txids, leaf_confirmed_height,
+                claimable_height, claimable_at, claim_txid, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                exit.vtxo_txid,
+                exit.amount as i64,
+                exit.phase.as_str(),
+                serde_json::to_string(&exit.broadcast_txids)?,
+                exit.leaf_confirmed_height,
+                exit.claimable_height,
+                exit.claimable_at,
+                exit.claim_txid,
+                exit.created_at,
+            ],
+        )?;
+
+        self.exits.write().insert(exit.vtxo_txid.clone(), exit);
+        Ok(())
+    }
+
+    // broadcasts the next missing node of the branch until the leaf is out
+    async fn unroll(&self, vtxo_txid: &str) -> Result<Vec<String>> {
+        let client = ark_client().await?;
+        let trees = client.build_unilateral_exit_trees().await
+            .map_err(|e| anyhow!("Failed to build exit trees: {}", e))?;
+
+        let branch: Vec<_> = trees.into_iter()
+            .filter(|branch| branch.last().map(|leaf| leaf.compute_txid().to_string()) == Some(vtxo_txid.to_string()))
+            .collect();
+        if branch.is_empty() {
+            return Err(anyhow!("No exit branch found for VTXO {}", vtxo_txid));
+        }
+
+        let mut broadcast = Vec::new();
+        for _ in 0..=branch[0].len() {
+            match client.broadcast_next_unilateral_exit_node(&branch).await
+                .map_err(|e| anyhow!("Failed to broadcast exit tx: {}", e))?
+            {
+                Some(txid) => broadcast.push(txid.to_string()),
+                None => return Ok(broadcast),
+            }
+        }
+
+        Err(anyhow!("Exit branch for {} didn't finish unrolling", vtxo_txid))
+    }
+
+    async fn check_timelock(&self, exit: &mut PendingExit) -> Result<()> {
+        let leaf = Txid::from_str(&exit.vtxo_txid)?;
+        let Some((height, time)) = APP_STATE.blockchain.get_tx_confirmation(&leaf).await? else {
+            return Ok(());
+        };
+
+        let exit_delay = APP_STATE.server_info.get()
+            .map(|c| c.info.unilateral_exit_delay)
+            .ok_or_else(|| anyhow!("Unknown exit delay, server info not loaded"))?;
+        let (claimable_height, claimable_at) = claimable_after(exit_delay, height, time);
+        exit.leaf_confirmed_height = Some(height);
+        exit.claimable_height = claimable_height;
+        exit.claimable_at = claimable_at;
+
+        let matured = match (claimable_height, claimable_at) {
+            (Some(target), _) => APP_STATE.blockchain.get_height().await? >= target,
+            (_, Some(target)) => chrono::Utc::now().timestamp() >= target,
+            _ => false,
+        };
+        if matured {
+            exit.phase = ExitPhase::Claimable;
+        }
+        Ok(())
+    }
+}
+
+// keyed by the VTXO for the whole exit, so claiming updates the entry recorded when it started
+pub fn exit_transaction(exit: &PendingExit) -> TransactionResponse {
+    let kind = TransactionKind::Exit {
+        vtxo_txid: exit.vtxo_txid.clone(),
+        claimable_height: exit.claimable_height,
+        claimable_at: exit.claimable_at,
+        claim_txid: exit.claim_txid.clone(),
+    };
+    TransactionResponse {
+        is_settled: Some(exit.phase == ExitPhase::Claimed),
+        // leaves the off-chain balance
+        ..TransactionResponse::new(exit.vtxo_txid.clone(), kind, -(exit.amount as i64), exit.created_at)
+    }
+}
+
+pub(crate) async fn claim_fee() -> Result<Amount> {
+    let fee_rate = crate::services::onchain::fee_estimator::FeeEstimator::new(APP_STATE.blockchain.clone())
+        .estimate_fee_rate().await?;
+    Ok(fee_rate.fee_vb(CLAIM_TX_VBYTES).unwrap_or(Amount::from_sat(1_000)))
+}
+
+fn load_exits(db: &DbManager) -> Result<Vec<PendingExit>> {
+    let conn = db.get_conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT vtxo_txid, amount, phase, broadcast_txids, leaf_confirmed_height,
+            claimable_height, claimable_at, claim_txid, created_at
+         FROM exits"
+    )?;
+
+    let exits = stmt
+        .query_map([], |row| {
+            let phase: String = row.get(2)?;
+            let broadcast_txids: String = row.get(3)?;
+            Ok(PendingExit {
+                vtxo_txid: row.get(0)?,
+                amount: row.get::<_, i64>(1)? as u64,
+                phase: ExitPhase::parse(&phase),
+                broadcast_txids: serde_json::from_str(&broadcast_txids).unwrap_or_default(),
+                leaf_confirmed_height: row.get(4)?,
+                claimable_height: row.get(5)?,
+                claimable_at: row.get(6)?,
+                claim_txid: row.get(7)?,
+                created_at: row.get(8)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(exits)
+}
+
+async fn ark_client() -> Result<Arc<ark_client::Client<crate::services::chain::ChainBackend, crate::services::ark_grpc::ArkWallet>>> {
+    let grpc_client = APP_STATE.grpc_client.lock().await;
+    let client_opt = grpc_client.get_ark_client();
+    client_opt.as_ref()
+        .map(Arc::clone)
+        .ok_or_else(|| anyhow!("Ark client not available"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claimable_after_blocks_and_time() {
+        // 144 blocks
+        assert_eq!(claimable_after(144, 1_000, 0), (Some(1_144), None));
+
+        // type flag set, 2 * 512 seconds
+        let seconds = Sequence::from_512_second_intervals(2).to_consensus_u32();
+        assert_eq!(claimable_after(seconds, 1_000, 1_700_000_000), (None, Some(1_700_001_024)));
+
+        assert_eq!(delay_secs(144), 144 * 600);
+        assert_eq!(delay_secs(seconds), 1_024);
+    }
+
+    #[test]
+    fn test_recommend() {
+        let day = 86_400;
+        assert_eq!(recommend(100_000, 500, 30 * day, 3, day as u64).0, ExitAction::Keep);
+        assert_eq!(recommend(100_000, 500, day / 2, 3, day as u64).0, ExitAction::Renew);
+        // 3 levels plus the margin need 1.5 hours
+        assert_eq!(recommend(100_000, 500, 3_600, 3, day as u64).0, ExitAction::Exit);
+        // dust never gets exited
+        assert_eq!(recommend(400, 500, 3_600, 3, day as u64).0, ExitAction::Renew);
+    }
+}
@@ -0,0 +1,494 @@
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use rusqlite::{params, TransactionBehavior};
+use tokio::sync::mpsc;
+
+use crate::services::onchain::fee_estimator::FeePriority;
+use crate::services::onchain::keychain::WalletKey;
+use crate::services::onchain::OnChainPaymentService;
+use crate::storage::DbManager;
+
+const CONFIG_SETTINGS_KEY: &str = "faucet_config";
+
+#[derive(Debug, thiserror::Error)]
+pub enum FaucetError {
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Faucet unavailable: {0}")]
+    Unavailable(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum FaucetRequestStatus {
+    Queued,
+    Sent { txid: String },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FaucetRequest {
+    pub id: u64,
+    pub address: String,
+    pub amount: u64,
+    pub created_at: i64,
+    #[serde(flatten)]
+    pub status: FaucetRequestStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FaucetResponse {
+    #[serde(flatten)]
+    pub request: FaucetRequest,
+    pub queue_position: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FaucetQueueStatus {
+    pub public_mode: bool,
+    pub faucet_amount: u64,
+    pub queue_length: usize,
+    pub budget_remaining: u64,
+    pub budget_resets_in_secs: u64,
+}
+
+// where payouts come from: the regtest node's own wallet, or the faucet's hot wallet on any network
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaucetFunding {
+    Bitcoind,
+    HotWallet,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FaucetBalance {
+    pub funding: FaucetFunding,
+    pub address: Option<String>, // hot wallet deposit address, top it up to keep the faucet running
+    pub balance: u64,
+}
+
+// runtime adjustable payout settings, persisted in the settings table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FaucetConfig {
+    pub enabled: bool, // the network profile must allow the faucet as well
+    pub faucet_amount: u64, // sats per request
+    pub cooldown_period: u64, // seconds between payouts to the same address
+    #[serde(default = "default_max_requests_per_ip")]
+    pub max_requests_per_ip: u32, // per budget window, public mode only
+    #[serde(default = "default_global_budget")]
+    pub global_budget: u64, // sats per budget window, public mode only
+}
+
+fn default_max_requests_per_ip() -> u32 {
+    10
+}
+
+fn default_global_budget() -> u64 {
+    100_000_000 // 1 BTC
+}
+
+impl Default for FaucetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            faucet_amount: 1_000_000, // 0.01 BTC
+            cooldown_period: 60,
+            max_requests_per_ip: default_max_requests_per_ip(),
+            global_budget: default_global_budget(),
+        }
+    }
+}
+
+impl FaucetConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.faucet_amount < 546 {
+            return Err(anyhow!("faucet_amount must be at least the 546 sat dust limit"));
+        }
+        if self.max_requests_per_ip == 0 {
+            return Err(anyhow!("max_requests_per_ip must be positive"));
+        }
+        if self.global_budget < self.faucet_amount {
+            return Err(anyhow!("global_budget must cover at least one faucet_amount"));
+        }
+        Ok(())
+    }
+
+    pub fn load(db: &DbManager) -> Self {
+        match db.get_setting(CONFIG_SETTINGS_KEY) {
+            Ok(Some(json)) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable faucet config: {}", e);
+                Self::default()
+            }),
+            Ok(None) => Self::default(),
+            Err(e) => {
+                tracing::warn!("Failed to load faucet config: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, db: &DbManager) -> Result<()> {
+        db.save_setting(CONFIG_SETTINGS_KEY, &serde_json::to_string(self)?)
+    }
+}
+
+struct FaucetQueue {
+    next_id: u64,
+    pending: VecDeque<u64>,
+    requests: HashMap<u64, FaucetRequest>,
+    // faucet_dispenses row booked for each entry, deleted again if the payout fails
+    reservations: HashMap<u64, i64>,
+}
+
+pub struct FaucetService {
+    config: Mutex<FaucetConfig>,
+    funding: FaucetFunding,
+    public_mode: bool,
+    budget_window: Duration, // rolling, counted from the faucet_dispenses table
+    queue: Mutex<FaucetQueue>,
+    sender: mpsc::UnboundedSender<u64>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<u64>>>,
+}
+
+impl FaucetService {
+    pub fn new(db: &DbManager) -> Self {
+        let public_mode = std::env::var("FAUCET_PUBLIC_MODE")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        // only regtest has a node wallet to mint from
+        let funding = match std::env::var("FAUCET_FUNDING").as_deref() {
+            Ok("hot_wallet") => FaucetFunding::HotWallet,
+            Ok("bitcoind") => FaucetFunding::Bitcoind,
+            _ if faucet_network() == bitcoin::Network::Regtest => FaucetFunding::Bitcoind,
+            _ => FaucetFunding::HotWallet,
+        };
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        Self {
+            config: Mutex::new(FaucetConfig::load(db)),
+            funding,
+            public_mode,
+            budget_window: Duration::from_secs(24 * 60 * 60),
+            queue: Mutex::new(FaucetQueue {
+                next_id: 1,
+                pending: VecDeque::new(),
+                requests: HashMap::new(),
+                reservations: HashMap::new(),
+            }),
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    pub fn is_public_mode(&self) -> bool {
+        self.public_mode
+    }
+
+    pub async fn balance(&self) -> Result<FaucetBalance> {
+        let (address, balance) = match self.funding {
+            FaucetFunding::Bitcoind => (None, crate::services::bitcoin_rpc::get_balance().await?),
+            FaucetFunding::HotWallet => {
+                let key = crate::services::onchain::keychain::faucet_key()?;
+                let balance = hot_wallet(key.clone()).get_balance().await?;
+                (Some(key.address.to_string()), balance)
+            }
+        };
+
+        Ok(FaucetBalance {
+            funding: self.funding,
+            address,
+            balance: balance.to_sat(),
+        })
+    }
+
+    pub fn config(&self) -> FaucetConfig {
+        self.config.lock().clone()
+    }
+
+    pub fn set_config(&self, config: FaucetConfig) -> Result<()> {
+        config.validate()?;
+        config.save(&crate::services::APP_STATE.db_manager)?;
+        tracing::info!("Faucet config updated: {:?}", config);
+        *self.config.lock() = config;
+        Ok(())
+    }
+
+    // spawns the worker that drains the public queue one request at a time
+    pub fn start_queue_worker(self: &Arc<Self>) {
+        let receiver = self.receiver.lock().take();
+        let Some(mut receiver) = receiver else {
+            tracing::warn!("Faucet queue worker already started");
+            return;
+        };
+
+        let faucet = Arc::clone(self);
+        tokio::spawn(async move {
+            tracing::info!("Faucet queue worker started");
+            while let Some(id) = receiver.recv().await {
+                // queued payouts wait for the wallet to be unlocked
+                while crate::services::APP_STATE.wallet_lock.skips("faucet payouts") {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                faucet.process_queued_request(id).await;
+                // keep some distance between payouts so the node isn't flooded
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        });
+    }
+
+    pub async fn request(&self, address: String, ip: String) -> Result<FaucetResponse, FaucetError> {
+        // a funded hot wallet can pay out on any network, the profile only gates the node wallet
+        let profile = crate::services::profiles::active();
+        if self.funding == FaucetFunding::Bitcoind && !profile.faucet_enabled {
+            return Err(FaucetError::Unavailable(format!("Faucet is disabled for profile {}", profile.name)));
+        }
+        let config = self.config();
+        if !config.enabled {
+            return Err(FaucetError::Unavailable("Faucet is disabled".to_string()));
+        }
+
+        self.validate_address(&address)?;
+        let reservation = self.reserve(&address, &ip, &config)?;
+
+        let request = {
+            let mut queue = self.queue.lock();
+            let id = queue.next_id;
+            queue.next_id += 1;
+
+            let request = FaucetRequest {
+                id,
+                address: address.clone(),
+                amount: config.faucet_amount,
+                created_at: chrono::Utc::now().timestamp(),
+                status: FaucetRequestStatus::Queued,
+            };
+            queue.requests.insert(id, request.clone());
+            queue.reservations.insert(id, reservation);
+            request
+        };
+
+        if self.public_mode {
+            let position = {
+                let mut queue = self.queue.lock();
+                queue.pending.push_back(request.id);
+                queue.pending.len()
+            };
+
+            self.sender.send(request.id)
+                .map_err(|_| FaucetError::Unavailable("Faucet queue is not running".to_string()))?;
+
+            tracing::info!("Queued faucet request {} for {} at position {}", request.id, address, position);
+            return Ok(FaucetResponse {
+                request,
+                queue_position: Some(position),
+            });
+        }
+
+        let status = match self.dispense(&address, config.faucet_amount).await {
+            Ok(txid) => {
+                self.record_txid(reservation, &txid);
+                FaucetRequestStatus::Sent { txid }
+            },
+            Err(e) => {
+                self.release(reservation);
+                return Err(FaucetError::Unavailable(e.to_string()));
+            }
+        };
+
+        let request = self.update_request(request.id, status)
+            .ok_or_else(|| FaucetError::Unavailable("Faucet request disappeared".to_string()))?;
+
+        Ok(FaucetResponse {
+            request,
+            queue_position: None,
+        })
+    }
+
+    pub fn get_request(&self, id: u64) -> Option<(FaucetRequest, Option<usize>)> {
+        let queue = self.queue.lock();
+        let request = queue.requests.get(&id)?.clone();
+        let position = queue.pending.iter().position(|pending| *pending == id).map(|p| p + 1);
+        Some((request, position))
+    }
+
+    pub fn queue_status(&self) -> Result<FaucetQueueStatus> {
+        let queue_length = self.queue.lock().pending.len();
+        let window_start = chrono::Utc::now().timestamp() - self.budget_window.as_secs() as i64;
+
+        let conn = crate::services::APP_STATE.db_manager.get_conn()?;
+        let (dispensed, oldest): (u64, Option<i64>) = conn.query_row(
+            "SELECT COALESCE(SUM(amount), 0), MIN(created_at) FROM faucet_dispenses WHERE created_at >= ?",
+            params![window_start],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let config = self.config();
+        Ok(FaucetQueueStatus {
+            public_mode: self.public_mode,
+            faucet_amount: config.faucet_amount,
+            queue_length,
+            budget_remaining: config.global_budget.saturating_sub(dispensed),
+            // the window is rolling, budget comes back as the oldest payout in it ages out
+            budget_resets_in_secs: oldest.map(|oldest| (oldest - window_start).max(0) as u64).unwrap_or(0),
+        })
+    }
+
+    async fn process_queued_request(&self, id: u64) {
+        // the amount is the one booked when the request was queued
+        let (address, amount, reservation) = {
+            let mut queue = self.queue.lock();
+            queue.pending.retain(|pending| *pending != id);
+            let request = queue.requests.get(&id).map(|r| (r.address.clone(), r.amount));
+            let reservation = queue.reservations.remove(&id);
+            match (request, reservation) {
+                (Some((address, amount)), Some(reservation)) => (address, amount, reservation),
+                _ => return,
+            }
+        };
+
+        let status = match self.dispense(&address, amount).await {
+            Ok(txid) => {
+                self.record_txid(reservation, &txid);
+                FaucetRequestStatus::Sent { txid }
+            },
+            Err(e) => {
+                tracing::error!("Faucet request {} failed: {}", id, e);
+                self.release(reservation);
+                FaucetRequestStatus::Failed { error: e.to_string() }
+            }
+        };
+
+        self.update_request(id, status);
+    }
+
+    fn update_request(&self, id: u64, status: FaucetRequestStatus) -> Option<FaucetRequest> {
+        let mut queue = self.queue.lock();
+        queue.reservations.remove(&id);
+        let request = queue.requests.get_mut(&id)?;
+        request.status = status;
+        Some(request.clone())
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), FaucetError> {
+        let network = faucet_network();
+        bitcoin::Address::from_str(address)
+            .map_err(|e| FaucetError::InvalidRequest(format!("Invalid address: {}", e)))?
+            .require_network(network)
+            .map_err(|e| FaucetError::InvalidRequest(format!("Address is not valid for {}: {}", network, e)))?;
+        Ok(())
+    }
+
+    // checks every limit and books the payout up front so queued requests can't overcommit the budget,
+    // limits live in the database so they survive restarts and are shared between instances
+    fn reserve(&self, address: &str, ip: &str, config: &FaucetConfig) -> Result<i64, FaucetError> {
+        let now = chrono::Utc::now().timestamp();
+        let window_start = now - self.budget_window.as_secs() as i64;
+
+        let mut conn = crate::services::APP_STATE.db_manager.get_conn().map_err(unavailable)?;
+        // immediate so two instances can't both pass the checks before either books its payout
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(unavailable)?;
+
+        let last: Option<i64> = tx.query_row(
+            "SELECT MAX(created_at) FROM faucet_dispenses WHERE address = ?",
+            params![address],
+            |row| row.get(0),
+        ).map_err(unavailable)?;
+        if let Some(remaining) = last.map(|last| last + config.cooldown_period as i64 - now).filter(|r| *r > 0) {
+            return Err(FaucetError::RateLimited(format!(
+                "Address already funded, try again in {} seconds",
+                remaining
+            )));
+        }
+
+        if self.public_mode {
+            let ip_count: u32 = tx.query_row(
+                "SELECT COUNT(*) FROM faucet_dispenses WHERE ip = ? AND created_at >= ?",
+                params![ip, window_start],
+                |row| row.get(0),
+            ).map_err(unavailable)?;
+            if ip_count >= config.max_requests_per_ip {
+                return Err(FaucetError::RateLimited(format!(
+                    "Per-IP limit of {} requests reached",
+                    config.max_requests_per_ip
+                )));
+            }
+
+            let dispensed: u64 = tx.query_row(
+                "SELECT COALESCE(SUM(amount), 0) FROM faucet_dispenses WHERE created_at >= ?",
+                params![window_start],
+                |row| row.get(0),
+            ).map_err(unavailable)?;
+            if dispensed + config.faucet_amount > config.global_budget {
+                return Err(FaucetError::RateLimited("Global faucet budget exhausted".to_string()));
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO faucet_dispenses (address, ip, amount, created_at) VALUES (?, ?, ?, ?)",
+            params![address, ip, config.faucet_amount, now],
+        ).map_err(unavailable)?;
+        let reservation = tx.last_insert_rowid();
+        tx.commit().map_err(unavailable)?;
+
+        Ok(reservation)
+    }
+
+    // a failed payout doesn't count toward any limit
+    fn release(&self, reservation: i64) {
+        let result = crate::services::APP_STATE.db_manager.get_conn()
+            .and_then(|conn| Ok(conn.execute("DELETE FROM faucet_dispenses WHERE id = ?", params![reservation])?));
+        if let Err(e) = result {
+            tracing::error!("Failed to release faucet reservation {}: {}", reservation, e);
+        }
+    }
+
+    fn record_txid(&self, reservation: i64, txid: &str) {
+        let result = crate::services::APP_STATE.db_manager.get_conn()
+            .and_then(|conn| Ok(conn.execute("UPDATE faucet_dispenses SET txid = ? WHERE id = ?", params![txid, reservation])?));
+        if let Err(e) = result {
+            tracing::warn!("Failed to record faucet txid {}: {}", txid, e);
+        }
+    }
+
+    async fn dispense(&self, address: &str, amount: u64) -> Result<String> {
+        let address = bitcoin::Address::from_str(address)?.require_network(faucet_network())?;
+        let amount_sat = bitcoin::Amount::from_sat(amount);
+
+        let txid = match self.funding {
+            FaucetFunding::Bitcoind => {
+                if faucet_network() != bitcoin::Network::Regtest {
+                    return Err(anyhow!("Faucet is only available on regtest"));
+                }
+                crate::services::bitcoin_rpc::send_to_address(address.clone(), amount_sat).await?
+            },
+            FaucetFunding::HotWallet => {
+                let wallet = hot_wallet(crate::services::onchain::keychain::faucet_key()?);
+                wallet.send_payment(address.clone(), amount_sat, None, FeePriority::Normal).await?
+            },
+        }.to_string();
+
+        tracing::info!("Faucet sent {} sats to {} in {}", amount, address, txid);
+        Ok(txid)
+    }
+}
+
+fn hot_wallet(key: WalletKey) -> OnChainPaymentService {
+    OnChainPaymentService::with_keys(crate::services::APP_STATE.blockchain.clone(), vec![key])
+}
+
+fn faucet_network() -> bitcoin::Network {
+    crate::services::profiles::active().network
+}
+
+fn unavailable(e: impl std::fmt::Display) -> FaucetError {
+    FaucetError::Unavailable(e.to_string())
+}
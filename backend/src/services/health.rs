@@ -0,0 +1,292 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use ark_grpc::Client as ArkGrpcClient;
+use bitcoincore_rpc::RpcApi;
+
+use crate::services::chain::ChainBackend;
+use crate::services::profiles::FeeSource;
+use crate::services::APP_STATE;
+
+// a probe that takes longer than this counts as down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Up,
+    Degraded, // a non-critical dependency is down
+    Down,
+    Skipped, // self-check only, not used by this configuration
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyCheck {
+    pub name: String,
+    pub status: HealthStatus,
+    pub critical: bool, // readiness fails when a critical dependency is down
+    pub latency_ms: u64,
+    pub detail: Option<String>,
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>, // self-check only, what to change when the check fails
+}
+
+impl DependencyCheck {
+    fn with_hint(self, hint: impl Into<String>) -> Self {
+        if self.status != HealthStatus::Down {
+            return self;
+        }
+        Self { hint: Some(hint.into()), ..self }
+    }
+
+    fn skipped(name: &str, reason: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status: HealthStatus::Skipped,
+            critical: false,
+            latency_ms: 0,
+            detail: Some(reason.to_string()),
+            error: None,
+            hint: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: Vec<DependencyCheck>,
+    pub checked_at: i64,
+}
+
+impl HealthReport {
+    fn new(checks: Vec<DependencyCheck>) -> Self {
+        let status = if checks.iter().any(|check| check.critical && check.status == HealthStatus::Down) {
+            HealthStatus::Down
+        } else if checks.iter().any(|check| check.status == HealthStatus::Down) {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Up
+        };
+
+        Self {
+            status,
+            checks,
+            checked_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.status != HealthStatus::Down
+    }
+}
+
+// liveness only looks at local state, so a flaky ASP doesn't get the process restarted
+pub async fn liveness() -> HealthReport {
+    HealthReport::new(vec![check_database().await])
+}
+
+// readiness probes every dependency the API needs to serve requests
+pub async fn readiness() -> HealthReport {
+    let (database, ark_server, chain) = tokio::join!(check_database(), check_ark_server(), check_chain());
+    HealthReport::new(vec![database, ark_server, chain])
+}
+
+async fn check_database() -> DependencyCheck {
+    timed("database", true, async {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))?;
+        Ok(None)
+    }).await
+}
+
+async fn check_ark_server() -> DependencyCheck {
+    timed("ark_server", true, async {
+        let server_url = &APP_STATE.config.profile.ark_server_url;
+        let mut grpc_client = ArkGrpcClient::new(server_url.clone());
+        grpc_client.connect().await
+            .map_err(|e| anyhow!("Failed to connect to Ark server: {}", e))?;
+        grpc_client.get_info().await
+            .map_err(|e| anyhow!("Failed to get server info: {}", e))?;
+        Ok(Some(server_url.clone()))
+    }).await
+}
+
+// whether the environment is set up consistently, run once at startup and by GET /api/admin/selfcheck.
+// a wrong network otherwise only shows up as empty balances
+pub async fn self_check() -> HealthReport {
+    let (ark_server, chain_network, bitcoind) = tokio::join!(check_ark_network(), check_chain_network(), check_bitcoind());
+    HealthReport::new(vec![ark_server, chain_network, check_data_dir().await, check_schema().await, bitcoind])
+}
+
+// logs every failed check with its hint, the server starts either way
+pub fn start_self_check() {
+    tokio::spawn(async {
+        let report = self_check().await;
+        for check in report.checks.iter().filter(|check| check.status == HealthStatus::Down) {
+            tracing::error!(
+                "Self-check {} failed: {}. {}",
+                check.name,
+                check.error.as_deref().unwrap_or("unknown error"),
+                check.hint.as_deref().unwrap_or_default(),
+            );
+        }
+        if report.status == HealthStatus::Up {
+            tracing::info!("Self-check passed");
+        }
+    });
+}
+
+async fn check_ark_network() -> DependencyCheck {
+    let expected = APP_STATE.config.profile.network;
+    timed("ark_server", true, async move {
+        let server_url = &APP_STATE.config.profile.ark_server_url;
+        let mut grpc_client = ArkGrpcClient::new(server_url.clone());
+        grpc_client.connect().await
+            .map_err(|e| anyhow!("Failed to connect to Ark server {}: {}", server_url, e))?;
+        let info = grpc_client.get_info().await
+            .map_err(|e| anyhow!("Failed to get server info: {}", e))?;
+        if info.network != expected {
+            return Err(anyhow!("Ark server {} is on {}, the wallet is configured for {}", server_url, info.network, expected));
+        }
+        Ok(Some(format!("{} on {}", server_url, info.network)))
+    }).await
+    .with_hint("Point ARK_SERVER_URL at a server for BITCOIN_NETWORK, or pick the matching NETWORK_PROFILE")
+}
+
+// every network has its own genesis block, custom signets share signet's
+async fn check_chain_network() -> DependencyCheck {
+    let expected = APP_STATE.config.profile.network;
+    let hint = match APP_STATE.blockchain.as_ref() {
+        ChainBackend::Esplora(_) => "Point ESPLORA_URL (and ESPLORA_FALLBACK_URLS) at an Esplora server for BITCOIN_NETWORK",
+        ChainBackend::BitcoinCore(_) => "Point BITCOIND_RPC_URL at a node running on BITCOIN_NETWORK",
+    };
+    timed(&format!("{}_network", APP_STATE.blockchain.name()), true, async move {
+        let genesis = APP_STATE.blockchain.get_block_hash(0).await?;
+        if genesis != bitcoin::constants::genesis_block(expected).block_hash() {
+            return Err(anyhow!("{} is not on {}, its genesis block is {}", APP_STATE.blockchain.name(), expected, genesis));
+        }
+        Ok(Some(format!("{} on {}", APP_STATE.blockchain.name(), expected)))
+    }).await
+    .with_hint(hint)
+}
+
+async fn check_data_dir() -> DependencyCheck {
+    let data_dir = APP_STATE.config.data_dir.clone();
+    timed("data_dir", true, async move {
+        let probe = std::path::Path::new(&data_dir).join(".selfcheck");
+        std::fs::write(&probe, b"ok").map_err(|e| anyhow!("Cannot write to {}: {}", data_dir, e))?;
+        std::fs::remove_file(&probe).map_err(|e| anyhow!("Cannot delete from {}: {}", data_dir, e))?;
+        Ok(Some(data_dir))
+    }).await
+    .with_hint("Set DATA_DIR to a directory the backend's user can write to")
+}
+
+async fn check_schema() -> DependencyCheck {
+    timed("database_schema", true, async {
+        let version = APP_STATE.db_manager.schema_version()?;
+        let expected = crate::storage::DbManager::SCHEMA_VERSION;
+        if version != expected {
+            return Err(anyhow!("Database schema is version {}, this backend uses {}", version, expected));
+        }
+        Ok(Some(format!("version {}", version)))
+    }).await
+    .with_hint("The database was written by a newer backend, upgrade the backend or restore a backup made by this version")
+}
+
+// bitcoind is the chain backend, a fee source, or mints regtest faucet payouts
+async fn check_bitcoind() -> DependencyCheck {
+    let profile = &APP_STATE.config.profile;
+    let chain_backend = matches!(APP_STATE.blockchain.as_ref(), ChainBackend::BitcoinCore(_));
+    let used = chain_backend
+        || profile.fee_sources.iter().any(|source| matches!(source, FeeSource::BitcoinCore))
+        || profile.network == bitcoin::Network::Regtest;
+    if !used {
+        return DependencyCheck::skipped("bitcoind_rpc", "not used by this configuration");
+    }
+
+    timed("bitcoind_rpc", chain_backend, async {
+        let height = crate::services::bitcoin_rpc::call(|client| client.get_block_count()).await?;
+        Ok(Some(format!("tip height {}", height)))
+    }).await
+    .with_hint("Check BITCOIND_RPC_URL and BITCOIND_RPC_COOKIE or BITCOIND_RPC_USER/BITCOIND_RPC_PASSWORD")
+}
+
+// the chain backend only matters for boarding, exits and on-chain sends
+async fn check_chain() -> DependencyCheck {
+    let name = APP_STATE.blockchain.name();
+    timed(name, false, async {
+        let height = APP_STATE.blockchain.get_height().await?;
+        Ok(Some(format!("tip height {}", height)))
+    }).await
+}
+
+async fn timed<F>(name: &str, critical: bool, check: F) -> DependencyCheck
+where
+    F: Future<Output = Result<Option<String>>>,
+{
+    let started = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(detail) => DependencyCheck {
+            name: name.to_string(),
+            status: HealthStatus::Up,
+            critical,
+            latency_ms,
+            detail,
+            error: None,
+            hint: None,
+        },
+        Err(e) => {
+            tracing::warn!("Health check {} failed: {}", name, e);
+            DependencyCheck {
+                name: name.to_string(),
+                status: HealthStatus::Down,
+                critical,
+                latency_ms,
+                detail: None,
+                error: Some(e.to_string()),
+                hint: None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(critical: bool, status: HealthStatus) -> DependencyCheck {
+        DependencyCheck {
+            name: "test".to_string(),
+            status,
+            critical,
+            latency_ms: 0,
+            detail: None,
+            error: None,
+            hint: None,
+        }
+    }
+
+    #[test]
+    fn test_report_status_follows_critical_checks() {
+        let report = HealthReport::new(vec![check(true, HealthStatus::Up), check(false, HealthStatus::Up)]);
+        assert_eq!(report.status, HealthStatus::Up);
+
+        let report = HealthReport::new(vec![check(true, HealthStatus::Up), check(false, HealthStatus::Down)]);
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(report.is_available());
+
+        let report = HealthReport::new(vec![check(true, HealthStatus::Down), check(false, HealthStatus::Up)]);
+        assert_eq!(report.status, HealthStatus::Down);
+        assert!(!report.is_available());
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use bitcoin::hashes::{sha256, Hash};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use crate::services::APP_STATE;
+
+// how long a key and its stored response are kept for replays
+const KEY_TTL_SECS: i64 = 24 * 60 * 60;
+
+pub enum IdempotencyState {
+    New(Reservation),
+    Replay { status: u16, body: serde_json::Value },
+    InProgress,
+    Mismatch, // key reused for a different endpoint or payload
+}
+
+// reserves the key for this request, or reports what was already recorded for it
+pub fn begin(key: &str, endpoint: &str, request: &impl Serialize) -> Result<IdempotencyState> {
+    let request_hash = sha256::Hash::hash(serde_json::to_string(request)?.as_bytes()).to_string();
+    let now = chrono::Utc::now().timestamp();
+
+    let conn = APP_STATE.db_manager.get_conn()?;
+    conn.execute(
+        "DELETE FROM idempotency_keys WHERE created_at < ?",
+        params![now - KEY_TTL_SECS],
+    )?;
+
+    // the primary key makes the reservation atomic even across pooled connections
+    let reserved = conn.execute(
+        "INSERT OR IGNORE INTO idempotency_keys (key, endpoint, request_hash, created_at) VALUES (?, ?, ?, ?)",
+        params![key, endpoint, request_hash, now],
+    )?;
+    if reserved == 1 {
+        return Ok(IdempotencyState::New(Reservation { key: key.to_string(), finished: false }));
+    }
+
+    let existing = conn.query_row(
+        "SELECT endpoint, request_hash, status_code, response FROM idempotency_keys WHERE key = ?",
+        params![key],
+        |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<u16>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+        )),
+    ).optional()?;
+
+    match existing {
+        // expired and purged by another request in between, treat as in flight and let the client retry
+        None => Ok(IdempotencyState::InProgress),
+        Some((stored_endpoint, stored_hash, _, _)) if stored_endpoint != endpoint || stored_hash != request_hash => {
+            Ok(IdempotencyState::Mismatch)
+        },
+        Some((_, _, Some(status), Some(response))) => Ok(IdempotencyState::Replay {
+            status,
+            body: serde_json::from_str(&response)?,
+        }),
+        Some(_) => Ok(IdempotencyState::InProgress),
+    }
+}
+
+// a key reserved by begin. It is released when dropped without a stored response, so a request
+// cancelled mid-send doesn't hold the key for the whole TTL
+pub struct Reservation {
+    key: String,
+    finished: bool,
+}
+
+impl Reservation {
+    // definitive responses are stored for replays. Retryable ones (5xx, locked, rate limited, timed out)
+    // release the key, so a retry after the cause went away runs the request again
+    pub fn finish(mut self, status: u16, body: &serde_json::Value) -> Result<()> {
+        self.finished = true;
+        if !is_definitive(status) {
+            return release(&self.key);
+        }
+
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.execute(
+            "UPDATE idempotency_keys SET status_code = ?, response = ? WHERE key = ?",
+            params![status, serde_json::to_string(body)?, self.key],
+        )?;
+        Ok(())
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        if let Err(e) = release(&self.key) {
+            tracing::error!("Failed to release idempotency key {}: {}", self.key, e);
+        }
+    }
+}
+
+fn is_definitive(status: u16) -> bool {
+    match status {
+        200..=299 => true,
+        408 | 423 | 429 => false,
+        400..=499 => true,
+        _ => false,
+    }
+}
+
+fn release(key: &str) -> Result<()> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    conn.execute(
+        "DELETE FROM idempotency_keys WHERE key = ? AND status_code IS NULL",
+        params![key],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_definitive_responses_are_kept() {
+        assert!(is_definitive(200));
+        assert!(is_definitive(202));
+        assert!(is_definitive(422));
+        assert!(!is_definitive(423));
+        assert!(!is_definitive(429));
+        assert!(!is_definitive(500));
+        assert!(!is_definitive(503));
+    }
+}
@@ -3,6 +3,38 @@ pub mod wallet;
 pub mod transactions;
 pub mod ark_grpc;
 pub mod onchain;
+pub mod payments;
+pub mod events;
+pub mod faucet;
+pub mod rounds;
+pub mod screening;
+pub mod swaps;
+pub mod payment_requests;
+pub mod profiles;
+pub mod server_info;
+pub mod idempotency;
+pub mod contacts;
+pub mod monitor;
+pub mod exit;
+pub mod renewal;
+pub mod round_schedule;
+pub mod bitcoin_rpc;
+pub mod chain;
+pub mod config;
+pub mod health;
+pub mod backup;
+pub mod signer;
+pub mod spending_policy;
+pub mod wallet_lock;
+pub mod approvals;
+pub mod rates;
+pub mod simulation;
+pub mod tls;
+pub mod confirmations;
+pub mod deposit_watcher;
+pub mod watched_addresses;
+pub mod raw_tx;
+pub mod runtime_settings;
 
 use anyhow::Result;
 use std::sync::Arc;
@@ -17,30 +49,59 @@ use std::sync::RwLock;
 
 use crate::storage::{DbManager, KeyManager};
 
+// the last recalculated balance, so it can be served without recalculating
+const BALANCE_SETTINGS_KEY: &str = "balance";
+
 #[derive(Clone)]
 pub struct AppState {
-    pub client: Arc<Mutex<Option<ark_client::Client<ark_grpc::EsploraBlockchain, ark_grpc::ArkWallet>>>>,
+    pub client: Arc<Mutex<Option<ark_client::Client<chain::ChainBackend, ark_grpc::ArkWallet>>>>,
     pub grpc_client: Arc<Mutex<ark_grpc::ArkGrpcService>>,
     pub transactions: Arc<Mutex<Vec<crate::models::wallet::TransactionResponse>>>,
     pub balance: Arc<Mutex<crate::models::wallet::WalletBalance>>,
     pub db_manager: Arc<DbManager>,
     pub key_manager: Arc<KeyManager>,
+    pub events: Arc<events::EventBus>,
+    pub faucet: Arc<faucet::FaucetService>,
+    pub server_info: Arc<server_info::ServerInfoCache>,
+    pub blockchain: Arc<chain::ChainBackend>, // shared so backend connections are reused
+    pub monitor: Arc<monitor::BackgroundMonitor>,
+    pub exits: Arc<exit::ExitManager>,
+    pub round_scheduler: Arc<round_schedule::RoundScheduler>,
+    pub swaps: Arc<swaps::SwapService>,
+    pub signer: Arc<dyn signer::Signer>,
+    pub wallet_lock: Arc<wallet_lock::WalletLock>,
+    pub spending: Arc<spending_policy::SpendingGuard>,
+    pub rates: Arc<rates::RateService>,
+    pub payment_requests: Arc<payment_requests::PaymentRequestService>,
+    pub config: &'static config::Config,
 }
 
 impl AppState {
     pub fn new() -> Result<Self> {
-        let network = match std::env::var("BITCOIN_NETWORK").unwrap_or_else(|_| "regtest".to_string()).as_str() {
-            "mainnet" => Network::Bitcoin,
-            "testnet" => Network::Testnet,
-            "signet" => Network::Signet,
-            _ => Network::Regtest,
-        };
+        let config = config::get();
+        let network = config.profile.network;
         
         // initialize storage
-        let data_dir = std::env::var("DATA_DIR").unwrap_or_else(|_| "./data".to_string());
-        let db_path = format!("{}/ark.db", data_dir);
+        let db_path = format!("{}/ark.db", config.data_dir);
         let db_manager = Arc::new(DbManager::new(&db_path)?);
-        let key_manager = Arc::new(KeyManager::new(&data_dir, network));
+        let key_manager = Arc::new(KeyManager::new(&config.data_dir, network));
+        let server_info = Arc::new(server_info::ServerInfoCache::load(&db_manager));
+        let blockchain = Arc::new(chain::ChainBackend::new(config)?);
+        let exits = Arc::new(exit::ExitManager::load(&db_manager));
+        let monitor = Arc::new(monitor::BackgroundMonitor::new(
+            &db_manager,
+            std::time::Duration::from_secs(config.monitor_interval_secs),
+        ));
+        let faucet = Arc::new(faucet::FaucetService::new(&db_manager));
+        let round_scheduler = Arc::new(round_schedule::RoundScheduler::new(&db_manager));
+        let signer: Arc<dyn signer::Signer> = Arc::from(signer::from_config(&config.signer));
+        let wallet_lock = Arc::new(wallet_lock::WalletLock::load(
+            &db_manager,
+            std::time::Duration::from_secs(config.lock_idle_timeout_secs),
+        )?);
+        if wallet_lock.is_locked() {
+            signer.lock();
+        }
         
         Ok(Self {
             client: Arc::new(Mutex::new(None)),
@@ -55,13 +116,33 @@ impl AppState {
             })),
             db_manager,
             key_manager,
+            events: Arc::new(events::EventBus::new(256)),
+            faucet,
+            server_info,
+            blockchain,
+            monitor,
+            exits,
+            round_scheduler,
+            swaps: Arc::new(swaps::SwapService::new(config)),
+            signer,
+            wallet_lock,
+            spending: Arc::new(spending_policy::SpendingGuard::load(&db_manager)),
+            rates: Arc::new(rates::RateService::new(config)),
+            payment_requests: Arc::new(payment_requests::PaymentRequestService::new()),
+            config,
         })
     }
     
     pub async fn initialize(&self) -> Result<()> {
+        if simulation::is_enabled() {
+            tracing::warn!("Simulation mode: not connecting to the Ark server, Ark operations return simulated data");
+            self.load_transactions_from_db().await?;
+            self.recalculate_balance().await?;
+            return Ok(());
+        }
+
         // initialize the Ark gRPC client
-        let ark_server_url = std::env::var("ARK_SERVER_URL")
-            .unwrap_or_else(|_| "http://localhost:7070".into());
+        let ark_server_url = profiles::active().ark_server_url.clone();
             
         tracing::info!("Initializing with ark server: {}", ark_server_url);
         
@@ -79,12 +160,17 @@ impl AppState {
             },
             Err(e) => {
                 tracing::error!("Failed to connect to Ark server via gRPC: {}", e);
-                // continue even if connection fails so the app can still run with dummy data
+                // keep running so stored history and on-chain features stay available
             }
         }
         
         // load tx from db
         self.load_transactions_from_db().await?;
+
+        // without the ASP the stored history is the only source for the balance
+        if !grpc_client.is_connected() {
+            self.recalculate_balance().await?;
+        }
         
         // load balance from db
         self.load_balance_from_db().await?;
@@ -92,8 +178,16 @@ impl AppState {
         Ok(())
     }
 
+    // restores history recorded in previous runs that the ASP sync didn't already provide
     async fn load_transactions_from_db(&self) -> Result<()> {
-        // [TODO!!]  currently just use the in-memory tx
+        let (stored, _) = transactions::get_transaction_history(&Default::default()).await?;
+
+        let mut transactions = self.transactions.lock().await;
+        let known: std::collections::HashSet<String> = transactions.iter().map(|tx| tx.txid.clone()).collect();
+        let missing: Vec<_> = stored.into_iter().filter(|tx| !known.contains(&tx.txid)).collect();
+
+        tracing::info!("Loaded {} transactions from database", missing.len());
+        transactions.extend(missing);
         Ok(())
     }
 
@@ -105,6 +199,7 @@ impl AppState {
     pub async fn recalculate_balance(&self) -> Result<()> {
         let transactions = self.transactions.lock().await;
         let mut balance = self.balance.lock().await;
+        let previous_total = (balance.confirmed, balance.total);
         
         // reset balance
         *balance = crate::models::wallet::WalletBalance {
@@ -117,6 +212,11 @@ impl AppState {
         
         // calculate balance from tx
         for tx in transactions.iter() {
+            if tx.is_quarantined() {
+                // held back until an operator reviews it
+                continue;
+            }
+
             if tx.is_settled == Some(true) {
                 // for settled tx
                 if tx.amount > 0 {
@@ -148,8 +248,13 @@ impl AppState {
         balance.total = balance.confirmed + balance.untrusted_pending;
         
         // save balance to db
-        let balance_json = serde_json::to_string(&*balance)?;
-        self.db_manager.save_setting("balance", &balance_json)?;
+        if !simulation::has_fallback_entries() {
+            self.save_balance_snapshot(&balance)?;
+        }
+
+        if previous_total != (balance.confirmed, balance.total) {
+            self.publish_balance(&balance);
+        }
         
         tracing::info!(
             "Recalculated balance: confirmed={}, trusted_pending={}, untrusted_pending={}, total={}",
@@ -159,6 +264,29 @@ impl AppState {
         Ok(())
     }
 
+    fn save_balance_snapshot(&self, balance: &crate::models::wallet::WalletBalance) -> Result<()> {
+        let snapshot = crate::models::wallet::BalanceSnapshot {
+            balance: balance.clone(),
+            last_updated: chrono::Utc::now().timestamp(),
+        };
+        self.db_manager.save_setting(BALANCE_SETTINGS_KEY, &serde_json::to_string(&snapshot)?)
+    }
+
+    pub fn cached_balance(&self) -> Result<Option<crate::models::wallet::BalanceSnapshot>> {
+        match self.db_manager.get_setting(BALANCE_SETTINGS_KEY)? {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn publish_balance(&self, balance: &crate::models::wallet::WalletBalance) {
+        self.events.publish(events::WalletEvent::BalanceUpdate {
+            confirmed: balance.confirmed,
+            pending: balance.trusted_pending + balance.untrusted_pending,
+            total: balance.total,
+        });
+    }
+
     pub async fn can_send(&self, amount: u64) -> Result<bool> {
         let balance = self.balance.lock().await;
         Ok(balance.confirmed >= amount)
@@ -0,0 +1,255 @@
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::services::events::WalletEvent;
+use crate::services::renewal::RenewalPolicy;
+use crate::services::APP_STATE;
+use crate::storage::DbManager;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MonitorStatus {
+    pub running: bool,
+    pub interval_secs: u64,
+    pub runs: u64,
+    pub last_run: Option<i64>,
+    pub last_error: Option<String>,
+    pub vtxos_renewed: u64,
+    pub vtxos_swept: u64,
+    pub sweep_recovered_sats: u64, // in exits started for expired VTXOs
+    pub deposits_detected: u64,
+}
+
+// periodically refreshes VTXOs and history, renews VTXOs close to expiry and reports new deposits
+pub struct BackgroundMonitor {
+    interval: Duration,
+    policy: Mutex<RenewalPolicy>,
+    running: AtomicBool,
+    generation: AtomicU64, // bumped on every start/stop so a stale loop exits
+    shutdown: Notify,
+    status: Mutex<MonitorStatus>,
+    seen_deposits: Mutex<Option<HashSet<String>>>, // None until the first sync has seeded it
+    warned_vtxos: Mutex<HashSet<String>>, // expiry warnings are only published once per VTXO
+}
+
+impl BackgroundMonitor {
+    pub fn new(db: &DbManager, interval: Duration) -> Self {
+        Self {
+            interval,
+            policy: Mutex::new(RenewalPolicy::load(db)),
+            running: AtomicBool::new(false),
+            generation: AtomicU64::new(0),
+            shutdown: Notify::new(),
+            status: Mutex::new(MonitorStatus {
+                interval_secs: interval.as_secs(),
+                ..Default::default()
+            }),
+            seen_deposits: Mutex::new(None),
+            warned_vtxos: Mutex::new(HashSet::new()),
+        }
+    }
+
+    // returns false if the monitor was already running
+    pub fn start(self: &Arc<Self>) -> bool {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            tracing::info!("Background monitor started, interval {}s", monitor.interval.as_secs());
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(monitor.interval) => {},
+                    _ = monitor.shutdown.notified() => {},
+                }
+                if monitor.generation.load(Ordering::SeqCst) != generation {
+                    break;
+                }
+
+                let result = monitor.run_once().await;
+
+                let mut status = monitor.status.lock();
+                status.runs += 1;
+                status.last_run = Some(chrono::Utc::now().timestamp());
+                status.last_error = result.err().map(|e| {
+                    tracing::warn!("Background monitor run failed: {}", e);
+                    e.to_string()
+                });
+            }
+
+            tracing::info!("Background monitor stopped");
+        });
+
+        true
+    }
+
+    // returns false if the monitor wasn't running
+    pub fn stop(&self) -> bool {
+        if !self.running.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.shutdown.notify_waiters();
+        true
+    }
+
+    pub fn status(&self) -> MonitorStatus {
+        let mut status = self.status.lock().clone();
+        status.running = self.running.load(Ordering::SeqCst);
+        status
+    }
+
+    pub fn policy(&self) -> RenewalPolicy {
+        self.policy.lock().clone()
+    }
+
+    pub fn set_policy(&self, policy: RenewalPolicy) -> Result<()> {
+        policy.validate()?;
+        policy.save(&APP_STATE.db_manager)?;
+        *self.policy.lock() = policy;
+        Ok(())
+    }
+
+    async fn run_once(&self) -> Result<()> {
+        {
+            let grpc_client = APP_STATE.grpc_client.lock().await;
+            if grpc_client.is_connected() {
+                // refreshes balance and publishes VTXO added/spent events
+                grpc_client.update_app_state().await?;
+            }
+        }
+
+        // history endpoints read from the database, refresh it here
+        let history = crate::services::transactions::sync_transaction_history().await?;
+        self.detect_deposits(&history);
+
+        if APP_STATE.wallet_lock.skips("VTXO sweeps and renewals") {
+            return Ok(());
+        }
+
+        // expired VTXOs would otherwise hold up every renewal round. A failed sweep still lets
+        // the VTXOs that can be renewed go into a round
+        if self.policy().sweep_expired {
+            match APP_STATE.exits.sweep_expired().await {
+                Ok(sweep) => {
+                    let mut status = self.status.lock();
+                    status.vtxos_swept += sweep.started.len() as u64;
+                    status.sweep_recovered_sats += sweep.amount;
+                },
+                Err(e) => tracing::error!("Failed to sweep expired VTXOs: {}", e),
+            }
+        }
+
+        self.renew_expiring_vtxos().await
+    }
+
+    fn detect_deposits(&self, history: &[crate::models::wallet::TransactionResponse]) {
+        let incoming = history.iter().filter(|tx| tx.amount > 0 && !matches!(tx.kind, crate::models::wallet::TransactionKind::Round { .. }));
+
+        let mut guard = self.seen_deposits.lock();
+        let Some(seen) = guard.as_mut() else {
+            // first run only records what already exists
+            *guard = Some(incoming.map(|tx| tx.txid.clone()).collect());
+            return;
+        };
+
+        let new: Vec<_> = incoming.filter(|tx| !seen.contains(&tx.txid)).collect();
+        drop(guard);
+        for tx in new {
+            self.note_deposit(tx);
+        }
+    }
+
+    // publishes DepositDetected once per txid, whether the history sync or the mempool watcher sees it first
+    pub fn note_deposit(&self, tx: &crate::models::wallet::TransactionResponse) {
+        if let Some(seen) = self.seen_deposits.lock().as_mut() {
+            if !seen.insert(tx.txid.clone()) {
+                return;
+            }
+        }
+
+        tracing::info!("Detected new deposit {} of {} sats", tx.txid, tx.amount);
+        APP_STATE.events.publish(WalletEvent::DepositDetected {
+            txid: tx.txid.clone(),
+            amount: tx.amount as u64,
+            type_name: tx.kind.name().to_string(),
+        });
+        self.status.lock().deposits_detected += 1;
+    }
+
+    // settles into a new round before the ASP can sweep VTXOs that are about to expire
+    async fn renew_expiring_vtxos(&self) -> Result<()> {
+        let client = {
+            let grpc_client = APP_STATE.grpc_client.lock().await;
+            let client_opt = grpc_client.get_ark_client();
+            client_opt.as_ref().map(Arc::clone)
+        };
+        let Some(client) = client else {
+            return Ok(());
+        };
+
+        let vtxos = client.spendable_vtxos().await
+            .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?;
+
+        let policy = self.policy();
+        let now = chrono::Utc::now().timestamp();
+        let mut expire_ats = Vec::new();
+        for outpoint in vtxos.iter().flat_map(|(outpoints, _)| outpoints.iter()) {
+            expire_ats.push(outpoint.expire_at);
+            let remaining = outpoint.expire_at - now;
+            if remaining <= policy.warn_before_secs as i64 && self.warned_vtxos.lock().insert(outpoint.outpoint.to_string()) {
+                tracing::warn!("VTXO {} expires in {}s", outpoint.outpoint, remaining);
+                APP_STATE.events.publish(WalletEvent::VtxoExpiring {
+                    outpoint: outpoint.outpoint.to_string(),
+                    expire_at: outpoint.expire_at,
+                    critical: remaining <= policy.critical_before_secs as i64,
+                });
+            }
+        }
+
+        let expiring = self.renewal_due(&expire_ats).await?;
+        if expiring == 0 {
+            return Ok(());
+        }
+
+        tracing::info!("{} VTXOs expire within {}s, renewing", expiring, policy.renew_before_secs);
+        crate::services::transactions::participate_in_round().await?;
+        self.status.lock().vtxos_renewed += expiring as u64;
+        Ok(())
+    }
+
+    // how many of the VTXOs expiring at these times the policy renews now. None with auto renewal off,
+    // or while fees are above the limit and none of them is critical. The round scheduler asks here too
+    pub async fn renewal_due(&self, expire_ats: &[i64]) -> Result<usize> {
+        let policy = self.policy();
+        let now = chrono::Utc::now().timestamp();
+        let expiring = expire_ats.iter().filter(|expire_at| **expire_at - now <= policy.renew_before_secs as i64).count();
+        if expiring == 0 {
+            return Ok(0);
+        }
+        if !policy.auto_renew {
+            tracing::info!("{} VTXOs are close to expiry but auto renewal is disabled", expiring);
+            return Ok(0);
+        }
+
+        // critical VTXOs are renewed at any fee, losing them costs more
+        let critical = expire_ats.iter().any(|expire_at| *expire_at - now <= policy.critical_before_secs as i64);
+        if let (Some(max_fee_rate), false) = (policy.max_fee_rate_sat_vb, critical) {
+            let fee_rate = crate::services::onchain::fee_estimator::FeeEstimator::new(APP_STATE.blockchain.clone())
+                .estimate_fee_rate().await?
+                .to_sat_per_vb_ceil();
+            if fee_rate > max_fee_rate {
+                tracing::info!("Postponing renewal of {} VTXOs, fee rate {} sat/vB is above {}", expiring, fee_rate, max_fee_rate);
+                return Ok(0);
+            }
+        }
+        Ok(expiring)
+    }
+}
@@ -3,9 +3,14 @@ use bitcoin::FeeRate;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use parking_lot::RwLock;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use crate::services::ark_grpc::EsploraBlockchain;
+use crate::services::chain::ChainBackend;
+use crate::services::profiles::{self, FeeSource};
+
+// anything above this is almost certainly a unit mistake rather than a real fee bid
+pub const MAX_FEE_RATE_SAT_VB: u64 = 1000;
 
 #[derive(Debug, Deserialize)]
 struct MempoolSpaceFees {
@@ -21,7 +26,7 @@ struct MempoolSpaceFees {
     minimum_fee: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FeeEstimates {
     pub fastest: u64,      // next block
     pub fast: u64,         // 2-3 blocks
@@ -29,6 +34,10 @@ pub struct FeeEstimates {
     pub slow: u64,         // 12-24 blocks
     pub minimum: u64,      // min relay fee
     pub timestamp: i64,
+    #[serde(default)]
+    pub source: String,    // "mempool_space", "blockstream", "bitcoin_core" or "default"
+    #[serde(default)]
+    pub age_seconds: u64,  // time since the estimates were fetched
 }
 
 #[derive(Debug, Clone)]
@@ -45,6 +54,18 @@ pub enum FeePriority {
     Slow,    // 12+ blocks
 }
 
+impl FeePriority {
+    // closest priority for a confirmation target in blocks
+    pub fn for_target_blocks(blocks: u32) -> Self {
+        match blocks {
+            0..=1 => FeePriority::Fastest,
+            2..=3 => FeePriority::Fast,
+            4..=6 => FeePriority::Normal,
+            _ => FeePriority::Slow,
+        }
+    }
+}
+
 impl From<String> for FeePriority {
     fn from(s: String) -> Self {
         match s.to_lowercase().as_str() {
@@ -57,22 +78,66 @@ impl From<String> for FeePriority {
     }
 }
 
+// shared by every estimator, kept warm by the background refresher
+static FEE_CACHE: Lazy<RwLock<Option<CachedFeeEstimates>>> = Lazy::new(|| RwLock::new(None));
+
+pub const SOURCE_NAMES: &[&str] = &["mempool_space", "blockstream", "bitcoin_core"];
+const SOURCE_PRIORITY_KEY: &str = "fee_source_priority";
+
+// source names tried before the rest of the profile's sources, None until read from the settings table
+static SOURCE_PRIORITY: Lazy<RwLock<Option<Vec<String>>>> = Lazy::new(|| RwLock::new(None));
+
+pub fn source_priority() -> Vec<String> {
+    if let Some(priority) = SOURCE_PRIORITY.read().clone() {
+        return priority;
+    }
+    let priority = match crate::services::APP_STATE.db_manager.get_setting(SOURCE_PRIORITY_KEY) {
+        Ok(Some(json)) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable fee source priority: {}", e);
+            Vec::new()
+        }),
+        Ok(None) => Vec::new(),
+        Err(e) => {
+            tracing::warn!("Failed to load fee source priority: {}", e);
+            Vec::new()
+        }
+    };
+    *SOURCE_PRIORITY.write() = Some(priority.clone());
+    priority
+}
+
+pub fn validate_source_priority(priority: &[String]) -> Result<()> {
+    for (i, name) in priority.iter().enumerate() {
+        if !SOURCE_NAMES.contains(&name.as_str()) {
+            return Err(anyhow!("Unknown fee source {}, expected one of {}", name, SOURCE_NAMES.join(", ")));
+        }
+        if priority[..i].contains(name) {
+            return Err(anyhow!("Fee source {} is listed twice", name));
+        }
+    }
+    Ok(())
+}
+
+// takes effect on the next fetch, cached estimates are kept until they expire
+pub fn set_source_priority(priority: Vec<String>) -> Result<()> {
+    validate_source_priority(&priority)?;
+    crate::services::APP_STATE.db_manager.save_setting(SOURCE_PRIORITY_KEY, &serde_json::to_string(&priority)?)?;
+    tracing::info!("Fee source priority updated: {:?}", priority);
+    *SOURCE_PRIORITY.write() = Some(priority);
+    Ok(())
+}
+
 pub struct FeeEstimator {
-    blockchain: Arc<EsploraBlockchain>,
+    blockchain: Arc<ChainBackend>,
     http_client: reqwest::Client,
     network: bitcoin::Network,
-    cache: Arc<RwLock<Option<CachedFeeEstimates>>>,
+    fee_sources: Vec<FeeSource>,
     cache_duration: Duration,
 }
 
 impl FeeEstimator {
-    pub fn new(blockchain: Arc<EsploraBlockchain>) -> Self {
-        let network = match std::env::var("BITCOIN_NETWORK").unwrap_or_else(|_| "regtest".to_string()).as_str() {
-            "mainnet" => bitcoin::Network::Bitcoin,
-            "testnet" => bitcoin::Network::Testnet,
-            "signet" => bitcoin::Network::Signet,
-            _ => bitcoin::Network::Regtest,
-        };
+    pub fn new(blockchain: Arc<ChainBackend>) -> Self {
+        let profile = profiles::active();
 
         Self {
             blockchain,
@@ -80,12 +145,29 @@ impl FeeEstimator {
                 .timeout(Duration::from_secs(10))
                 .build()
                 .expect("Failed to build HTTP client"),
-            network,
-            cache: Arc::new(RwLock::new(None)),
-            cache_duration: Duration::from_secs(300), // 5 minutes
+            network: profile.network,
+            fee_sources: profile.fee_sources.clone(),
+            cache_duration: Duration::from_secs(300), // only fetched on demand when the refresher falls behind
         }
     }
 
+    // refetches estimates on an interval so requests never wait on a fee source
+    pub fn start_refresher(blockchain: Arc<ChainBackend>, interval: Duration) {
+        tokio::spawn(async move {
+            let estimator = Self::new(blockchain);
+            loop {
+                estimator.refresh().await;
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    pub async fn refresh(&self) -> FeeEstimates {
+        let estimates = self.fetch_from_sources().await;
+        self.cache_estimates(estimates.clone());
+        estimates
+    }
+
     pub async fn estimate_fee_rate(&self) -> Result<FeeRate> {
         let estimates = self.get_fee_estimates().await?;
         // normal priority as default
@@ -99,69 +181,33 @@ impl FeeEstimator {
             return Ok(cached);
         }
 
-        // multiple sources in order of preference
-        let estimates = match self.network {
-            bitcoin::Network::Bitcoin | bitcoin::Network::Testnet => {
-                self.try_multiple_sources().await?
-            },
-            bitcoin::Network::Regtest => {
-                self.get_regtest_estimates().await?
-            },
-            bitcoin::Network::Signet => {
-                self.try_signet_sources().await?
-            },
-            _ => {
-                self.get_default_estimates()
-            }
-        };
-
-        // cache the estimates
-        self.cache_estimates(estimates.clone());
-        
-        Ok(estimates)
-    }
-
-    async fn try_signet_sources(&self) -> Result<FeeEstimates> {
-        tracing::info!("Fetching signet fee estimates");
-
-        if let Ok(estimates) = self.fetch_mempool_space_estimates().await {
-            tracing::info!("Successfully fetched signet fees from mempool.space");
-            return Ok(estimates);
-        }
-
-        tracing::warn!("Signet fee sources failed, using defaults");
-        Ok(self.get_signet_estimates())
+        Ok(self.refresh().await)
     }
 
-    async fn try_multiple_sources(&self) -> Result<FeeEstimates> {
-        // mempool.space first
-        if let Ok(estimates) = self.fetch_mempool_space_estimates().await {
-            return Ok(estimates);
-        }
-
-        // blockstream
-        if let Ok(estimates) = self.fetch_blockstream_estimates().await {
-            return Ok(estimates);
-        }
+    // sources come from the network profile, in order of preference
+    async fn fetch_from_sources(&self) -> FeeEstimates {
+        for source in order_sources(&self.fee_sources, &source_priority()) {
+            let result = match source {
+                FeeSource::MempoolSpace(base_url) => self.fetch_mempool_space_estimates(base_url).await,
+                FeeSource::Blockstream(base_url) => self.fetch_blockstream_estimates(base_url).await,
+                FeeSource::BitcoinCore => self.fetch_bitcoin_core_estimates().await,
+            };
 
-        // local node
-        if let Ok(estimates) = self.fetch_bitcoin_core_estimates().await {
-            return Ok(estimates);
+            match result {
+                Ok(estimates) => {
+                    tracing::info!("Fetched fee estimates from {:?}", source);
+                    return FeeEstimates { source: source_name(source).to_string(), ..estimates };
+                },
+                Err(e) => tracing::warn!("Fee source {:?} failed: {}", source, e),
+            }
         }
 
         // fallback to defaults
-        tracing::warn!("All fee sources failed, using mainnet defaults");
-        Ok(self.get_default_estimates())
+        tracing::warn!("All fee sources failed, using {} defaults", self.network);
+        FeeEstimates { source: "default".to_string(), ..self.get_default_estimates() }
     }
 
-    async fn fetch_mempool_space_estimates(&self) -> Result<FeeEstimates> {
-        let base_url = match self.network {
-            bitcoin::Network::Bitcoin => "https://mempool.space",
-            bitcoin::Network::Testnet => "https://mempool.space/testnet",
-            bitcoin::Network::Signet => "https://mempool.space/signet",
-            _ => return Err(anyhow!("Network not supported by mempool.space")),
-        };
-
+    async fn fetch_mempool_space_estimates(&self, base_url: &str) -> Result<FeeEstimates> {
         let url = format!("{}/api/v1/fees/recommended", base_url);
         tracing::debug!("Fetching fees from mempool.space: {}", url);
 
@@ -183,16 +229,11 @@ impl FeeEstimator {
             slow: fees.economy_fee,
             minimum: fees.minimum_fee,
             timestamp: chrono::Utc::now().timestamp(),
+            ..Default::default()
         })
     }
 
-    async fn fetch_blockstream_estimates(&self) -> Result<FeeEstimates> {
-        let base_url = match self.network {
-            bitcoin::Network::Bitcoin => "https://blockstream.info",
-            bitcoin::Network::Testnet => "https://blockstream.info/testnet",
-            _ => return Err(anyhow!("Network not supported by blockstream")),
-        };
-
+    async fn fetch_blockstream_estimates(&self, base_url: &str) -> Result<FeeEstimates> {
         let url = format!("{}/api/fee-estimates", base_url);
         tracing::debug!("Fetching fees from blockstream: {}", url);
 
@@ -220,86 +261,22 @@ impl FeeEstimator {
             slow,
             minimum: 1,
             timestamp: chrono::Utc::now().timestamp(),
+            ..Default::default()
         })
     }
 
     async fn fetch_bitcoin_core_estimates(&self) -> Result<FeeEstimates> {
-        let is_regtest = match self.network {
-            bitcoin::Network::Bitcoin => false,
-            bitcoin::Network::Regtest => true,
-            _ => return Err(anyhow!("Unsupported network: only regtest and mainnet are supported"))
-        };
-
-        let command_base = if is_regtest { "nigiri" } else { "bitcoin-cli" };
-        let command_args_base: Vec<String> = if is_regtest {
-            vec!["rpc", "estimatesmartfee"]
-                .into_iter()
-                .map(String::from)
-                .collect()
-        } else {
-            vec!["-named", "estimatesmartfee"]
-                .into_iter()
-                .map(String::from)
-                .collect()
-        };        
-        
-        tracing::info!("Bitcoin network: {:?}", self.network);
-        tracing::info!("Using {} to estimate fees", command_base);
-
-        let targets = vec![1, 3, 6, 144];
         let mut estimates = vec![];
-    
-        for target in targets {
-            tracing::info!("Fetching fee estimate for {} blocks", target);
-            
-            let mut args = command_args_base.clone();
-            if is_regtest {
-                args.push(target.to_string());
-            } else {
-                args.push(format!("conf_target={}", target));
-            }
-
-            let output = tokio::process::Command::new(command_base)
-                .args(&args)
-                .output()
-                .await?;
-
-            tracing::debug!("Command exit status: {}", output.status);
-            tracing::debug!("Stdout: {}", String::from_utf8_lossy(&output.stdout));
-            tracing::debug!("Stderr: {}", String::from_utf8_lossy(&output.stderr));
-    
-            if output.status.success() {
-                let stdout_str = String::from_utf8_lossy(&output.stdout);
-                
-                let clean_json = Self::strip_ansi_codes(&stdout_str);
-                tracing::info!("Cleaned JSON for {} blocks: {}", target, clean_json);
-                
-                match serde_json::from_str::<serde_json::Value>(&clean_json) {
-                    Ok(response) => {
-                        tracing::debug!("Parsed JSON: {:?}", response);
-                        
-                        if let Some(feerate) = response.get("feerate").and_then(|v| v.as_f64()) {
-                            // convert BTC/kvB to sat/vB
-                            let sat_per_vb = (feerate * 100_000.0) as u64;
-                            estimates.push(sat_per_vb);
-                            tracing::info!("Fee estimate for {} blocks: {} BTC/kvB = {} sat/vB", target, feerate, sat_per_vb);
-                        } else {
-                            tracing::warn!("No 'feerate' field found in response for {} blocks", target);
-                        }
-                    },
-                    Err(e) => {
-                        tracing::error!("Failed to parse JSON for {} blocks: {}", target, e);
-                        tracing::debug!("Clean JSON was: {}", clean_json);
-                    }
+        for target in [1, 3, 6, 144] {
+            match crate::services::bitcoin_rpc::estimate_smart_fee(target).await? {
+                Some(sat_per_vb) => {
+                    tracing::debug!("Fee estimate for {} blocks: {} sat/vB", target, sat_per_vb);
+                    estimates.push(sat_per_vb);
                 }
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                tracing::warn!("Command failed for {} blocks. Exit code: {}, stderr: {}", target, output.status, stderr);
+                None => tracing::warn!("bitcoind has no fee estimate for {} blocks", target),
             }
         }
-    
-        tracing::info!("Collected {} estimates: {:?}", estimates.len(), estimates);
-    
+
         if estimates.len() >= 4 {
             let fee_estimates = FeeEstimates {
                 fastest: estimates[0],
@@ -308,6 +285,7 @@ impl FeeEstimator {
                 slow: estimates[3],
                 minimum: 1,
                 timestamp: chrono::Utc::now().timestamp(),
+                ..Default::default()
             };
             tracing::info!("Successfully created fee estimates: {:?}", fee_estimates);
             Ok(fee_estimates)
@@ -315,53 +293,6 @@ impl FeeEstimator {
             Err(anyhow!("Failed to get enough fee estimates from bitcoin core: got {} estimates, need 4", estimates.len()))
         }
     }
-    
-    // helper function to strip ANSI color codes (alt: no color env var for nigiri)
-    fn strip_ansi_codes(input: &str) -> String {
-        // simple regex to remove ANSI escape seq
-        // pattern: \x1b\[[0-9;]*m
-        let mut result = String::new();
-        let mut chars = input.chars();
-        
-        while let Some(ch) = chars.next() {
-            if ch == '\x1b' {
-                // skip escape seq
-                if chars.next() == Some('[') {
-                    // skip until we find 'm'
-                    while let Some(c) = chars.next() {
-                        if c == 'm' {
-                            break;
-                        }
-                    }
-                }
-            } else {
-                result.push(ch);
-            }
-        }
-        
-        result
-    }
-
-    async fn get_regtest_estimates(&self) -> Result<FeeEstimates> {
-        tracing::info!("Testing API calls on regtest...");
-
-        // for regtest => bitcoin-cli first
-        if let Ok(estimates) = self.fetch_bitcoin_core_estimates().await {
-            tracing::info!("Successfully fetched from bitcoin-cli: {:?}", estimates);
-            return Ok(estimates);
-        }
-
-        // otherwise use fixed values
-        tracing::warn!("using hardcoded regtest values");
-        Ok(FeeEstimates {
-            fastest: 10,
-            fast: 5,
-            normal: 2,
-            slow: 1,
-            minimum: 1,
-            timestamp: chrono::Utc::now().timestamp(),
-        })
-    }
 
     fn get_signet_estimates(&self) -> FeeEstimates {
         FeeEstimates {
@@ -371,6 +302,7 @@ impl FeeEstimator {
             slow: 1,
             minimum: 1,
             timestamp: chrono::Utc::now().timestamp(),
+            ..Default::default()
         }
     }
 
@@ -384,7 +316,9 @@ impl FeeEstimator {
                 slow: 10,
                 minimum: 1,
                 timestamp: chrono::Utc::now().timestamp(),
+                ..Default::default()
             },
+            bitcoin::Network::Signet => self.get_signet_estimates(),
             _ => FeeEstimates {
                 fastest: 10,
                 fast: 5,
@@ -392,22 +326,24 @@ impl FeeEstimator {
                 slow: 1,
                 minimum: 1,
                 timestamp: chrono::Utc::now().timestamp(),
+                ..Default::default()
             },
         }
     }
 
     fn get_cached_estimates(&self) -> Option<FeeEstimates> {
-        let cache = self.cache.read();
+        let cache = FEE_CACHE.read();
         if let Some(cached) = cache.as_ref() {
-            if cached.last_updated.elapsed() < self.cache_duration {
-                return Some(cached.estimates.clone());
+            let age = cached.last_updated.elapsed();
+            if age < self.cache_duration {
+                return Some(FeeEstimates { age_seconds: age.as_secs(), ..cached.estimates.clone() });
             }
         }
         None
     }
 
     fn cache_estimates(&self, estimates: FeeEstimates) {
-        let mut cache = self.cache.write();
+        let mut cache = FEE_CACHE.write();
         *cache = Some(CachedFeeEstimates {
             estimates,
             last_updated: Instant::now(),
@@ -434,6 +370,20 @@ impl FeeEstimator {
             .ok_or_else(|| anyhow!("Invalid fee rate"))
     }
 
+    // explicit fee rate from a caller, must relay and must not be absurd
+    pub async fn check_fee_rate(&self, sat_per_vb: u64) -> Result<FeeRate> {
+        let minimum = self.get_fee_estimates().await?.minimum.max(1);
+        if sat_per_vb < minimum {
+            return Err(anyhow!("Fee rate {} sat/vB is below the minimum relay fee of {} sat/vB", sat_per_vb, minimum));
+        }
+        if sat_per_vb > MAX_FEE_RATE_SAT_VB {
+            return Err(anyhow!("Fee rate {} sat/vB is above the maximum of {} sat/vB", sat_per_vb, MAX_FEE_RATE_SAT_VB));
+        }
+
+        FeeRate::from_sat_per_vb(sat_per_vb)
+            .ok_or_else(|| anyhow!("Invalid fee rate"))
+    }
+
     // legacy method for backward compatibility
     pub fn get_priority_fee_rate(&self, priority: FeePriority) -> FeeRate {
         let base_rate = self.get_fallback_fee_rate();
@@ -472,4 +422,48 @@ impl FeeEstimator {
             _ => FeeRate::from_sat_per_vb(1).expect("Valid fee rate"),
         }
     }
-}
\ No newline at end of file
+}
+
+fn source_name(source: &FeeSource) -> &'static str {
+    match source {
+        FeeSource::MempoolSpace(_) => "mempool_space",
+        FeeSource::Blockstream(_) => "blockstream",
+        FeeSource::BitcoinCore => "bitcoin_core",
+    }
+}
+
+// listed sources first and in that order, the others after them in the profile's order.
+// a listed source the profile doesn't have is skipped
+fn order_sources<'a>(sources: &'a [FeeSource], priority: &[String]) -> Vec<&'a FeeSource> {
+    let mut ordered: Vec<_> = sources.iter().collect();
+    ordered.sort_by_key(|source| {
+        priority.iter().position(|name| name == source_name(source)).unwrap_or(priority.len())
+    });
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_sources() {
+        let sources = vec![
+            FeeSource::MempoolSpace("https://mempool.space".to_string()),
+            FeeSource::Blockstream("https://blockstream.info".to_string()),
+            FeeSource::BitcoinCore,
+        ];
+        let names = |priority: &[&str]| {
+            let priority: Vec<String> = priority.iter().map(|name| name.to_string()).collect();
+            order_sources(&sources, &priority).into_iter().map(source_name).collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&[]), vec!["mempool_space", "blockstream", "bitcoin_core"]);
+        assert_eq!(names(&["bitcoin_core"]), vec!["bitcoin_core", "mempool_space", "blockstream"]);
+        assert_eq!(names(&["blockstream", "bitcoin_core"]), vec!["blockstream", "bitcoin_core", "mempool_space"]);
+
+        assert!(validate_source_priority(&["blockstream".to_string()]).is_ok());
+        assert!(validate_source_priority(&["esplora".to_string()]).is_err());
+        assert!(validate_source_priority(&["blockstream".to_string(), "blockstream".to_string()]).is_err());
+    }
+}
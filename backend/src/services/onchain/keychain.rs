@@ -0,0 +1,226 @@
+use anyhow::{anyhow, Result};
+use bitcoin::key::{CompressedPublicKey, Keypair, Secp256k1};
+use bitcoin::{Address, Script};
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::services::APP_STATE;
+
+const RECEIVE_CHAIN: u32 = 0;
+const CHANGE_CHAIN: u32 = 1;
+
+// settings key holding the receive address of the seed discovery last ran for
+const DISCOVERY_SETTING: &str = "address_discovery";
+
+// an on-chain address of this wallet and the key that spends from it
+#[derive(Debug, Clone)]
+pub struct WalletKey {
+    pub address: Address,
+    pub keypair: Keypair,
+}
+
+fn p2wpkh(keypair: &Keypair) -> Address {
+    let network = crate::services::profiles::active().network;
+    Address::p2wpkh(&CompressedPublicKey(keypair.public_key()), network)
+}
+
+pub fn receive_address() -> Result<Address> {
+    let (keypair, _) = APP_STATE.key_manager.load_or_create_wallet()?;
+    Ok(p2wpkh(&keypair))
+}
+
+// hands out the next change address and records it so it gets scanned from now on
+pub fn next_change_address() -> Result<Address> {
+    let index = issued(CHANGE_CHAIN)?;
+    let keypair = APP_STATE.key_manager.derive_change_keypairs(index..index + 1)?
+        .pop()
+        .ok_or_else(|| anyhow!("Failed to derive change key {}", index))?;
+    let address = p2wpkh(&keypair);
+
+    APP_STATE.db_manager.get_conn()?.execute(
+        "INSERT INTO wallet_addresses (chain, idx, address, created_at) VALUES (?, ?, ?, ?)",
+        params![CHANGE_CHAIN, index, address.to_string(), chrono::Utc::now().timestamp()],
+    )?;
+
+    tracing::debug!("Derived change address {} at index {}", address, index);
+    Ok(address)
+}
+
+// every address the wallet can hold coins on: the receive key as P2WPKH and P2TR, further receive addresses
+// found by discovery, plus all change addresses handed out
+pub fn wallet_keys() -> Result<Vec<WalletKey>> {
+    let (keypair, _) = APP_STATE.key_manager.load_or_create_wallet()?;
+    let network = crate::services::profiles::active().network;
+    let secp = Secp256k1::new();
+
+    let mut keys = vec![
+        WalletKey { address: p2wpkh(&keypair), keypair },
+        WalletKey { address: Address::p2tr(&secp, keypair.x_only_public_key().0, None, network), keypair },
+    ];
+
+    let receive_issued = issued(RECEIVE_CHAIN)?;
+    if receive_issued > 1 {
+        keys.extend(APP_STATE.key_manager.derive_receive_keypairs(1..receive_issued)?
+            .into_iter()
+            .map(|keypair| WalletKey { address: p2wpkh(&keypair), keypair }));
+    }
+
+    let change_issued = issued(CHANGE_CHAIN)?;
+    if change_issued > 0 {
+        keys.extend(APP_STATE.key_manager.derive_change_keypairs(0..change_issued)?
+            .into_iter()
+            .map(|keypair| WalletKey { address: p2wpkh(&keypair), keypair }));
+    }
+
+    Ok(keys)
+}
+
+// one past the highest index recorded for a chain, receive index 0 is never recorded
+fn issued(chain: u32) -> Result<u32> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let index = conn.query_row(
+        "SELECT COALESCE(MAX(idx) + 1, 0) FROM wallet_addresses WHERE chain = ?",
+        params![chain],
+        |row| row.get(0),
+    )?;
+    Ok(index)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoverySummary {
+    pub gap_limit: u32,
+    pub receive_addresses: u32, // including the main receive address
+    pub change_addresses: u32,
+    pub boarding_address: Option<String>, // None while the Ark server is unreachable
+}
+
+// runs discovery once per seed, so a wallet imported from its mnemonic finds the addresses it used before
+pub fn start_discovery() {
+    if crate::services::simulation::is_enabled() {
+        return;
+    }
+    tokio::spawn(async move {
+        let receive = match receive_address() {
+            Ok(address) => address.to_string(),
+            Err(e) => {
+                tracing::warn!("Address discovery skipped: {}", e);
+                return;
+            }
+        };
+        if APP_STATE.db_manager.get_setting(DISCOVERY_SETTING).ok().flatten().as_deref() == Some(receive.as_str()) {
+            return;
+        }
+
+        match discover(APP_STATE.config.address_gap_limit).await {
+            Ok(summary) => {
+                if let Err(e) = APP_STATE.db_manager.save_setting(DISCOVERY_SETTING, &receive) {
+                    tracing::warn!("Failed to record address discovery: {}", e);
+                }
+                tracing::info!(
+                    "Address discovery found {} receive and {} change addresses",
+                    summary.receive_addresses, summary.change_addresses
+                );
+            },
+            Err(e) => tracing::warn!("Address discovery failed, retried on the next start: {}", e),
+        }
+    });
+}
+
+// scans both chains until gap_limit unused addresses in a row and records every index up to the last used one,
+// already recorded addresses are kept so discovery never shrinks the wallet
+pub async fn discover(gap_limit: u32) -> Result<DiscoverySummary> {
+    let receive_used = scan_chain(RECEIVE_CHAIN, gap_limit).await?;
+    let change_used = scan_chain(CHANGE_CHAIN, gap_limit).await?;
+
+    {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        let now = chrono::Utc::now().timestamp();
+        if let Some(last) = receive_used.filter(|last| *last > 0) {
+            for (offset, keypair) in APP_STATE.key_manager.derive_receive_keypairs(1..last + 1)?.iter().enumerate() {
+                conn.execute(
+                    "INSERT OR IGNORE INTO wallet_addresses (chain, idx, address, created_at) VALUES (?, ?, ?, ?)",
+                    params![RECEIVE_CHAIN, offset as u32 + 1, p2wpkh(keypair).to_string(), now],
+                )?;
+            }
+        }
+        if let Some(last) = change_used {
+            for (index, keypair) in APP_STATE.key_manager.derive_change_keypairs(0..last + 1)?.iter().enumerate() {
+                conn.execute(
+                    "INSERT OR IGNORE INTO wallet_addresses (chain, idx, address, created_at) VALUES (?, ?, ?, ?)",
+                    params![CHANGE_CHAIN, index as u32, p2wpkh(keypair).to_string(), now],
+                )?;
+            }
+        }
+    }
+
+    // every boarding address is built on the receive key, fetching the current server's one registers it
+    // so the monitor picks up boarding outputs made before the import
+    let boarding_address = match crate::services::wallet::get_boarding_address().await {
+        Ok(boarding) => Some(boarding.address),
+        Err(e) => {
+            tracing::debug!("Boarding address not registered during discovery: {}", e);
+            None
+        }
+    };
+
+    Ok(DiscoverySummary {
+        gap_limit,
+        receive_addresses: issued(RECEIVE_CHAIN)?.max(1),
+        change_addresses: issued(CHANGE_CHAIN)?,
+        boarding_address,
+    })
+}
+
+// highest used index of a chain, checked in batches of gap_limit addresses
+async fn scan_chain(chain: u32, gap_limit: u32) -> Result<Option<u32>> {
+    let mut last_used = None;
+    let mut start = 0;
+    loop {
+        let batch = start..start + gap_limit;
+        let keypairs = match chain {
+            RECEIVE_CHAIN => APP_STATE.key_manager.derive_receive_keypairs(batch)?,
+            _ => APP_STATE.key_manager.derive_change_keypairs(batch)?,
+        };
+        for (offset, keypair) in keypairs.iter().enumerate() {
+            if APP_STATE.blockchain.address_used(&p2wpkh(keypair)).await? {
+                last_used = Some(start + offset as u32);
+            }
+        }
+        start += gap_limit;
+
+        if gap_reached(last_used, start, gap_limit) {
+            return Ok(last_used);
+        }
+    }
+}
+
+// true once the addresses after the last used one fill a whole gap
+fn gap_reached(last_used: Option<u32>, scanned: u32, gap_limit: u32) -> bool {
+    let first_unused = last_used.map_or(0, |index| index + 1);
+    scanned - first_unused >= gap_limit
+}
+
+// the faucet's hot wallet, its own account (m/84'/0'/1'/0/0) so its coins never mix with the user's
+pub fn faucet_key() -> Result<WalletKey> {
+    let keypair = APP_STATE.key_manager.derive_faucet_keypair()?;
+    Ok(WalletKey { address: p2wpkh(&keypair), keypair })
+}
+
+pub fn find_key<'a>(keys: &'a [WalletKey], script: &Script) -> Option<&'a WalletKey> {
+    keys.iter().find(|key| key.address.script_pubkey().as_script() == script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gap_reached() {
+        // nothing used, one batch is enough
+        assert!(gap_reached(None, 20, 20));
+        // index 5 used, 6..20 is only 14 unused
+        assert!(!gap_reached(Some(5), 20, 20));
+        assert!(gap_reached(Some(5), 40, 20));
+        assert!(!gap_reached(Some(39), 40, 20));
+    }
+}
@@ -1,6 +1,8 @@
 pub mod utxo_manager;
 pub mod fee_estimator;
 pub mod transaction_builder;
+pub mod keychain;
+pub mod multisig;
 
 pub use utxo_manager::UtxoManager;
 pub use fee_estimator::FeeEstimator;
@@ -8,7 +10,8 @@ pub use transaction_builder::TransactionBuilder;
 
 use anyhow::Result;
 use bitcoin::{Address, Amount, Txid};
-use crate::services::ark_grpc::EsploraBlockchain;
+use crate::services::chain::ChainBackend;
+use fee_estimator::FeePriority;
 
 pub struct OnChainPaymentService {
     pub utxo_manager: UtxoManager,
@@ -17,7 +20,7 @@ pub struct OnChainPaymentService {
 }
 
 impl OnChainPaymentService {
-    pub fn new(blockchain: std::sync::Arc<EsploraBlockchain>) -> Self {
+    pub fn new(blockchain: std::sync::Arc<ChainBackend>) -> Self {
         let utxo_manager = UtxoManager::new(blockchain.clone());
         let fee_estimator = FeeEstimator::new(blockchain.clone());
         let transaction_builder = TransactionBuilder::new(blockchain);
@@ -29,20 +32,37 @@ impl OnChainPaymentService {
         }
     }
 
+    // an explicit fee rate is used as is once checked, the priority only matters without one
+    // separate on-chain wallet spending only from the given keys, used for the faucet's hot wallet
+    pub fn with_keys(blockchain: std::sync::Arc<ChainBackend>, keys: Vec<keychain::WalletKey>) -> Self {
+        let addresses = keys.iter().map(|key| key.address.clone()).collect();
+
+        Self {
+            utxo_manager: UtxoManager::for_addresses(blockchain.clone(), addresses),
+            fee_estimator: FeeEstimator::new(blockchain.clone()),
+            transaction_builder: TransactionBuilder::with_keys(blockchain, keys),
+        }
+    }
+
     pub async fn send_payment(
         &self,
         to_address: Address,
         amount: Amount,
-        fee_rate: Option<bitcoin::FeeRate>,
+        fee_rate_sat_vb: Option<u64>,
+        priority: FeePriority,
     ) -> Result<Txid> {
         // 1. get available UTXOs
         let utxos = self.utxo_manager.get_spendable_utxos().await?;
         
         // 2. estimate fee if not provided
-        let fee_rate = match fee_rate {
-            Some(rate) => rate,
-            None => self.fee_estimator.estimate_fee_rate().await?,
+        let fee_rate = match fee_rate_sat_vb {
+            Some(rate) => self.fee_estimator.check_fee_rate(rate).await?,
+            None => self.fee_estimator.estimate_fee_for_priority(priority).await?,
         };
+        tracing::info!(
+            "Sending {} to {} at {} sat/vB",
+            amount, to_address, fee_rate.to_sat_per_vb_ceil()
+        );
 
         // 3. select UTXOs and build tx
         let txid = self.transaction_builder
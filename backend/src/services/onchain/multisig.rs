@@ -0,0 +1,525 @@
+use anyhow::{anyhow, Result};
+use bitcoin::bip32::{ChildNumber, DerivationPath, Fingerprint, KeySource, Xpriv, Xpub};
+use bitcoin::blockdata::opcodes::all::OP_CHECKMULTISIG;
+use bitcoin::blockdata::script::Builder;
+use bitcoin::hashes::Hash;
+use bitcoin::key::{Keypair, Secp256k1};
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::sighash::SighashCache;
+use bitcoin::{
+    absolute::LockTime, transaction::Version, Address, Amount, FeeRate, NetworkKind, Psbt,
+    ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::services::onchain::fee_estimator::FeePriority;
+use crate::services::onchain::utxo_manager::SpendableUtxo;
+use crate::services::onchain::{FeeEstimator, UtxoManager};
+use crate::services::APP_STATE;
+use crate::storage::DbManager;
+
+const SETTINGS_KEY: &str = "multisig";
+const THRESHOLD: usize = 2;
+const RECEIVE_CHAIN: u32 = 0;
+const CHANGE_CHAIN: u32 = 1;
+// our account key, BIP48 script type 2 (native segwit multisig)
+const ACCOUNT_PATH: &str = "m/48'/0'/0'/2'";
+
+// the 2-of-3 vault: our account key plus two cosigner xpubs, stored in the settings table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigSetup {
+    pub cosigners: Vec<String>, // "[fingerprint/path]xpub" or a bare xpub
+    pub receive_index: u32,     // next unused receive address
+    pub change_index: u32,      // next unused change address
+    pub created_at: i64,
+}
+
+impl MultisigSetup {
+    pub fn load(db: &DbManager) -> Result<Option<Self>> {
+        match db.get_setting(SETTINGS_KEY)? {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save(&self, db: &DbManager) -> Result<()> {
+        db.save_setting(SETTINGS_KEY, &serde_json::to_string(self)?)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MultisigInfo {
+    pub descriptor: String,
+    pub change_descriptor: String,
+    pub our_key: String, // share this with the cosigners
+    pub cosigners: Vec<String>,
+    pub threshold: usize,
+    pub balance: u64,
+    pub utxos: usize,
+}
+
+// one of the three keys, with the origin signers need to find their child key
+#[derive(Debug, Clone)]
+struct MultisigKey {
+    origin: KeySource,
+    xpub: Xpub,
+}
+
+impl MultisigKey {
+    fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let (origin, xpub) = match value.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+            Some((origin, xpub)) => {
+                let (fingerprint, path) = origin.split_once('/').unwrap_or((origin, ""));
+                let fingerprint = Fingerprint::from_str(fingerprint)
+                    .map_err(|e| anyhow!("Invalid key origin fingerprint '{}': {}", fingerprint, e))?;
+                let path = DerivationPath::from_str(&format!("m/{}", path).trim_end_matches('/'))
+                    .map_err(|e| anyhow!("Invalid key origin path '{}': {}", path, e))?;
+                (Some((fingerprint, path)), xpub)
+            },
+            None => (None, value),
+        };
+
+        let xpub = Xpub::from_str(xpub).map_err(|e| anyhow!("Invalid xpub '{}': {}", xpub, e))?;
+        let origin = origin.unwrap_or_else(|| (xpub.fingerprint(), DerivationPath::master()));
+        Ok(Self { origin, xpub })
+    }
+
+    fn ours() -> Result<(Self, Xpriv)> {
+        let (fingerprint, account) = APP_STATE.key_manager.derive_multisig_account()?;
+        let path = DerivationPath::from_str(ACCOUNT_PATH)?;
+        let key = Self {
+            origin: (fingerprint, path),
+            xpub: Xpub::from_priv(&Secp256k1::new(), &account),
+        };
+        Ok((key, account))
+    }
+
+    // "[fingerprint/48'/0'/0'/2']xpub", the usual way to hand a multisig key around
+    fn to_descriptor_key(&self) -> String {
+        let path = self.origin.1.to_string();
+        let path = path.trim_start_matches('m').trim_start_matches('/');
+        match path.is_empty() {
+            true => format!("[{}]{}", self.origin.0, self.xpub),
+            false => format!("[{}/{}]{}", self.origin.0, path, self.xpub),
+        }
+    }
+
+    fn derive(&self, chain: u32, index: u32) -> Result<(PublicKey, KeySource)> {
+        let children = [ChildNumber::Normal { index: chain }, ChildNumber::Normal { index }];
+        let child = self.xpub.derive_pub(&Secp256k1::new(), &children)
+            .map_err(|e| anyhow!("Failed to derive multisig key: {}", e))?;
+        Ok((child.public_key, (self.origin.0, self.origin.1.extend(children))))
+    }
+}
+
+// an address of the vault with everything needed to spend from it
+#[derive(Debug, Clone)]
+struct VaultAddress {
+    address: Address,
+    witness_script: ScriptBuf,
+    derivation: BTreeMap<PublicKey, KeySource>,
+    our_keypair: Keypair,
+}
+
+struct Vault {
+    keys: Vec<MultisigKey>, // ours first
+    our_account: Xpriv,
+    setup: MultisigSetup,
+}
+
+impl Vault {
+    fn load() -> Result<Self> {
+        let setup = MultisigSetup::load(&APP_STATE.db_manager)?
+            .ok_or_else(|| anyhow!("No multisig wallet set up, POST /api/multisig first"))?;
+        let (ours, our_account) = MultisigKey::ours()?;
+        let mut keys = vec![ours];
+        for cosigner in &setup.cosigners {
+            keys.push(MultisigKey::parse(cosigner)?);
+        }
+        Ok(Self { keys, our_account, setup })
+    }
+
+    fn descriptor(&self, chain: u32) -> String {
+        let keys: Vec<String> = self.keys.iter()
+            .map(|key| format!("{}/{}/*", key.to_descriptor_key(), chain))
+            .collect();
+        with_checksum(&format!("wsh(sortedmulti({},{}))", THRESHOLD, keys.join(",")))
+    }
+
+    fn address(&self, chain: u32, index: u32) -> Result<VaultAddress> {
+        let mut derivation = BTreeMap::new();
+        for key in &self.keys {
+            let (public_key, source) = key.derive(chain, index)?;
+            derivation.insert(public_key, source);
+        }
+
+        // sortedmulti: keys in lexicographic order of their compressed encoding
+        let mut public_keys: Vec<PublicKey> = derivation.keys().copied().collect();
+        public_keys.sort_by_key(|key| key.serialize());
+        let mut builder = Builder::new().push_int(THRESHOLD as i64);
+        for public_key in &public_keys {
+            builder = builder.push_key(&bitcoin::PublicKey::new(*public_key));
+        }
+        let witness_script = builder
+            .push_int(public_keys.len() as i64)
+            .push_opcode(OP_CHECKMULTISIG)
+            .into_script();
+
+        let secp = Secp256k1::new();
+        let children = [ChildNumber::Normal { index: chain }, ChildNumber::Normal { index }];
+        let our_keypair = self.our_account.derive_priv(&secp, &children)
+            .map_err(|e| anyhow!("Failed to derive multisig key: {}", e))?
+            .to_keypair(&secp);
+
+        Ok(VaultAddress {
+            address: Address::p2wsh(&witness_script, crate::services::profiles::active().network),
+            witness_script,
+            derivation,
+            our_keypair,
+        })
+    }
+
+    // every address handed out so far, the only ones that can hold coins
+    fn issued_addresses(&self) -> Result<Vec<VaultAddress>> {
+        let mut addresses = Vec::new();
+        for index in 0..self.setup.receive_index {
+            addresses.push(self.address(RECEIVE_CHAIN, index)?);
+        }
+        for index in 0..self.setup.change_index {
+            addresses.push(self.address(CHANGE_CHAIN, index)?);
+        }
+        Ok(addresses)
+    }
+
+    fn next_address(&mut self, chain: u32) -> Result<VaultAddress> {
+        let index = match chain {
+            CHANGE_CHAIN => &mut self.setup.change_index,
+            _ => &mut self.setup.receive_index,
+        };
+        let address = self.address(chain, *index)?;
+        *index += 1;
+        self.setup.save(&APP_STATE.db_manager)?;
+        Ok(address)
+    }
+
+    async fn utxos(&self) -> Result<Vec<SpendableUtxo>> {
+        let addresses = self.issued_addresses()?.into_iter().map(|a| a.address).collect();
+        UtxoManager::for_addresses(APP_STATE.blockchain.clone(), addresses)
+            .get_spendable_utxos()
+            .await
+    }
+}
+
+pub fn setup(cosigners: Vec<String>) -> Result<MultisigSetup> {
+    if MultisigSetup::load(&APP_STATE.db_manager)?.is_some() {
+        return Err(anyhow!("A multisig wallet is already set up, changing its keys would strand its coins"));
+    }
+    if cosigners.len() != 2 {
+        return Err(anyhow!("Expected exactly 2 cosigner xpubs, got {}", cosigners.len()));
+    }
+
+    let network = NetworkKind::from(crate::services::profiles::active().network);
+    let (ours, _) = MultisigKey::ours()?;
+    let mut seen = vec![ours.xpub];
+    for cosigner in &cosigners {
+        let key = MultisigKey::parse(cosigner)?;
+        if key.xpub.network != network {
+            return Err(anyhow!("Cosigner key {} is for a different network", cosigner));
+        }
+        if seen.contains(&key.xpub) {
+            return Err(anyhow!("Each of the three keys must be different"));
+        }
+        seen.push(key.xpub);
+    }
+
+    let setup = MultisigSetup {
+        cosigners,
+        receive_index: 0,
+        change_index: 0,
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    setup.save(&APP_STATE.db_manager)?;
+    tracing::info!("Set up 2-of-3 multisig wallet");
+    Ok(setup)
+}
+
+pub async fn info() -> Result<MultisigInfo> {
+    let vault = Vault::load()?;
+    let utxos = vault.utxos().await?;
+
+    Ok(MultisigInfo {
+        descriptor: vault.descriptor(RECEIVE_CHAIN),
+        change_descriptor: vault.descriptor(CHANGE_CHAIN),
+        our_key: vault.keys[0].to_descriptor_key(),
+        cosigners: vault.setup.cosigners.clone(),
+        threshold: THRESHOLD,
+        balance: utxos.iter().map(|utxo| utxo.amount.to_sat()).sum(),
+        utxos: utxos.len(),
+    })
+}
+
+pub fn new_receive_address() -> Result<String> {
+    let mut vault = Vault::load()?;
+    Ok(vault.next_address(RECEIVE_CHAIN)?.address.to_string())
+}
+
+// PSBT spending from the vault, already carrying our signature, for the cosigners to complete
+pub async fn create_psbt(address: String, amount: u64, priority: FeePriority) -> Result<(Psbt, Amount)> {
+    let network = crate::services::profiles::active().network;
+    let to_address = Address::from_str(&address)?.require_network(network)?;
+    let amount = Amount::from_sat(amount);
+
+    let mut vault = Vault::load()?;
+    let fee_rate = FeeEstimator::new(APP_STATE.blockchain.clone())
+        .estimate_fee_for_priority(priority)
+        .await?;
+    let (selected, fee, change) = select_inputs(vault.utxos().await?, amount, fee_rate)?;
+
+    let mut outputs = vec![TxOut { value: amount, script_pubkey: to_address.script_pubkey() }];
+    let change_address = match change > Amount::ZERO {
+        true => Some(vault.next_address(CHANGE_CHAIN)?),
+        false => None,
+    };
+    if let Some(change_address) = &change_address {
+        outputs.push(TxOut { value: change, script_pubkey: change_address.address.script_pubkey() });
+    }
+
+    let tx = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: selected.iter()
+            .map(|utxo| TxIn {
+                previous_output: utxo.outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output: outputs,
+    };
+    let mut psbt = Psbt::from_unsigned_tx(tx).map_err(|e| anyhow!("Failed to create PSBT: {}", e))?;
+
+    let addresses = vault.issued_addresses()?;
+    for (input, utxo) in psbt.inputs.iter_mut().zip(&selected) {
+        let vault_address = find_address(&addresses, &utxo.address.script_pubkey())
+            .ok_or_else(|| anyhow!("UTXO {} isn't a vault address", utxo.outpoint))?;
+        input.witness_utxo = Some(TxOut { value: utxo.amount, script_pubkey: utxo.address.script_pubkey() });
+        input.witness_script = Some(vault_address.witness_script.clone());
+        input.bip32_derivation = vault_address.derivation.clone();
+    }
+    // lets the cosigners' wallets recognise the change as coming back to the vault
+    if let (Some(change_address), Some(output)) = (&change_address, psbt.outputs.get_mut(1)) {
+        output.witness_script = Some(change_address.witness_script.clone());
+        output.bip32_derivation = change_address.derivation.clone();
+    }
+
+    sign(&mut psbt, &addresses)?;
+    tracing::info!("Created multisig PSBT sending {} to {} with {} inputs, fee {}", amount, to_address, selected.len(), fee);
+    Ok((psbt, fee))
+}
+
+// adds our signature to a vault PSBT and finalizes whatever inputs have enough signatures
+pub fn sign_psbt(psbt: &mut Psbt) -> Result<bool> {
+    let vault = Vault::load()?;
+    sign(psbt, &vault.issued_addresses()?)?;
+    Ok(finalize(psbt))
+}
+
+// merges the signatures cosigners added to separate copies of the same PSBT
+pub fn combine_psbts(psbts: Vec<Psbt>) -> Result<(Psbt, bool)> {
+    let mut psbts = psbts.into_iter();
+    let mut combined = psbts.next().ok_or_else(|| anyhow!("No PSBTs to combine"))?;
+    for psbt in psbts {
+        combined.combine(psbt).map_err(|e| anyhow!("PSBTs don't belong to the same transaction: {}", e))?;
+    }
+    let finalized = finalize(&mut combined);
+    Ok((combined, finalized))
+}
+
+pub async fn broadcast_psbt(mut psbt: Psbt) -> Result<String> {
+    if !finalize(&mut psbt) {
+        return Err(anyhow!("PSBT doesn't have {} signatures on every input yet", THRESHOLD));
+    }
+    let tx = psbt.extract_tx().map_err(|e| anyhow!("Failed to extract transaction: {}", e))?;
+
+    use ark_client::Blockchain;
+    APP_STATE.blockchain.broadcast(&tx).await
+        .map_err(|e| anyhow!("Failed to broadcast transaction: {}", e))?;
+    let txid = tx.compute_txid();
+    tracing::info!("Broadcast multisig transaction {}", txid);
+    Ok(txid.to_string())
+}
+
+fn find_address<'a>(addresses: &'a [VaultAddress], script: &bitcoin::Script) -> Option<&'a VaultAddress> {
+    addresses.iter().find(|a| a.address.script_pubkey().as_script() == script)
+}
+
+fn sign(psbt: &mut Psbt, addresses: &[VaultAddress]) -> Result<()> {
+    let mut signatures = Vec::new();
+    {
+        let mut sighash_cache = SighashCache::new(&psbt.unsigned_tx);
+        for (index, input) in psbt.inputs.iter().enumerate() {
+            let Some(prevout) = &input.witness_utxo else {
+                continue;
+            };
+            let Some(vault_address) = find_address(addresses, &prevout.script_pubkey) else {
+                continue;
+            };
+            let public_key = bitcoin::PublicKey::new(vault_address.our_keypair.public_key());
+            if input.final_script_witness.is_some() || input.partial_sigs.contains_key(&public_key) {
+                continue;
+            }
+
+            let sighash = sighash_cache
+                .p2wsh_signature_hash(index, &vault_address.witness_script, prevout.value, bitcoin::EcdsaSighashType::All)
+                .map_err(|e| anyhow!("Failed to compute p2wsh sighash: {}", e))?;
+            let message = bitcoin::secp256k1::Message::from_digest(sighash.to_byte_array());
+            APP_STATE.signer.register(&vault_address.our_keypair);
+            let signature = APP_STATE.signer.sign_ecdsa(&public_key.inner, &message)?;
+            signatures.push((index, public_key, bitcoin::ecdsa::Signature::sighash_all(signature)));
+        }
+    }
+
+    for (index, public_key, signature) in signatures {
+        psbt.inputs[index].partial_sigs.insert(public_key, signature);
+    }
+    Ok(())
+}
+
+// builds the witness of every input with THRESHOLD signatures, true once all inputs are final
+fn finalize(psbt: &mut Psbt) -> bool {
+    for input in psbt.inputs.iter_mut() {
+        if input.final_script_witness.is_some() || input.partial_sigs.len() < THRESHOLD {
+            continue;
+        }
+        let Some(witness_script) = input.witness_script.clone() else {
+            continue;
+        };
+
+        // CHECKMULTISIG wants the signatures in the order their keys appear in the script
+        let keys: Vec<bitcoin::PublicKey> = witness_script.instructions()
+            .filter_map(|instruction| match instruction {
+                Ok(bitcoin::script::Instruction::PushBytes(bytes)) => bitcoin::PublicKey::from_slice(bytes.as_bytes()).ok(),
+                _ => None,
+            })
+            .collect();
+        let signatures: Vec<Vec<u8>> = keys.iter()
+            .filter_map(|key| input.partial_sigs.get(key))
+            .take(THRESHOLD)
+            .map(|signature| signature.to_vec())
+            .collect();
+        if signatures.len() < THRESHOLD {
+            continue;
+        }
+
+        // the leading empty item is eaten by CHECKMULTISIG's off-by-one pop
+        let mut witness = vec![Vec::new()];
+        witness.extend(signatures);
+        witness.push(witness_script.to_bytes());
+        input.final_script_witness = Some(Witness::from_slice(&witness));
+        input.partial_sigs.clear();
+    }
+
+    psbt.inputs.iter().all(|input| input.final_script_witness.is_some())
+}
+
+// largest first until amount and fee are covered, sized for 2-of-3 P2WSH inputs
+fn select_inputs(utxos: Vec<SpendableUtxo>, amount: Amount, fee_rate: FeeRate) -> Result<(Vec<SpendableUtxo>, Amount, Amount)> {
+    let utxo_manager = UtxoManager::new(APP_STATE.blockchain.clone());
+    let mut inputs = 1;
+    loop {
+        let fee = fee_rate.fee_vb(estimate_vsize(inputs, 2))
+            .ok_or_else(|| anyhow!("Fee calculation overflowed"))?;
+        let selected = utxo_manager.select_utxos(utxos.clone(), amount + fee)?;
+        if selected.len() > inputs {
+            inputs = selected.len();
+            continue;
+        }
+
+        let total: Amount = selected.iter().map(|utxo| utxo.amount).sum();
+        let change = total - amount - fee;
+        // dust change goes to the fee
+        return Ok(match change < Amount::from_sat(546) {
+            true => (selected, fee + change, Amount::ZERO),
+            false => (selected, fee, change),
+        });
+    }
+}
+
+fn estimate_vsize(inputs: usize, outputs: usize) -> u64 {
+    // 41 vB per input plus its witness (two signatures and the 105 byte script, ~254 WU),
+    // 43 vB per P2WSH-sized output
+    (11 + inputs * (41 + 64) + outputs * 43) as u64
+}
+
+// BIP380 descriptor checksum
+pub(crate) fn with_checksum(descriptor: &str) -> String {
+    const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+    const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+    fn polymod(c: u64, value: u64) -> u64 {
+        let c0 = c >> 35;
+        let mut c = ((c & 0x7ffffffff) << 5) ^ value;
+        for (bit, generator) in [0xf5dee51989, 0xa9fdca3312, 0x1bab10e32d, 0x3706b1677a, 0x644d626ffd].iter().enumerate() {
+            if c0 & (1 << bit) != 0 {
+                c ^= generator;
+            }
+        }
+        c
+    }
+
+    let mut c = 1u64;
+    let mut class = 0u64;
+    let mut class_count = 0;
+    for ch in descriptor.chars() {
+        // every character used in our descriptors is in the charset
+        let position = INPUT_CHARSET.find(ch).unwrap_or(0) as u64;
+        c = polymod(c, position & 31);
+        class = class * 3 + (position >> 5);
+        class_count += 1;
+        if class_count == 3 {
+            c = polymod(c, class);
+            class = 0;
+            class_count = 0;
+        }
+    }
+    if class_count > 0 {
+        c = polymod(c, class);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+
+    let checksum: String = (0..8)
+        .map(|j| CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char)
+        .collect();
+    format!("{}#{}", descriptor, checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descriptor_checksum() {
+        // test vector from BIP380
+        assert_eq!(with_checksum("raw(deadbeef)"), "raw(deadbeef)#89f8spxm");
+    }
+
+    #[test]
+    fn test_parse_key_with_origin() {
+        // BIP32 test vector 1 master key
+        let xpub = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+        let key = MultisigKey::parse(&format!("[d34db33f/48'/1'/0'/2']{}", xpub)).unwrap();
+        assert_eq!(key.origin.0.to_string(), "d34db33f");
+        assert_eq!(key.to_descriptor_key(), format!("[d34db33f/48'/1'/0'/2']{}", xpub));
+
+        let bare = MultisigKey::parse(xpub).unwrap();
+        assert_eq!(bare.origin.1, DerivationPath::master());
+        assert!(MultisigKey::parse("[zz/48']xpub").is_err());
+    }
+}
@@ -4,20 +4,28 @@ use bitcoin::{
     absolute::LockTime, transaction::Version, Witness, ScriptBuf, AddressType
 };
 use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::hashes::Hash;
+use bitcoin::Psbt;
 use ark_client::Blockchain;
 use std::sync::Arc;
 use std::str::FromStr;
-use crate::services::ark_grpc::EsploraBlockchain;
+use crate::services::chain::ChainBackend;
 use crate::services::onchain::fee_estimator::{FeeEstimates, FeePriority};
+use super::keychain::WalletKey;
 use super::utxo_manager::SpendableUtxo;
 
 pub struct TransactionBuilder {
-    blockchain: Arc<EsploraBlockchain>,
+    blockchain: Arc<ChainBackend>,
+    keys: Option<Vec<WalletKey>>, // spends from these instead of the main wallet, change goes to the first
 }
 
 impl TransactionBuilder {
-    pub fn new(blockchain: Arc<EsploraBlockchain>) -> Self {
-        Self { blockchain }
+    pub fn new(blockchain: Arc<ChainBackend>) -> Self {
+        Self { blockchain, keys: None }
+    }
+
+    pub fn with_keys(blockchain: Arc<ChainBackend>, keys: Vec<WalletKey>) -> Self {
+        Self { blockchain, keys: Some(keys) }
     }
 
     pub async fn build_and_broadcast(
@@ -57,7 +65,7 @@ impl TransactionBuilder {
             to_address,
             amount,
             fee_rate,
-        ).await?;
+        )?;
 
         Ok(fee)
     }
@@ -110,10 +118,7 @@ impl TransactionBuilder {
             to_address.clone(),
             amount,
             fee_rate,
-        ).await?;
-
-        let (keypair, _) = crate::services::APP_STATE.key_manager.load_or_create_wallet()?;
-        let change_address = self.get_change_address(&keypair)?;
+        )?;
 
         // build ip
         let inputs: Vec<TxIn> = selected_utxos
@@ -136,7 +141,7 @@ impl TransactionBuilder {
         if change_amount > Amount::ZERO {
             outputs.push(TxOut {
                 value: change_amount,
-                script_pubkey: change_address.script_pubkey(),
+                script_pubkey: self.get_change_address()?.script_pubkey(),
             });
         }
 
@@ -148,12 +153,13 @@ impl TransactionBuilder {
             output: outputs,
         };
 
-        self.sign_transaction(&mut tx, &selected_utxos, &keypair).await?;
+        self.sign_transaction(&mut tx, &selected_utxos).await?;
 
         Ok((tx, change_amount))
     }
 
-    async fn calculate_transaction_details(
+    // picks inputs and works out fee and change, shared with ArkWallet's PSBT path
+    pub fn calculate_transaction_details(
         &self,
         available_utxos: Vec<SpendableUtxo>,
         to_address: Address,
@@ -233,12 +239,11 @@ impl TransactionBuilder {
         &self,
         tx: &mut Transaction,
         selected_utxos: &[SpendableUtxo],
-        keypair: &bitcoin::key::Keypair,
     ) -> Result<()> {
         use bitcoin::sighash::{Prevouts, SighashCache};
         use bitcoin::AddressType;
         
-        let secp = bitcoin::secp256k1::Secp256k1::new();
+        let signer = &crate::services::APP_STATE.signer;
         
         // prepare prevouts for sighash calculation
         let prevouts: Vec<TxOut> = selected_utxos
@@ -249,8 +254,17 @@ impl TransactionBuilder {
             })
             .collect();
     
+        // receive and change addresses are spent with different keys
+        let keys = self.wallet_keys()?;
+        for key in &keys {
+            signer.register(&key.keypair);
+        }
+
         // sign each ip
         for (input_index, utxo) in selected_utxos.iter().enumerate() {
+            let keypair = super::keychain::find_key(&keys, &utxo.address.script_pubkey())
+                .map(|key| key.keypair)
+                .ok_or_else(|| anyhow!("No key for input {} paying {}", input_index, utxo.address))?;
             let mut sighash_cache = SighashCache::new(&*tx);
             
             match utxo.address.address_type() {
@@ -267,7 +281,7 @@ impl TransactionBuilder {
                     let message = bitcoin::secp256k1::Message::from_digest_slice(&sighash[..])
                         .map_err(|e| anyhow!("Failed to create message: {}", e))?;
                     
-                    let signature = secp.sign_ecdsa(&message, &keypair.secret_key());
+                    let signature = signer.sign_ecdsa(&keypair.public_key(), &message)?;
                     let mut sig_bytes = signature.serialize_der().to_vec();
                     sig_bytes.push(bitcoin::EcdsaSighashType::All as u8);
     
@@ -278,7 +292,23 @@ impl TransactionBuilder {
                     tracing::debug!("Signed input {} with P2WPKH", input_index);
                 },
                 Some(AddressType::P2tr) => {
-                    return Err(anyhow!("Taproot addresses not supported for regular on-chain payments. Use boarding address only for Ark operations."));
+                    // key-path spend, the output key is the internal key tweaked with an empty script tree
+                    let sighash = sighash_cache
+                        .taproot_key_spend_signature_hash(
+                            input_index,
+                            &Prevouts::All(&prevouts),
+                            TapSighashType::Default,
+                        )
+                        .map_err(|e| anyhow!("Failed to compute taproot sighash: {}", e))?;
+
+                    let message = bitcoin::secp256k1::Message::from_digest(sighash.to_byte_array());
+                    let signature = bitcoin::taproot::Signature {
+                        signature: signer.sign_schnorr(&keypair.x_only_public_key().0, &message, true)?,
+                        sighash_type: TapSighashType::Default,
+                    };
+
+                    tx.input[input_index].witness = Witness::p2tr_key_spend(&signature);
+                    tracing::debug!("Signed input {} with P2TR key path", input_index);
                 },
                 Some(address_type) => {
                     return Err(anyhow!("Unsupported address type for signing: {:?}", address_type));
@@ -293,20 +323,309 @@ impl TransactionBuilder {
         Ok(())
     }
     
-    fn get_change_address(&self, keypair: &bitcoin::key::Keypair) -> Result<Address> {
-        let network = match std::env::var("BITCOIN_NETWORK").unwrap_or_else(|_| "regtest".to_string()).as_str() {
-            "mainnet" => bitcoin::Network::Bitcoin,
-            "testnet" => bitcoin::Network::Testnet,
-            "signet" => bitcoin::Network::Signet,
-            _ => bitcoin::Network::Regtest,
+    // re-signed copy of one of our unconfirmed txs paying fee_rate, the extra fee comes out of the change
+    pub async fn build_replacement(&self, original: &Transaction, fee_rate: FeeRate) -> Result<(Transaction, Amount)> {
+        if !original.is_explicitly_rbf() {
+            return Err(anyhow!("Transaction {} doesn't signal replaceability", original.compute_txid()));
+        }
+
+        let keys = self.wallet_keys()?;
+
+        // BIP125 replacements have to spend the same inputs, all of which must be ours to re-sign
+        let mut prevouts = Vec::new();
+        for input in &original.input {
+            let outpoint = input.previous_output;
+            let parent = self.blockchain.find_tx(&outpoint.txid).await
+                .map_err(|e| anyhow!("Failed to fetch parent {}: {}", outpoint.txid, e))?
+                .ok_or_else(|| anyhow!("Parent transaction {} not found", outpoint.txid))?;
+            let prevout = parent.output.get(outpoint.vout as usize)
+                .ok_or_else(|| anyhow!("Parent {} has no output {}", outpoint.txid, outpoint.vout))?;
+            let key = super::keychain::find_key(&keys, &prevout.script_pubkey)
+                .ok_or_else(|| anyhow!("Input {} doesn't belong to this wallet", outpoint))?;
+
+            prevouts.push(SpendableUtxo {
+                outpoint,
+                amount: prevout.value,
+                address: key.address.clone(),
+                confirmation_time: None,
+            });
+        }
+
+        let input_total: Amount = prevouts.iter().map(|utxo| utxo.amount).sum();
+        let output_total: Amount = original.output.iter().map(|output| output.value).sum();
+        let old_fee = input_total.checked_sub(output_total)
+            .ok_or_else(|| anyhow!("Outputs exceed inputs"))?;
+
+        let change_index = original.output.iter()
+            .rposition(|output| super::keychain::find_key(&keys, &output.script_pubkey).is_some())
+            .ok_or_else(|| anyhow!("No change output to take the higher fee from"))?;
+
+        // the replacement must also pay for its own relay at the minimum incremental rate of 1 sat/vB
+        let vsize = self.estimate_transaction_size(original.input.len(), original.output.len()) as u64;
+        let target_fee = fee_rate.fee_vb(vsize).ok_or_else(|| anyhow!("Fee calculation overflowed"))?;
+        let new_fee = target_fee.max(old_fee + Amount::from_sat(vsize));
+        let extra_fee = new_fee - old_fee;
+
+        let mut tx = original.clone();
+        for input in tx.input.iter_mut() {
+            input.witness = Witness::new();
+        }
+
+        let change = tx.output[change_index].value.checked_sub(extra_fee)
+            .ok_or_else(|| anyhow!("Change of {} can't cover the extra fee of {}", tx.output[change_index].value, extra_fee))?;
+        if change < Amount::from_sat(546) {
+            // dust change goes to the miners as well
+            tx.output.remove(change_index);
+        } else {
+            tx.output[change_index].value = change;
+        }
+        if tx.output.is_empty() {
+            return Err(anyhow!("Nothing left to send after the fee bump"));
+        }
+
+        self.sign_transaction(&mut tx, &prevouts).await?;
+
+        let fee = input_total - tx.output.iter().map(|output| output.value).sum();
+        Ok((tx, fee))
+    }
+
+    // child spending our outputs of an unconfirmed parent back to us, paying enough for the pair to reach fee_rate
+    pub async fn build_cpfp_child(
+        &self,
+        parent: &Transaction,
+        parent_fee: Amount,
+        parent_vsize: u64,
+        fee_rate: FeeRate,
+    ) -> Result<(Transaction, Amount)> {
+        let keys = self.wallet_keys()?;
+        let parent_txid = parent.compute_txid();
+
+        let mut utxos = Vec::new();
+        for (vout, output) in parent.output.iter().enumerate() {
+            let Some(key) = super::keychain::find_key(&keys, &output.script_pubkey) else {
+                continue;
+            };
+            let spent = self.blockchain.get_output_status(&parent_txid, vout as u32).await
+                .map_err(|e| anyhow!("Failed to check output status: {}", e))?
+                .spend_txid
+                .is_some();
+            if !spent {
+                utxos.push(SpendableUtxo {
+                    outpoint: OutPoint { txid: parent_txid, vout: vout as u32 },
+                    amount: output.value,
+                    address: key.address.clone(),
+                    confirmation_time: None,
+                });
+            }
+        }
+        if utxos.is_empty() {
+            return Err(anyhow!("Transaction {} has no unspent output paying this wallet", parent_txid));
+        }
+
+        let child_vsize = self.estimate_transaction_size(utxos.len(), 1) as u64;
+        let package_fee = fee_rate.fee_vb(parent_vsize + child_vsize)
+            .ok_or_else(|| anyhow!("Fee calculation overflowed"))?;
+        if package_fee <= parent_fee {
+            return Err(anyhow!("Transaction {} already pays at least {} sat/vB", parent_txid, fee_rate.to_sat_per_vb_ceil()));
+        }
+        // the child has to at least pay its own relay fee
+        let fee = (package_fee - parent_fee).max(Amount::from_sat(child_vsize));
+
+        let input_total: Amount = utxos.iter().map(|utxo| utxo.amount).sum();
+        let value = input_total.checked_sub(fee)
+            .filter(|value| *value >= Amount::from_sat(546))
+            .ok_or_else(|| anyhow!("Outputs worth {} can't pay a child fee of {}", input_total, fee))?;
+
+        let mut tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: utxos
+                .iter()
+                .map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence: bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: vec![TxOut {
+                value,
+                script_pubkey: self.receive_address()?.script_pubkey(),
+            }],
         };
-    
-        let pubkey = keypair.public_key();
-        let pubkey_bytes = pubkey.serialize();
-        let wpkh = bitcoin::key::CompressedPublicKey::from_slice(&pubkey_bytes)
-            .map_err(|e| anyhow!("Failed to create WPKH: {}", e))?;
-        let address = bitcoin::Address::p2wpkh(&wpkh, network);
-    
-        Ok(address)
+
+        self.sign_transaction(&mut tx, &utxos).await?;
+        Ok((tx, fee))
+    }
+
+    // sweeps the given UTXOs into a single output back to us
+    pub async fn build_consolidation(&self, utxos: Vec<SpendableUtxo>, fee_rate: FeeRate) -> Result<(Transaction, Amount)> {
+        if utxos.len() < 2 {
+            return Err(anyhow!("Need at least two UTXOs to consolidate, found {}", utxos.len()));
+        }
+
+        let address = self.receive_address()?;
+
+        let vsize = self.estimate_transaction_size(utxos.len(), 1) as u64;
+        let fee = fee_rate.fee_vb(vsize)
+            .ok_or_else(|| anyhow!("Fee calculation overflowed"))?
+            .max(Amount::from_sat(vsize));
+        let input_total: Amount = utxos.iter().map(|utxo| utxo.amount).sum();
+        let value = input_total.checked_sub(fee)
+            .filter(|value| *value >= Amount::from_sat(546))
+            .ok_or_else(|| anyhow!("UTXOs worth {} can't pay a consolidation fee of {}", input_total, fee))?;
+
+        let mut tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: utxos
+                .iter()
+                .map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence: bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: vec![TxOut {
+                value,
+                script_pubkey: address.script_pubkey(),
+            }],
+        };
+
+        self.sign_transaction(&mut tx, &utxos).await?;
+        Ok((tx, fee))
+    }
+
+    // unsigned PSBT with witness_utxo set on every input so external signers can compute sighashes
+    pub fn build_psbt(
+        &self,
+        available_utxos: Vec<SpendableUtxo>,
+        to_address: Address,
+        amount: Amount,
+        fee_rate: FeeRate,
+    ) -> Result<(Psbt, Amount)> {
+        let (selected_utxos, fee, change_amount) = self.calculate_transaction_details(
+            available_utxos,
+            to_address.clone(),
+            amount,
+            fee_rate,
+        )?;
+
+        let mut outputs = vec![TxOut {
+            value: amount,
+            script_pubkey: to_address.script_pubkey(),
+        }];
+        if change_amount > Amount::ZERO {
+            outputs.push(TxOut {
+                value: change_amount,
+                script_pubkey: self.get_change_address()?.script_pubkey(),
+            });
+        }
+
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: selected_utxos
+                .iter()
+                .map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence: bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: outputs,
+        };
+
+        let mut psbt = Psbt::from_unsigned_tx(tx)
+            .map_err(|e| anyhow!("Failed to create PSBT: {}", e))?;
+        for (input, utxo) in psbt.inputs.iter_mut().zip(&selected_utxos) {
+            input.witness_utxo = Some(TxOut {
+                value: utxo.amount,
+                script_pubkey: utxo.address.script_pubkey(),
+            });
+        }
+
+        Ok((psbt, fee))
+    }
+
+    // signs and finalizes the P2WPKH inputs paying to our keys, true once every input is finalized
+    pub fn sign_psbt(&self, psbt: &mut Psbt) -> Result<bool> {
+        let signer = &crate::services::APP_STATE.signer;
+        let keys = self.wallet_keys()?;
+        for key in &keys {
+            signer.register(&key.keypair);
+        }
+
+        let mut witnesses = Vec::new();
+        {
+            let mut sighash_cache = SighashCache::new(&psbt.unsigned_tx);
+            for (index, input) in psbt.inputs.iter().enumerate() {
+                let Some(prevout) = input.witness_utxo.as_ref().filter(|o| o.script_pubkey.is_p2wpkh()) else {
+                    continue;
+                };
+                let Some(key) = super::keychain::find_key(&keys, &prevout.script_pubkey) else {
+                    continue;
+                };
+
+                let sighash = sighash_cache
+                    .p2wpkh_signature_hash(index, &prevout.script_pubkey, prevout.value, bitcoin::EcdsaSighashType::All)
+                    .map_err(|e| anyhow!("Failed to compute p2wpkh sighash: {}", e))?;
+                let message = bitcoin::secp256k1::Message::from_digest(sighash.to_byte_array());
+                let signature = bitcoin::ecdsa::Signature::sighash_all(signer.sign_ecdsa(&key.keypair.public_key(), &message)?);
+
+                witnesses.push((index, Witness::p2wpkh(&signature, &key.keypair.public_key())));
+            }
+        }
+
+        for (index, witness) in witnesses {
+            psbt.inputs[index].final_script_witness = Some(witness);
+        }
+
+        Ok(Self::finalize_psbt(psbt))
+    }
+
+    // turns the single signature an external signer leaves on a P2WPKH input into its witness
+    pub fn finalize_psbt(psbt: &mut Psbt) -> bool {
+        for input in psbt.inputs.iter_mut() {
+            if input.final_script_witness.is_some() || input.partial_sigs.len() != 1 {
+                continue;
+            }
+            let is_p2wpkh = input.witness_utxo.as_ref().is_some_and(|o| o.script_pubkey.is_p2wpkh());
+            if !is_p2wpkh {
+                continue;
+            }
+
+            let Some((public_key, signature)) = input.partial_sigs.iter().next().map(|(pk, sig)| (pk.inner, *sig)) else {
+                continue;
+            };
+            input.final_script_witness = Some(Witness::p2wpkh(&signature, &public_key));
+            input.partial_sigs.clear();
+        }
+
+        psbt.inputs.iter().all(|input| input.final_script_witness.is_some())
+    }
+
+    // fresh address on the internal chain so change isn't linked to the receive address
+    fn get_change_address(&self) -> Result<Address> {
+        match &self.keys {
+            Some(_) => self.receive_address(),
+            None => super::keychain::next_change_address(),
+        }
+    }
+
+    fn wallet_keys(&self) -> Result<Vec<WalletKey>> {
+        match &self.keys {
+            Some(keys) => Ok(keys.clone()),
+            None => super::keychain::wallet_keys(),
+        }
+    }
+
+    fn receive_address(&self) -> Result<Address> {
+        match self.keys.as_ref().and_then(|keys| keys.first()) {
+            Some(key) => Ok(key.address.clone()),
+            None => super::keychain::receive_address(),
+        }
     }
 }
\ No newline at end of file
@@ -2,8 +2,7 @@ use anyhow::{Result, anyhow};
 use bitcoin::{Address, Amount, OutPoint};
 use ark_client::{Blockchain, ExplorerUtxo};
 use std::sync::Arc;
-use std::str::FromStr;
-use crate::services::ark_grpc::EsploraBlockchain;
+use crate::services::chain::ChainBackend;
 
 #[derive(Debug, Clone)]
 pub struct SpendableUtxo {
@@ -25,30 +24,50 @@ impl From<(ExplorerUtxo, Address)> for SpendableUtxo {
 }
 
 pub struct UtxoManager {
-    blockchain: Arc<EsploraBlockchain>,
+    blockchain: Arc<ChainBackend>,
+    addresses: Option<Vec<Address>>, // scanned instead of the main wallet's addresses
 }
 
 impl UtxoManager {
-    pub fn new(blockchain: Arc<EsploraBlockchain>) -> Self {
-        Self { blockchain }
+    pub fn new(blockchain: Arc<ChainBackend>) -> Self {
+        Self { blockchain, addresses: None }
     }
 
-    pub async fn get_spendable_utxos(&self) -> Result<Vec<SpendableUtxo>> {
-        let address_str = crate::services::wallet::get_onchain_address().await?;
-        let address = bitcoin::Address::from_str(&address_str)?
-            .assume_checked();
+    pub fn for_addresses(blockchain: Arc<ChainBackend>, addresses: Vec<Address>) -> Self {
+        Self { blockchain, addresses: Some(addresses) }
+    }
 
-        tracing::info!("Looking for UTXOs at regular Bitcoin address: {}", address);
+    pub async fn get_spendable_utxos(&self) -> Result<Vec<SpendableUtxo>> {
+        let addresses = match &self.addresses {
+            Some(addresses) => addresses.clone(),
+            None => crate::services::wallet::get_onchain_addresses().await?,
+        };
+
+        let mut explorer_utxos = Vec::new();
+        for address in addresses {
+            tracing::info!("Looking for UTXOs at regular Bitcoin address: {}", address);
+
+            // find UTXOs for this address
+            let utxos = self.blockchain.find_outpoints(&address).await
+                .map_err(|e| anyhow!("Failed to find outpoints: {}", e))?;
+            explorer_utxos.extend(utxos.into_iter().map(|utxo| (utxo, address.clone())));
+        }
 
-        // find UTXOs for this address
-        let explorer_utxos = self.blockchain.find_outpoints(&address).await
-            .map_err(|e| anyhow!("Failed to find outpoints: {}", e))?;
+        // quarantined deposits must not be spent until they are reviewed
+        let quarantined: std::collections::HashSet<String> = {
+            let transactions = crate::services::APP_STATE.transactions.lock().await;
+            transactions.iter()
+                .filter(|tx| tx.is_quarantined())
+                .map(|tx| tx.txid.clone())
+                .collect()
+        };
 
         // filter for unspent UTXOs and convert to SpendableUtxo
         let spendable_utxos: Vec<SpendableUtxo> = explorer_utxos
             .into_iter()
-            .filter(|utxo| !utxo.is_spent)
-            .map(|utxo| SpendableUtxo::from((utxo, address.clone())))
+            .filter(|(utxo, _)| !utxo.is_spent)
+            .filter(|(utxo, _)| !quarantined.contains(&utxo.outpoint.txid.to_string()))
+            .map(SpendableUtxo::from)
             .collect();
 
         tracing::info!("Found {} spendable UTXOs totaling {} sats", 
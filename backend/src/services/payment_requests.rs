@@ -0,0 +1,283 @@
+use anyhow::{anyhow, Result};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::services::events::{VtxoStatus, WalletEvent};
+use crate::services::APP_STATE;
+
+pub const MIN_EXPIRY_SECS: u64 = 60;
+pub const MAX_EXPIRY_SECS: u64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentRequestStatus {
+    Pending,
+    Paid,
+    Expired,
+}
+
+impl PaymentRequestStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PaymentRequestStatus::Pending => "pending",
+            PaymentRequestStatus::Paid => "paid",
+            PaymentRequestStatus::Expired => "expired",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "paid" => PaymentRequestStatus::Paid,
+            "expired" => PaymentRequestStatus::Expired,
+            _ => PaymentRequestStatus::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentRequest {
+    pub id: String,
+    pub amount: u64,
+    pub memo: Option<String>,
+    pub ark_address: String,
+    pub uri: String,
+    pub status: PaymentRequestStatus,
+    pub paid_outpoint: Option<String>,
+    pub webhook_url: Option<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub paid_at: Option<i64>,
+}
+
+// merchant style invoices for Ark payments, settled by the first incoming VTXO of the exact amount
+pub struct PaymentRequestService {
+    http_client: reqwest::Client,
+}
+
+impl PaymentRequestService {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to build HTTP client"),
+        }
+    }
+
+    pub async fn create(
+        &self,
+        amount: u64,
+        memo: Option<String>,
+        expiry_secs: u64,
+        webhook_url: Option<String>,
+    ) -> Result<PaymentRequest> {
+        if amount == 0 {
+            return Err(anyhow!("amount must be positive"));
+        }
+        if let Some(dust) = APP_STATE.server_info.dust() {
+            if amount < dust {
+                return Err(anyhow!("Amount {} sats is below the server dust limit of {} sats", amount, dust));
+            }
+        }
+        if !(MIN_EXPIRY_SECS..=MAX_EXPIRY_SECS).contains(&expiry_secs) {
+            return Err(anyhow!("expiry_secs must be between {} and {}", MIN_EXPIRY_SECS, MAX_EXPIRY_SECS));
+        }
+        if let Some(url) = &webhook_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(anyhow!("webhook_url must be an http(s) URL"));
+            }
+        }
+
+        let payment = crate::services::payments::create_payment_uri(Some(amount), None, memo.clone()).await?;
+        let ark_address = payment.ark.clone()
+            .ok_or_else(|| anyhow!("Ark address unavailable, is the ASP reachable?"))?;
+
+        let now = chrono::Utc::now().timestamp();
+        let request = PaymentRequest {
+            id: hex::encode(rand::random::<[u8; 16]>()),
+            amount,
+            memo,
+            ark_address,
+            uri: payment.to_uri(),
+            status: PaymentRequestStatus::Pending,
+            paid_outpoint: None,
+            webhook_url,
+            created_at: now,
+            expires_at: now + expiry_secs as i64,
+            paid_at: None,
+        };
+
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.execute(
+            "INSERT INTO payment_requests (id, amount, memo, ark_address, uri, status, webhook_url, created_at, expires_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                request.id,
+                request.amount as i64,
+                request.memo,
+                request.ark_address,
+                request.uri,
+                request.status.as_str(),
+                request.webhook_url,
+                request.created_at,
+                request.expires_at,
+            ],
+        )?;
+
+        tracing::info!("Created payment request {} for {} sats", request.id, amount);
+        Ok(request)
+    }
+
+    pub fn list(&self) -> Result<Vec<PaymentRequest>> {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM payment_requests ORDER BY created_at DESC", COLUMNS))?;
+        let requests = stmt.query_map([], row_to_request)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(requests)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<PaymentRequest>> {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        let request = conn.query_row(
+            &format!("SELECT {} FROM payment_requests WHERE id = ?", COLUMNS),
+            params![id],
+            row_to_request,
+        ).optional()?;
+        Ok(request)
+    }
+
+    // settles requests from incoming VTXOs and expires stale ones
+    pub fn start_watcher(self: &Arc<Self>) {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut events = APP_STATE.events.subscribe();
+            let mut expiry_tick = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(WalletEvent::VtxoUpdate { outpoint, amount, status: VtxoStatus::Added }) => {
+                            if let Err(e) = service.settle(&outpoint, amount).await {
+                                tracing::warn!("Failed to match VTXO {} to a payment request: {}", outpoint, e);
+                            }
+                        },
+                        Ok(_) => {},
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Payment request watcher missed {} events", skipped);
+                        },
+                        Err(RecvError::Closed) => break,
+                    },
+                    _ = expiry_tick.tick() => {
+                        if let Err(e) = service.expire_stale().await {
+                            tracing::warn!("Failed to expire payment requests: {}", e);
+                        }
+                    },
+                }
+            }
+        });
+    }
+
+    // the oldest pending request of exactly this amount is the one being paid
+    async fn settle(&self, outpoint: &str, amount: u64) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let request = {
+            let conn = APP_STATE.db_manager.get_conn()?;
+            let id: Option<String> = conn.query_row(
+                "SELECT id FROM payment_requests
+                 WHERE status = 'pending' AND amount = ? AND expires_at > ?
+                 ORDER BY created_at LIMIT 1",
+                params![amount as i64, now],
+                |row| row.get(0),
+            ).optional()?;
+            let Some(id) = id else {
+                return Ok(());
+            };
+
+            conn.execute(
+                "UPDATE payment_requests SET status = 'paid', paid_outpoint = ?, paid_at = ? WHERE id = ?",
+                params![outpoint, now, id],
+            )?;
+            drop(conn);
+            self.get(&id)?
+        };
+        let Some(request) = request else {
+            return Ok(());
+        };
+
+        tracing::info!("Payment request {} paid by {}", request.id, outpoint);
+        APP_STATE.events.publish(WalletEvent::PaymentRequestPaid {
+            id: request.id.clone(),
+            outpoint: outpoint.to_string(),
+            amount,
+        });
+        self.notify(&request);
+        Ok(())
+    }
+
+    async fn expire_stale(&self) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let expired: Vec<String> = {
+            let conn = APP_STATE.db_manager.get_conn()?;
+            let mut stmt = conn.prepare("SELECT id FROM payment_requests WHERE status = 'pending' AND expires_at <= ?")?;
+            let ids = stmt.query_map(params![now], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            conn.execute(
+                "UPDATE payment_requests SET status = 'expired' WHERE status = 'pending' AND expires_at <= ?",
+                params![now],
+            )?;
+            ids
+        };
+
+        for id in expired {
+            if let Some(request) = self.get(&id)? {
+                tracing::info!("Payment request {} expired unpaid", request.id);
+                self.notify(&request);
+            }
+        }
+        Ok(())
+    }
+
+    // POSTs the request to its webhook in the background, with a few retries
+    fn notify(&self, request: &PaymentRequest) {
+        let Some(url) = request.webhook_url.clone() else {
+            return;
+        };
+        let client = self.http_client.clone();
+        let body = serde_json::json!({
+            "event": format!("payment_request.{}", request.status.as_str()),
+            "payment_request": request,
+        });
+
+        tokio::spawn(async move {
+            for attempt in 1..=3u32 {
+                match client.post(&url).json(&body).send().await {
+                    Ok(response) if response.status().is_success() => return,
+                    Ok(response) => tracing::warn!("Webhook {} returned {} (attempt {})", url, response.status(), attempt),
+                    Err(e) => tracing::warn!("Webhook {} failed: {} (attempt {})", url, e, attempt),
+                }
+                tokio::time::sleep(Duration::from_secs(5 * attempt as u64)).await;
+            }
+            tracing::error!("Giving up on webhook {}", url);
+        });
+    }
+}
+
+const COLUMNS: &str = "id, amount, memo, ark_address, uri, status, paid_outpoint, webhook_url, created_at, expires_at, paid_at";
+
+fn row_to_request(row: &rusqlite::Row) -> rusqlite::Result<PaymentRequest> {
+    Ok(PaymentRequest {
+        id: row.get(0)?,
+        amount: row.get::<_, i64>(1)? as u64,
+        memo: row.get(2)?,
+        ark_address: row.get(3)?,
+        uri: row.get(4)?,
+        status: PaymentRequestStatus::parse(&row.get::<_, String>(5)?),
+        paid_outpoint: row.get(6)?,
+        webhook_url: row.get(7)?,
+        created_at: row.get(8)?,
+        expires_at: row.get(9)?,
+        paid_at: row.get(10)?,
+    })
+}
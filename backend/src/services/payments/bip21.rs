@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+// BIP21 payment URI with the Ark address carried as an extension parameter:
+// bitcoin:<address>?amount=<btc>&label=<label>&message=<message>&ark=<ark address>
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentUri {
+    pub address: String,
+    pub amount: Option<u64>, // sats
+    pub label: Option<String>,
+    pub message: Option<String>,
+    pub ark: Option<String>,
+}
+
+impl PaymentUri {
+    pub fn new(address: String) -> Self {
+        Self {
+            address,
+            amount: None,
+            label: None,
+            message: None,
+            ark: None,
+        }
+    }
+
+    pub fn parse(uri: &str) -> Result<Self> {
+        let uri = uri.trim();
+        let scheme_end = uri.find(':').ok_or_else(|| anyhow!("Missing URI scheme"))?;
+        if !uri[..scheme_end].eq_ignore_ascii_case("bitcoin") {
+            return Err(anyhow!("Unsupported URI scheme: {}", &uri[..scheme_end]));
+        }
+
+        let rest = &uri[scheme_end + 1..];
+        let (address, query) = match rest.split_once('?') {
+            Some((address, query)) => (address, Some(query)),
+            None => (rest, None),
+        };
+
+        let mut payment_uri = Self::new(percent_decode(address)?);
+
+        for pair in query.unwrap_or("").split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+
+            match key {
+                "amount" => payment_uri.amount = Some(parse_btc_amount(&value)?),
+                "label" => payment_uri.label = Some(value),
+                "message" => payment_uri.message = Some(value),
+                "ark" => payment_uri.ark = Some(value),
+                // unknown required params must make the URI invalid (BIP21)
+                k if k.starts_with("req-") => {
+                    return Err(anyhow!("Unsupported required parameter: {}", k));
+                }
+                _ => tracing::debug!("Ignoring unknown BIP21 parameter: {}", key),
+            }
+        }
+
+        if payment_uri.address.is_empty() && payment_uri.ark.is_none() {
+            return Err(anyhow!("URI contains neither an address nor an ark parameter"));
+        }
+
+        Ok(payment_uri)
+    }
+
+    pub fn to_uri(&self) -> String {
+        let mut params = Vec::new();
+
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", format_btc_amount(amount)));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        if let Some(message) = &self.message {
+            params.push(format!("message={}", percent_encode(message)));
+        }
+        if let Some(ark) = &self.ark {
+            params.push(format!("ark={}", ark));
+        }
+
+        if params.is_empty() {
+            format!("bitcoin:{}", self.address)
+        } else {
+            format!("bitcoin:{}?{}", self.address, params.join("&"))
+        }
+    }
+}
+
+fn parse_btc_amount(value: &str) -> Result<u64> {
+    let amount = bitcoin::Amount::from_str_in(value, bitcoin::Denomination::Bitcoin)
+        .map_err(|e| anyhow!("Invalid amount '{}': {}", value, e))?;
+    Ok(amount.to_sat())
+}
+
+fn format_btc_amount(sats: u64) -> String {
+    let formatted = format!("{}.{:08}", sats / 100_000_000, sats % 100_000_000);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn percent_encode(input: &str) -> String {
+    let mut result = String::new();
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => result.push(byte as char),
+            _ => result.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    result
+}
+
+fn percent_decode(input: &str) -> Result<String> {
+    let bytes = input.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = input.get(i + 1..i + 3)
+                .ok_or_else(|| anyhow!("Truncated percent-encoding in '{}'", input))?;
+            let byte = u8::from_str_radix(hex, 16)
+                .map_err(|_| anyhow!("Invalid percent-encoding in '{}'", input))?;
+            result.push(byte);
+            i += 3;
+        } else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(result).map_err(|e| anyhow!("Invalid UTF-8 in URI: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let uri = PaymentUri {
+            address: "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string(),
+            amount: Some(150_000),
+            label: Some("Coffee & cake".to_string()),
+            message: None,
+            ark: Some("tark1qexample".to_string()),
+        };
+
+        let encoded = uri.to_uri();
+        assert_eq!(
+            encoded,
+            "bitcoin:bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080?amount=0.0015&label=Coffee%20%26%20cake&ark=tark1qexample"
+        );
+        assert_eq!(PaymentUri::parse(&encoded).unwrap(), uri);
+    }
+
+    #[test]
+    fn test_parse_whole_btc_amount() {
+        let uri = PaymentUri::parse("BITCOIN:bcrt1qexample?amount=2").unwrap();
+        assert_eq!(uri.address, "bcrt1qexample");
+        assert_eq!(uri.amount, Some(200_000_000));
+    }
+
+    #[test]
+    fn test_rejects_unknown_required_param() {
+        assert!(PaymentUri::parse("bitcoin:bcrt1qexample?req-somethingnew=1").is_err());
+        assert!(PaymentUri::parse("lightning:lnbc1").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::{anyhow, Result};
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescription};
+use serde::Serialize;
+use std::str::FromStr;
+
+// the fields of a BOLT11 invoice the frontend and the swap flow care about
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedInvoice {
+    pub invoice: String,
+    pub network: String,
+    pub amount_msat: Option<u64>, // None for "any amount" invoices
+    pub amount_sat: Option<u64>,
+    pub description: Option<String>,
+    pub description_hash: Option<String>, // set instead of description for long descriptions
+    pub payment_hash: String,
+    pub payee_pubkey: String,
+    pub created_at: u64,
+    pub expiry_secs: u64,
+    pub expires_at: u64,
+    pub expired: bool,
+    pub min_final_cltv_expiry_delta: u64,
+}
+
+pub fn decode(invoice: &str) -> Result<DecodedInvoice> {
+    let invoice = invoice.trim().trim_start_matches("lightning:").trim_start_matches("LIGHTNING:");
+    let parsed = Bolt11Invoice::from_str(invoice)
+        .map_err(|e| anyhow!("Invalid BOLT11 invoice: {}", e))?;
+
+    let (description, description_hash) = match parsed.description() {
+        Bolt11InvoiceDescription::Direct(description) => (Some(description.to_string()), None),
+        Bolt11InvoiceDescription::Hash(hash) => (None, Some(hash.0.to_string())),
+    };
+
+    let created_at = parsed.duration_since_epoch().as_secs();
+    let expiry_secs = parsed.expiry_time().as_secs();
+
+    Ok(DecodedInvoice {
+        invoice: invoice.to_string(),
+        network: parsed.network().to_string(),
+        amount_msat: parsed.amount_milli_satoshis(),
+        amount_sat: parsed.amount_milli_satoshis().map(|msat| msat / 1000),
+        description,
+        description_hash,
+        payment_hash: parsed.payment_hash().to_string(),
+        payee_pubkey: parsed.get_payee_pub_key().to_string(),
+        created_at,
+        expiry_secs,
+        expires_at: created_at + expiry_secs,
+        expired: parsed.is_expired(),
+        min_final_cltv_expiry_delta: parsed.min_final_cltv_expiry_delta(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_non_invoices() {
+        assert!(decode("").is_err());
+        assert!(decode("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq").is_err());
+        assert!(decode("lnbc1invalid").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+pub mod bip21;
+pub mod bolt11;
+
+pub use bip21::PaymentUri;
+
+use anyhow::{anyhow, Result};
+use crate::models::wallet::ContactKind;
+use crate::services::APP_STATE;
+
+pub async fn create_payment_uri(
+    amount: Option<u64>,
+    label: Option<String>,
+    message: Option<String>,
+) -> Result<PaymentUri> {
+    let onchain_address = crate::services::wallet::get_onchain_address().await?;
+
+    // the ark address is optional so the URI is still usable while the ASP is unreachable
+    let ark_address = {
+        let grpc_client = APP_STATE.grpc_client.lock().await;
+        match grpc_client.get_address().await {
+            Ok(address) => Some(address),
+            Err(e) => {
+                tracing::warn!("Creating payment URI without ark address: {}", e);
+                None
+            }
+        }
+    };
+
+    Ok(PaymentUri {
+        address: onchain_address,
+        amount,
+        label,
+        message,
+        ark: ark_address,
+    })
+}
+
+// resolves the recipient of a send request which carries either a raw address + amount
+// or a BIP21 URI. returns (address, amount in sats)
+pub fn resolve_recipient(
+    address: &str,
+    amount: u64,
+    uri: Option<&str>,
+    contact_id: Option<i64>,
+    offchain: bool,
+) -> Result<(String, u64)> {
+    let (address, amount) = match (uri, contact_id) {
+        (Some(_), Some(_)) => return Err(anyhow!("Provide either a uri or a contact_id, not both")),
+        (Some(uri), None) => resolve_from_uri(&PaymentUri::parse(uri)?, amount, offchain)?,
+        (None, Some(id)) => (resolve_contact(id, offchain)?, amount),
+        (None, None) => {
+            if address.is_empty() {
+                return Err(anyhow!("Either address or uri must be provided"));
+            }
+            (address.to_string(), amount)
+        }
+    };
+
+    // VTXOs below the ASP's dust limit are rejected by the server anyway
+    if offchain {
+        if let Some(dust) = APP_STATE.server_info.dust() {
+            if amount < dust {
+                return Err(anyhow!("Amount {} sats is below the server dust limit of {} sats", amount, dust));
+            }
+        }
+    }
+
+    Ok((address, amount))
+}
+
+fn resolve_contact(id: i64, offchain: bool) -> Result<String> {
+    let contact = crate::services::contacts::get_contact(id)?;
+    let expected = if offchain { ContactKind::Ark } else { ContactKind::Onchain };
+    if contact.kind != expected {
+        return Err(anyhow!(
+            "Contact '{}' has an {} address, expected {}",
+            contact.name, contact.kind.as_str(), expected.as_str()
+        ));
+    }
+    Ok(contact.address)
+}
+
+fn resolve_from_uri(uri: &PaymentUri, amount: u64, offchain: bool) -> Result<(String, u64)> {
+    let address = if offchain {
+        uri.ark.clone().ok_or_else(|| anyhow!("Payment URI does not contain an ark address"))?
+    } else {
+        if uri.address.is_empty() {
+            return Err(anyhow!("Payment URI does not contain an on-chain address"));
+        }
+        uri.address.clone()
+    };
+
+    // an explicit amount in the request overrides the one in the URI
+    let amount = if amount > 0 {
+        amount
+    } else {
+        uri.amount.ok_or_else(|| anyhow!("No amount specified in request or payment URI"))?
+    };
+
+    Ok((address, amount))
+}
@@ -0,0 +1,118 @@
+use bitcoin::Network;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "source", content = "url", rename_all = "snake_case")]
+pub enum FeeSource {
+    MempoolSpace(String), // base url of a mempool.space compatible API
+    Blockstream(String),  // base url of a blockstream.info compatible API
+    BitcoinCore,
+}
+
+// bundles everything needed to point the wallet at one Ark operator
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkProfile {
+    pub name: String,
+    #[serde(serialize_with = "serialize_network")]
+    pub network: Network,
+    pub ark_server_url: String,
+    pub esplora_url: String,
+    pub esplora_fallback_urls: Vec<String>, // tried in order when esplora_url is down
+    pub fee_sources: Vec<FeeSource>, // tried in order, static defaults if all fail
+    pub faucet_enabled: bool,
+}
+
+impl NetworkProfile {
+    pub fn regtest_nigiri() -> Self {
+        Self {
+            name: "regtest-nigiri".to_string(),
+            network: Network::Regtest,
+            ark_server_url: "http://localhost:7070".to_string(),
+            esplora_url: "http://localhost:3000".to_string(),
+            esplora_fallback_urls: Vec::new(),
+            fee_sources: vec![FeeSource::BitcoinCore],
+            faucet_enabled: true,
+        }
+    }
+
+    pub fn mutinynet_arkade() -> Self {
+        Self {
+            name: "mutinynet-arkade".to_string(),
+            network: Network::Signet,
+            ark_server_url: "https://mutinynet.arkade.sh".to_string(),
+            esplora_url: "https://mutinynet.com/api".to_string(),
+            esplora_fallback_urls: Vec::new(),
+            fee_sources: vec![FeeSource::MempoolSpace("https://mutinynet.com".to_string())],
+            // mutinynet has its own public faucet
+            faucet_enabled: false,
+        }
+    }
+
+    // local defaults for the given network, the config fills in the urls
+    pub fn custom(network: Network) -> Self {
+        Self {
+            name: "custom".to_string(),
+            network,
+            ark_server_url: "http://localhost:7070".to_string(),
+            esplora_url: "http://localhost:3000".to_string(),
+            esplora_fallback_urls: Vec::new(),
+            fee_sources: default_fee_sources(network),
+            faucet_enabled: network == Network::Regtest,
+        }
+    }
+
+    // primary first, then the fallbacks
+    pub fn esplora_urls(&self) -> Vec<String> {
+        std::iter::once(self.esplora_url.clone())
+            .chain(self.esplora_fallback_urls.iter().cloned())
+            .collect()
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "regtest-nigiri" | "regtest" => Some(Self::regtest_nigiri()),
+            "mutinynet-arkade" | "mutinynet" => Some(Self::mutinynet_arkade()),
+            "custom" => Some(Self::custom(Network::Regtest)),
+            _ => None,
+        }
+    }
+
+    pub fn available() -> Vec<Self> {
+        vec![Self::regtest_nigiri(), Self::mutinynet_arkade(), Self::custom(Network::Regtest)]
+    }
+}
+
+// the profile is part of the config loaded at startup
+pub fn active() -> &'static NetworkProfile {
+    &crate::services::config::get().profile
+}
+
+pub fn parse_network(value: &str) -> Option<Network> {
+    match value {
+        "mainnet" | "bitcoin" => Some(Network::Bitcoin),
+        "testnet" => Some(Network::Testnet),
+        "signet" | "mutinynet" => Some(Network::Signet),
+        "regtest" => Some(Network::Regtest),
+        _ => None,
+    }
+}
+
+fn default_fee_sources(network: Network) -> Vec<FeeSource> {
+    match network {
+        Network::Bitcoin => vec![
+            FeeSource::MempoolSpace("https://mempool.space".to_string()),
+            FeeSource::Blockstream("https://blockstream.info".to_string()),
+            FeeSource::BitcoinCore,
+        ],
+        Network::Testnet => vec![
+            FeeSource::MempoolSpace("https://mempool.space/testnet".to_string()),
+            FeeSource::Blockstream("https://blockstream.info/testnet".to_string()),
+        ],
+        Network::Signet => vec![FeeSource::MempoolSpace("https://mempool.space/signet".to_string())],
+        _ => vec![FeeSource::BitcoinCore],
+    }
+}
+
+fn serialize_network<S: serde::Serializer>(network: &Network, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&network.to_string())
+}
@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Result};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::services::config::Config;
+
+const SATS_PER_BTC: f64 = 100_000_000.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RateSnapshot {
+    pub source: String,
+    pub rates: BTreeMap<String, f64>, // price of one BTC per currency code
+    pub fetched_at: i64,
+}
+
+// an amount in sats converted at the cached rate, not the rate at the time of a transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct FiatValue {
+    pub currency: String,
+    pub value: f64,
+    pub rate: f64,
+    pub rate_updated_at: i64,
+}
+
+// BTC prices polled from rates_url, disabled without one
+pub struct RateService {
+    url: Option<String>,
+    http_client: reqwest::Client,
+    cached: RwLock<Option<RateSnapshot>>,
+}
+
+impl RateService {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            url: config.rates_url.clone(),
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to build HTTP client"),
+            cached: RwLock::new(None),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.url.is_some()
+    }
+
+    pub fn get(&self) -> Option<RateSnapshot> {
+        self.cached.read().clone()
+    }
+
+    pub fn convert(&self, sats: i64, currency: &str) -> Result<FiatValue> {
+        if !self.is_enabled() {
+            return Err(anyhow!("Exchange rates are disabled, set rates_url to enable them"));
+        }
+        let currency = currency.to_uppercase();
+        let cached = self.cached.read();
+        let snapshot = cached.as_ref()
+            .ok_or_else(|| anyhow!("Exchange rates have not been fetched yet"))?;
+        let rate = *snapshot.rates.get(&currency)
+            .ok_or_else(|| anyhow!("No exchange rate for {}", currency))?;
+
+        Ok(FiatValue {
+            value: to_fiat(sats, rate),
+            currency,
+            rate,
+            rate_updated_at: snapshot.fetched_at,
+        })
+    }
+
+    pub fn start_refresher(self: &Arc<Self>, interval: Duration) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.refresh().await {
+                    // keep serving the last rates until the source answers again
+                    tracing::warn!("Failed to refresh exchange rates: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn refresh(&self) -> Result<()> {
+        let Some(url) = &self.url else {
+            return Ok(());
+        };
+        let body: serde_json::Value = self.http_client.get(url).send().await?
+            .error_for_status()?
+            .json().await?;
+        let rates = parse_prices(&body)?;
+
+        tracing::debug!("Fetched {} exchange rates from {}", rates.len(), url);
+        *self.cached.write() = Some(RateSnapshot {
+            source: url.clone(),
+            rates,
+            fetched_at: chrono::Utc::now().timestamp(),
+        });
+        Ok(())
+    }
+}
+
+// mempool.space style {"time": ..., "USD": 43753, "EUR": 40545}, currency codes are the keys
+fn parse_prices(body: &serde_json::Value) -> Result<BTreeMap<String, f64>> {
+    let object = body.as_object()
+        .ok_or_else(|| anyhow!("Price API returned {} instead of an object", body))?;
+    let rates: BTreeMap<String, f64> = object.iter()
+        .filter(|(code, _)| code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()))
+        .filter_map(|(code, price)| price.as_f64().filter(|price| *price > 0.0).map(|price| (code.clone(), price)))
+        .collect();
+    if rates.is_empty() {
+        return Err(anyhow!("Price API response has no currency prices"));
+    }
+    Ok(rates)
+}
+
+// rounded to cents
+pub fn to_fiat(sats: i64, rate: f64) -> f64 {
+    (sats as f64 / SATS_PER_BTC * rate * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prices() {
+        let body = serde_json::json!({"time": 1703252411, "USD": 43753, "EUR": 40545.5, "JPY": 0});
+        let rates = parse_prices(&body).unwrap();
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates["USD"], 43753.0);
+        assert_eq!(rates["EUR"], 40545.5);
+        assert!(parse_prices(&serde_json::json!({"time": 1})).is_err());
+        assert!(parse_prices(&serde_json::json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_to_fiat_rounds_to_cents() {
+        assert_eq!(to_fiat(100_000_000, 43753.0), 43753.0);
+        assert_eq!(to_fiat(12_345, 43753.0), 5.4);
+        assert_eq!(to_fiat(-50_000, 40000.0), -20.0);
+    }
+}
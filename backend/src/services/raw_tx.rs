@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use ark_client::Blockchain;
+use bitcoin::{Address, Transaction, Txid};
+
+use crate::models::wallet::{DecodedInput, DecodedOutput, DecodedTransaction};
+use crate::services::APP_STATE;
+
+pub fn parse(hex: &str) -> Result<Transaction> {
+    bitcoin::consensus::encode::deserialize_hex(hex.trim())
+        .map_err(|e| anyhow!("Not a raw transaction: {}", e))
+}
+
+// input values come from the spent txs, the fee is only known when every one of them could be fetched
+// (bitcoind needs -txindex for confirmed ones)
+pub async fn decode(tx: &Transaction) -> DecodedTransaction {
+    let network = crate::services::profiles::active().network;
+    let address = |script: &bitcoin::Script| Address::from_script(script, network).ok().map(|a| a.to_string());
+
+    let mut inputs = Vec::with_capacity(tx.input.len());
+    for input in &tx.input {
+        let prevout = if input.previous_output.is_null() {
+            None
+        } else {
+            match APP_STATE.blockchain.find_tx(&input.previous_output.txid).await {
+                Ok(prev) => prev.and_then(|prev| prev.output.get(input.previous_output.vout as usize).cloned()),
+                Err(e) => {
+                    tracing::debug!("Failed to look up {}: {}", input.previous_output.txid, e);
+                    None
+                }
+            }
+        };
+        inputs.push(DecodedInput {
+            txid: input.previous_output.txid.to_string(),
+            vout: input.previous_output.vout,
+            sequence: input.sequence.to_consensus_u32(),
+            witness_items: input.witness.len(),
+            value: prevout.as_ref().map(|output| output.value.to_sat()),
+            address: prevout.as_ref().and_then(|output| address(&output.script_pubkey)),
+        });
+    }
+
+    let outputs: Vec<DecodedOutput> = tx.output.iter().enumerate()
+        .map(|(vout, output)| DecodedOutput {
+            vout: vout as u32,
+            value: output.value.to_sat(),
+            script_pubkey: output.script_pubkey.to_hex_string(),
+            address: address(&output.script_pubkey),
+        })
+        .collect();
+
+    let input_total: Option<u64> = inputs.iter().map(|input| input.value).sum();
+    let output_total: u64 = outputs.iter().map(|output| output.value).sum();
+    let fee = input_total.map(|total| total.saturating_sub(output_total));
+    let vsize = tx.vsize() as u64;
+
+    DecodedTransaction {
+        txid: tx.compute_txid().to_string(),
+        wtxid: tx.compute_wtxid().to_string(),
+        version: tx.version.0,
+        locktime: tx.lock_time.to_consensus_u32(),
+        size: tx.total_size() as u64,
+        vsize,
+        weight: tx.weight().to_wu(),
+        fee,
+        fee_rate_sat_vb: fee.map(|fee| fee as f64 / vsize as f64),
+        inputs,
+        outputs,
+    }
+}
+
+pub async fn broadcast(tx: &Transaction) -> Result<Txid> {
+    APP_STATE.blockchain.broadcast(tx).await
+        .map_err(|e| anyhow!("Broadcast rejected: {}", e))?;
+    let txid = tx.compute_txid();
+    tracing::info!("Broadcast external transaction {}", txid);
+    Ok(txid)
+}
@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::DbManager;
+
+const SETTINGS_KEY: &str = "renewal_policy";
+
+// when the monitor settles VTXOs before the ASP can sweep them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenewalPolicy {
+    pub auto_renew: bool,
+    pub renew_before_secs: u64,    // settle VTXOs once they are this close to expiring
+    pub warn_before_secs: u64,     // publish a warning for VTXOs still unrenewed this close to expiry
+    pub critical_before_secs: u64, // renew even when fees are above the limit
+    pub max_fee_rate_sat_vb: Option<u64>, // skip non critical renewals while on-chain fees are higher
+    #[serde(default)]
+    pub sweep_expired: bool, // start unilateral exits for VTXOs that expired before they could be renewed
+}
+
+impl Default for RenewalPolicy {
+    fn default() -> Self {
+        let renew_before_secs = std::env::var("MONITOR_RENEW_BEFORE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(24 * 60 * 60);
+
+        Self {
+            auto_renew: true,
+            renew_before_secs,
+            warn_before_secs: 60 * 60,
+            critical_before_secs: 30 * 60,
+            max_fee_rate_sat_vb: None,
+            sweep_expired: false,
+        }
+    }
+}
+
+impl RenewalPolicy {
+    pub fn validate(&self) -> Result<()> {
+        if self.critical_before_secs > self.warn_before_secs {
+            return Err(anyhow!("critical_before_secs must not exceed warn_before_secs"));
+        }
+        if self.critical_before_secs > self.renew_before_secs {
+            return Err(anyhow!("critical_before_secs must not exceed renew_before_secs"));
+        }
+        if self.max_fee_rate_sat_vb == Some(0) {
+            return Err(anyhow!("max_fee_rate_sat_vb must be positive"));
+        }
+        Ok(())
+    }
+
+    pub fn load(db: &DbManager) -> Self {
+        match db.get_setting(SETTINGS_KEY) {
+            Ok(Some(json)) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable renewal policy: {}", e);
+                Self::default()
+            }),
+            Ok(None) => Self::default(),
+            Err(e) => {
+                tracing::warn!("Failed to load renewal policy: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, db: &DbManager) -> Result<()> {
+        db.save_setting(SETTINGS_KEY, &serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_threshold_order() {
+        let policy = RenewalPolicy {
+            auto_renew: true,
+            renew_before_secs: 7200,
+            warn_before_secs: 3600,
+            critical_before_secs: 1800,
+            max_fee_rate_sat_vb: Some(20),
+            sweep_expired: false,
+        };
+        assert!(policy.validate().is_ok());
+
+        let policy = RenewalPolicy { critical_before_secs: 5400, ..policy };
+        assert!(policy.validate().is_err());
+    }
+}
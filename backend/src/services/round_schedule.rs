@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Result};
+use ark_client::Blockchain;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::services::APP_STATE;
+use crate::storage::DbManager;
+
+const SETTINGS_KEY: &str = "round_schedule";
+
+// opt-in automatic round participation, checked every interval_secs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundSchedule {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    pub on_boarding_confirmed: bool, // join a round once a boarding deposit confirms
+    pub on_vtxo_expiry: bool,        // join a round once VTXOs are inside the renewal window
+    #[serde(default)]
+    pub consolidate_above: Option<usize>, // join a round once the wallet holds more VTXOs than this, merging them into one
+}
+
+impl Default for RoundSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 600,
+            on_boarding_confirmed: true,
+            on_vtxo_expiry: true,
+            consolidate_above: None,
+        }
+    }
+}
+
+impl RoundSchedule {
+    pub fn validate(&self) -> Result<()> {
+        if self.interval_secs < 10 {
+            return Err(anyhow!("interval_secs must be at least 10"));
+        }
+        if self.consolidate_above == Some(0) {
+            return Err(anyhow!("consolidate_above must be at least 1"));
+        }
+        if self.enabled && !self.on_boarding_confirmed && !self.on_vtxo_expiry && self.consolidate_above.is_none() {
+            return Err(anyhow!("an enabled schedule needs at least one trigger"));
+        }
+        Ok(())
+    }
+
+    pub fn load(db: &DbManager) -> Self {
+        match db.get_setting(SETTINGS_KEY) {
+            Ok(Some(json)) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable round schedule: {}", e);
+                Self::default()
+            }),
+            Ok(None) => Self::default(),
+            Err(e) => {
+                tracing::warn!("Failed to load round schedule: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, db: &DbManager) -> Result<()> {
+        db.save_setting(SETTINGS_KEY, &serde_json::to_string(self)?)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScheduleStatus {
+    pub next_check_at: Option<i64>, // None while the schedule is disabled
+    pub last_check_at: Option<i64>,
+    pub last_participation_at: Option<i64>,
+    pub last_round_txid: Option<String>,
+    pub last_error: Option<String>,
+}
+
+// what the next check would do, for GET /api/round/schedule
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedParticipation {
+    pub schedule: RoundSchedule,
+    #[serde(flatten)]
+    pub status: ScheduleStatus,
+    pub confirmed_boarding_outputs: usize,
+    pub expiring_vtxos: usize,
+    pub spendable_vtxos: usize,
+    pub will_participate: bool,
+}
+
+// counts behind a participation decision, per enabled trigger
+struct PendingWork {
+    boarding: usize,
+    expiring: usize,
+    vtxos: usize,
+    consolidate: bool,
+}
+
+impl PendingWork {
+    fn any(&self) -> bool {
+        self.boarding + self.expiring > 0 || self.consolidate
+    }
+}
+
+pub struct RoundScheduler {
+    schedule: Mutex<RoundSchedule>,
+    status: Mutex<ScheduleStatus>,
+    wake: Notify, // lets a schedule change take effect without waiting out the old interval
+}
+
+impl RoundScheduler {
+    pub fn new(db: &DbManager) -> Self {
+        Self {
+            schedule: Mutex::new(RoundSchedule::load(db)),
+            status: Mutex::new(ScheduleStatus::default()),
+            wake: Notify::new(),
+        }
+    }
+
+    pub fn schedule(&self) -> RoundSchedule {
+        self.schedule.lock().clone()
+    }
+
+    pub fn set_schedule(&self, schedule: RoundSchedule) -> Result<()> {
+        schedule.validate()?;
+        schedule.save(&APP_STATE.db_manager)?;
+        *self.schedule.lock() = schedule;
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    pub fn start(self: &Arc<Self>) {
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                let schedule = scheduler.schedule();
+                let interval = Duration::from_secs(schedule.interval_secs);
+                scheduler.status.lock().next_check_at = schedule.enabled
+                    .then(|| chrono::Utc::now().timestamp() + interval.as_secs() as i64);
+
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {},
+                    _ = scheduler.wake.notified() => continue,
+                }
+
+                if !scheduler.schedule().enabled {
+                    continue;
+                }
+                let result = scheduler.run_once().await;
+
+                let mut status = scheduler.status.lock();
+                status.last_check_at = Some(chrono::Utc::now().timestamp());
+                status.last_error = result.err().map(|e| {
+                    tracing::warn!("Scheduled round participation failed: {}", e);
+                    e.to_string()
+                });
+            }
+        });
+    }
+
+    pub async fn planned(&self) -> Result<PlannedParticipation> {
+        let schedule = self.schedule();
+        let work = self.pending_work(&schedule).await?;
+
+        Ok(PlannedParticipation {
+            will_participate: schedule.enabled && work.any(),
+            schedule,
+            status: self.status.lock().clone(),
+            confirmed_boarding_outputs: work.boarding,
+            expiring_vtxos: work.expiring,
+            spendable_vtxos: work.vtxos,
+        })
+    }
+
+    async fn run_once(&self) -> Result<()> {
+        if APP_STATE.wallet_lock.skips("scheduled round participation") {
+            return Ok(());
+        }
+        let schedule = self.schedule();
+        let work = self.pending_work(&schedule).await?;
+        if !work.any() {
+            return Ok(());
+        }
+
+        tracing::info!(
+            "Scheduled round participation: {} confirmed boarding outputs, {} expiring VTXOs, {} VTXOs held",
+            work.boarding, work.expiring, work.vtxos
+        );
+        if let Some(txid) = crate::services::transactions::participate_in_round().await? {
+            let mut status = self.status.lock();
+            status.last_participation_at = Some(chrono::Utc::now().timestamp());
+            status.last_round_txid = Some(txid);
+        }
+        Ok(())
+    }
+
+    async fn pending_work(&self, schedule: &RoundSchedule) -> Result<PendingWork> {
+        use ark_client::wallet::BoardingWallet;
+
+        let (client, ark_wallet) = {
+            let grpc_client = APP_STATE.grpc_client.lock().await;
+            let client = grpc_client.get_ark_client().as_ref().map(Arc::clone);
+            (client, grpc_client.get_ark_wallet())
+        };
+        let Some(client) = client else {
+            return Err(anyhow!("Ark client not available"));
+        };
+
+        let mut boarding = 0;
+        if let (true, Some(ark_wallet)) = (schedule.on_boarding_confirmed, ark_wallet) {
+            let boarding_outputs = ark_wallet.get_boarding_outputs()
+                .map_err(|e| anyhow!("Failed to get boarding outputs: {}", e))?;
+            for boarding_output in boarding_outputs {
+                let utxos = APP_STATE.blockchain.find_outpoints(boarding_output.address()).await
+                    .map_err(|e| anyhow!("Failed to look up boarding outputs: {}", e))?;
+                boarding += utxos.iter()
+                    .filter(|utxo| !utxo.is_spent && utxo.confirmation_blocktime.is_some())
+                    .count();
+            }
+        }
+
+        let vtxos = client.spendable_vtxos().await
+            .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?;
+        let outpoints: Vec<_> = vtxos.iter().flat_map(|(outpoints, _)| outpoints.iter()).collect();
+
+        // the renewal policy decides, so auto_renew and its fee limit hold for scheduled rounds too
+        let mut expiring = 0;
+        if schedule.on_vtxo_expiry {
+            let expire_ats: Vec<i64> = outpoints.iter().map(|outpoint| outpoint.expire_at).collect();
+            expiring = APP_STATE.monitor.renewal_due(&expire_ats).await?;
+        }
+
+        Ok(PendingWork {
+            boarding,
+            expiring,
+            vtxos: outpoints.len(),
+            consolidate: schedule.consolidate_above.is_some_and(|limit| outpoints.len() > limit),
+        })
+    }
+}
@@ -0,0 +1,354 @@
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use rusqlite::params;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ark_client::Blockchain;
+use ark_grpc::Client as ArkGrpcClient;
+use ark_grpc::RoundStreamEvent;
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+use crate::models::wallet::{ConsolidationPreview, PreviewVtxo, RoundRecord};
+use crate::services::ark_grpc::ArkWallet;
+use crate::services::chain::ChainBackend;
+use crate::services::events::WalletEvent;
+use crate::services::APP_STATE;
+
+type ArkClient = ark_client::Client<ChainBackend, ArkWallet>;
+
+const MAX_HISTORY_LIMIT: u32 = 500;
+
+// relays the ASP's round event stream into the wallet event bus
+pub struct RoundCoordinator {
+    server_url: String,
+    running: AtomicBool,
+}
+
+impl RoundCoordinator {
+    pub fn new(server_url: String) -> Self {
+        Self {
+            server_url,
+            running: AtomicBool::new(false),
+        }
+    }
+
+    pub fn start_event_loop(self: &Arc<Self>) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            tracing::warn!("Round event loop already running");
+            return;
+        }
+
+        let coordinator = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut retries: u64 = 0;
+            loop {
+                match coordinator.relay_events().await {
+                    Ok(_) => {
+                        tracing::info!("ASP round event stream ended, reconnecting");
+                        retries = 0;
+                    },
+                    Err(e) => {
+                        tracing::warn!("ASP round event stream failed: {}", e);
+                        retries += 1;
+                    }
+                }
+
+                // back off up to a minute while the ASP is unreachable
+                let delay = Duration::from_secs((2 * retries).clamp(1, 60));
+                tokio::time::sleep(delay).await;
+            }
+        });
+    }
+
+    async fn relay_events(&self) -> Result<()> {
+        let mut grpc_client = ArkGrpcClient::new(self.server_url.clone());
+        grpc_client.connect().await
+            .map_err(|e| anyhow!("Failed to connect to Ark server: {}", e))?;
+
+        let mut stream = grpc_client.get_event_stream().await
+            .map_err(|e| anyhow!("Failed to subscribe to round events: {}", e))?;
+
+        tracing::info!("Subscribed to ASP round events");
+
+        while let Some(event) = stream.next().await {
+            let event = event.map_err(|e| anyhow!("Round event stream error: {}", e))?;
+
+            let wallet_event = match event {
+                RoundStreamEvent::RoundSigning(e) => WalletEvent::RoundSigning {
+                    round_id: e.id,
+                },
+                RoundStreamEvent::RoundFinalization(e) => WalletEvent::RoundFinalizing {
+                    round_id: e.id,
+                },
+                RoundStreamEvent::RoundFinalized(e) => WalletEvent::RoundFinalized {
+                    round_id: e.id,
+                    txid: e.round_txid.to_string(),
+                },
+                RoundStreamEvent::RoundFailed(e) => WalletEvent::RoundFailed {
+                    round_id: Some(e.id),
+                    reason: e.reason,
+                },
+                // nonce exchange events are internal to the signing session
+                _ => continue,
+            };
+
+            tracing::debug!("Round event: {:?}", wallet_event);
+            APP_STATE.events.publish(wallet_event);
+        }
+
+        Ok(())
+    }
+}
+
+// what the wallet holds as it joins a round, board() settles all of it
+pub struct RoundInputs {
+    vtxos: HashSet<String>,
+    vtxo_amount: u64,
+    boarding_inputs: u32,
+    boarding_amount: u64,
+    started_at: i64,
+    started: Instant,
+}
+
+impl RoundInputs {
+    pub async fn snapshot(client: &ArkClient, ark_wallet: Option<&ArkWallet>) -> Result<Self> {
+        use ark_client::wallet::BoardingWallet;
+
+        let mut vtxos = HashSet::new();
+        let mut vtxo_amount = 0;
+        let spendable = client.spendable_vtxos().await
+            .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?;
+        for outpoint in spendable.iter().flat_map(|(outpoints, _)| outpoints.iter()) {
+            vtxos.insert(outpoint.outpoint.to_string());
+            vtxo_amount += outpoint.amount.to_sat();
+        }
+
+        let mut boarding_inputs = 0;
+        let mut boarding_amount = 0;
+        if let Some(ark_wallet) = ark_wallet {
+            let boarding_outputs = ark_wallet.get_boarding_outputs()
+                .map_err(|e| anyhow!("Failed to get boarding outputs: {}", e))?;
+            for boarding_output in boarding_outputs {
+                let utxos = APP_STATE.blockchain.find_outpoints(boarding_output.address()).await
+                    .map_err(|e| anyhow!("Failed to look up boarding outputs: {}", e))?;
+                for utxo in utxos.iter().filter(|utxo| !utxo.is_spent && utxo.confirmation_blocktime.is_some()) {
+                    boarding_inputs += 1;
+                    boarding_amount += utxo.amount.to_sat();
+                }
+            }
+        }
+
+        Ok(Self {
+            vtxos,
+            vtxo_amount,
+            boarding_inputs,
+            boarding_amount,
+            started_at: chrono::Utc::now().timestamp(),
+            started: Instant::now(),
+        })
+    }
+
+    // the commitment txids of the VTXOs the wallet gained since the snapshot, one of them is the round we joined
+    pub async fn commitment_txids(&self, client: &ArkClient) -> Result<HashSet<String>> {
+        let spendable = client.spendable_vtxos().await
+            .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?;
+        Ok(spendable.iter()
+            .flat_map(|(outpoints, _)| outpoints.iter())
+            .filter(|outpoint| !self.vtxos.contains(&outpoint.outpoint.to_string()))
+            .map(|outpoint| outpoint.round_txid.to_string())
+            .collect())
+    }
+
+    // VTXOs the wallet holds now that it didn't before the round are the round's outputs,
+    // so a payment received while the round ran is counted as one
+    pub async fn finish(self, client: &ArkClient, txid: &str) -> Result<RoundRecord> {
+        let spendable = client.spendable_vtxos().await
+            .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?;
+        let created: Vec<u64> = spendable.iter()
+            .flat_map(|(outpoints, _)| outpoints.iter())
+            .filter(|outpoint| !self.vtxos.contains(&outpoint.outpoint.to_string()))
+            .map(|outpoint| outpoint.amount.to_sat())
+            .collect();
+
+        let input_amount = self.vtxo_amount + self.boarding_amount;
+        let output_amount: u64 = created.iter().sum();
+        let record = RoundRecord {
+            txid: txid.to_string(),
+            vtxos_renewed: self.vtxos.len() as u32,
+            boarding_inputs: self.boarding_inputs,
+            outputs_created: created.len() as u32,
+            input_amount,
+            output_amount,
+            fee: input_amount.saturating_sub(output_amount),
+            started_at: self.started_at,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+        };
+        save(&record)?;
+        Ok(record)
+    }
+}
+
+// board() settles every VTXO and confirmed boarding output into a single new VTXO, so the next round
+// always consolidates, this shows what it would merge
+pub async fn consolidation_preview() -> Result<ConsolidationPreview> {
+    use ark_client::wallet::BoardingWallet;
+
+    let (client, ark_wallet) = {
+        let grpc_client = APP_STATE.grpc_client.lock().await;
+        let client = grpc_client.get_ark_client().as_ref().map(Arc::clone);
+        (client, grpc_client.get_ark_wallet())
+    };
+    let client = client.ok_or(crate::services::ark_grpc::ClientUnavailable)?;
+
+    let spendable = client.spendable_vtxos().await
+        .map_err(|e| anyhow!("Failed to list VTXOs: {}", e))?;
+    let mut before: Vec<PreviewVtxo> = spendable.iter()
+        .flat_map(|(outpoints, _)| outpoints.iter())
+        .map(|outpoint| PreviewVtxo {
+            outpoint: Some(outpoint.outpoint.to_string()),
+            amount: outpoint.amount.to_sat(),
+            expire_at: Some(outpoint.expire_at),
+        })
+        .collect();
+    before.sort_by_key(|vtxo| vtxo.expire_at);
+
+    let mut boarding_outputs = Vec::new();
+    if let Some(ark_wallet) = ark_wallet {
+        let outputs = ark_wallet.get_boarding_outputs()
+            .map_err(|e| anyhow!("Failed to get boarding outputs: {}", e))?;
+        for boarding_output in outputs {
+            let utxos = APP_STATE.blockchain.find_outpoints(boarding_output.address()).await
+                .map_err(|e| anyhow!("Failed to look up boarding outputs: {}", e))?;
+            boarding_outputs.extend(utxos.iter()
+                .filter(|utxo| !utxo.is_spent && utxo.confirmation_blocktime.is_some())
+                .map(|utxo| PreviewVtxo {
+                    outpoint: Some(utxo.outpoint.to_string()),
+                    amount: utxo.amount.to_sat(),
+                    expire_at: None,
+                }));
+        }
+    }
+
+    let total: u64 = before.iter().chain(boarding_outputs.iter()).map(|vtxo| vtxo.amount).sum();
+    // the new VTXO's outpoint is only known once the round is finalized
+    let after = if total > 0 {
+        let lifetime = APP_STATE.server_info.get()
+            .map(|cached| crate::services::exit::delay_secs(cached.info.vtxo_tree_expiry) as i64);
+        vec![PreviewVtxo {
+            outpoint: None,
+            amount: total,
+            expire_at: lifetime.map(|secs| chrono::Utc::now().timestamp() + secs),
+        }]
+    } else {
+        Vec::new()
+    };
+
+    // every VTXO is claimed with its own tx on a unilateral exit
+    let claim_fee = crate::services::exit::claim_fee().await?.to_sat();
+    Ok(ConsolidationPreview {
+        claim_fees_before: claim_fee * before.len() as u64,
+        claim_fees_after: claim_fee * after.len() as u64,
+        before,
+        boarding_outputs,
+        after,
+    })
+}
+
+fn save(record: &RoundRecord) -> Result<()> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    conn.execute(
+        "INSERT OR REPLACE INTO rounds
+         (txid, vtxos_renewed, boarding_inputs, outputs_created, input_amount, output_amount, fee, started_at, duration_ms)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            record.txid,
+            record.vtxos_renewed,
+            record.boarding_inputs,
+            record.outputs_created,
+            record.input_amount as i64,
+            record.output_amount as i64,
+            record.fee as i64,
+            record.started_at,
+            record.duration_ms as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+// newest first
+pub fn history(limit: Option<u32>, offset: Option<u32>) -> Result<Vec<RoundRecord>> {
+    let limit = limit.unwrap_or(50).min(MAX_HISTORY_LIMIT);
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT txid, vtxos_renewed, boarding_inputs, outputs_created, input_amount, output_amount, fee, started_at, duration_ms
+         FROM rounds ORDER BY started_at DESC LIMIT ? OFFSET ?"
+    )?;
+
+    let rounds = stmt
+        .query_map(params![limit, offset.unwrap_or(0)], row_to_round)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rounds)
+}
+
+// totals over every recorded round, not just the requested page
+pub fn totals() -> Result<(u64, u64, u64)> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let (rounds, vtxos_renewed, fees): (i64, i64, i64) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(vtxos_renewed), 0), COALESCE(SUM(fee), 0) FROM rounds",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    Ok((rounds as u64, vtxos_renewed as u64, fees as u64))
+}
+
+fn row_to_round(row: &rusqlite::Row) -> rusqlite::Result<RoundRecord> {
+    Ok(RoundRecord {
+        txid: row.get(0)?,
+        vtxos_renewed: row.get(1)?,
+        boarding_inputs: row.get(2)?,
+        outputs_created: row.get(3)?,
+        input_amount: row.get::<_, i64>(4)? as u64,
+        output_amount: row.get::<_, i64>(5)? as u64,
+        fee: row.get::<_, i64>(6)? as u64,
+        started_at: row.get(7)?,
+        duration_ms: row.get::<_, i64>(8)? as u64,
+    })
+}
+
+// the id and commitment txid of the round we just took part in, as announced on the ASP event stream.
+// other rounds can finalize while ours runs, so only a finalization whose commitment tx created one
+// of our new VTXOs counts. None when nothing matches, the caller then records a placeholder.
+pub async fn wait_for_round_txid(
+    receiver: &mut broadcast::Receiver<WalletEvent>,
+    commitment_txids: &HashSet<String>,
+    timeout: Duration,
+) -> Option<(String, String)> {
+    if commitment_txids.is_empty() {
+        return None;
+    }
+
+    loop {
+        match receiver.try_recv() {
+            Ok(WalletEvent::RoundFinalized { round_id, txid }) if commitment_txids.contains(&txid) => {
+                return Some((round_id, txid));
+            },
+            Ok(_) | Err(TryRecvError::Lagged(_)) => continue,
+            Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+        }
+    }
+
+    // the stream can lag slightly behind the client
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        match tokio::time::timeout_at(deadline, receiver.recv()).await {
+            Ok(Ok(WalletEvent::RoundFinalized { round_id, txid })) if commitment_txids.contains(&txid) => {
+                return Some((round_id, txid));
+            },
+            Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+            Ok(Err(broadcast::error::RecvError::Closed)) | Err(_) => return None,
+        }
+    }
+}
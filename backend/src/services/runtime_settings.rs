@@ -0,0 +1,56 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::services::faucet::FaucetConfig;
+use crate::services::onchain::fee_estimator;
+use crate::services::renewal::RenewalPolicy;
+use crate::services::APP_STATE;
+
+// the configuration that can change without a restart, each part is persisted in the settings table
+// by the service that owns it and read from there on the next start
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeSettings {
+    pub fee_source_priority: Vec<String>, // tried before the profile's other fee sources
+    pub renewal: RenewalPolicy,
+    pub faucet: FaucetConfig, // payout amount, cooldown and public mode rate limits
+}
+
+// parts left out are kept as they are
+#[derive(Debug, Deserialize)]
+pub struct RuntimeSettingsUpdate {
+    pub fee_source_priority: Option<Vec<String>>,
+    pub renewal: Option<RenewalPolicy>,
+    pub faucet: Option<FaucetConfig>,
+}
+
+pub fn current() -> RuntimeSettings {
+    RuntimeSettings {
+        fee_source_priority: fee_estimator::source_priority(),
+        renewal: APP_STATE.monitor.policy(),
+        faucet: APP_STATE.faucet.config(),
+    }
+}
+
+// every part is validated before any is applied, so a bad value leaves all settings unchanged
+pub fn update(update: RuntimeSettingsUpdate) -> Result<RuntimeSettings> {
+    if let Some(priority) = &update.fee_source_priority {
+        fee_estimator::validate_source_priority(priority)?;
+    }
+    if let Some(renewal) = &update.renewal {
+        renewal.validate()?;
+    }
+    if let Some(faucet) = &update.faucet {
+        faucet.validate()?;
+    }
+
+    if let Some(priority) = update.fee_source_priority {
+        fee_estimator::set_source_priority(priority)?;
+    }
+    if let Some(renewal) = update.renewal {
+        APP_STATE.monitor.set_policy(renewal)?;
+    }
+    if let Some(faucet) = update.faucet {
+        APP_STATE.faucet.set_config(faucet)?;
+    }
+    Ok(current())
+}
@@ -0,0 +1,216 @@
+use anyhow::Result;
+use ark_client::Blockchain;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::services::chain::ChainBackend;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningAction {
+    Flag,       // shown in the activity feed with a warning, still counted
+    Quarantine, // excluded from balance and coin selection
+    Pending,    // screening failed, held back like a quarantine and screened again on the next sync
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreeningResult {
+    pub action: ScreeningAction,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct InboundDeposit {
+    pub txid: String,
+    pub amount: u64,
+    pub source_outpoints: Vec<String>,
+    pub source_addresses: Vec<String>,
+}
+
+// operator-supplied rules for incoming on-chain deposits
+#[derive(Debug, Default)]
+pub struct ScreeningPolicy {
+    denylist: HashSet<String>, // addresses, txids or outpoints (txid:vout)
+    flag_above: Option<u64>,
+    quarantine_above: Option<u64>,
+}
+
+impl ScreeningPolicy {
+    pub fn from_env() -> Self {
+        let mut denylist: HashSet<String> = std::env::var("SCREENING_DENYLIST")
+            .unwrap_or_default()
+            .split(',')
+            .map(|entry| entry.trim().to_string())
+            .filter(|entry| !entry.is_empty())
+            .collect();
+
+        // larger lists can be kept in a file, one entry per line
+        if let Ok(path) = std::env::var("SCREENING_DENYLIST_FILE") {
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => denylist.extend(
+                    contents.lines()
+                        .map(|line| line.trim().to_string())
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                ),
+                Err(e) => tracing::error!("Failed to read screening denylist {}: {}", path, e),
+            }
+        }
+
+        let threshold = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<u64>().ok());
+
+        Self {
+            denylist,
+            flag_above: threshold("SCREENING_FLAG_ABOVE_SATS"),
+            quarantine_above: threshold("SCREENING_QUARANTINE_ABOVE_SATS"),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.denylist.is_empty() || self.flag_above.is_some() || self.quarantine_above.is_some()
+    }
+
+    pub fn needs_sources(&self) -> bool {
+        !self.denylist.is_empty()
+    }
+
+    pub fn evaluate(&self, deposit: &InboundDeposit) -> Option<ScreeningResult> {
+        let denylisted = std::iter::once(&deposit.txid)
+            .chain(deposit.source_outpoints.iter())
+            .chain(deposit.source_addresses.iter())
+            .find(|entry| self.denylist.contains(*entry));
+
+        if let Some(entry) = denylisted {
+            return Some(ScreeningResult {
+                action: ScreeningAction::Quarantine,
+                reason: format!("Denylisted source: {}", entry),
+            });
+        }
+
+        if let Some(limit) = self.quarantine_above {
+            if deposit.amount > limit {
+                return Some(ScreeningResult {
+                    action: ScreeningAction::Quarantine,
+                    reason: format!("Amount {} sats exceeds quarantine threshold of {} sats", deposit.amount, limit),
+                });
+            }
+        }
+
+        if let Some(limit) = self.flag_above {
+            if deposit.amount > limit {
+                return Some(ScreeningResult {
+                    action: ScreeningAction::Flag,
+                    reason: format!("Amount {} sats exceeds review threshold of {} sats", deposit.amount, limit),
+                });
+            }
+        }
+
+        None
+    }
+}
+
+// screens a newly seen incoming deposit, looking up where its inputs came from if the policy needs it
+pub async fn screen_deposit(
+    blockchain: &ChainBackend,
+    txid: &str,
+    amount: u64,
+) -> Result<Option<ScreeningResult>> {
+    let policy = ScreeningPolicy::from_env();
+    if !policy.is_enabled() {
+        return Ok(None);
+    }
+
+    let mut deposit = InboundDeposit {
+        txid: txid.to_string(),
+        amount,
+        ..Default::default()
+    };
+
+    if policy.needs_sources() {
+        let network = crate::services::profiles::active().network;
+
+        let parsed_txid = bitcoin::Txid::from_str(txid)?;
+        if let Some(tx) = blockchain.find_tx(&parsed_txid).await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch deposit {}: {}", txid, e))? {
+            for input in &tx.input {
+                let prevout = input.previous_output;
+                deposit.source_outpoints.push(prevout.to_string());
+
+                let prev_tx = blockchain.find_tx(&prevout.txid).await
+                    .map_err(|e| anyhow::anyhow!("Failed to fetch input {}: {}", prevout, e))?;
+                let source_address = prev_tx
+                    .and_then(|prev_tx| prev_tx.output.get(prevout.vout as usize).cloned())
+                    .and_then(|output| bitcoin::Address::from_script(&output.script_pubkey, network).ok());
+                if let Some(address) = source_address {
+                    deposit.source_addresses.push(address.to_string());
+                }
+            }
+        }
+    }
+
+    let result = policy.evaluate(&deposit);
+    if let Some(result) = &result {
+        tracing::warn!("Deposit {} screened: {:?} ({})", txid, result.action, result.reason);
+    }
+
+    Ok(result)
+}
+
+// what to store for an incoming deposit. Screening fails closed: a deposit that couldn't be screened
+// is held back as pending instead of counted
+pub async fn screen_or_hold(blockchain: &ChainBackend, txid: &str, amount: u64) -> Option<ScreeningResult> {
+    match screen_deposit(blockchain, txid, amount).await {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::warn!("Failed to screen deposit {}, holding it back until it can be: {}", txid, e);
+            Some(ScreeningResult {
+                action: ScreeningAction::Pending,
+                reason: format!("Not screened yet: {}", e),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(denylist: &[&str], flag_above: Option<u64>, quarantine_above: Option<u64>) -> ScreeningPolicy {
+        ScreeningPolicy {
+            denylist: denylist.iter().map(|s| s.to_string()).collect(),
+            flag_above,
+            quarantine_above,
+        }
+    }
+
+    #[test]
+    fn test_denylisted_source_address_is_quarantined() {
+        let policy = policy(&["bcrt1qbadactor"], None, None);
+        let deposit = InboundDeposit {
+            txid: "aa".to_string(),
+            amount: 1_000,
+            source_addresses: vec!["bcrt1qbadactor".to_string()],
+            ..Default::default()
+        };
+
+        let result = policy.evaluate(&deposit).unwrap();
+        assert_eq!(result.action, ScreeningAction::Quarantine);
+    }
+
+    #[test]
+    fn test_amount_thresholds() {
+        let policy = policy(&[], Some(10_000), Some(1_000_000));
+        let mut deposit = InboundDeposit {
+            txid: "aa".to_string(),
+            amount: 5_000,
+            ..Default::default()
+        };
+        assert_eq!(policy.evaluate(&deposit), None);
+
+        deposit.amount = 50_000;
+        assert_eq!(policy.evaluate(&deposit).unwrap().action, ScreeningAction::Flag);
+
+        deposit.amount = 2_000_000;
+        assert_eq!(policy.evaluate(&deposit).unwrap().action, ScreeningAction::Quarantine);
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ark_grpc::Client as ArkGrpcClient;
+
+use crate::services::events::WalletEvent;
+use crate::services::APP_STATE;
+use crate::storage::DbManager;
+
+const SETTINGS_KEY: &str = "server_info";
+
+// the parts of the ASP's get_info response the wallet depends on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub pk: String,
+    pub network: String,
+    pub unilateral_exit_delay: u32,
+    pub boarding_exit_delay: u32,
+    pub round_interval: i64,
+    pub dust: u64, // sats
+    #[serde(default)] // missing in info cached by older versions
+    pub vtxo_tree_expiry: u32,
+}
+
+impl From<&ark_core::server::Info> for ServerInfo {
+    fn from(info: &ark_core::server::Info) -> Self {
+        Self {
+            pk: info.pk.to_string(),
+            network: info.network.to_string(),
+            unilateral_exit_delay: info.unilateral_exit_delay.to_consensus_u32(),
+            boarding_exit_delay: info.boarding_exit_delay.to_consensus_u32(),
+            round_interval: info.round_interval,
+            dust: info.dust.to_sat(),
+            vtxo_tree_expiry: info.vtxo_tree_expiry.to_consensus_u32(),
+        }
+    }
+}
+
+impl ServerInfo {
+    // human readable list of fields that differ, empty if nothing changed
+    pub fn diff(&self, other: &ServerInfo) -> Vec<String> {
+        let mut changes = Vec::new();
+        let mut check = |field: &str, old: String, new: String| {
+            if old != new {
+                changes.push(format!("{}: {} -> {}", field, old, new));
+            }
+        };
+
+        check("pk", self.pk.clone(), other.pk.clone());
+        check("network", self.network.clone(), other.network.clone());
+        check("unilateral_exit_delay", self.unilateral_exit_delay.to_string(), other.unilateral_exit_delay.to_string());
+        check("boarding_exit_delay", self.boarding_exit_delay.to_string(), other.boarding_exit_delay.to_string());
+        check("round_interval", self.round_interval.to_string(), other.round_interval.to_string());
+        check("dust", self.dust.to_string(), other.dust.to_string());
+        check("vtxo_tree_expiry", self.vtxo_tree_expiry.to_string(), other.vtxo_tree_expiry.to_string());
+
+        changes
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedServerInfo {
+    #[serde(flatten)]
+    pub info: ServerInfo,
+    pub fetched_at: i64,
+}
+
+// last known ASP parameters, kept in memory and mirrored to the settings table
+pub struct ServerInfoCache {
+    cached: RwLock<Option<CachedServerInfo>>,
+}
+
+impl ServerInfoCache {
+    // seeds the cache from the last run so callers have values before the ASP answers
+    pub fn load(db: &DbManager) -> Self {
+        let cached = match db.get_setting(SETTINGS_KEY) {
+            Ok(Some(json)) => serde_json::from_str(&json)
+                .map_err(|e| tracing::warn!("Ignoring unreadable cached server info: {}", e))
+                .ok(),
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!("Failed to load cached server info: {}", e);
+                None
+            }
+        };
+
+        Self {
+            cached: RwLock::new(cached),
+        }
+    }
+
+    pub fn get(&self) -> Option<CachedServerInfo> {
+        self.cached.read().clone()
+    }
+
+    pub fn dust(&self) -> Option<u64> {
+        self.cached.read().as_ref().map(|c| c.info.dust)
+    }
+
+    // stores fresh info and returns what changed compared to the previous value
+    pub fn update(&self, info: ServerInfo) -> Vec<String> {
+        let changes = {
+            let mut cached = self.cached.write();
+            let changes = cached.as_ref()
+                .map(|previous| previous.info.diff(&info))
+                .unwrap_or_default();
+
+            *cached = Some(CachedServerInfo {
+                info,
+                fetched_at: chrono::Utc::now().timestamp(),
+            });
+            changes
+        };
+
+        if let Some(cached) = self.get() {
+            match serde_json::to_string(&cached) {
+                Ok(json) => {
+                    if let Err(e) = APP_STATE.db_manager.save_setting(SETTINGS_KEY, &json) {
+                        tracing::error!("Failed to persist server info: {}", e);
+                    }
+                },
+                Err(e) => tracing::error!("Failed to serialize server info: {}", e),
+            }
+        }
+
+        if !changes.is_empty() {
+            tracing::warn!("ASP server info changed: {}", changes.join(", "));
+            APP_STATE.events.publish(WalletEvent::ServerInfoChanged {
+                changes: changes.clone(),
+            });
+        }
+
+        changes
+    }
+
+    pub fn start_refresh_loop(self: &Arc<Self>, server_url: String, interval: Duration) {
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(e) = cache.refresh(&server_url).await {
+                    tracing::warn!("Failed to refresh server info: {}", e);
+                }
+            }
+        });
+    }
+
+    pub async fn refresh(&self, server_url: &str) -> Result<CachedServerInfo> {
+        let mut grpc_client = ArkGrpcClient::new(server_url.to_string());
+        grpc_client.connect().await
+            .map_err(|e| anyhow!("Failed to connect to Ark server: {}", e))?;
+
+        let info = grpc_client.get_info().await
+            .map_err(|e| anyhow!("Failed to get server info: {}", e))?;
+
+        self.update(ServerInfo::from(&info));
+        self.get().ok_or_else(|| anyhow!("Server info cache is empty"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_reports_changed_fields() {
+        let old = ServerInfo {
+            pk: "02aa".to_string(),
+            network: "regtest".to_string(),
+            unilateral_exit_delay: 512,
+            boarding_exit_delay: 1024,
+            round_interval: 10,
+            dust: 330,
+            vtxo_tree_expiry: 604672,
+        };
+        assert!(old.diff(&old.clone()).is_empty());
+
+        let new = ServerInfo {
+            unilateral_exit_delay: 1024,
+            dust: 546,
+            ..old.clone()
+        };
+        assert_eq!(old.diff(&new), vec![
+            "unilateral_exit_delay: 512 -> 1024".to_string(),
+            "dust: 330 -> 546".to_string(),
+        ]);
+    }
+}
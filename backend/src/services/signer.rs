@@ -0,0 +1,269 @@
+use anyhow::{anyhow, Result};
+use bitcoin::key::{Keypair, TapTweak};
+use bitcoin::secp256k1::{ecdsa, schnorr, All, Message, PublicKey, Secp256k1, XOnlyPublicKey};
+use parking_lot::RwLock;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::services::config::{SignerConfig, SignerKind};
+
+// produces signatures for our keys, wherever those keys live
+pub trait Signer: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // hands a derived key to a signer that holds keys in process, remote signers keep their own
+    fn register(&self, _keypair: &Keypair) {}
+
+    // wallet lock: drop in-process keys and refuse to sign until unlocked
+    fn lock(&self) {}
+
+    fn unlock(&self) {}
+
+    // ECDSA over a sighash, for P2WPKH inputs
+    fn sign_ecdsa(&self, public_key: &PublicKey, message: &Message) -> Result<ecdsa::Signature>;
+
+    // BIP340 Schnorr over a sighash, with the key tweaked for a taproot key-path spend when asked
+    fn sign_schnorr(&self, public_key: &XOnlyPublicKey, message: &Message, taproot_key_spend: bool) -> Result<schnorr::Signature>;
+}
+
+pub fn from_config(config: &SignerConfig) -> Box<dyn Signer> {
+    match (config.kind, &config.url) {
+        (SignerKind::Remote, Some(url)) => {
+            tracing::info!("Signing with the remote signer at {}", url);
+            Box::new(RemoteSigner::new(url))
+        },
+        _ => Box::new(LocalSigner::new()),
+    }
+}
+
+// hot keys in memory, the default
+pub struct LocalSigner {
+    secp: Secp256k1<All>,
+    keys: RwLock<HashMap<XOnlyPublicKey, Keypair>>,
+    locked: AtomicBool,
+}
+
+impl LocalSigner {
+    pub fn new() -> Self {
+        Self {
+            secp: Secp256k1::new(),
+            keys: RwLock::new(HashMap::new()),
+            locked: AtomicBool::new(false),
+        }
+    }
+
+    fn keypair(&self, public_key: &XOnlyPublicKey) -> Result<Keypair> {
+        if self.locked.load(Ordering::SeqCst) {
+            return Err(anyhow!("Wallet is locked"));
+        }
+        self.keys.read().get(public_key).copied()
+            .ok_or_else(|| anyhow!("No secret key found for public key: {}", public_key))
+    }
+}
+
+impl Signer for LocalSigner {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn register(&self, keypair: &Keypair) {
+        if self.locked.load(Ordering::SeqCst) {
+            return;
+        }
+        self.keys.write().insert(keypair.x_only_public_key().0, *keypair);
+    }
+
+    fn lock(&self) {
+        self.locked.store(true, Ordering::SeqCst);
+        let mut keys = self.keys.write();
+        for keypair in keys.values_mut() {
+            keypair.non_secure_erase();
+        }
+        keys.clear();
+    }
+
+    fn unlock(&self) {
+        self.locked.store(false, Ordering::SeqCst);
+    }
+
+    fn sign_ecdsa(&self, public_key: &PublicKey, message: &Message) -> Result<ecdsa::Signature> {
+        let keypair = self.keypair(&public_key.x_only_public_key().0)?;
+        Ok(self.secp.sign_ecdsa(message, &keypair.secret_key()))
+    }
+
+    fn sign_schnorr(&self, public_key: &XOnlyPublicKey, message: &Message, taproot_key_spend: bool) -> Result<schnorr::Signature> {
+        let mut keypair = self.keypair(public_key)?;
+        if taproot_key_spend {
+            keypair = keypair.tap_tweak(&self.secp, None).to_inner();
+        }
+        Ok(self.secp.sign_schnorr_no_aux_rand(message, &keypair))
+    }
+}
+
+#[derive(Deserialize)]
+struct RemoteSignature {
+    signature: String, // hex, DER for ecdsa
+}
+
+struct SignRequest {
+    body: serde_json::Value,
+    reply: std::sync::mpsc::SyncSender<Result<String>>,
+}
+
+// forwards sighashes to a signing daemon so the keys can live on another machine,
+// every signature is checked before it's used
+pub struct RemoteSigner {
+    requests: tokio::sync::mpsc::UnboundedSender<SignRequest>,
+    secp: Secp256k1<All>,
+    locked: AtomicBool, // the daemon's keys stay there, nothing is forwarded while locked
+}
+
+impl RemoteSigner {
+    // ark-client's wallet traits are sync, so requests go to one thread that owns a runtime and
+    // the http client for the signer's lifetime, block_in_place would panic when called from a
+    // current-thread runtime
+    pub fn new(url: &str) -> Self {
+        let url = format!("{}/sign", url.trim_end_matches('/'));
+        let (requests, incoming) = tokio::sync::mpsc::unbounded_channel();
+        let worker = std::thread::Builder::new()
+            .name("remote-signer".to_string())
+            .spawn(move || {
+                if let Err(e) = serve_requests(&url, incoming) {
+                    tracing::error!("Remote signer stopped: {}", e);
+                }
+            });
+        if let Err(e) = worker {
+            tracing::error!("Failed to start the remote signer: {}", e);
+        }
+
+        Self {
+            requests,
+            secp: Secp256k1::new(),
+            locked: AtomicBool::new(false),
+        }
+    }
+
+    fn request(&self, body: serde_json::Value) -> Result<String> {
+        if self.locked.load(Ordering::SeqCst) {
+            return Err(anyhow!("Wallet is locked"));
+        }
+        let (reply, response) = std::sync::mpsc::sync_channel(1);
+        self.requests.send(SignRequest { body, reply })
+            .map_err(|_| anyhow!("Remote signer is not running"))?;
+        response.recv()
+            .map_err(|_| anyhow!("Remote signer is not running"))?
+    }
+}
+
+fn serve_requests(url: &str, mut incoming: tokio::sync::mpsc::UnboundedReceiver<SignRequest>) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()?;
+
+    runtime.block_on(async {
+        while let Some(request) = incoming.recv().await {
+            let client = client.clone();
+            let url = url.to_string();
+            tokio::spawn(async move {
+                let signature = post_request(&client, &url, &request.body).await
+                    .map_err(|e| anyhow!("Remote signer request failed: {}", e));
+                let _ = request.reply.send(signature);
+            });
+        }
+    });
+    Ok(())
+}
+
+async fn post_request(client: &reqwest::Client, url: &str, body: &serde_json::Value) -> Result<String> {
+    let response = client.post(url).json(body).send().await?;
+    Ok(response.error_for_status()?.json::<RemoteSignature>().await?.signature)
+}
+
+impl Signer for RemoteSigner {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    fn lock(&self) {
+        self.locked.store(true, Ordering::SeqCst);
+    }
+
+    fn unlock(&self) {
+        self.locked.store(false, Ordering::SeqCst);
+    }
+
+    fn sign_ecdsa(&self, public_key: &PublicKey, message: &Message) -> Result<ecdsa::Signature> {
+        let signature = self.request(serde_json::json!({
+            "scheme": "ecdsa",
+            "public_key": public_key.to_string(),
+            "message": digest_hex(message),
+        }))?;
+
+        let signature = ecdsa::Signature::from_str(&signature)
+            .map_err(|e| anyhow!("Remote signer returned an invalid signature: {}", e))?;
+        self.secp.verify_ecdsa(message, &signature, public_key)
+            .map_err(|_| anyhow!("Remote signer returned a signature that doesn't verify"))?;
+        Ok(signature)
+    }
+
+    fn sign_schnorr(&self, public_key: &XOnlyPublicKey, message: &Message, taproot_key_spend: bool) -> Result<schnorr::Signature> {
+        let signature = self.request(serde_json::json!({
+            "scheme": "schnorr",
+            "public_key": public_key.to_string(),
+            "message": digest_hex(message),
+            "taproot_key_spend": taproot_key_spend,
+        }))?;
+
+        let signature = schnorr::Signature::from_str(&signature)
+            .map_err(|e| anyhow!("Remote signer returned an invalid signature: {}", e))?;
+        let signing_key = match taproot_key_spend {
+            true => public_key.tap_tweak(&self.secp, None).0.to_inner(),
+            false => *public_key,
+        };
+        self.secp.verify_schnorr(&signature, message, &signing_key)
+            .map_err(|_| anyhow!("Remote signer returned a signature that doesn't verify"))?;
+        Ok(signature)
+    }
+}
+
+fn digest_hex(message: &Message) -> String {
+    let digest: &[u8; 32] = message.as_ref();
+    hex::encode(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_signer_signs_registered_keys_only() {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::new(&secp, &mut bitcoin::secp256k1::rand::thread_rng());
+        let (xonly, _) = keypair.x_only_public_key();
+        let message = Message::from_digest([7u8; 32]);
+
+        let signer = LocalSigner::new();
+        assert!(signer.sign_schnorr(&xonly, &message, false).is_err());
+
+        signer.register(&keypair);
+        let signature = signer.sign_schnorr(&xonly, &message, false).unwrap();
+        assert!(secp.verify_schnorr(&signature, &message, &xonly).is_ok());
+
+        let signature = signer.sign_schnorr(&xonly, &message, true).unwrap();
+        let tweaked = xonly.tap_tweak(&secp, None).0.to_inner();
+        assert!(secp.verify_schnorr(&signature, &message, &tweaked).is_ok());
+
+        let signature = signer.sign_ecdsa(&keypair.public_key(), &message).unwrap();
+        assert!(secp.verify_ecdsa(&message, &signature, &keypair.public_key()).is_ok());
+
+        signer.lock();
+        signer.register(&keypair);
+        assert!(signer.sign_schnorr(&xonly, &message, false).is_err());
+        signer.unlock();
+        assert!(signer.sign_schnorr(&xonly, &message, false).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use ark_core::ArkAddress;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::key::TweakedPublicKey;
+use bitcoin::secp256k1::{Keypair, Secp256k1, SecretKey};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::models::wallet::{TransactionKind, TransactionResponse};
+use crate::services::APP_STATE;
+
+// stands in for the ASP's key in simulated Ark addresses
+const SERVER_SECRET: [u8; 32] = [0x5a; 32];
+// what a simulated board adds to the balance
+const DEPOSIT_SATS: i64 = 100_000_000;
+
+// counts simulated txids, so a fresh run hands out the same sequence again
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+// fake txids handed out while falling back with strict_mode = false. Their entries only live in memory,
+// the database is the real wallet's. Simulation mode has a data dir of its own and stores them as usual
+static FALLBACK_TXIDS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+tokio::task_local! {
+    // set when the request being answered used simulated data
+    static USED: Cell<bool>;
+}
+
+// SIMULATION_MODE, the Ark operations below answer instead of the ASP
+pub fn is_enabled() -> bool {
+    crate::services::config::get().simulation_mode
+}
+
+// also with strict_mode = false, as a stand-in while the Ark client isn't connected
+pub fn fallback_enabled() -> bool {
+    let config = crate::services::config::get();
+    config.simulation_mode || !config.strict_mode
+}
+
+// runs a request and reports whether any of the functions below answered part of it
+pub async fn track<F: Future>(request: F) -> (F::Output, bool) {
+    USED.scope(Cell::new(false), async {
+        let output = request.await;
+        (output, USED.with(Cell::get))
+    }).await
+}
+
+fn mark_used() {
+    // outside track(), e.g. in the round scheduler, there's no response to mark
+    let _ = USED.try_with(|used| used.set(true));
+}
+
+pub fn txid(kind: &str, detail: &str) -> String {
+    mark_used();
+    let txid = fake_txid(kind, detail, SEQUENCE.fetch_add(1, Ordering::SeqCst));
+    if !is_enabled() {
+        FALLBACK_TXIDS.lock().insert(txid.clone());
+    }
+    txid
+}
+
+// an entry that must not be written to the database
+pub fn is_fallback_entry(txid: &str) -> bool {
+    FALLBACK_TXIDS.lock().contains(txid)
+}
+
+// the in-memory balance includes made up entries, so it isn't cached either
+pub fn has_fallback_entries() -> bool {
+    !FALLBACK_TXIDS.lock().is_empty()
+}
+
+fn is_simulated(txid: &str) -> bool {
+    is_enabled() || is_fallback_entry(txid)
+}
+
+fn fake_txid(kind: &str, detail: &str, sequence: u64) -> String {
+    sha256::Hash::hash(format!("{}:{}:{}", kind, detail, sequence).as_bytes()).to_string()
+}
+
+// a valid Ark address for the wallet key, behind the stand-in server key
+pub fn offchain_address() -> Result<String> {
+    mark_used();
+    let (keypair, _) = APP_STATE.key_manager.load_or_create_wallet()?;
+    let secp = Secp256k1::new();
+    let server = Keypair::from_secret_key(&secp, &SecretKey::from_slice(&SERVER_SECRET)?);
+    let (vtxo_key, _) = keypair.x_only_public_key();
+
+    let address = ArkAddress::new(
+        crate::services::profiles::active().network,
+        server.x_only_public_key().0,
+        TweakedPublicKey::dangerous_assume_tweaked(vtxo_key),
+    );
+    Ok(address.to_string())
+}
+
+// the wallet's on-chain address, nothing watches it for deposits
+pub async fn boarding_address() -> Result<String> {
+    mark_used();
+    crate::services::wallet::get_onchain_address().await
+}
+
+// credits a settled 1 BTC boarding deposit on every call
+pub async fn board() -> Result<bool> {
+    let tx = TransactionResponse {
+        is_settled: Some(true),
+        ..TransactionResponse::new(
+            txid("deposit", &DEPOSIT_SATS.to_string()),
+            TransactionKind::Boarding,
+            DEPOSIT_SATS,
+            chrono::Utc::now().timestamp(),
+        )
+    };
+    crate::services::transactions::record_transaction(&tx).await?;
+    APP_STATE.recalculate_balance().await?;
+
+    tracing::info!("Simulated a deposit of {} sats as {}", DEPOSIT_SATS, tx.txid);
+    Ok(true)
+}
+
+// settles every pending simulated transaction in a round of its own, None when nothing is pending.
+// real ones pending while falling back are left to the ASP
+pub async fn settle_round() -> Result<Option<String>> {
+    mark_used();
+    let mut transactions = APP_STATE.transactions.lock().await;
+
+    let mut settled_txids = Vec::new();
+    let mut updated = Vec::new();
+    for tx in transactions.iter_mut() {
+        if tx.is_settled == Some(false) && is_simulated(&tx.txid) {
+            tx.is_settled = Some(true);
+            settled_txids.push(tx.txid.clone());
+            updated.push(tx.clone());
+        }
+    }
+    if settled_txids.is_empty() {
+        return Ok(None);
+    }
+
+    let round_txid = txid("round", &settled_txids.join(","));
+    let round_tx = TransactionResponse {
+        is_settled: Some(true),
+        // rounds don't change balance directly
+        ..TransactionResponse::new(round_txid.clone(), TransactionKind::Round { round_id: None }, 0, chrono::Utc::now().timestamp())
+    };
+    transactions.push(round_tx.clone());
+    updated.push(round_tx);
+    drop(transactions);
+
+    // skipped for fallback entries
+    for tx in &updated {
+        if let Err(e) = crate::services::transactions::save_transaction_to_db(tx).await {
+            tracing::error!("Failed to save transaction {} to database: {}", tx.txid, e);
+        }
+    }
+    APP_STATE.recalculate_balance().await?;
+
+    tracing::info!("Simulated round {} settled {} transactions", round_txid, settled_txids.len());
+    Ok(Some(round_txid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_txid_is_deterministic() {
+        let txid = fake_txid("send", "addr:1000", 0);
+        assert_eq!(txid.len(), 64);
+        assert_eq!(txid, fake_txid("send", "addr:1000", 0));
+        assert_ne!(txid, fake_txid("send", "addr:1000", 1));
+        assert_ne!(txid, fake_txid("onchain", "addr:1000", 0));
+    }
+}
@@ -0,0 +1,209 @@
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::services::APP_STATE;
+use crate::storage::DbManager;
+
+const SETTINGS_KEY: &str = "spending_policy";
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+// a send the policy doesn't allow, answered with 403 rather than as a failure
+#[derive(Debug, thiserror::Error)]
+#[error("Spending policy: {0}")]
+pub struct PolicyViolation(pub String);
+
+// guardrails for outgoing payments, everything is off by default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpendingPolicy {
+    pub max_per_tx: Option<u64>,
+    pub daily_limit: Option<u64>, // over the last 24 hours, not the calendar day
+    #[serde(default)]
+    pub allowlist: Vec<String>,   // when not empty, the only destinations allowed
+}
+
+impl SpendingPolicy {
+    pub fn validate(&self) -> Result<()> {
+        if self.max_per_tx == Some(0) {
+            return Err(anyhow!("max_per_tx must be positive"));
+        }
+        if self.daily_limit == Some(0) {
+            return Err(anyhow!("daily_limit must be positive"));
+        }
+        for address in &self.allowlist {
+            crate::services::contacts::classify_address(address)
+                .map_err(|e| anyhow!("allowlist entry {}: {}", address, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn load(db: &DbManager) -> Self {
+        match db.get_setting(SETTINGS_KEY) {
+            Ok(Some(json)) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable spending policy: {}", e);
+                Self::default()
+            }),
+            Ok(None) => Self::default(),
+            Err(e) => {
+                tracing::warn!("Failed to load spending policy: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, db: &DbManager) -> Result<()> {
+        db.save_setting(SETTINGS_KEY, &serde_json::to_string(self)?)
+    }
+
+    // anything set at all, a default policy lets every send through
+    pub fn is_restricted(&self) -> bool {
+        self != &Self::default()
+    }
+
+    // spent_today is what already left the wallet in the last 24 hours
+    fn check(&self, address: &str, amount: u64, spent_today: u64) -> Result<(), PolicyViolation> {
+        if !self.allowlist.is_empty() && !self.allowlist.iter().any(|allowed| allowed == address) {
+            return Err(PolicyViolation(format!("{} is not on the allowlist", address)));
+        }
+        if let Some(max) = self.max_per_tx {
+            if amount > max {
+                return Err(PolicyViolation(format!("{} sats is above the {} sats per transaction limit", amount, max)));
+            }
+        }
+        if let Some(limit) = self.daily_limit {
+            if spent_today.saturating_add(amount) > limit {
+                return Err(PolicyViolation(format!(
+                    "{} sats would exceed the daily limit of {} sats, {} sats left",
+                    amount, limit, limit.saturating_sub(spent_today)
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // a tx paying several destinations: each one must be allowed, the limits apply to their total
+    fn check_outputs(&self, outputs: &[(String, u64)], spent_today: u64) -> Result<(), PolicyViolation> {
+        let total = outputs.iter().map(|(_, amount)| amount).sum();
+        for (address, _) in outputs {
+            self.check(address, total, spent_today)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpendingStatus {
+    #[serde(flatten)]
+    pub policy: SpendingPolicy,
+    pub spent_last_24h: u64,
+    pub remaining_today: Option<u64>, // None without a daily limit
+}
+
+pub struct SpendingGuard {
+    policy: Mutex<SpendingPolicy>,
+    // one send at a time, so two concurrent sends can't both fit under the daily limit
+    in_flight: tokio::sync::Mutex<()>,
+}
+
+impl SpendingGuard {
+    pub fn load(db: &DbManager) -> Self {
+        Self {
+            policy: Mutex::new(SpendingPolicy::load(db)),
+            in_flight: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn policy(&self) -> SpendingPolicy {
+        self.policy.lock().clone()
+    }
+
+    pub fn set_policy(&self, policy: SpendingPolicy) -> Result<()> {
+        policy.validate()?;
+        policy.save(&APP_STATE.db_manager)?;
+        *self.policy.lock() = policy;
+        Ok(())
+    }
+
+    pub fn status(&self) -> Result<SpendingStatus> {
+        let policy = self.policy();
+        let spent_last_24h = spent_since(chrono::Utc::now().timestamp() - DAY_SECS)?;
+        Ok(SpendingStatus {
+            remaining_today: policy.daily_limit.map(|limit| limit.saturating_sub(spent_last_24h)),
+            policy,
+            spent_last_24h,
+        })
+    }
+
+    // checks a send against the policy, hold the guard until the send is recorded
+    pub async fn authorize(&self, address: &str, amount: u64) -> Result<tokio::sync::MutexGuard<'_, ()>> {
+        self.authorize_outputs(&[(address.to_string(), amount)]).await
+    }
+
+    // same for a tx with several external outputs, as (address, amount)
+    pub async fn authorize_outputs(&self, outputs: &[(String, u64)]) -> Result<tokio::sync::MutexGuard<'_, ()>> {
+        let guard = self.in_flight.lock().await;
+        let spent_today = spent_since(chrono::Utc::now().timestamp() - DAY_SECS)?;
+        if let Err(violation) = self.policy().check_outputs(outputs, spent_today) {
+            tracing::warn!("Blocked send to {:?}: {}", outputs, violation);
+            return Err(violation.into());
+        }
+        Ok(guard)
+    }
+}
+
+// outgoing Ark and on-chain payments, replaced transactions (is_settled NULL) don't count twice
+fn spent_since(since: i64) -> Result<u64> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let spent: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(-amount), 0) FROM transactions
+         WHERE amount < 0 AND type_name IN ('Redeem', 'OnChain') AND is_settled IS NOT NULL AND timestamp >= ?",
+        [since],
+        |row| row.get(0),
+    )?;
+    Ok(spent as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_limits() {
+        let policy = SpendingPolicy {
+            max_per_tx: Some(10_000),
+            daily_limit: Some(25_000),
+            allowlist: Vec::new(),
+        };
+        assert!(policy.check("addr", 10_000, 0).is_ok());
+        assert!(policy.check("addr", 10_001, 0).is_err());
+        assert!(policy.check("addr", 10_000, 15_000).is_ok());
+        assert!(policy.check("addr", 10_000, 15_001).is_err());
+
+        let policy = SpendingPolicy {
+            allowlist: vec!["allowed".to_string()],
+            ..Default::default()
+        };
+        assert!(policy.check("allowed", 1_000_000, 0).is_ok());
+        assert!(policy.check("other", 1, 0).is_err());
+        assert!(SpendingPolicy::default().check("anything", u64::MAX, u64::MAX).is_ok());
+        assert!(!SpendingPolicy::default().is_restricted());
+        assert!(policy.is_restricted());
+    }
+
+    #[test]
+    fn test_check_outputs() {
+        let policy = SpendingPolicy {
+            max_per_tx: Some(10_000),
+            allowlist: vec!["a".to_string(), "b".to_string()],
+            ..Default::default()
+        };
+        let outputs = |amounts: &[(&str, u64)]| -> Vec<(String, u64)> {
+            amounts.iter().map(|(address, amount)| (address.to_string(), *amount)).collect()
+        };
+        assert!(policy.check_outputs(&outputs(&[("a", 5_000), ("b", 5_000)]), 0).is_ok());
+        // the limit applies to the whole tx, not each output
+        assert!(policy.check_outputs(&outputs(&[("a", 6_000), ("b", 6_000)]), 0).is_err());
+        assert!(policy.check_outputs(&outputs(&[("a", 1), ("c", 1)]), 0).is_err());
+        assert!(policy.check_outputs(&[], 0).is_ok());
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use bitcoin::secp256k1::PublicKey;
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::{SubmarineSwap, SwapFunding, SwapProvider};
+
+// Boltz v2 REST API, https://api.boltz.exchange/swagger
+pub struct BoltzProvider {
+    base_url: String,
+    http_client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateSubmarineResponse {
+    id: String,
+    address: String,
+    expected_amount: u64,
+    timeout_block_height: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwapStatusResponse {
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+impl BoltzProvider {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to build HTTP client"),
+        }
+    }
+
+    // Boltz puts the reason in {"error": ...} for 4xx responses
+    async fn parse<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+        let status = response.status();
+        if !status.is_success() {
+            let reason = response.json::<ErrorResponse>().await
+                .map(|body| body.error)
+                .unwrap_or_else(|_| status.to_string());
+            return Err(anyhow!("Boltz API error: {}", reason));
+        }
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl SwapProvider for BoltzProvider {
+    fn name(&self) -> &str {
+        "boltz"
+    }
+
+    async fn create_submarine_swap(
+        &self,
+        invoice: &str,
+        funding: SwapFunding,
+        refund_pubkey: &PublicKey,
+    ) -> Result<SubmarineSwap> {
+        let from = match funding {
+            SwapFunding::Offchain => "ARK",
+            SwapFunding::Onchain => "BTC",
+        };
+
+        let response = self.http_client
+            .post(format!("{}/v2/swap/submarine", self.base_url))
+            .json(&serde_json::json!({
+                "invoice": invoice,
+                "from": from,
+                "to": "BTC",
+                "refundPublicKey": refund_pubkey.to_string(),
+            }))
+            .send()
+            .await?;
+        let swap: CreateSubmarineResponse = Self::parse(response).await?;
+
+        Ok(SubmarineSwap {
+            id: swap.id,
+            lockup_address: swap.address,
+            expected_amount: swap.expected_amount,
+            timeout_block_height: swap.timeout_block_height,
+        })
+    }
+
+    async fn swap_status(&self, id: &str) -> Result<String> {
+        let response = self.http_client
+            .get(format!("{}/v2/swap/{}", self.base_url, id))
+            .send()
+            .await?;
+        let status: SwapStatusResponse = Self::parse(response).await?;
+        Ok(status.status)
+    }
+}
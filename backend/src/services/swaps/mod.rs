@@ -0,0 +1,329 @@
+pub mod boltz;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use bitcoin::secp256k1::PublicKey;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::services::config::Config;
+use crate::services::events::WalletEvent;
+use crate::services::onchain::fee_estimator::FeePriority;
+use crate::services::APP_STATE;
+
+// a swap the provider fails to pay leaves our lockup to be refunded after its timeout, and nothing
+// signs that refund yet. Paying invoices stays off until it does
+pub const REFUNDS_SUPPORTED: bool = false;
+
+// provider statuses after which nothing changes anymore
+const FINAL_STATUSES: &[&str] = &[
+    "transaction.claimed",
+    "invoice.failedToPay",
+    "transaction.lockupFailed",
+    "transaction.refunded",
+    "swap.expired",
+    "funding_failed",
+];
+
+// which balance pays the swap's lockup
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapFunding {
+    Offchain, // VTXOs sent to the provider's Ark lockup address
+    Onchain,  // on-chain payment to the provider's HTLC address
+}
+
+impl SwapFunding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwapFunding::Offchain => "offchain",
+            SwapFunding::Onchain => "onchain",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "onchain" => SwapFunding::Onchain,
+            _ => SwapFunding::Offchain,
+        }
+    }
+}
+
+// what a provider hands back for a new submarine swap (chain -> Lightning)
+#[derive(Debug, Clone)]
+pub struct SubmarineSwap {
+    pub id: String,
+    pub lockup_address: String,
+    pub expected_amount: u64, // invoice amount plus the provider's fees
+    pub timeout_block_height: Option<u32>,
+}
+
+#[async_trait]
+pub trait SwapProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn create_submarine_swap(
+        &self,
+        invoice: &str,
+        funding: SwapFunding,
+        refund_pubkey: &PublicKey,
+    ) -> Result<SubmarineSwap>;
+
+    async fn swap_status(&self, id: &str) -> Result<String>;
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Swap {
+    pub id: String,
+    pub provider: String,
+    pub invoice: String,
+    pub funding: SwapFunding,
+    pub lockup_address: String,
+    pub expected_amount: u64,
+    pub lockup_txid: Option<String>,
+    pub status: String, // the provider's status, or funding_failed if our lockup payment failed
+    pub error: Option<String>,
+    pub timeout_block_height: Option<u32>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Swap {
+    pub fn is_final(&self) -> bool {
+        FINAL_STATUSES.contains(&self.status.as_str())
+    }
+}
+
+// pays Lightning invoices from the wallet's balance through a swap provider
+pub struct SwapService {
+    provider: Option<Box<dyn SwapProvider>>,
+}
+
+impl SwapService {
+    pub fn new(config: &Config) -> Self {
+        let provider = config.boltz_url.as_deref()
+            .map(|url| Box::new(boltz::BoltzProvider::new(url)) as Box<dyn SwapProvider>);
+        Self { provider }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    fn provider(&self) -> Result<&dyn SwapProvider> {
+        self.provider.as_deref()
+            .ok_or_else(|| anyhow!("Swaps are disabled, set boltz_url to enable them"))
+    }
+
+    pub async fn pay_invoice(&self, invoice: &str, funding: SwapFunding) -> Result<Swap> {
+        let provider = self.provider()?;
+        if !REFUNDS_SUPPORTED {
+            return Err(anyhow!("Paying invoices through swaps is disabled until failed swaps can be refunded"));
+        }
+        let decoded = crate::services::payments::bolt11::decode(invoice)?;
+        if decoded.expired {
+            return Err(anyhow!("Invoice expired at {}", decoded.expires_at));
+        }
+        if decoded.amount_msat.is_none() {
+            return Err(anyhow!("Invoices without an amount can't be paid through a swap"));
+        }
+        let invoice = decoded.invoice.as_str();
+
+        // only needed to refund a failed swap, one key per swap from the seed so a backup restores it
+        let refund_key_index = self.next_refund_key_index()?;
+        let refund_key = APP_STATE.key_manager.derive_swap_refund_keypair(refund_key_index)?.public_key();
+        let created = provider.create_submarine_swap(invoice, funding, &refund_key).await?;
+        tracing::info!(
+            "Created {} swap {}: lock {} sats at {}",
+            provider.name(), created.id, created.expected_amount, created.lockup_address
+        );
+
+        let now = chrono::Utc::now().timestamp();
+        let mut swap = Swap {
+            id: created.id,
+            provider: provider.name().to_string(),
+            invoice: invoice.to_string(),
+            funding,
+            lockup_address: created.lockup_address,
+            expected_amount: created.expected_amount,
+            lockup_txid: None,
+            status: "swap.created".to_string(),
+            error: None,
+            timeout_block_height: created.timeout_block_height,
+            created_at: now,
+            updated_at: now,
+        };
+        self.insert(&swap, &refund_key, refund_key_index)?;
+
+        let funded = match funding {
+            SwapFunding::Offchain => crate::services::wallet::send_vtxo(
+                swap.lockup_address.clone(),
+                swap.expected_amount,
+            ).await,
+            SwapFunding::Onchain => crate::services::wallet::send_onchain_payment_with_fee_priority(
+                swap.lockup_address.clone(),
+                swap.expected_amount,
+                FeePriority::Fast,
+                None,
+            ).await,
+        };
+
+        match funded {
+            Ok(response) => {
+                swap.lockup_txid = Some(response.txid);
+                if let Ok(status) = provider.swap_status(&swap.id).await {
+                    swap.status = status;
+                }
+            },
+            Err(e) => {
+                tracing::error!("Failed to fund swap {}: {}", swap.id, e);
+                swap.status = "funding_failed".to_string();
+                swap.error = Some(e.to_string());
+            },
+        }
+        self.update(&mut swap)?;
+
+        match &swap.error {
+            Some(error) => Err(anyhow!("Failed to fund swap {}: {}", swap.id, error)),
+            None => Ok(swap),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<Swap>> {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM swaps ORDER BY created_at DESC", COLUMNS))?;
+        let swaps = stmt.query_map([], row_to_swap)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(swaps)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Swap>> {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        let swap = conn.query_row(
+            &format!("SELECT {} FROM swaps WHERE id = ?", COLUMNS),
+            params![id],
+            row_to_swap,
+        ).optional()?;
+        Ok(swap)
+    }
+
+    // asks the provider for the current status of a swap that isn't final yet
+    pub async fn refresh(&self, id: &str) -> Result<Option<Swap>> {
+        let Some(mut swap) = self.get(id)? else {
+            return Ok(None);
+        };
+        if swap.is_final() {
+            return Ok(Some(swap));
+        }
+
+        let status = self.provider()?.swap_status(&swap.id).await?;
+        if status != swap.status {
+            tracing::info!("Swap {} is now {}", swap.id, status);
+            swap.status = status;
+            self.update(&mut swap)?;
+        }
+        Ok(Some(swap))
+    }
+
+    pub fn start_status_poller(self: &Arc<Self>, interval: Duration) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                // only asks the provider for the status, nothing is signed, so it also runs while locked
+                let pending = match service.list() {
+                    Ok(swaps) => swaps.into_iter().filter(|swap| !swap.is_final()),
+                    Err(e) => {
+                        tracing::warn!("Failed to list swaps: {}", e);
+                        continue;
+                    }
+                };
+                for swap in pending {
+                    if let Err(e) = service.refresh(&swap.id).await {
+                        tracing::warn!("Failed to refresh swap {}: {}", swap.id, e);
+                    }
+                }
+            }
+        });
+    }
+
+    // the index after the highest one a swap used
+    fn next_refund_key_index(&self) -> Result<u32> {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        let index: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(refund_key_index) + 1, 0) FROM swaps",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(index as u32)
+    }
+
+    fn insert(&self, swap: &Swap, refund_key: &PublicKey, refund_key_index: u32) -> Result<()> {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.execute(
+            "INSERT INTO swaps (id, provider, invoice, funding, lockup_address, expected_amount, lockup_txid,
+                status, error, timeout_block_height, refund_key, refund_key_index, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                swap.id,
+                swap.provider,
+                swap.invoice,
+                swap.funding.as_str(),
+                swap.lockup_address,
+                swap.expected_amount as i64,
+                swap.lockup_txid,
+                swap.status,
+                swap.error,
+                swap.timeout_block_height,
+                refund_key.to_string(),
+                refund_key_index,
+                swap.created_at,
+                swap.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn update(&self, swap: &mut Swap) -> Result<()> {
+        swap.updated_at = chrono::Utc::now().timestamp();
+        {
+            let conn = APP_STATE.db_manager.get_conn()?;
+            conn.execute(
+                "UPDATE swaps SET lockup_txid = ?, status = ?, error = ?, updated_at = ? WHERE id = ?",
+                params![swap.lockup_txid, swap.status, swap.error, swap.updated_at, swap.id],
+            )?;
+        }
+
+        APP_STATE.events.publish(WalletEvent::SwapUpdated {
+            id: swap.id.clone(),
+            status: swap.status.clone(),
+        });
+        Ok(())
+    }
+}
+
+const COLUMNS: &str = "id, provider, invoice, funding, lockup_address, expected_amount, lockup_txid,
+    status, error, timeout_block_height, created_at, updated_at";
+
+fn row_to_swap(row: &rusqlite::Row) -> rusqlite::Result<Swap> {
+    Ok(Swap {
+        id: row.get(0)?,
+        provider: row.get(1)?,
+        invoice: row.get(2)?,
+        funding: SwapFunding::parse(&row.get::<_, String>(3)?),
+        lockup_address: row.get(4)?,
+        expected_amount: row.get::<_, i64>(5)? as u64,
+        lockup_txid: row.get(6)?,
+        status: row.get(7)?,
+        error: row.get(8)?,
+        timeout_block_height: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
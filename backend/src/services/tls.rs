@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use std::path::Path;
+
+use crate::services::config::TlsConfig;
+
+// None serves plain HTTP
+pub async fn load(config: &TlsConfig, data_dir: &str) -> Result<Option<RustlsConfig>> {
+    if let (Some(cert_path), Some(key_path)) = (&config.cert_path, &config.key_path) {
+        let rustls = RustlsConfig::from_pem_file(cert_path, key_path).await
+            .with_context(|| format!("Failed to load TLS certificate {} and key {}", cert_path, key_path))?;
+        tracing::info!("Serving HTTPS with the certificate from {}", cert_path);
+        return Ok(Some(rustls));
+    }
+    if config.self_signed {
+        let (cert_path, key_path) = self_signed(data_dir)?;
+        let rustls = RustlsConfig::from_pem_file(&cert_path, &key_path).await
+            .context("Failed to load the self-signed TLS certificate")?;
+        return Ok(Some(rustls));
+    }
+    Ok(None)
+}
+
+// generated once under <data_dir>/tls and reused, so a browser exception survives restarts.
+// for localhost development only, nothing trusts it
+fn self_signed(data_dir: &str) -> Result<(String, String)> {
+    let dir = Path::new(data_dir).join("tls");
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+
+    if !(cert_path.exists() && key_path.exists()) {
+        let certified = rcgen::generate_simple_self_signed(vec![
+            "localhost".to_string(),
+            "127.0.0.1".to_string(),
+        ]).context("Failed to generate a self-signed certificate")?;
+
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(&cert_path, certified.cert.pem())?;
+        std::fs::write(&key_path, certified.key_pair.serialize_pem())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        tracing::warn!("Generated a self-signed TLS certificate at {}", cert_path.display());
+    } else {
+        tracing::warn!("Serving HTTPS with the self-signed certificate at {}", cert_path.display());
+    }
+
+    Ok((cert_path.to_string_lossy().into_owned(), key_path.to_string_lossy().into_owned()))
+}
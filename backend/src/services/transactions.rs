@@ -1,28 +1,137 @@
 #![allow(unused_imports, unused_variables, unused_assignments)]
-use crate::models::wallet::TransactionResponse;
+use crate::models::wallet::{TransactionDetail, TransactionDirection, TransactionHistoryQuery, TransactionKind, TransactionResponse};
 use crate::services::APP_STATE;
+use crate::services::confirmations;
 use ark_client::Blockchain;
 use anyhow::{Result, Context};
 
 use std::sync::Arc;
 use std::str::FromStr;
 
-pub async fn get_transaction_history() -> Result<Vec<TransactionResponse>> {
+// serves history from the transactions table, kept up to date by sync_transaction_history
+pub async fn get_transaction_history(query: &TransactionHistoryQuery) -> Result<(Vec<TransactionResponse>, u64)> {
+    use rusqlite::types::Value;
+
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+
+    if let Some(type_name) = &query.type_name {
+        conditions.push("type_name = ?");
+        values.push(Value::Text(type_name.clone()));
+    }
+    if let Some(from) = query.from {
+        conditions.push("timestamp >= ?");
+        values.push(Value::Integer(from));
+    }
+    if let Some(to) = query.to {
+        conditions.push("timestamp <= ?");
+        values.push(Value::Integer(to));
+    }
+    if let Some(direction) = query.direction {
+        conditions.push("direction = ?");
+        values.push(Value::Text(direction.name().to_string()));
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let conn = APP_STATE.db_manager.get_conn()?;
+
+    let total: u64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM transactions {}", where_clause),
+        rusqlite::params_from_iter(values.iter()),
+        |row| row.get(0),
+    )?;
+
+    // no limit returns everything, as the endpoint did before pagination
+    values.push(Value::Integer(query.limit.map(i64::from).unwrap_or(-1)));
+    values.push(Value::Integer(query.offset.map(i64::from).unwrap_or(0)));
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT txid, amount, timestamp, type_name, is_settled, screening, block_height, block_hash, details,
+                direction, counterparty_address, fee
+         FROM transactions {}
+         ORDER BY timestamp DESC, txid LIMIT ? OFFSET ?",
+        where_clause
+    ))?;
+
+    let transactions = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), row_to_transaction)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok((transactions, total))
+}
+
+// the stored entry, the in-memory list only holds what the last Ark sync returned
+pub fn find_stored_transaction(txid: &str) -> Result<Option<TransactionResponse>> {
+    use rusqlite::OptionalExtension;
+
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let transaction = conn.query_row(
+        "SELECT txid, amount, timestamp, type_name, is_settled, screening, block_height, block_hash, details,
+                direction, counterparty_address, fee
+         FROM transactions WHERE txid = ?",
+        [txid],
+        row_to_transaction,
+    ).optional()?;
+    Ok(transaction)
+}
+
+fn row_to_transaction(row: &rusqlite::Row) -> rusqlite::Result<TransactionResponse> {
+    let screening: Option<String> = row.get(5)?;
+    let txid: String = row.get(0)?;
+    let type_name: String = row.get(3)?;
+    let block_height: Option<u32> = row.get(6)?;
+    let details: Option<String> = row.get(8)?;
+    let amount: i64 = row.get(1)?;
+    let direction: Option<String> = row.get(9)?;
+    let kind = details.and_then(|details| serde_json::from_str(&details).ok())
+        .or_else(|| TransactionKind::from_name(&type_name))
+        .ok_or_else(|| rusqlite::Error::FromSqlConversionFailure(
+            3,
+            rusqlite::types::Type::Text,
+            format!("unknown transaction type {}", type_name).into(),
+        ))?;
+    let confirmations = confirmations::count(kind.name(), &txid, block_height);
+    let direction = direction.as_deref()
+        .and_then(TransactionDirection::from_name)
+        .unwrap_or_else(|| TransactionDirection::of(amount));
+    Ok(TransactionResponse {
+        direction,
+        counterparty_address: row.get(10)?,
+        fee: row.get::<_, Option<i64>>(11)?.map(|fee| fee as u64),
+        is_settled: row.get(4)?,
+        screening: screening.and_then(|s| serde_json::from_str(&s).ok()),
+        confirmations,
+        block_height,
+        block_hash: row.get(7)?,
+        ..TransactionResponse::new(txid, kind, amount, row.get(2)?)
+    })
+}
+
+// pulls Ark and on-chain history from the ASP and esplora and persists it
+pub async fn sync_transaction_history() -> Result<Vec<TransactionResponse>> {
     let mut all_transactions = Vec::new();
     
     // Ark related tx
     let grpc_client = APP_STATE.grpc_client.lock().await;
     match grpc_client.get_transaction_history().await {
         Ok(ark_history) => {
-            let ark_transactions = ark_history.into_iter().map(|(txid, amount, timestamp, type_name, is_settled)| {
+            let ark_transactions = ark_history.into_iter().map(|(txid, amount, timestamp, kind, is_settled)| {
                 TransactionResponse {
-                    txid,
-                    amount,
-                    timestamp,
-                    type_name,
                     is_settled: Some(is_settled),
+                    ..TransactionResponse::new(txid, kind, amount, timestamp)
                 }
             }).collect::<Vec<_>>();
+
+            for tx in &ark_transactions {
+                if let Err(e) = save_transaction_to_db(tx).await {
+                    tracing::error!("Failed to save transaction {} to database: {}", tx.txid, e);
+                }
+            }
             all_transactions.extend(ark_transactions);
         },
         Err(e) => {
@@ -47,33 +156,37 @@ pub async fn get_transaction_history() -> Result<Vec<TransactionResponse>> {
 }
 
 async fn get_onchain_transactions() -> Result<Vec<TransactionResponse>> {
-    let esplora_url = std::env::var("ESPLORA_URL")
-        .unwrap_or_else(|_| "http://localhost:3000".to_string());
-    let blockchain = Arc::new(crate::services::ark_grpc::EsploraBlockchain::new(&esplora_url)?);
-    
-    let address_str = crate::services::wallet::get_onchain_address().await?;
-    let address = bitcoin::Address::from_str(&address_str)?.assume_checked();
+    let blockchain = APP_STATE.blockchain.clone();
+    rescreen_pending(&blockchain).await?;
     
     let mut onchain_transactions = Vec::new();
-    let existing_txids: std::collections::HashSet<String> = {
+    let mut existing_txids: std::collections::HashSet<String> = {
         let app_transactions = APP_STATE.transactions.lock().await;
         app_transactions.iter().map(|tx| tx.txid.clone()).collect()
     };
     
-    let all_transactions = get_all_address_transactions(&blockchain, &address).await?;
+    let mut all_transactions = Vec::new();
+    for address in crate::services::wallet::get_onchain_addresses().await? {
+        all_transactions.extend(get_all_address_transactions(&blockchain, &address).await?);
+    }
     
     for (txid, net_amount, timestamp) in all_transactions {
-        if existing_txids.contains(&txid) {
+        if !existing_txids.insert(txid.clone()) {
             continue;
         }
         
         if net_amount != 0 {
+            // incoming deposits go through the screening hook before they count towards the balance
+            let screening = if net_amount > 0 {
+                crate::services::screening::screen_or_hold(&blockchain, &txid, net_amount as u64).await
+            } else {
+                None
+            };
+
             let tx_response = TransactionResponse {
-                txid: txid.clone(),
-                amount: net_amount,
-                timestamp,
-                type_name: "OnChain".to_string(),
                 is_settled: Some(true),
+                screening,
+                ..TransactionResponse::new(txid.clone(), TransactionKind::OnChain, net_amount, timestamp)
             };
             
             onchain_transactions.push(tx_response);
@@ -95,8 +208,57 @@ async fn get_onchain_transactions() -> Result<Vec<TransactionResponse>> {
     Ok(onchain_transactions)
 }
 
+// deposits whose screening failed before, they stay held back until it succeeds
+async fn rescreen_pending(blockchain: &crate::services::chain::ChainBackend) -> Result<()> {
+    use crate::services::screening::ScreeningAction;
+
+    let pending: Vec<TransactionResponse> = APP_STATE.transactions.lock().await.iter()
+        .filter(|tx| matches!(&tx.screening, Some(result) if result.action == ScreeningAction::Pending))
+        .cloned()
+        .collect();
+    let mut rescreened = false;
+    for mut tx in pending {
+        match crate::services::screening::screen_deposit(blockchain, &tx.txid, tx.amount.unsigned_abs()).await {
+            Ok(screening) => {
+                tracing::info!("Deposit {} screened after an earlier failure", tx.txid);
+                tx.screening = screening;
+                record_transaction(&tx).await?;
+                rescreened = true;
+            },
+            Err(e) => tracing::warn!("Deposit {} still can't be screened: {}", tx.txid, e),
+        }
+    }
+    if rescreened {
+        APP_STATE.recalculate_balance().await?;
+    }
+    Ok(())
+}
+
+// lets an operator count a quarantined or unscreened deposit after reviewing it.
+// It stays flagged with the reason it was held, None if no deposit with that txid is held back
+pub async fn release_deposit(txid: &str) -> Result<Option<TransactionResponse>> {
+    use crate::services::screening::{ScreeningAction, ScreeningResult};
+
+    let held = APP_STATE.transactions.lock().await.iter()
+        .find(|tx| tx.txid == txid && tx.is_quarantined())
+        .cloned();
+    let Some(mut tx) = held else {
+        return Ok(None);
+    };
+
+    let reason = tx.screening.as_ref().map(|result| result.reason.clone()).unwrap_or_default();
+    tx.screening = Some(ScreeningResult {
+        action: ScreeningAction::Flag,
+        reason: format!("Released by the operator, was held for: {}", reason),
+    });
+    record_transaction(&tx).await?;
+    APP_STATE.recalculate_balance().await?;
+    tracing::info!("Released held deposit {}", txid);
+    Ok(Some(tx))
+}
+
 async fn get_all_address_transactions(
-    blockchain: &Arc<crate::services::ark_grpc::EsploraBlockchain>,
+    blockchain: &Arc<crate::services::chain::ChainBackend>,
     address: &bitcoin::Address,
 ) -> Result<Vec<(String, i64, i64)>> {
     let script_pubkey = address.script_pubkey();
@@ -138,19 +300,87 @@ async fn get_all_address_transactions(
     Ok(result)
 }
 
-pub async fn get_transaction(txid: String) -> Result<TransactionResponse> {
-    // tx from the app state
-    let transactions = APP_STATE.transactions.lock().await;
-    
-    // find tx with given txid
-    let transaction = transactions.iter()
-        .find(|tx| tx.txid == txid)
-        .cloned()
-        .ok_or_else(|| anyhow::anyhow!("Transaction not found: {}", txid))?;
-    
+// tx from the database, which keeps the details of its kind the Ark sync drops, or the app state
+pub async fn find_transaction(txid: &str) -> Result<TransactionResponse> {
+    let stored = find_stored_transaction(txid)?;
+    let mut transaction = match stored {
+        Some(tx) => tx,
+        None => APP_STATE.transactions.lock().await.iter()
+            .find(|tx| tx.txid == txid)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Transaction not found: {}", txid))?,
+    };
+    transaction.confirmations = confirmations::count(transaction.kind.name(), &transaction.txid, transaction.block_height);
     Ok(transaction)
 }
 
+// the history entry plus, for on-chain txs, the full transaction with its fee and raw hex
+pub async fn get_transaction(txid: String) -> Result<TransactionDetail> {
+    let transaction = find_transaction(&txid).await?;
+
+    let raw_hex = if confirmations::is_on_chain(transaction.kind.name()) {
+        raw_transaction(&txid).await?
+    } else {
+        None
+    };
+    let decoded = match raw_hex.as_deref().map(crate::services::raw_tx::parse) {
+        Some(Ok(tx)) => Some(crate::services::raw_tx::decode(&tx).await),
+        Some(Err(e)) => {
+            tracing::warn!("Stored raw transaction {} is unreadable: {}", txid, e);
+            None
+        },
+        None => None,
+    };
+
+    Ok(TransactionDetail { transaction, raw_hex, decoded })
+}
+
+// the stored hex, fetched from the chain backend and kept on first use
+async fn raw_transaction(txid: &str) -> Result<Option<String>> {
+    let stored: Option<String> = {
+        use rusqlite::OptionalExtension;
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.query_row("SELECT raw_tx FROM transactions WHERE txid = ?", [txid], |row| row.get(0))
+            .optional()?
+            .flatten()
+    };
+    if stored.is_some() {
+        return Ok(stored);
+    }
+
+    let Ok(parsed) = bitcoin::Txid::from_str(txid) else {
+        return Ok(None);
+    };
+    let tx = match APP_STATE.blockchain.find_tx(&parsed).await {
+        Ok(Some(tx)) => tx,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            tracing::warn!("Failed to fetch transaction {}: {}", txid, e);
+            return Ok(None);
+        }
+    };
+    let hex = bitcoin::consensus::encode::serialize_hex(&tx);
+    APP_STATE.db_manager.get_conn()?
+        .execute("UPDATE transactions SET raw_tx = ? WHERE txid = ?", rusqlite::params![hex, txid])?;
+    Ok(Some(hex))
+}
+
+// the fee of an on-chain tx we just broadcast, from its inputs as the chain backend reports them
+pub async fn onchain_fee(txid: &str) -> Option<u64> {
+    if crate::services::simulation::is_enabled() {
+        return None;
+    }
+    let parsed = bitcoin::Txid::from_str(txid).ok()?;
+    match APP_STATE.blockchain.find_tx(&parsed).await {
+        Ok(Some(tx)) => crate::services::raw_tx::decode(&tx).await.fee,
+        Ok(None) => None,
+        Err(e) => {
+            tracing::debug!("Failed to look up the fee of {}: {}", txid, e);
+            None
+        }
+    }
+}
+
 pub async fn participate_in_round() -> Result<Option<String>> {
     tracing::info!("Starting round participation");
     let grpc_client = APP_STATE.grpc_client.lock().await;
@@ -168,6 +398,18 @@ pub async fn participate_in_round() -> Result<Option<String>> {
         
         // try to board
         tracing::info!("Attempting to board funds");
+        APP_STATE.events.publish(crate::services::events::WalletEvent::RoundStarted);
+        // subscribe before boarding so the finalization event can't be missed
+        let mut round_events = APP_STATE.events.subscribe();
+        // what goes into the round, for the round history
+        let ark_wallet = grpc_client.get_ark_wallet();
+        let round_inputs = match crate::services::rounds::RoundInputs::snapshot(&client, ark_wallet.as_deref()).await {
+            Ok(inputs) => Some(inputs),
+            Err(e) => {
+                tracing::warn!("Round will be missing from the round history: {}", e);
+                None
+            }
+        };
         match client.board(&mut rng).await {
             Ok(_) => {
                 tracing::info!("Successfully participated in round");
@@ -184,16 +426,49 @@ pub async fn participate_in_round() -> Result<Option<String>> {
                     Err(e) => tracing::warn!("Failed to recalculate balance after round participation: {}", e),
                 }
                 
-                // return a placeholder txid for now
-                let txid = format!("round_{}", chrono::Utc::now().timestamp());
+                // the round's commitment tx is the one that created our new VTXOs
+                let commitment_txids = match &round_inputs {
+                    Some(inputs) => inputs.commitment_txids(&client).await.unwrap_or_else(|e| {
+                        tracing::warn!("Failed to look up the round's commitment tx: {}", e);
+                        std::collections::HashSet::new()
+                    }),
+                    None => std::collections::HashSet::new(),
+                };
+                let (round_id, txid) = match crate::services::rounds::wait_for_round_txid(
+                    &mut round_events,
+                    &commitment_txids,
+                    std::time::Duration::from_secs(10),
+                ).await {
+                    Some((round_id, txid)) => (Some(round_id), txid),
+                    None => {
+                        // no finalization on the event stream matched our round, keep a local id so history still records it
+                        tracing::warn!("Round txid not announced by the ASP, recording a local placeholder");
+                        (None, format!("round_{}", chrono::Utc::now().timestamp()))
+                    }
+                };
                 
+                let mut round_fee = None;
+                if let Some(inputs) = round_inputs {
+                    match inputs.finish(&client, &txid).await {
+                        Ok(round) => {
+                            tracing::info!("Round {} renewed {} VTXOs for {} sats", txid, round.vtxos_renewed, round.fee);
+                            round_fee = Some(round.fee);
+                        },
+                        Err(e) => tracing::warn!("Failed to record round {} in the round history: {}", txid, e),
+                    }
+                }
+
                 // create a tx record
+                // rounds don't change the total balance
                 let tx = crate::models::wallet::TransactionResponse {
-                    txid: txid.clone(),
-                    amount: 0, // rounds don't change the total balance
-                    timestamp: chrono::Utc::now().timestamp(),
-                    type_name: "Round".to_string(),
+                    fee: round_fee,
                     is_settled: Some(true),
+                    ..crate::models::wallet::TransactionResponse::new(
+                        txid.clone(),
+                        TransactionKind::Round { round_id },
+                        0,
+                        chrono::Utc::now().timestamp(),
+                    )
                 };
                 
                 // save to in-memory state
@@ -206,10 +481,19 @@ pub async fn participate_in_round() -> Result<Option<String>> {
                     Ok(_) => tracing::info!("Successfully saved round transaction to database"),
                     Err(e) => tracing::error!("Error saving transaction to database: {}", e),
                 }
+
+                APP_STATE.events.publish(crate::services::events::WalletEvent::RoundCompleted {
+                    txid: txid.clone(),
+                });
                 
                 Ok(Some(txid))
             },
             Err(e) => {
+                APP_STATE.events.publish(crate::services::events::WalletEvent::RoundFailed {
+                    round_id: None,
+                    reason: e.to_string(),
+                });
+
                 if e.to_string().contains("No boarding outputs") && e.to_string().contains("No VTXOs") {
                     tracing::info!("No outputs to include in round");
                     Ok(None)
@@ -221,10 +505,59 @@ pub async fn participate_in_round() -> Result<Option<String>> {
             }
         }
     } 
+    else if crate::services::simulation::fallback_enabled() {
+        drop(grpc_client);
+        crate::services::simulation::settle_round().await
+    }
     else {
         tracing::error!("Ark client not available");
-        Err(anyhow::anyhow!("Ark client not available"))
+        Err(crate::services::ark_grpc::ClientUnavailable.into())
+    }
+}
+
+// redeems VTXOs to an on-chain output in the next round, the ASP co-signs so no exit delay applies
+pub async fn collaborative_redeem(address: bitcoin::Address, amount: bitcoin::Amount) -> Result<String> {
+    let client = {
+        let grpc_client = APP_STATE.grpc_client.lock().await;
+        let client_opt = grpc_client.get_ark_client();
+        client_opt.as_ref().map(Arc::clone)
+    };
+    let client = client.ok_or(crate::services::ark_grpc::ClientUnavailable)?;
+
+    tracing::info!("Redeeming {} to {} in the next round", amount, address);
+    APP_STATE.events.publish(crate::services::events::WalletEvent::RoundStarted);
+    let mut rng = bip39::rand::rngs::OsRng;
+    let counterparty = address.to_string();
+    let txid = match client.collaborative_redeem(&mut rng, address, amount).await {
+        Ok(txid) => txid.to_string(),
+        Err(e) => {
+            APP_STATE.events.publish(crate::services::events::WalletEvent::RoundFailed {
+                round_id: None,
+                reason: e.to_string(),
+            });
+            return Err(anyhow::anyhow!("Collaborative redeem failed: {}", e));
+        }
+    };
+
+    if let Err(e) = APP_STATE.grpc_client.lock().await.update_app_state().await {
+        tracing::warn!("Failed to update app state after redeem: {}", e);
     }
+    // no fee, the ASP's round fee isn't reported per output
+    record_transaction(&TransactionResponse {
+        counterparty_address: Some(counterparty),
+        is_settled: Some(false),
+        ..TransactionResponse::new(
+            txid.clone(),
+            TransactionKind::Redeem,
+            -(amount.to_sat() as i64),
+            chrono::Utc::now().timestamp(),
+        )
+    }).await?;
+
+    APP_STATE.events.publish(crate::services::events::WalletEvent::RoundCompleted {
+        txid: txid.clone(),
+    });
+    Ok(txid)
 }
 
 pub async fn create_redeem_transaction(
@@ -247,17 +580,13 @@ pub async fn create_redeem_transaction(
     let txid = format!("redeem_{}", chrono::Utc::now().timestamp());
     
     // Add tx to history
-    let mut transactions = APP_STATE.transactions.lock().await;
     let tx = TransactionResponse {
-        txid: txid.clone(),
-        amount: -(amount as i64), // -ve amount for outgoing tx
-        timestamp: chrono::Utc::now().timestamp(),
-        type_name: "Redeem".to_string(),
+        counterparty_address: Some(recipient_address.clone()),
         is_settled: Some(false), // initially pending
+        // -ve amount for outgoing tx
+        ..TransactionResponse::new(txid.clone(), TransactionKind::Redeem, -(amount as i64), chrono::Utc::now().timestamp())
     };
-    transactions.push(tx.clone());
-    
-    drop(transactions);
+    record_transaction(&tx).await?;
     
     APP_STATE.recalculate_balance().await?; // for consistency
     
@@ -270,46 +599,85 @@ pub async fn receive_redeem_transaction(
     txid: String,
 ) -> Result<TransactionResponse> {
     // add the tx to history
-    let mut transactions = APP_STATE.transactions.lock().await;
     let tx = TransactionResponse {
-        txid: txid.clone(),
-        amount: amount as i64, // +ve for incoming tx
-        timestamp: chrono::Utc::now().timestamp(),
-        type_name: "Redeem".to_string(),
+        counterparty_address: Some(sender_address.clone()),
         is_settled: Some(false), // pending initially
+        // +ve for incoming tx
+        ..TransactionResponse::new(txid.clone(), TransactionKind::Redeem, amount as i64, chrono::Utc::now().timestamp())
     };
-    transactions.push(tx.clone());
-    
-    drop(transactions);
+    record_transaction(&tx).await?;
     
     APP_STATE.recalculate_balance().await?; // for consistency
     
     Ok(tx)
 }
 
-pub async fn unilateral_exit(vtxo_txid: String) -> Result<TransactionResponse> {
-    let grpc_client = APP_STATE.grpc_client.lock().await;
-    
-    match grpc_client.unilateral_exit(vtxo_txid).await {
-        Ok(tx) => Ok(tx),
-        Err(e) => Err(anyhow::anyhow!("Failed to perform unilateral exit: {}", e))
+pub async fn unilateral_exit(vtxo_txid: String) -> Result<crate::services::exit::PendingExit> {
+    let exit = APP_STATE.exits.start_exit(&vtxo_txid).await
+        .map_err(|e| anyhow::anyhow!("Failed to perform unilateral exit: {}", e))?;
+
+    record_transaction(&crate::services::exit::exit_transaction(&exit)).await?;
+    Ok(exit)
+}
+
+// adds or updates a tx in memory and in the database so it survives restarts
+pub async fn record_transaction(tx: &TransactionResponse) -> Result<()> {
+    {
+        let mut transactions = APP_STATE.transactions.lock().await;
+        match transactions.iter_mut().find(|existing| existing.txid == tx.txid) {
+            Some(existing) => {
+                let block_height = tx.block_height.or(existing.block_height);
+                let block_hash = tx.block_hash.clone().or(existing.block_hash.take());
+                *existing = TransactionResponse { block_height, block_hash, ..tx.clone() };
+            },
+            None => transactions.push(tx.clone()),
+        }
     }
+
+    save_transaction_to_db(tx).await
 }
 
 pub async fn save_transaction_to_db(tx: &crate::models::wallet::TransactionResponse) -> Result<()> {
+    if crate::services::simulation::is_fallback_entry(&tx.txid) {
+        return Ok(());
+    }
     let conn = APP_STATE.db_manager.get_conn()?;
     
+    // the block a tx confirmed in is only known to the confirmation tracker, so a resync keeps it.
+    // the ASP's history doesn't carry a kind's details, counterparty or fee either, those recorded locally are kept the same way.
+    // only the sender knows a tx paid ourselves, a resync would call it outgoing
     conn.execute(
-        "INSERT OR REPLACE INTO transactions (
-            txid, amount, timestamp, type_name, is_settled, raw_tx
-        ) VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO transactions (
+            txid, amount, timestamp, type_name, is_settled, raw_tx, screening, block_height, block_hash, details,
+            direction, counterparty_address, fee
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(txid) DO UPDATE SET
+            amount = excluded.amount,
+            timestamp = excluded.timestamp,
+            type_name = excluded.type_name,
+            details = COALESCE(excluded.details, transactions.details),
+            direction = CASE WHEN transactions.direction = 'internal' THEN 'internal' ELSE excluded.direction END,
+            counterparty_address = COALESCE(excluded.counterparty_address, transactions.counterparty_address),
+            fee = COALESCE(excluded.fee, transactions.fee),
+            is_settled = excluded.is_settled,
+            raw_tx = COALESCE(excluded.raw_tx, transactions.raw_tx),
+            screening = excluded.screening,
+            block_height = COALESCE(excluded.block_height, transactions.block_height),
+            block_hash = COALESCE(excluded.block_hash, transactions.block_hash)",
         rusqlite::params![
             tx.txid,
             tx.amount,
             tx.timestamp,
-            tx.type_name,
+            tx.kind.name(),
             tx.is_settled,
             Option::<String>::None, // raw_tx (optional)
+            tx.screening.as_ref().map(|s| serde_json::to_string(s)).transpose()?,
+            tx.block_height,
+            tx.block_hash,
+            tx.kind.has_details().then(|| serde_json::to_string(&tx.kind)).transpose()?,
+            tx.direction.name(),
+            tx.counterparty_address,
+            tx.fee.map(|fee| fee as i64),
         ],
     )?;
     
@@ -4,31 +4,50 @@ use crate::services::APP_STATE;
 use crate::services::onchain::{OnChainPaymentService, FeeEstimator};
 use crate::services::onchain::fee_estimator::{FeePriority, FeeEstimates};
 use anyhow::{Result, Context};
+use ark_client::Blockchain;
 use ark_core::ArkAddress;
 use bitcoin::Amount;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use std::str::FromStr;
 
+static BALANCE_REFRESHING: AtomicBool = AtomicBool::new(false);
+
 pub async fn get_wallet_info() -> Result<WalletInfo> {
     let grpc_client = APP_STATE.grpc_client.lock().await;
     
-    let network = std::env::var("BITCOIN_NETWORK")
-        .unwrap_or_else(|_| "regtest".into());
-    let server_url = std::env::var("ARK_SERVER_URL")
-        .unwrap_or_else(|_| "http://localhost:7070".into());
+    let profile = crate::services::profiles::active();
 
     let connected = grpc_client.is_connected();
 
     let info = WalletInfo {
-        network,
-        server_url,
+        network: profile.network.to_string(),
+        server_url: profile.ark_server_url.clone(),
+        profile: profile.name.clone(),
         connected,
     };
     
     Ok(info)
 }
 
+// syncs with the ASP and recalculates in the background, at most one refresh at a time
+pub fn refresh_balance_in_background() {
+    if BALANCE_REFRESHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(async {
+        if let Err(e) = APP_STATE.grpc_client.lock().await.update_app_state().await {
+            tracing::warn!("Background balance sync failed: {}", e);
+        }
+        if let Err(e) = APP_STATE.recalculate_balance().await {
+            tracing::warn!("Background balance refresh failed: {}", e);
+        }
+        BALANCE_REFRESHING.store(false, Ordering::SeqCst);
+    });
+}
+
 pub async fn get_available_balance() -> Result<u64> {
     APP_STATE.recalculate_balance().await?;
 
@@ -45,6 +64,7 @@ pub async fn get_offchain_address() -> Result<AddressResponse> {
     
     match grpc_client.get_address().await {
         Ok(address) => Ok(AddressResponse { address }),
+        Err(e) if crate::services::ark_grpc::is_unavailable(&e) => Err(e),
         Err(e) => Err(anyhow::anyhow!("Failed to get offchain address: {}", e))
     }
 }
@@ -54,6 +74,7 @@ pub async fn get_boarding_address() -> Result<AddressResponse> {
     
     match grpc_client.get_boarding_address().await {
         Ok(address) => Ok(AddressResponse { address }),
+        Err(e) if crate::services::ark_grpc::is_unavailable(&e) => Err(e),
         Err(e) => Err(anyhow::anyhow!("Failed to get boarding address: {}", e))
     }
 }
@@ -73,11 +94,121 @@ pub async fn check_deposits() -> Result<serde_json::Value> {
             "message": "No deposits to process",
             "success": false
         })),
+        Err(e) if crate::services::ark_grpc::is_unavailable(&e) => Err(e),
         Err(e) => Err(anyhow::anyhow!("Failed to check deposits: {}", e))
     }
 }
 
+// unspent deposits on boarding addresses with confirmations and time left to board them
+pub async fn list_boarding_outputs() -> Result<Vec<BoardingOutputInfo>> {
+    use ark_client::wallet::BoardingWallet;
+
+    let ark_wallet = APP_STATE.grpc_client.lock().await.get_ark_wallet()
+        .ok_or_else(|| anyhow::anyhow!("Ark wallet not available"))?;
+    let boarding_outputs = ark_wallet.get_boarding_outputs()
+        .map_err(|e| anyhow::anyhow!("Failed to get boarding outputs: {}", e))?;
+    let boarding_exit_delay = APP_STATE.server_info.get()
+        .map(|c| c.info.boarding_exit_delay)
+        .ok_or_else(|| anyhow::anyhow!("Unknown boarding exit delay, server info not loaded"))?;
+
+    let tip = APP_STATE.blockchain.get_height().await?;
+    let now = chrono::Utc::now().timestamp();
+
+    let mut outputs = Vec::new();
+    for boarding_output in boarding_outputs {
+        let utxos = APP_STATE.blockchain.find_outpoints(boarding_output.address()).await
+            .map_err(|e| anyhow::anyhow!("Failed to look up boarding outputs: {}", e))?;
+
+        for utxo in utxos.into_iter().filter(|utxo| !utxo.is_spent) {
+            let confirmation = APP_STATE.blockchain.get_tx_confirmation(&utxo.outpoint.txid).await?;
+            let mut info = BoardingOutputInfo {
+                outpoint: utxo.outpoint.to_string(),
+                address: boarding_output.address().to_string(),
+                amount: utxo.amount.to_sat(),
+                confirmations: 0,
+                confirmed_at: None,
+                expires_at_height: None,
+                expires_at: None,
+                seconds_until_expiry: None,
+            };
+
+            // the countdown only starts once the deposit confirms
+            if let Some((height, time)) = confirmation {
+                let (expires_at_height, expires_at) = crate::services::exit::claimable_after(boarding_exit_delay, height, time);
+                let expires_at = expires_at.or_else(|| expires_at_height.map(|target| {
+                    now + (target as i64 - tip as i64) * crate::services::exit::BLOCK_INTERVAL_SECS as i64
+                }));
+
+                info.confirmations = tip.saturating_sub(height) + 1;
+                info.confirmed_at = Some(time as i64);
+                info.expires_at_height = expires_at_height;
+                info.expires_at = expires_at;
+                info.seconds_until_expiry = expires_at.map(|at| (at - now).max(0));
+            }
+            outputs.push(info);
+        }
+    }
+
+    Ok(outputs)
+}
+
+pub async fn preview_onchain_withdrawal(address: &str, amount: u64) -> Result<WithdrawalPreview> {
+    let network = crate::services::profiles::active().network;
+    bitcoin::Address::from_str(address)?
+        .require_network(network)?;
+
+    let available = get_available_balance().await?;
+    if amount > available {
+        return Err(anyhow::anyhow!("Insufficient off-chain balance: {} available, {} requested", available, amount));
+    }
+
+    // best effort, the comparison is informational
+    let exit_plan = APP_STATE.exits.plan_exit_all().await
+        .map_err(|e| tracing::debug!("No unilateral exit estimate for withdrawal preview: {}", e))
+        .ok();
+
+    Ok(WithdrawalPreview {
+        address: address.to_string(),
+        amount,
+        available,
+        change: available - amount,
+        fee: 0,
+        unilateral_exit_fees: exit_plan.as_ref().map(|plan| plan.estimated_fees),
+        unilateral_exit_wait_secs: exit_plan.as_ref().map(|plan| plan.expected_wait_secs),
+    })
+}
+
+// cooperative alternative to a unilateral exit, returns the round txid
+pub async fn withdraw_onchain(address: String, amount: u64) -> Result<SendResponse> {
+    let _spend = APP_STATE.spending.authorize(&address, amount).await?;
+    preview_onchain_withdrawal(&address, amount).await?;
+    let network = crate::services::profiles::active().network;
+    let to_address = bitcoin::Address::from_str(&address)?
+        .require_network(network)?;
+
+    let txid = crate::services::transactions::collaborative_redeem(to_address, Amount::from_sat(amount)).await?;
+    APP_STATE.recalculate_balance().await?;
+    Ok(SendResponse { txid, inputs: None })
+}
+
+pub async fn withdrawal_status(txid: &str) -> Result<WithdrawalStatus> {
+    let parsed = bitcoin::Txid::from_str(txid)?;
+    let confirmation = APP_STATE.blockchain.get_tx_confirmation(&parsed).await?;
+    let confirmations = match confirmation {
+        Some((height, _)) => APP_STATE.blockchain.get_height().await?.saturating_sub(height) + 1,
+        None => 0,
+    };
+
+    Ok(WithdrawalStatus {
+        txid: txid.to_string(),
+        confirmed: confirmation.is_some(),
+        confirmations,
+        confirmed_at: confirmation.map(|(_, time)| time as i64),
+    })
+}
+
 pub async fn send_vtxo(address: String, amount: u64) -> Result<SendResponse> {
+    let _spend = APP_STATE.spending.authorize(&address, amount).await?;
     let available_balance = get_available_balance().await?;
     if available_balance < amount {
         return Err(anyhow::anyhow!(
@@ -95,17 +226,16 @@ pub async fn send_vtxo(address: String, amount: u64) -> Result<SendResponse> {
         Ok(ark_address) => {
             tracing::info!("Successfully parsed Ark address");
             
-            match grpc_client.send_vtxo(address, amount).await {
-                Ok(txid) => {
+            match grpc_client.send_vtxo(address.clone(), amount).await {
+                Ok((txid, inputs)) => {
                     tracing::info!("Successfully sent VTXO with txid: {}", txid);
                     
                     // create tx record
+                    // no fee off-chain, the ASP charges when the VTXOs next settle
                     let tx = TransactionResponse {
-                        txid: txid.clone(),
-                        amount: -(amount as i64),
-                        timestamp: chrono::Utc::now().timestamp(),
-                        type_name: "Redeem".to_string(),
+                        counterparty_address: Some(address.clone()),
                         is_settled: Some(false),
+                        ..TransactionResponse::new(txid.clone(), TransactionKind::Redeem, -(amount as i64), chrono::Utc::now().timestamp())
                     };
                     
                     // save to in-memory state
@@ -121,7 +251,7 @@ pub async fn send_vtxo(address: String, amount: u64) -> Result<SendResponse> {
                     // recalculate balance
                     APP_STATE.recalculate_balance().await?;
                     
-                    Ok(SendResponse { txid })
+                    Ok(SendResponse { txid, inputs: Some(inputs) })
                 },
                 Err(e) => {
                     tracing::error!("Failed to send VTXO: {}", e);
@@ -136,17 +266,19 @@ pub async fn send_vtxo(address: String, amount: u64) -> Result<SendResponse> {
     }
 }
 
+// incoming VTXOs come from the ASP sync, recording one by hand only makes sense when simulating
 pub async fn receive_vtxo(from_address: String, amount: u64) -> Result<TransactionResponse> {
-    // unique tx ID
-    let txid = format!("rx_{}_{}", chrono::Utc::now().timestamp(), rand::random::<u32>());
+    if !crate::services::simulation::is_enabled() {
+        return Err(anyhow::anyhow!("Receiving by hand is only available in simulation mode"));
+    }
+    let txid = crate::services::simulation::txid("receive", &format!("{}:{}", from_address, amount));
     
     // add tx to the history
     let tx = TransactionResponse {
-        txid: txid.clone(),
-        amount: amount as i64, // +ve amount for incoming tx
-        timestamp: chrono::Utc::now().timestamp(),
-        type_name: "Receive".to_string(),
+        counterparty_address: Some(from_address.clone()),
         is_settled: Some(false), // initially pending
+        // +ve amount for incoming tx
+        ..TransactionResponse::new(txid.clone(), TransactionKind::Receive, amount as i64, chrono::Utc::now().timestamp())
     };
     
     // save to in-memory state
@@ -169,12 +301,7 @@ pub async fn receive_vtxo(from_address: String, amount: u64) -> Result<Transacti
 pub async fn get_onchain_address() -> Result<String> {
     let (keypair, _) = APP_STATE.key_manager.load_or_create_wallet()?;
     
-    let network = match std::env::var("BITCOIN_NETWORK").unwrap_or_else(|_| "regtest".to_string()).as_str() {
-        "mainnet" => bitcoin::Network::Bitcoin,
-        "testnet" => bitcoin::Network::Testnet,
-        "signet" => bitcoin::Network::Signet,
-        _ => bitcoin::Network::Regtest,
-    };
+    let network = crate::services::profiles::active().network;
 
     let pubkey = keypair.public_key();
     let pubkey_bytes = pubkey.serialize();
@@ -185,6 +312,87 @@ pub async fn get_onchain_address() -> Result<String> {
     Ok(address.to_string())
 }
 
+// key-path only P2TR address for the same key, spent with a tweaked Schnorr signature
+pub async fn get_taproot_address() -> Result<String> {
+    let (keypair, _) = APP_STATE.key_manager.load_or_create_wallet()?;
+
+    let network = crate::services::profiles::active().network;
+    let secp = bitcoin::secp256k1::Secp256k1::new();
+    let (internal_key, _) = keypair.x_only_public_key();
+    let address = bitcoin::Address::p2tr(&secp, internal_key, None, network);
+
+    Ok(address.to_string())
+}
+
+// reruns gap-limit discovery, for seeds whose history outgrew the last scan
+pub async fn rescan_addresses() -> Result<crate::services::onchain::keychain::DiscoverySummary> {
+    let summary = crate::services::onchain::keychain::discover(APP_STATE.config.address_gap_limit).await?;
+    APP_STATE.recalculate_balance().await?;
+    Ok(summary)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OffchainRecoverySummary {
+    pub vtxos: usize,
+    pub offchain_balance: u64,
+    pub transactions: usize,
+    pub transactions_restored: usize, // missing from the database before the scan
+    pub boarding_address: Option<String>,
+}
+
+// rebuilds off-chain state from the ASP for a wallet whose database was lost but whose mnemonic wasn't,
+// the ASP indexes VTXOs by the wallet's key so nothing here depends on what was cached locally
+pub async fn recover_offchain() -> Result<OffchainRecoverySummary> {
+    let grpc_client = APP_STATE.grpc_client.lock().await;
+    let client = grpc_client.get_ark_client().as_ref().map(Arc::clone)
+        .ok_or(crate::services::ark_grpc::ClientUnavailable)?;
+
+    let vtxos = client.spendable_vtxos().await
+        .map_err(|e| anyhow::anyhow!("Failed to list VTXOs from the Ark server: {}", e))?;
+    let (count, amount) = vtxos.iter()
+        .flat_map(|(outpoints, _)| outpoints.iter())
+        .fold((0, 0), |(count, amount), outpoint| (count + 1, amount + outpoint.amount.to_sat()));
+
+    // replaces the in-memory history with the ASP's, then writes back whatever the database lost
+    grpc_client.update_app_state().await?;
+    let history = APP_STATE.transactions.lock().await.clone();
+    let mut restored = 0;
+    for tx in &history {
+        if crate::services::transactions::find_stored_transaction(&tx.txid)?.is_none() {
+            restored += 1;
+        }
+        crate::services::transactions::save_transaction_to_db(tx).await?;
+    }
+
+    // registers the boarding output so deposits that were never boarded show up again
+    let boarding_address = match grpc_client.get_boarding_address().await {
+        Ok(address) => Some(address),
+        Err(e) => {
+            tracing::warn!("Boarding address not restored: {}", e);
+            None
+        }
+    };
+    drop(grpc_client);
+
+    APP_STATE.recalculate_balance().await?;
+    tracing::info!("Recovered {} VTXOs ({} sats) and {} missing transactions from the Ark server", count, amount, restored);
+    Ok(OffchainRecoverySummary {
+        vtxos: count,
+        offchain_balance: amount,
+        transactions: history.len(),
+        transactions_restored: restored,
+        boarding_address,
+    })
+}
+
+// every on-chain address the wallet owns, receive and issued change, scanned for UTXOs and history
+pub async fn get_onchain_addresses() -> Result<Vec<bitcoin::Address>> {
+    Ok(crate::services::onchain::keychain::wallet_keys()?
+        .into_iter()
+        .map(|key| key.address)
+        .collect())
+}
+
 pub async fn debug_vtxos() -> Result<serde_json::Value> {
     let grpc_client = APP_STATE.grpc_client.lock().await;
     
@@ -231,8 +439,7 @@ pub async fn debug_vtxos() -> Result<serde_json::Value> {
 
 
 pub async fn get_onchain_balance() -> Result<u64> {
-    let esplora_url = std::env::var("ESPLORA_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
-    let blockchain = Arc::new(crate::services::ark_grpc::EsploraBlockchain::new(&esplora_url)?);
+    let blockchain = APP_STATE.blockchain.clone();
     
     let payment_service = OnChainPaymentService::new(blockchain);
     let balance = payment_service.get_balance().await?;
@@ -241,9 +448,7 @@ pub async fn get_onchain_balance() -> Result<u64> {
 }
 
 pub async fn get_detailed_fee_estimates() -> Result<FeeEstimates> {
-    let esplora_url = std::env::var("ESPLORA_URL")
-        .unwrap_or_else(|_| "http://localhost:3000".to_string());
-    let blockchain = Arc::new(crate::services::ark_grpc::EsploraBlockchain::new(&esplora_url)?);
+    let blockchain = APP_STATE.blockchain.clone();
     
     let fee_estimator = FeeEstimator::new(blockchain);
     fee_estimator.get_fee_estimates().await
@@ -253,43 +458,37 @@ pub async fn send_onchain_payment_with_fee_priority(
     address: String,
     amount: u64,
     priority: FeePriority,
+    fee_rate_sat_vb: Option<u64>,
 ) -> Result<SendResponse> {
+    let _spend = APP_STATE.spending.authorize(&address, amount).await?;
     let bitcoin_address = bitcoin::Address::from_str(&address)?
         .assume_checked();
 
-    let esplora_url = std::env::var("ESPLORA_URL")
-        .unwrap_or_else(|_| "http://localhost:3000".to_string());
-    let blockchain = Arc::new(crate::services::ark_grpc::EsploraBlockchain::new(&esplora_url)?);
+    let blockchain = APP_STATE.blockchain.clone();
     
     let payment_service = OnChainPaymentService::new(blockchain);
     
-    // fee rate for the selected priority
-    let fee_rate = payment_service.fee_estimator
-        .estimate_fee_for_priority(priority)
-        .await?;
-    
-    tracing::info!(
-        "Sending {} sats to {} with {:?} priority ({} sat/vB)",
-        amount, address, priority, fee_rate.to_sat_per_vb_ceil()
-    );
-    
     let amount = bitcoin::Amount::from_sat(amount);
-    let txid = payment_service.transaction_builder
-        .build_and_broadcast(
-            payment_service.utxo_manager.get_spendable_utxos().await?,
-            bitcoin_address,
-            amount,
-            fee_rate
-        )
-        .await?;
+    let txid = if crate::services::simulation::is_enabled() {
+        crate::services::simulation::txid("onchain", &format!("{}:{}", address, amount.to_sat()))
+    } else {
+        payment_service
+            .send_payment(bitcoin_address, amount, fee_rate_sat_vb, priority)
+            .await?
+            .to_string()
+    };
     
     // record tx
     let tx = TransactionResponse {
-        txid: txid.to_string(),
-        amount: -(amount.to_sat() as i64),
-        timestamp: chrono::Utc::now().timestamp(),
-        type_name: "OnChain".to_string(),
+        counterparty_address: Some(address.clone()),
+        fee: crate::services::transactions::onchain_fee(&txid).await,
         is_settled: Some(false),
+        ..TransactionResponse::new(
+            txid.clone(),
+            TransactionKind::OnChain,
+            -(amount.to_sat() as i64),
+            chrono::Utc::now().timestamp(),
+        )
     };
     
     let mut transactions = APP_STATE.transactions.lock().await;
@@ -300,7 +499,7 @@ pub async fn send_onchain_payment_with_fee_priority(
         tracing::error!("Error saving transaction to database: {}", e);
     }
     
-    Ok(SendResponse { txid: txid.to_string() })
+    Ok(SendResponse { txid, inputs: None })
 }
 
 
@@ -311,9 +510,7 @@ pub async fn estimate_onchain_fee_detailed(
     let bitcoin_address = bitcoin::Address::from_str(&address)?
         .assume_checked();
     
-    let esplora_url = std::env::var("ESPLORA_URL")
-        .unwrap_or_else(|_| "http://localhost:3000".to_string());
-    let blockchain = Arc::new(crate::services::ark_grpc::EsploraBlockchain::new(&esplora_url)?);
+    let blockchain = APP_STATE.blockchain.clone();
     
     let payment_service = OnChainPaymentService::new(blockchain);
     let fee_estimator = &payment_service.fee_estimator;
@@ -351,4 +548,401 @@ pub async fn estimate_onchain_fee_detailed(
         estimates,
         transaction_fees,
     })
-}
\ No newline at end of file
+}
+
+// replaces a stuck outgoing tx with one paying a higher fee rate
+pub async fn bump_fee(txid: String, request: BumpFeeRequest) -> Result<BumpFeeResponse> {
+    let original_txid = bitcoin::Txid::from_str(&txid)?;
+    if APP_STATE.blockchain.get_tx_confirmation(&original_txid).await?.is_some() {
+        return Err(anyhow::anyhow!("Transaction {} is already confirmed", txid));
+    }
+    let original = APP_STATE.blockchain.find_tx(&original_txid).await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch transaction: {}", e))?
+        .ok_or_else(|| anyhow::anyhow!("Transaction {} not found", txid))?;
+
+    let payment_service = OnChainPaymentService::new(APP_STATE.blockchain.clone());
+    let fee_rate = match request.fee_rate_sat_vb {
+        Some(rate) => payment_service.fee_estimator.check_fee_rate(rate).await?,
+        None => {
+            let priority = request.priority.unwrap_or_else(|| "fastest".to_string());
+            payment_service.fee_estimator.estimate_fee_for_priority(priority.into()).await?
+        }
+    };
+
+    let (replacement, fee) = payment_service.transaction_builder
+        .build_replacement(&original, fee_rate)
+        .await?;
+    APP_STATE.blockchain.broadcast(&replacement).await
+        .map_err(|e| anyhow::anyhow!("Failed to broadcast replacement: {}", e))?;
+    let replacement_txid = replacement.compute_txid().to_string();
+    tracing::info!("Replaced {} with {} paying {}", txid, replacement_txid, fee);
+
+    {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO tx_replacements (original_txid, replacement_txid, fee, created_at) VALUES (?, ?, ?, ?)",
+            rusqlite::params![txid, replacement_txid, fee.to_sat() as i64, chrono::Utc::now().timestamp()],
+        )?;
+    }
+
+    // the original stays in history but no longer counts toward the balance
+    let previous = crate::services::transactions::find_transaction(&txid).await.ok();
+    if let Some(mut previous) = previous.clone() {
+        previous.is_settled = None;
+        crate::services::transactions::record_transaction(&previous).await?;
+    }
+    crate::services::transactions::record_transaction(&TransactionResponse {
+        direction: previous.as_ref().map(|tx| tx.direction).unwrap_or(TransactionDirection::Outgoing),
+        counterparty_address: previous.as_ref().and_then(|tx| tx.counterparty_address.clone()),
+        fee: Some(fee.to_sat()),
+        is_settled: Some(false),
+        ..TransactionResponse::new(
+            replacement_txid.clone(),
+            previous.as_ref().map(|tx| tx.kind.clone()).unwrap_or(TransactionKind::OnChain),
+            previous.as_ref().map(|tx| tx.amount).unwrap_or_default(),
+            chrono::Utc::now().timestamp(),
+        )
+    }).await?;
+    APP_STATE.recalculate_balance().await?;
+
+    Ok(BumpFeeResponse {
+        txid: replacement_txid,
+        replaces: txid,
+        fee: fee.to_sat(),
+    })
+}
+
+// speeds up a stuck incoming tx by spending our output from it with a high fee child
+pub async fn cpfp(txid: String, request: CpfpRequest) -> Result<CpfpResponse> {
+    let parent_txid = bitcoin::Txid::from_str(&txid)?;
+    if APP_STATE.blockchain.get_tx_confirmation(&parent_txid).await?.is_some() {
+        return Err(anyhow::anyhow!("Transaction {} is already confirmed", txid));
+    }
+    let parent = APP_STATE.blockchain.find_tx(&parent_txid).await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch transaction: {}", e))?
+        .ok_or_else(|| anyhow::anyhow!("Transaction {} not found", txid))?;
+    let (parent_fee, parent_vsize) = APP_STATE.blockchain.get_tx_fee_and_vsize(&parent_txid).await?
+        .ok_or_else(|| anyhow::anyhow!("Transaction {} not found", txid))?;
+
+    let payment_service = OnChainPaymentService::new(APP_STATE.blockchain.clone());
+    let priority = FeePriority::for_target_blocks(request.target_blocks.unwrap_or(1));
+    let fee_rate = payment_service.fee_estimator.estimate_fee_for_priority(priority).await?;
+
+    let (child, fee) = payment_service.transaction_builder
+        .build_cpfp_child(&parent, Amount::from_sat(parent_fee), parent_vsize, fee_rate)
+        .await?;
+    APP_STATE.blockchain.broadcast(&child).await
+        .map_err(|e| anyhow::anyhow!("Failed to broadcast child transaction: {}", e))?;
+    let child_txid = child.compute_txid().to_string();
+    tracing::info!("Bumped {} with child {} paying {}", txid, child_txid, fee);
+
+    // pays ourselves, only the fee leaves the wallet
+    crate::services::transactions::record_transaction(&TransactionResponse {
+        direction: TransactionDirection::Internal,
+        fee: Some(fee.to_sat()),
+        is_settled: Some(false),
+        ..TransactionResponse::new(
+            child_txid.clone(),
+            TransactionKind::OnChain,
+            -(fee.to_sat() as i64),
+            chrono::Utc::now().timestamp(),
+        )
+    }).await?;
+
+    Ok(CpfpResponse {
+        txid: child_txid,
+        parent_txid: txid,
+        fee: fee.to_sat(),
+        package_fee_rate: fee_rate.to_sat_per_vb_ceil(),
+    })
+}
+
+// merges the smallest UTXOs into one at the slow fee rate to make later sends cheaper
+pub async fn consolidate_utxos(request: ConsolidateRequest) -> Result<ConsolidateResponse> {
+    let dust_threshold = Amount::from_sat(request.dust_threshold.unwrap_or(546));
+    let max_inputs = request.max_inputs.unwrap_or(50);
+
+    let payment_service = OnChainPaymentService::new(APP_STATE.blockchain.clone());
+    let fee_rate = payment_service.fee_estimator.estimate_fee_for_priority(FeePriority::Slow).await?;
+    if let Some(max_fee_rate) = request.max_fee_rate_sat_vb {
+        if fee_rate.to_sat_per_vb_ceil() > max_fee_rate {
+            return Err(anyhow::anyhow!(
+                "Mempool is busy, slow fee rate is {} sat/vB (limit {})",
+                fee_rate.to_sat_per_vb_ceil(), max_fee_rate
+            ));
+        }
+    }
+
+    // unconfirmed outputs could still be replaced, leave them alone
+    let mut utxos: Vec<_> = payment_service.utxo_manager.get_spendable_utxos().await?
+        .into_iter()
+        .filter(|utxo| utxo.confirmation_time.is_some() && utxo.amount >= dust_threshold)
+        .collect();
+    utxos.sort_by_key(|utxo| utxo.amount);
+    utxos.truncate(max_inputs);
+
+    let inputs = utxos.len();
+    let (tx, fee) = payment_service.transaction_builder.build_consolidation(utxos, fee_rate).await?;
+    APP_STATE.blockchain.broadcast(&tx).await
+        .map_err(|e| anyhow::anyhow!("Failed to broadcast consolidation: {}", e))?;
+    let txid = tx.compute_txid().to_string();
+    tracing::info!("Consolidated {} UTXOs in {} paying {}", inputs, txid, fee);
+
+    // pays ourselves, only the fee leaves the wallet
+    crate::services::transactions::record_transaction(&TransactionResponse {
+        direction: TransactionDirection::Internal,
+        fee: Some(fee.to_sat()),
+        is_settled: Some(false),
+        ..TransactionResponse::new(txid.clone(), TransactionKind::OnChain, -(fee.to_sat() as i64), chrono::Utc::now().timestamp())
+    }).await?;
+
+    Ok(ConsolidateResponse {
+        txid,
+        inputs,
+        amount: tx.output[0].value.to_sat(),
+        fee: fee.to_sat(),
+    })
+}
+
+// unsigned transaction for an external signer, nothing is signed or broadcast here
+pub async fn create_psbt(address: String, amount: u64, priority: FeePriority) -> Result<PsbtResponse> {
+    let network = crate::services::profiles::active().network;
+    let to_address = bitcoin::Address::from_str(&address)?
+        .require_network(network)?;
+
+    let payment_service = OnChainPaymentService::new(APP_STATE.blockchain.clone());
+    let fee_rate = payment_service.fee_estimator
+        .estimate_fee_for_priority(priority)
+        .await?;
+
+    let (psbt, fee) = payment_service.transaction_builder.build_psbt(
+        payment_service.utxo_manager.get_spendable_utxos().await?,
+        to_address,
+        Amount::from_sat(amount),
+        fee_rate,
+    )?;
+
+    Ok(PsbtResponse {
+        psbt: psbt.to_string(),
+        fee: Some(fee.to_sat()),
+        finalized: false,
+    })
+}
+
+// signs whatever inputs belong to the hot wallet keys. A signed PSBT can be broadcast anywhere,
+// past the spending policy and approvals, so signing is refused while either is configured
+pub async fn sign_psbt(psbt: String) -> Result<PsbtResponse> {
+    if APP_STATE.spending.policy().is_restricted() || APP_STATE.config.approvals.is_enabled() {
+        return Err(crate::services::spending_policy::PolicyViolation(
+            "PSBTs can't be signed while a spending policy or approvals are configured".to_string()
+        ).into());
+    }
+
+    let mut psbt = bitcoin::Psbt::from_str(&psbt)
+        .context("Invalid PSBT")?;
+
+    let payment_service = OnChainPaymentService::new(APP_STATE.blockchain.clone());
+    let finalized = payment_service.transaction_builder.sign_psbt(&mut psbt)?;
+
+    Ok(PsbtResponse {
+        psbt: psbt.to_string(),
+        fee: psbt.fee().ok().map(|fee| fee.to_sat()),
+        finalized,
+    })
+}
+
+// what broadcasting a signed PSBT would send, so it can be held for approval first
+pub fn psbt_outgoing(psbt: &str) -> Result<Vec<(String, u64)>> {
+    let (tx, _) = extract_signed_tx(psbt)?;
+    external_outputs(&tx)
+}
+
+pub async fn broadcast_psbt(psbt: String) -> Result<SendResponse> {
+    let (tx, fee) = extract_signed_tx(&psbt)?;
+    let external = external_outputs(&tx)?;
+    let sent: u64 = external.iter().map(|(_, amount)| amount).sum();
+    let _spend = APP_STATE.spending.authorize_outputs(&external).await?;
+
+    APP_STATE.blockchain.broadcast(&tx).await
+        .map_err(|e| anyhow::anyhow!("Failed to broadcast transaction: {}", e))?;
+    let txid = tx.compute_txid();
+    tracing::info!("Broadcast externally signed transaction {}", txid);
+
+    let network = crate::services::profiles::active().network;
+    let recipient = external.iter()
+        .find_map(|(destination, _)| bitcoin::Address::from_str(destination).ok()?.require_network(network).ok());
+
+    crate::services::transactions::record_transaction(&TransactionResponse {
+        counterparty_address: recipient.map(|address| address.to_string()),
+        fee,
+        is_settled: Some(false),
+        ..TransactionResponse::new(txid.to_string(), TransactionKind::OnChain, -(sent as i64), chrono::Utc::now().timestamp())
+    }).await?;
+
+    Ok(SendResponse { txid: txid.to_string(), inputs: None })
+}
+
+// the finalized tx of a PSBT signed elsewhere, with its fee
+fn extract_signed_tx(psbt: &str) -> Result<(bitcoin::Transaction, Option<u64>)> {
+    let mut psbt = bitcoin::Psbt::from_str(psbt)
+        .context("Invalid PSBT")?;
+
+    if !crate::services::onchain::TransactionBuilder::finalize_psbt(&mut psbt) {
+        return Err(anyhow::anyhow!("PSBT is not fully signed"));
+    }
+    let fee = psbt.fee().ok().map(|fee| fee.to_sat());
+    let tx = psbt.extract_tx()
+        .map_err(|e| anyhow::anyhow!("Failed to extract transaction: {}", e))?;
+    Ok((tx, fee))
+}
+
+// everything not coming back to us counts as sent, as (destination, amount).
+// outputs without an address go by their script
+fn external_outputs(tx: &bitcoin::Transaction) -> Result<Vec<(String, u64)>> {
+    let keys = crate::services::onchain::keychain::wallet_keys()?;
+    let network = crate::services::profiles::active().network;
+    Ok(tx.output.iter()
+        .filter(|o| crate::services::onchain::keychain::find_key(&keys, &o.script_pubkey).is_none())
+        .map(|o| {
+            let destination = bitcoin::Address::from_script(&o.script_pubkey, network)
+                .map(|address| address.to_string())
+                .unwrap_or_else(|_| o.script_pubkey.to_hex_string());
+            (destination, o.value.to_sat())
+        })
+        .collect())
+}
+
+// exports the taproot spend conditions backing every boarding output and VTXO,
+// so they can be verified independently of the ark client
+pub async fn get_script_descriptors() -> Result<Vec<ScriptDescriptor>> {
+    use ark_client::wallet::BoardingWallet;
+
+    let grpc_client = APP_STATE.grpc_client.lock().await;
+    let client = {
+        let client_opt = grpc_client.get_ark_client();
+        client_opt.as_ref().map(|c| Arc::clone(c))
+    };
+    let ark_wallet = grpc_client.get_ark_wallet();
+    drop(grpc_client);
+
+    let mut descriptors = Vec::new();
+
+    if let Some(ark_wallet) = ark_wallet {
+        let boarding_outputs = ark_wallet.get_boarding_outputs()
+            .map_err(|e| anyhow::anyhow!("Failed to get boarding outputs: {}", e))?;
+
+        for boarding_output in boarding_outputs {
+            descriptors.push(build_script_descriptor(
+                "boarding",
+                None,
+                boarding_output.address(),
+                vec![
+                    ("exit", boarding_output.exit_spend_info()),
+                    ("forfeit", boarding_output.forfeit_spend_info()),
+                ],
+            ));
+        }
+    }
+
+    let client = client.ok_or_else(|| anyhow::anyhow!("Ark client not available"))?;
+    let vtxos = client.spendable_vtxos().await
+        .map_err(|e| anyhow::anyhow!("Failed to get spendable VTXOs: {}", e))?;
+
+    for (outpoints, vtxo) in vtxos.iter() {
+        for o in outpoints {
+            descriptors.push(build_script_descriptor(
+                "vtxo",
+                Some(o.outpoint.to_string()),
+                vtxo.address(),
+                vec![
+                    ("exit", vtxo.exit_spend_info()),
+                    ("forfeit", vtxo.forfeit_spend_info()),
+                ],
+            ));
+        }
+    }
+
+    Ok(descriptors)
+}
+
+fn build_script_descriptor(
+    kind: &str,
+    outpoint: Option<String>,
+    address: &bitcoin::Address,
+    leaves: Vec<(&str, (bitcoin::ScriptBuf, bitcoin::taproot::ControlBlock))>,
+) -> ScriptDescriptor {
+    // the taproot output key is the witness program of the P2TR script
+    let script_pubkey = address.script_pubkey();
+    let output_key = hex::encode(&script_pubkey.as_bytes()[2..]);
+
+    let internal_key = leaves.first()
+        .map(|(_, (_, control_block))| control_block.internal_key);
+    // rawtr() only if the leaves don't rebuild the address, it hides the spend conditions
+    let descriptor = internal_key
+        .and_then(|internal_key| tap_tree_descriptor(internal_key, &leaves, &script_pubkey))
+        .unwrap_or_else(|| format!("rawtr({})", output_key));
+
+    ScriptDescriptor {
+        kind: kind.to_string(),
+        outpoint,
+        address: address.to_string(),
+        descriptor: crate::services::onchain::multisig::with_checksum(&descriptor),
+        internal_key: internal_key.map(|key| key.to_string()).unwrap_or_default(),
+        leaves: leaves.into_iter().map(|(name, (script, control_block))| TapLeafInfo {
+            name: name.to_string(),
+            script_hex: script.to_hex_string(),
+            script_asm: script.to_asm_string(),
+            control_block: hex::encode(control_block.serialize()),
+        }).collect(),
+    }
+}
+
+// tr(<internal key>,{<leaf>,<leaf>}) with the tree shape taken from the control blocks, checked against
+// the address. Leaves that aren't miniscript, like the CSV exit path, are written as rawleaf(<hex>)
+fn tap_tree_descriptor(
+    internal_key: bitcoin::XOnlyPublicKey,
+    leaves: &[(&str, (bitcoin::ScriptBuf, bitcoin::taproot::ControlBlock))],
+    script_pubkey: &bitcoin::ScriptBuf,
+) -> Option<String> {
+    // deepest first is a valid depth-first order for any tree
+    let mut leaves: Vec<(u8, &bitcoin::ScriptBuf)> = leaves.iter()
+        .map(|(_, (script, control_block))| (control_block.merkle_branch.len() as u8, script))
+        .collect();
+    leaves.sort_by_key(|(depth, _)| std::cmp::Reverse(*depth));
+
+    let mut builder = bitcoin::taproot::TaprootBuilder::new();
+    for (depth, script) in &leaves {
+        builder = builder.add_leaf(*depth, (*script).clone()).ok()?;
+    }
+    let spend_info = builder.finalize(&bitcoin::key::Secp256k1::verification_only(), internal_key).ok()?;
+    if bitcoin::ScriptBuf::new_p2tr_tweaked(spend_info.output_key()) != *script_pubkey {
+        return None;
+    }
+
+    let leaves: Vec<(u8, String)> = leaves.into_iter()
+        .map(|(depth, script)| {
+            let leaf = miniscript::Miniscript::<bitcoin::XOnlyPublicKey, miniscript::Tap>::parse_insane(script)
+                .map(|ms| ms.to_string())
+                .unwrap_or_else(|_| format!("rawleaf({})", script.to_hex_string()));
+            (depth, leaf)
+        })
+        .collect();
+    let mut position = 0;
+    let tree = tap_tree(&leaves, &mut position, 0)?;
+    (position == leaves.len()).then(|| format!("tr({},{})", internal_key, tree))
+}
+
+// nests the depth-first leaves back into {left,right} branches
+fn tap_tree(leaves: &[(u8, String)], position: &mut usize, depth: u8) -> Option<String> {
+    let (leaf_depth, leaf) = leaves.get(*position)?;
+    if *leaf_depth == depth {
+        *position += 1;
+        return Some(leaf.clone());
+    }
+    if *leaf_depth < depth {
+        return None;
+    }
+    let left = tap_tree(leaves, position, depth + 1)?;
+    let right = tap_tree(leaves, position, depth + 1)?;
+    Some(format!("{{{},{}}}", left, right))
+}
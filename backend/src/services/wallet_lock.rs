@@ -0,0 +1,193 @@
+use anyhow::{anyhow, Result};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::services::APP_STATE;
+use crate::storage::DbManager;
+
+// Argon2id PHC string of the unlock passphrase, locking is off until one is set
+const SETTINGS_KEY: &str = "wallet_lock_passphrase";
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    pub idle_timeout_secs: u64,
+    pub relocks_in_secs: Option<u64>, // None while locked or without auto-relock
+}
+
+// gates spending behind a passphrase. The mnemonic is encrypted under it, and while locked the API is
+// read-only, the signer, the key manager and the Ark client hold no keys and background jobs don't sign
+pub struct WalletLock {
+    passphrase_hash: Mutex<Option<String>>,
+    locked: AtomicBool,
+    last_activity: Mutex<Instant>,
+    idle_timeout: Duration, // zero disables auto-relock
+}
+
+impl WalletLock {
+    // a wallet with a passphrase starts locked
+    pub fn load(db: &DbManager, idle_timeout: Duration) -> Result<Self> {
+        let passphrase_hash = db.get_setting(SETTINGS_KEY)?;
+        Ok(Self {
+            locked: AtomicBool::new(passphrase_hash.is_some()),
+            passphrase_hash: Mutex::new(passphrase_hash),
+            last_activity: Mutex::new(Instant::now()),
+            idle_timeout,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.passphrase_hash.lock().is_some()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    // changing an existing passphrase needs the current one
+    pub fn set_passphrase(&self, current: Option<&str>, passphrase: &str) -> Result<()> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(anyhow!("passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+        }
+        if self.is_enabled() {
+            let current = current.ok_or_else(|| anyhow!("current_passphrase is required to change the passphrase"))?;
+            if !self.verify(current)? {
+                return Err(anyhow!("current_passphrase is wrong"));
+            }
+        }
+
+        let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+            .map_err(|e| anyhow!("Failed to encode salt: {}", e))?;
+        let hash = Argon2::default()
+            .hash_password(passphrase.as_bytes(), &salt)
+            .map_err(|e| anyhow!("Failed to hash passphrase: {}", e))?
+            .to_string();
+        let previous = self.passphrase_hash.lock().clone();
+        APP_STATE.db_manager.save_setting(SETTINGS_KEY, &hash)?;
+        // the mnemonic is encrypted under the new passphrase, without that the stored hash goes back
+        if let Err(e) = APP_STATE.key_manager.encrypt_mnemonic(passphrase) {
+            let restored = match &previous {
+                Some(previous) => APP_STATE.db_manager.save_setting(SETTINGS_KEY, previous),
+                None => APP_STATE.db_manager.delete_setting(SETTINGS_KEY),
+            };
+            if let Err(restore_error) = restored {
+                tracing::error!("Failed to restore the previous lock passphrase: {}", restore_error);
+            }
+            return Err(anyhow!("Failed to encrypt the mnemonic: {}", e));
+        }
+        *self.passphrase_hash.lock() = Some(hash);
+        self.touch();
+
+        tracing::info!("Wallet lock passphrase updated");
+        Ok(())
+    }
+
+    // false when the passphrase is wrong
+    pub async fn unlock(&self, passphrase: &str) -> Result<bool> {
+        if !self.is_enabled() {
+            return Err(anyhow!("No lock passphrase set"));
+        }
+        if !self.verify(passphrase)? {
+            tracing::warn!("Wallet unlock attempt with a wrong passphrase");
+            return Ok(false);
+        }
+        if !APP_STATE.key_manager.unlock(passphrase)? {
+            return Err(anyhow!("The passphrase doesn't decrypt the stored mnemonic"));
+        }
+
+        // the signer dropped its keys on lock, hand the wallet key back
+        let (keypair, _) = APP_STATE.key_manager.load_or_create_wallet()?;
+        APP_STATE.signer.unlock();
+        APP_STATE.signer.register(&keypair);
+        self.locked.store(false, Ordering::SeqCst);
+        self.touch();
+        tracing::info!("Wallet unlocked");
+
+        // the Ark client was dropped with its copy of the key, an unreachable ASP doesn't fail the unlock
+        if !crate::services::simulation::is_enabled() {
+            if let Err(e) = APP_STATE.grpc_client.lock().await.reconnect_client().await {
+                tracing::warn!("Ark client not set up again after unlocking: {}", e);
+            }
+        }
+        Ok(true)
+    }
+
+    pub async fn lock(&self) -> Result<()> {
+        if !self.is_enabled() {
+            return Err(anyhow!("Set a lock passphrase before locking the wallet"));
+        }
+        self.locked.store(true, Ordering::SeqCst);
+        APP_STATE.signer.lock();
+        APP_STATE.key_manager.lock();
+        APP_STATE.grpc_client.lock().await.drop_client();
+
+        tracing::info!("Wallet locked");
+        Ok(())
+    }
+
+    // background jobs that sign skip their run while locked, instead of failing halfway through
+    pub fn skips(&self, job: &str) -> bool {
+        let locked = self.is_locked();
+        if locked {
+            tracing::debug!("Wallet is locked, skipping {}", job);
+        }
+        locked
+    }
+
+    // called for every write request, reads don't keep the wallet unlocked
+    pub fn touch(&self) {
+        *self.last_activity.lock() = Instant::now();
+    }
+
+    pub fn status(&self) -> LockStatus {
+        let locked = self.is_locked();
+        let relocks_in_secs = match (self.is_enabled(), locked, self.idle_timeout.is_zero()) {
+            (true, false, false) => Some(self.idle_timeout.saturating_sub(self.last_activity.lock().elapsed()).as_secs()),
+            _ => None,
+        };
+        LockStatus {
+            enabled: self.is_enabled(),
+            locked,
+            idle_timeout_secs: self.idle_timeout.as_secs(),
+            relocks_in_secs,
+        }
+    }
+
+    pub fn start_auto_relock(self: &Arc<Self>) {
+        if self.idle_timeout.is_zero() {
+            return;
+        }
+
+        let lock = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(15));
+            loop {
+                interval.tick().await;
+                if !lock.is_enabled() || lock.is_locked() {
+                    continue;
+                }
+                if lock.last_activity.lock().elapsed() >= lock.idle_timeout {
+                    match lock.lock().await {
+                        Ok(()) => tracing::info!("Wallet relocked after {}s idle", lock.idle_timeout.as_secs()),
+                        Err(e) => tracing::warn!("Failed to relock wallet: {}", e),
+                    }
+                }
+            }
+        });
+    }
+
+    fn verify(&self, passphrase: &str) -> Result<bool> {
+        let stored = self.passphrase_hash.lock().clone()
+            .ok_or_else(|| anyhow!("No lock passphrase set"))?;
+        let hash = PasswordHash::new(&stored)
+            .map_err(|e| anyhow!("Stored lock passphrase is unreadable: {}", e))?;
+        Ok(Argon2::default().verify_password(passphrase.as_bytes(), &hash).is_ok())
+    }
+}
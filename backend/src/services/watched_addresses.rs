@@ -0,0 +1,231 @@
+use once_cell::sync::Lazy;
+use rusqlite::{params, OptionalExtension};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::wallet::{WatchAddressRequest, WatchedAddress, WatchedAddressTx};
+use crate::services::ark_grpc::EsploraBlockchain;
+use crate::services::chain::ChainBackend;
+use crate::services::events::WalletEvent;
+use crate::services::APP_STATE;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WatchError {
+    #[error("Address is not watched: {0}")]
+    NotFound(String),
+    #[error("Invalid watched address: {0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+impl From<rusqlite::Error> for WatchError {
+    fn from(e: rusqlite::Error) -> Self {
+        WatchError::Storage(e.into())
+    }
+}
+
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+
+pub fn list() -> Result<Vec<WatchedAddress>, WatchError> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT address, label, webhook_url, created_at FROM watched_addresses ORDER BY created_at"
+    )?;
+
+    let addresses = stmt
+        .query_map([], row_to_address)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(addresses)
+}
+
+pub fn get(address: &str) -> Result<WatchedAddress, WatchError> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    conn.query_row(
+        "SELECT address, label, webhook_url, created_at FROM watched_addresses WHERE address = ?",
+        params![address],
+        row_to_address,
+    )
+    .optional()?
+    .ok_or_else(|| WatchError::NotFound(address.to_string()))
+}
+
+// watching an address again updates its label and webhook, the history is kept
+pub fn watch(request: WatchAddressRequest) -> Result<WatchedAddress, WatchError> {
+    let address = request.address.trim();
+    let network = crate::services::profiles::active().network;
+    bitcoin::Address::from_str(address)
+        .map_err(|e| WatchError::Invalid(format!("Not a Bitcoin address: {}", e)))?
+        .require_network(network)
+        .map_err(|_| WatchError::Invalid(format!("Address is not valid for {}", network)))?;
+
+    {
+        let conn = APP_STATE.db_manager.get_conn()?;
+        conn.execute(
+            "INSERT INTO watched_addresses (address, label, webhook_url, created_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT(address) DO UPDATE SET label = excluded.label, webhook_url = excluded.webhook_url",
+            params![address, request.label, request.webhook_url, chrono::Utc::now().timestamp()],
+        )?;
+    }
+
+    get(address)
+}
+
+pub fn unwatch(address: &str) -> Result<(), WatchError> {
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let removed = conn.execute("DELETE FROM watched_addresses WHERE address = ?", params![address])?;
+    if removed == 0 {
+        return Err(WatchError::NotFound(address.to_string()));
+    }
+    conn.execute("DELETE FROM watched_address_txs WHERE address = ?", params![address])?;
+    Ok(())
+}
+
+// newest first, unconfirmed txs before confirmed ones
+pub fn history(address: &str) -> Result<Vec<WatchedAddressTx>, WatchError> {
+    get(address)?;
+
+    let conn = APP_STATE.db_manager.get_conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT address, txid, amount, block_height, seen_at FROM watched_address_txs
+         WHERE address = ? ORDER BY block_height IS NOT NULL, block_height DESC, seen_at DESC"
+    )?;
+
+    let txs = stmt
+        .query_map(params![address], |row| {
+            let block_height: Option<u32> = row.get(3)?;
+            Ok(WatchedAddressTx {
+                address: row.get(0)?,
+                txid: row.get(1)?,
+                amount: row.get(2)?,
+                confirmed: block_height.is_some(),
+                block_height,
+                seen_at: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(txs)
+}
+
+// polls esplora's address index, bitcoind's UTXO scan can't see spends or the mempool
+pub fn start_watcher(interval: Duration) {
+    if crate::services::simulation::is_enabled() {
+        return;
+    }
+    let ChainBackend::Esplora(esplora) = APP_STATE.blockchain.as_ref() else {
+        tracing::info!("Watched addresses need the esplora chain backend, watcher not started");
+        return;
+    };
+
+    let esplora = Arc::clone(esplora);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let watched = match list() {
+                Ok(watched) => watched,
+                Err(e) => {
+                    tracing::warn!("Failed to load watched addresses: {}", e);
+                    continue;
+                }
+            };
+            for watched in watched {
+                if let Err(e) = poll(&esplora, &watched).await {
+                    tracing::warn!("Failed to check watched address {}: {}", watched.address, e);
+                }
+            }
+        }
+    });
+}
+
+async fn poll(esplora: &EsploraBlockchain, watched: &WatchedAddress) -> anyhow::Result<()> {
+    let script = bitcoin::Address::from_str(&watched.address)?.assume_checked().script_pubkey();
+
+    for tx in esplora.get_script_txs(&script).await? {
+        let received: i64 = tx.vout.iter()
+            .filter(|output| output.scriptpubkey == script)
+            .map(|output| output.value as i64)
+            .sum();
+        let spent: i64 = tx.vin.iter()
+            .filter_map(|input| input.prevout.as_ref())
+            .filter(|prevout| prevout.scriptpubkey == script)
+            .map(|prevout| prevout.value as i64)
+            .sum();
+        let block_height = tx.status.block_height.filter(|_| tx.status.confirmed);
+
+        let txid = tx.txid.to_string();
+        let known: Option<Option<u32>> = {
+            let conn = APP_STATE.db_manager.get_conn()?;
+            conn.query_row(
+                "SELECT block_height FROM watched_address_txs WHERE address = ? AND txid = ?",
+                params![watched.address, txid],
+                |row| row.get(0),
+            ).optional()?
+        };
+        // new, or confirmed since the last poll
+        if known.is_some_and(|known_height| known_height.is_some() || block_height.is_none()) {
+            continue;
+        }
+
+        let activity = WatchedAddressTx {
+            address: watched.address.clone(),
+            txid,
+            amount: received - spent,
+            confirmed: block_height.is_some(),
+            block_height,
+            seen_at: chrono::Utc::now().timestamp(),
+        };
+        {
+            let conn = APP_STATE.db_manager.get_conn()?;
+            conn.execute(
+                "INSERT INTO watched_address_txs (address, txid, amount, block_height, seen_at) VALUES (?, ?, ?, ?, ?)
+                 ON CONFLICT(address, txid) DO UPDATE SET block_height = excluded.block_height",
+                params![activity.address, activity.txid, activity.amount, activity.block_height, activity.seen_at],
+            )?;
+        }
+        notify(watched, &activity);
+    }
+    Ok(())
+}
+
+// WebSocket subscribers get an event, the address's webhook a POST with a few retries
+fn notify(watched: &WatchedAddress, activity: &WatchedAddressTx) {
+    tracing::info!("Activity on watched address {}: {} ({} sats)", activity.address, activity.txid, activity.amount);
+    APP_STATE.events.publish(WalletEvent::WatchedAddressActivity {
+        address: activity.address.clone(),
+        txid: activity.txid.clone(),
+        amount: activity.amount,
+        confirmed: activity.confirmed,
+    });
+
+    let Some(url) = watched.webhook_url.clone() else {
+        return;
+    };
+    let body = serde_json::json!({
+        "event": if activity.confirmed { "watched_address.confirmed" } else { "watched_address.seen" },
+        "label": watched.label,
+        "transaction": activity,
+    });
+    tokio::spawn(async move {
+        for attempt in 1..=3u32 {
+            match HTTP_CLIENT.post(&url).json(&body).send().await {
+                Ok(response) if response.status().is_success() => return,
+                Ok(response) => tracing::warn!("Webhook {} returned {} (attempt {})", url, response.status(), attempt),
+                Err(e) => tracing::warn!("Webhook {} failed: {} (attempt {})", url, e, attempt),
+            }
+            tokio::time::sleep(Duration::from_secs(5 * attempt as u64)).await;
+        }
+        tracing::error!("Giving up on webhook {}", url);
+    });
+}
+
+fn row_to_address(row: &rusqlite::Row) -> rusqlite::Result<WatchedAddress> {
+    Ok(WatchedAddress {
+        address: row.get(0)?,
+        label: row.get(1)?,
+        webhook_url: row.get(2)?,
+        created_at: row.get(3)?,
+    })
+}
@@ -1,26 +1,46 @@
 #![allow(unused_features, dead_code)]
 use anyhow::{Result, anyhow};
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{Connection, params};
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 pub struct DbManager {
-    conn: Arc<Mutex<Connection>>,
+    pool: Pool<SqliteConnectionManager>,
+    path: String,
 }
 
+// first bytes of every SQLite database file
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
 impl DbManager {
+    // stored in PRAGMA user_version, bump it with migrations an older backend can't read
+    pub const SCHEMA_VERSION: i64 = 1;
+
     pub fn new(db_path: &str) -> Result<Self> {
         // ensure directory exists
         if let Some(parent) = Path::new(db_path).parent() {
             std::fs::create_dir_all(parent)?;
         }
+        Self::apply_staged_restore(db_path)?;
 
-        // open SQLite connection
-        let conn = Connection::open(db_path)?;
+        // WAL lets balance reads proceed while history is being written
+        let connection_manager = SqliteConnectionManager::file(db_path).with_init(|conn| {
+            conn.busy_timeout(Duration::from_secs(5))?;
+            conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")
+        });
+
+        let pool = Pool::builder()
+            .max_size(8)
+            .connection_timeout(Duration::from_secs(10))
+            .build(connection_manager)
+            .map_err(|e| anyhow!("Failed to open database pool: {}", e))?;
         
         // create instance
         let manager = Self {
-            conn: Arc::new(Mutex::new(conn)),
+            pool,
+            path: db_path.to_string(),
         };
         
         // initialize database schema
@@ -30,7 +50,7 @@ impl DbManager {
     }
 
     fn init_schema(&self) -> Result<()> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("Failed to lock connection: {}", e))?;
+        let conn = self.get_conn()?;
         
         // create tables
         conn.execute(
@@ -45,6 +65,37 @@ impl DbManager {
             [],
         )?;
 
+        // columns added after the initial schema
+        Self::add_column_if_missing(&conn, "transactions", "screening", "TEXT")?;
+        Self::add_column_if_missing(&conn, "transactions", "block_height", "INTEGER")?;
+        Self::add_column_if_missing(&conn, "transactions", "block_hash", "TEXT")?;
+        Self::add_column_if_missing(&conn, "transactions", "details", "TEXT")?; // the kind's fields as JSON
+        Self::add_column_if_missing(&conn, "transactions", "direction", "TEXT")?;
+        Self::add_column_if_missing(&conn, "transactions", "counterparty_address", "TEXT")?;
+        Self::add_column_if_missing(&conn, "transactions", "fee", "INTEGER")?;
+        // entries recorded before direction was stored go by their amount
+        conn.execute(
+            "UPDATE transactions SET direction = CASE
+                WHEN amount > 0 THEN 'incoming' WHEN amount < 0 THEN 'outgoing' ELSE 'internal' END
+             WHERE direction IS NULL",
+            [],
+        )?;
+
+        // history pages are ordered newest first, optionally filtered by type or direction.
+        // txid lookups use the primary key
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transactions_timestamp ON transactions (timestamp DESC, txid)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transactions_type ON transactions (type_name, timestamp)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transactions_direction ON transactions (direction, timestamp)",
+            [],
+        )?;
+
         conn.execute(
             "CREATE TABLE IF NOT EXISTS secret_keys (
                 public_key TEXT PRIMARY KEY,
@@ -61,11 +112,271 @@ impl DbManager {
             [],
         )?;
 
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS contacts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                address TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // addresses outside the wallet watched for activity, e.g. cold storage
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS watched_addresses (
+                address TEXT PRIMARY KEY,
+                label TEXT,
+                webhook_url TEXT,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS watched_address_txs (
+                address TEXT NOT NULL,
+                txid TEXT NOT NULL,
+                amount INTEGER NOT NULL,
+                block_height INTEGER,
+                seen_at INTEGER NOT NULL,
+                PRIMARY KEY (address, txid)
+            )",
+            [],
+        )?;
+
+        // responses of send requests, replayed when a client retries with the same Idempotency-Key
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS idempotency_keys (
+                key TEXT PRIMARY KEY,
+                endpoint TEXT NOT NULL,
+                request_hash TEXT NOT NULL,
+                status_code INTEGER,
+                response TEXT,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // addresses handed out on derived chains, chain 1 is change (m/84'/0'/0'/1/idx)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS wallet_addresses (
+                chain INTEGER NOT NULL,
+                idx INTEGER NOT NULL,
+                address TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (chain, idx)
+            )",
+            [],
+        )?;
+
+        // fee bumped on-chain txs and the RBF replacement that superseded them
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tx_replacements (
+                original_txid TEXT PRIMARY KEY,
+                replacement_txid TEXT NOT NULL,
+                fee INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // unilateral exits that haven't been swept to the on-chain wallet yet
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS exits (
+                vtxo_txid TEXT PRIMARY KEY,
+                amount INTEGER NOT NULL,
+                phase TEXT NOT NULL,
+                broadcast_txids TEXT NOT NULL,
+                leaf_confirmed_height INTEGER,
+                claimable_height INTEGER,
+                claimable_at INTEGER,
+                claim_txid TEXT,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // faucet payouts, cooldowns and budgets are counted from here so they survive restarts
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS faucet_dispenses (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                address TEXT NOT NULL,
+                ip TEXT NOT NULL,
+                amount INTEGER NOT NULL,
+                txid TEXT,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_faucet_dispenses_address ON faucet_dispenses (address, created_at)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_faucet_dispenses_ip ON faucet_dispenses (ip, created_at)",
+            [],
+        )?;
+
+        // Lightning swaps, refund_key is the public key a refund would be signed with. Older rows hold the secret
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS swaps (
+                id TEXT PRIMARY KEY,
+                provider TEXT NOT NULL,
+                invoice TEXT NOT NULL,
+                funding TEXT NOT NULL,
+                lockup_address TEXT NOT NULL,
+                expected_amount INTEGER NOT NULL,
+                lockup_txid TEXT,
+                status TEXT NOT NULL,
+                error TEXT,
+                timeout_block_height INTEGER,
+                refund_key TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // payment requests handed out to payers, status is pending, paid or expired
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS payment_requests (
+                id TEXT PRIMARY KEY,
+                amount INTEGER NOT NULL,
+                memo TEXT,
+                ark_address TEXT NOT NULL,
+                uri TEXT NOT NULL,
+                status TEXT NOT NULL,
+                paid_outpoint TEXT,
+                webhook_url TEXT,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                paid_at INTEGER
+            )",
+            [],
+        )?;
+
+        // boarding addresses handed out, rebuilt into the wallet on startup
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS boarding_outputs (
+                address TEXT PRIMARY KEY,
+                server_pk TEXT NOT NULL,
+                owner_pk TEXT NOT NULL,
+                exit_delay INTEGER NOT NULL,
+                network TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // rounds the wallet took part in, what went in and what came out
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS rounds (
+                txid TEXT PRIMARY KEY,
+                vtxos_renewed INTEGER NOT NULL,
+                boarding_inputs INTEGER NOT NULL,
+                outputs_created INTEGER NOT NULL,
+                input_amount INTEGER NOT NULL,
+                output_amount INTEGER NOT NULL,
+                fee INTEGER NOT NULL,
+                started_at INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // sends above the approval threshold, status is pending, approved, rejected, sent or failed
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS pending_approvals (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                address TEXT NOT NULL,
+                amount INTEGER NOT NULL,
+                priority TEXT,
+                fee_rate_sat_vb INTEGER,
+                status TEXT NOT NULL,
+                txid TEXT,
+                error TEXT,
+                reason TEXT,
+                created_at INTEGER NOT NULL,
+                decided_at INTEGER
+            )",
+            [],
+        )?;
+        // the refund key is derived from the seed at this index of the swap account
+        Self::add_column_if_missing(&conn, "swaps", "refund_key_index", "INTEGER")?;
+        // held swaps keep their funding source, held PSBT broadcasts the signed PSBT
+        Self::add_column_if_missing(&conn, "pending_approvals", "funding", "TEXT")?;
+        Self::add_column_if_missing(&conn, "pending_approvals", "psbt", "TEXT")?;
+
+        // a newer backend's database is left as it is, the self-check reports it
+        let version = Self::read_schema_version(&conn)?;
+        if version > Self::SCHEMA_VERSION {
+            tracing::error!("Database schema version {} is newer than this backend's {}", version, Self::SCHEMA_VERSION);
+        } else {
+            conn.execute_batch(&format!("PRAGMA user_version = {}", Self::SCHEMA_VERSION))?;
+        }
+
         Ok(())
     }
 
-    pub fn get_conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
-        self.conn.lock().map_err(|e| anyhow!("Failed to lock connection: {}", e))
+    pub fn schema_version(&self) -> Result<i64> {
+        Self::read_schema_version(&self.get_conn()?)
+    }
+
+    fn read_schema_version(conn: &Connection) -> Result<i64> {
+        Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
+    fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+
+        if !exists {
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+        }
+
+        Ok(())
+    }
+
+    // a restored database is staged next to the live one and swapped in on the next start,
+    // the open pool can't be pointed at a new file
+    fn apply_staged_restore(db_path: &str) -> Result<()> {
+        let staged = format!("{}.restore", db_path);
+        if !Path::new(&staged).exists() {
+            return Ok(());
+        }
+
+        for suffix in ["-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", db_path, suffix));
+        }
+        std::fs::rename(&staged, db_path)?;
+        tracing::warn!("Replaced {} with the restored database", db_path);
+        Ok(())
+    }
+
+    // consistent copy of the live database, safe to take while it's being written
+    pub fn snapshot(&self) -> Result<Vec<u8>> {
+        let dir = tempfile::tempdir()?;
+        let snapshot_path = dir.path().join("snapshot.db");
+        let conn = self.get_conn()?;
+        conn.execute("VACUUM INTO ?", params![snapshot_path.to_string_lossy()])?;
+        Ok(std::fs::read(&snapshot_path)?)
+    }
+
+    pub fn stage_restore(&self, database: &[u8]) -> Result<()> {
+        if !database.starts_with(SQLITE_HEADER) {
+            return Err(anyhow!("Backup doesn't contain a SQLite database"));
+        }
+        std::fs::write(format!("{}.restore", self.path), database)?;
+        Ok(())
+    }
+
+    pub fn get_conn(&self) -> Result<PooledConnection<SqliteConnectionManager>> {
+        self.pool.get().map_err(|e| anyhow!("Failed to get database connection: {}", e))
     }
     
     pub fn save_setting(&self, key: &str, value: &str) -> Result<()> {
@@ -79,6 +390,12 @@ impl DbManager {
         Ok(())
     }
     
+    pub fn delete_setting(&self, key: &str) -> Result<()> {
+        let conn = self.get_conn()?;
+        conn.execute("DELETE FROM settings WHERE key = ?", params![key])?;
+        Ok(())
+    }
+
     pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
         let conn = self.get_conn()?;
         
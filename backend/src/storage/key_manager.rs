@@ -1,18 +1,43 @@
 #![allow(unused_features, dead_code)]
 use anyhow::{anyhow, Result};
+use argon2::Argon2;
 use bip39::{Language, Mnemonic};
-use bitcoin::bip32::{DerivationPath, Xpriv};
+use bitcoin::bip32::{DerivationPath, Fingerprint, Xpriv};
 use bitcoin::key::{Keypair, Secp256k1};
 use bitcoin::secp256k1::SecretKey;
 use bitcoin::Network;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use parking_lot::RwLock;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use zeroize::Zeroizing;
+
+const MNEMONIC_FILE: &str = "mnemonic.txt";
+const ENCRYPTED_MNEMONIC_FILE: &str = "mnemonic.enc";
+// encrypted mnemonic layout: MAGIC | salt | nonce | ChaCha20-Poly1305 ciphertext of the phrase
+const MAGIC: &[u8] = b"ARKMNE1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+// the mnemonic is encrypted and the key manager doesn't hold it, nothing can be derived until unlocked
+#[derive(Debug, thiserror::Error)]
+#[error("Wallet is locked")]
+pub struct WalletLocked;
+
+// the opened encrypted mnemonic, with the key it's written back under
+struct Unlocked {
+    phrase: Option<Zeroizing<String>>, // None until a wallet is created
+    key: Zeroizing<[u8; 32]>,
+    salt: [u8; SALT_LEN],
+}
 
-// manages wallet keys using BIP39 mnemonics
+// manages wallet keys using BIP39 mnemonics, stored in plain text until a lock passphrase encrypts them
 pub struct KeyManager {
     storage_path: String,
     network: Network,
+    unlocked: RwLock<Option<Unlocked>>,
 }
 
 impl KeyManager {
@@ -20,6 +45,7 @@ impl KeyManager {
         Self {
             storage_path: storage_path.to_string(),
             network,
+            unlocked: RwLock::new(None),
         }
     }
 
@@ -35,12 +61,7 @@ impl KeyManager {
         // derive keypair from mnemonic
         let keypair = self.keypair_from_mnemonic(&phrase)?;
 
-        // [TODO!!: Encrypt this file] save mnemonic to file
-        let mnemonic_path = Path::new(&self.storage_path).join("mnemonic.txt");
-        if let Some(parent) = mnemonic_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::write(&mnemonic_path, &phrase)?;
+        self.save_phrase(&phrase)?;
 
         tracing::info!("Generated new wallet with mnemonic");
         Ok((keypair, phrase))
@@ -48,24 +69,69 @@ impl KeyManager {
 
     
     // returns: (keypair, mnemonic phrase)
+    // a locked wallet is an error, never a reason to generate a new one
     pub fn load_or_create_wallet(&self) -> Result<(Keypair, String)> {
-        let mnemonic_path = Path::new(&self.storage_path).join("mnemonic.txt");
-
-        if mnemonic_path.exists() {
-            // load existing mnemonic
-            let phrase = fs::read_to_string(&mnemonic_path)?;
-            let keypair = self.keypair_from_mnemonic(&phrase)?;
-            tracing::info!("Loaded existing wallet from mnemonic");
-            Ok((keypair, phrase))
-        } else {
+        match self.read_phrase()? {
+            Some(phrase) => {
+                // load existing mnemonic
+                let keypair = self.keypair_from_mnemonic(&phrase)?;
+                tracing::info!("Loaded existing wallet from mnemonic");
+                Ok((keypair, String::clone(&phrase)))
+            },
             // generate new wallet
-            self.generate_new_wallet()
+            None => self.generate_new_wallet(),
         }
     }
 
 
     // returns: Bitcoin keypair
     fn keypair_from_mnemonic(&self, phrase: &str) -> Result<Keypair> {
+        // derive account key (m/84'/0'/0'/0/0 for BIP84 SegWit)
+        let mut keypairs = self.keypairs_from_mnemonic(phrase, &["m/84'/0'/0'/0/0".to_string()])?;
+        Ok(keypairs.remove(0))
+    }
+
+    // returns: keypairs for the external (receive) chain m/84'/0'/0'/0/i, index 0 is the wallet's main key
+    pub fn derive_receive_keypairs(&self, indices: std::ops::Range<u32>) -> Result<Vec<Keypair>> {
+        let phrase = self.get_mnemonic()?;
+        let paths: Vec<String> = indices.map(|i| format!("m/84'/0'/0'/0/{}", i)).collect();
+        self.keypairs_from_mnemonic(&phrase, &paths)
+    }
+
+    // returns: keypairs for the internal (change) chain m/84'/0'/0'/1/i
+    pub fn derive_change_keypairs(&self, indices: std::ops::Range<u32>) -> Result<Vec<Keypair>> {
+        let phrase = self.get_mnemonic()?;
+        let paths: Vec<String> = indices.map(|i| format!("m/84'/0'/0'/1/{}", i)).collect();
+        self.keypairs_from_mnemonic(&phrase, &paths)
+    }
+
+    // returns: keypair of the faucet account m/84'/0'/1'/0/0
+    pub fn derive_faucet_keypair(&self) -> Result<Keypair> {
+        let phrase = self.get_mnemonic()?;
+        let mut keypairs = self.keypairs_from_mnemonic(&phrase, &["m/84'/0'/1'/0/0".to_string()])?;
+        Ok(keypairs.remove(0))
+    }
+
+    // returns: refund keypair of the swap account m/84'/0'/2'/0/i, one index per swap
+    pub fn derive_swap_refund_keypair(&self, index: u32) -> Result<Keypair> {
+        let phrase = self.get_mnemonic()?;
+        let mut keypairs = self.keypairs_from_mnemonic(&phrase, &[format!("m/84'/0'/2'/0/{}", index)])?;
+        Ok(keypairs.remove(0))
+    }
+
+    // returns: master fingerprint and the multisig account key m/48'/0'/0'/2' (BIP48, P2WSH)
+    pub fn derive_multisig_account(&self) -> Result<(Fingerprint, Xpriv)> {
+        let secp = Secp256k1::new();
+        let master_key = self.master_key(&self.get_mnemonic()?)?;
+        let path = DerivationPath::from_str("m/48'/0'/0'/2'")
+            .map_err(|e| anyhow!("Invalid derivation path: {}", e))?;
+        let account = master_key
+            .derive_priv(&secp, &path)
+            .map_err(|e| anyhow!("Failed to derive multisig account key: {}", e))?;
+        Ok((master_key.fingerprint(&secp), account))
+    }
+
+    fn master_key(&self, phrase: &str) -> Result<Xpriv> {
         // parse the mnemonic phrase
         let mnemonic = Mnemonic::parse_in(Language::English, phrase)
             .map_err(|e| anyhow!("Invalid mnemonic: {}", e))?;
@@ -74,23 +140,26 @@ impl KeyManager {
         let seed = mnemonic.to_seed("");
 
         // derive master key using BIP32
-        let secp = Secp256k1::new();
-        let master_key = Xpriv::new_master(self.network, &seed)
-            .map_err(|e| anyhow!("Failed to derive master key: {}", e))?;
-
-        // derive account key (m/84'/0'/0'/0/0 for BIP84 SegWit)
-        let path = DerivationPath::from_str("m/84'/0'/0'/0/0")
-            .map_err(|e| anyhow!("Invalid derivation path: {}", e))?;
-        let child_key = master_key
-            .derive_priv(&secp, &path)
-            .map_err(|e| anyhow!("Failed to derive child key: {}", e))?;
-
-        // convert to keypair
-        let secret_key = SecretKey::from_slice(&child_key.private_key.secret_bytes())
-            .map_err(|e| anyhow!("Invalid secret key: {}", e))?;
-        let keypair = Keypair::from_secret_key(&secp, &secret_key);
+        Xpriv::new_master(self.network, &seed)
+            .map_err(|e| anyhow!("Failed to derive master key: {}", e))
+    }
 
-        Ok(keypair)
+    fn keypairs_from_mnemonic(&self, phrase: &str, paths: &[String]) -> Result<Vec<Keypair>> {
+        let secp = Secp256k1::new();
+        let master_key = self.master_key(phrase)?;
+
+        paths.iter().map(|path| {
+            let path = DerivationPath::from_str(path)
+                .map_err(|e| anyhow!("Invalid derivation path: {}", e))?;
+            let child_key = master_key
+                .derive_priv(&secp, &path)
+                .map_err(|e| anyhow!("Failed to derive child key: {}", e))?;
+
+            // convert to keypair
+            let secret_key = SecretKey::from_slice(&child_key.private_key.secret_bytes())
+                .map_err(|e| anyhow!("Invalid secret key: {}", e))?;
+            Ok(Keypair::from_secret_key(&secp, &secret_key))
+        }).collect()
     }
 
     
@@ -99,30 +168,164 @@ impl KeyManager {
         // validate and derive keypair from mnemonic
         let keypair = self.keypair_from_mnemonic(phrase)?;
 
-        // [TODO!!: Encrypt it] save mnemonic to file
-        let mnemonic_path = Path::new(&self.storage_path).join("mnemonic.txt");
-        if let Some(parent) = mnemonic_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::write(&mnemonic_path, phrase)?;
+        self.save_phrase(phrase)?;
 
         tracing::info!("Imported wallet from mnemonic");
         Ok(keypair)
     }
 
+    // moves the mnemonic aside before another one replaces it, so the old wallet can still be recovered.
+    // an encrypted one stays encrypted under the current passphrase
+    // returns: where it was moved, None without a wallet
+    pub fn archive_mnemonic(&self) -> Result<Option<PathBuf>> {
+        let (mnemonic_path, extension) = match self.is_encrypted() {
+            true => (self.encrypted_path(), "enc"),
+            false => (self.mnemonic_path(), "txt"),
+        };
+        if !mnemonic_path.exists() {
+            return Ok(None);
+        }
+
+        let archived = Path::new(&self.storage_path)
+            .join(format!("mnemonic-replaced-{}.{}", chrono::Utc::now().format("%Y%m%dT%H%M%SZ"), extension));
+        fs::rename(&mnemonic_path, &archived)?;
+        tracing::warn!("Moved the previous mnemonic to {}", archived.display());
+        Ok(Some(archived))
+    }
+
     
     // retuns: BIP39 mnemonic phrase
     pub fn get_mnemonic(&self) -> Result<String> {
-        let mnemonic_path = Path::new(&self.storage_path).join("mnemonic.txt");
+        let phrase = self.read_phrase()?.ok_or_else(|| anyhow!("No wallet found"))?;
+        Ok(String::clone(&phrase))
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted_path().exists()
+    }
+
+    // encrypts the mnemonic under a key derived from the passphrase and deletes the plain text file,
+    // also re-encrypts it when the passphrase changes. The mnemonic stays open afterwards
+    pub fn encrypt_mnemonic(&self, passphrase: &str) -> Result<()> {
+        let phrase = self.read_phrase()?;
+        let salt = rand::random::<[u8; SALT_LEN]>();
+        let key = derive_key(passphrase, &salt)?;
+
+        if let Some(phrase) = &phrase {
+            fs::create_dir_all(&self.storage_path)?;
+            write_encrypted(&self.encrypted_path(), phrase, &key, &salt)?;
+            if self.mnemonic_path().exists() {
+                fs::remove_file(self.mnemonic_path())?;
+            }
+            tracing::info!("Encrypted the wallet mnemonic");
+        }
+        *self.unlocked.write() = Some(Unlocked { phrase, key, salt });
+        Ok(())
+    }
+
+    // opens the encrypted mnemonic, a plain text one from before encryption existed is encrypted now
+    // returns: false when the passphrase doesn't decrypt it
+    pub fn unlock(&self, passphrase: &str) -> Result<bool> {
+        if !self.is_encrypted() {
+            self.encrypt_mnemonic(passphrase)?;
+            return Ok(true);
+        }
+
+        let file = fs::read(self.encrypted_path())?;
+        let body = file.strip_prefix(MAGIC)
+            .filter(|body| body.len() > SALT_LEN + NONCE_LEN)
+            .ok_or_else(|| anyhow!("{} is not an encrypted mnemonic", ENCRYPTED_MNEMONIC_FILE))?;
+        let (salt, rest) = body.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let key = derive_key(passphrase, salt)?;
+        let Ok(plaintext) = ChaCha20Poly1305::new(Key::from_slice(key.as_slice())).decrypt(Nonce::from_slice(nonce), ciphertext) else {
+            return Ok(false);
+        };
+        let phrase = String::from_utf8(plaintext)
+            .map_err(|_| anyhow!("{} is corrupted", ENCRYPTED_MNEMONIC_FILE))?;
+
+        *self.unlocked.write() = Some(Unlocked {
+            phrase: Some(Zeroizing::new(phrase)),
+            key,
+            salt: salt.try_into()?,
+        });
+        Ok(true)
+    }
+
+    // forgets the opened mnemonic and its key, both are zeroed on drop
+    pub fn lock(&self) {
+        *self.unlocked.write() = None;
+    }
+
+    fn mnemonic_path(&self) -> PathBuf {
+        Path::new(&self.storage_path).join(MNEMONIC_FILE)
+    }
+
+    fn encrypted_path(&self) -> PathBuf {
+        Path::new(&self.storage_path).join(ENCRYPTED_MNEMONIC_FILE)
+    }
+
+    // returns: the stored phrase, None without a wallet
+    fn read_phrase(&self) -> Result<Option<Zeroizing<String>>> {
+        if self.is_encrypted() {
+            return match &*self.unlocked.read() {
+                Some(unlocked) => Ok(unlocked.phrase.clone()),
+                None => Err(WalletLocked.into()),
+            };
+        }
+
+        let mnemonic_path = self.mnemonic_path();
         if !mnemonic_path.exists() {
-            return Err(anyhow!("No wallet found"));
+            return Ok(None);
         }
+        Ok(Some(Zeroizing::new(fs::read_to_string(&mnemonic_path)?)))
+    }
 
-        let phrase = fs::read_to_string(&mnemonic_path)?;
-        Ok(phrase)
+    // encrypted once a passphrase is set, plain text before
+    fn save_phrase(&self, phrase: &str) -> Result<()> {
+        fs::create_dir_all(&self.storage_path)?;
+
+        let mut unlocked = self.unlocked.write();
+        match unlocked.as_mut() {
+            Some(unlocked) => {
+                write_encrypted(&self.encrypted_path(), phrase, &unlocked.key, &unlocked.salt)?;
+                unlocked.phrase = Some(Zeroizing::new(phrase.to_string()));
+            },
+            None if self.is_encrypted() => return Err(WalletLocked.into()),
+            None => fs::write(self.mnemonic_path(), phrase)?,
+        }
+        Ok(())
     }
 }
 
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut_slice())
+        .map_err(|e| anyhow!("Failed to derive mnemonic key: {}", e))?;
+    Ok(key)
+}
+
+// a fresh nonce for every write, through a temp file so a crash can't leave half a mnemonic
+fn write_encrypted(path: &Path, phrase: &str, key: &[u8; 32], salt: &[u8; SALT_LEN]) -> Result<()> {
+    let nonce = rand::random::<[u8; NONCE_LEN]>();
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(Nonce::from_slice(&nonce), phrase.as_bytes())
+        .map_err(|_| anyhow!("Failed to encrypt the mnemonic"))?;
+
+    let mut file = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    file.extend_from_slice(MAGIC);
+    file.extend_from_slice(salt);
+    file.extend_from_slice(&nonce);
+    file.extend_from_slice(&ciphertext);
+
+    let partial = path.with_extension("partial");
+    fs::write(&partial, &file)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,4 +405,60 @@ mod tests {
             loaded_keypair.public_key().to_string()
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_encrypted_mnemonic() {
+        let temp_dir = tempdir().unwrap();
+        let key_manager = KeyManager::new(
+            temp_dir.path().to_str().unwrap(),
+            Network::Regtest,
+        );
+
+        let (keypair, phrase) = key_manager.load_or_create_wallet().unwrap();
+        key_manager.encrypt_mnemonic("correct horse").unwrap();
+        assert!(!temp_dir.path().join("mnemonic.txt").exists());
+        assert!(key_manager.is_encrypted());
+
+        // locked: nothing to derive from, and no new wallet behind the user's back
+        key_manager.lock();
+        assert!(key_manager.get_mnemonic().unwrap_err().is::<WalletLocked>());
+        assert!(key_manager.load_or_create_wallet().unwrap_err().is::<WalletLocked>());
+
+        assert!(!key_manager.unlock("wrong horse").unwrap());
+        assert!(key_manager.unlock("correct horse").unwrap());
+        assert_eq!(key_manager.get_mnemonic().unwrap(), phrase);
+
+        // an import while unlocked is written encrypted too
+        let imported = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        key_manager.import_wallet(imported).unwrap();
+        assert!(!temp_dir.path().join("mnemonic.txt").exists());
+        key_manager.lock();
+        assert!(key_manager.unlock("correct horse").unwrap());
+        let (loaded, _) = key_manager.load_or_create_wallet().unwrap();
+        assert_ne!(loaded.public_key(), keypair.public_key());
+        assert_eq!(key_manager.get_mnemonic().unwrap(), imported);
+    }
+
+    #[test]
+    fn test_derive_change_keypairs() {
+        let temp_dir = tempdir().unwrap();
+        let key_manager = KeyManager::new(
+            temp_dir.path().to_str().unwrap(),
+            Network::Regtest,
+        );
+
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let receive = key_manager.import_wallet(phrase).unwrap();
+
+        let change = key_manager.derive_change_keypairs(0..2).unwrap();
+        assert_eq!(change.len(), 2);
+
+        // change keys are distinct from the receive key and from each other, and stable across calls
+        assert_ne!(change[0].public_key(), receive.public_key());
+        assert_ne!(change[0].public_key(), change[1].public_key());
+        assert_eq!(key_manager.derive_change_keypairs(1..2).unwrap()[0].public_key(), change[1].public_key());
+
+        // receive index 0 is the wallet's main key
+        assert_eq!(key_manager.derive_receive_keypairs(0..1).unwrap()[0].public_key(), receive.public_key());
+    }
+}
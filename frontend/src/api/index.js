@@ -1,4 +1,4 @@
-const API_URL = process.env.REACT_APP_API_URL || 'http://localhost:3030/api';
+const API_URL = process.env.REACT_APP_API_URL || 'http://localhost:3030/api/v1';
 
 // Wallet API
 export const getWalletInfo = async () => {
@@ -28,7 +28,7 @@ function Transactions() {
   const [exitError, setExitError] = useState(null);
   const [exitSuccess, setExitSuccess] = useState(null);
 
-  const API_URL = process.env.REACT_APP_API_URL || 'http://localhost:3030/api';
+  const API_URL = process.env.REACT_APP_API_URL || 'http://localhost:3030/api/v1';
 
   const fetchTransactions = async () => {
     try {
@@ -185,7 +185,9 @@ function Transactions() {
               id: 'status',
               header: 'Status',
               cell: item => {
-                if (item.is_settled === undefined || item.is_settled === null) {
+                if (typeof item.confirmations === 'number' && item.confirmations < 6) {
+                  return <StatusIndicator type="pending">{item.confirmations}/6 confirmations</StatusIndicator>;
+                } else if (item.is_settled === undefined || item.is_settled === null) {
                   return <StatusIndicator type="error">Cancelled</StatusIndicator>;
                 } else if (item.is_settled) {
                   return <StatusIndicator type="success">Settled</StatusIndicator>;